@@ -0,0 +1,290 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sled::Db;
+
+use crate::models::{Operation, PendingRestore, RepoState, UndoSnapshot};
+
+/// sled tree holding the operation log, keyed by a monotonic id
+const OPERATIONS_TREE: &str = "operations";
+
+/// sled tree holding the snapshot of the last pull/restore for `mgit undo`
+const UNDO_TREE: &str = "undo";
+const UNDO_KEY: &str = "last";
+
+/// sled tree holding the repositories a restore failed on, for `mgit restore --continue`
+const RESTORE_TREE: &str = "restore";
+const PENDING_RESTORE_KEY: &str = "pending";
+
+/// Version of the format written by [`StateDb::export`]; newer versions are refused on import
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Everything the state database holds, in a form that doesn't depend on sled (see `mgit db export`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DbExport {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub repositories: Vec<RepoState>,
+    /// Operation log, oldest first
+    #[serde(default)]
+    pub operations: Vec<Operation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo: Option<UndoSnapshot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_restore: Option<PendingRestore>,
+}
+
+/// What [`StateDb::import`] took over from an export
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub repositories: usize,
+    pub operations: usize,
+    pub undo: bool,
+    pub pending_restore: bool,
+}
+
+/// Encode a record as MessagePack with field names: compact and quick to write, while fields
+/// can still be added or left out like in JSON
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec_named(value)?)
+}
+
+/// Decode a record; records written as JSON by earlier versions start with '{'
+/// (never the first byte of a MessagePack map) and are rewritten in the new format on their next save
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    if bytes.first() == Some(&b'{') {
+        return Ok(serde_json::from_slice(bytes)?);
+    }
+    Ok(rmp_serde::from_slice(bytes)?)
+}
+
+/// The workspace's state database
+/// Writes are not flushed one by one: sled flushes in the background, and everything is flushed
+/// when the database is closed (once per command).
+pub struct StateDb {
+    db: Db,
+}
+
+impl Drop for StateDb {
+    fn drop(&mut self) {
+        if let Err(e) = self.db.flush() {
+            tracing::warn!("Could not flush the state database: {}", e);
+        }
+    }
+}
+
+impl StateDb {
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let db = sled::open(path)?;
+        Ok(Self { db })
+    }
+
+    pub fn save_repo_state(&self, state: &RepoState) -> Result<()> {
+        self.db.insert(state.name.as_bytes(), encode(state)?)?;
+        Ok(())
+    }
+
+    /// Save several repository states at once (all or none of them are written)
+    pub fn save_repo_states(&self, states: &[RepoState]) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for state in states {
+            batch.insert(state.name.as_bytes(), encode(state)?);
+        }
+        self.db.apply_batch(batch)?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn get_repo_state(&self, name: &str) -> Result<Option<RepoState>> {
+        if let Some(value) = self.db.get(name.as_bytes())? {
+            Ok(Some(decode(&value)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Remove the cached state of a repository (no error if there is none)
+    pub fn remove_repo_state(&self, name: &str) -> Result<()> {
+        self.db.remove(name.as_bytes())?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn list_all_states(&self) -> Result<Vec<RepoState>> {
+        let mut states = Vec::new();
+        for item in self.db.iter() {
+            let (_, value) = item?;
+            states.push(decode(&value)?);
+        }
+        Ok(states)
+    }
+
+    /// Append an operation to the operation log
+    pub fn record_operation(&self, operation: &Operation) -> Result<()> {
+        let tree = self.db.open_tree(OPERATIONS_TREE)?;
+        let key = self.db.generate_id()?.to_be_bytes();
+        tree.insert(key, encode(operation)?)?;
+        Ok(())
+    }
+
+    /// List the operation log, most recent first
+    pub fn list_operations(&self) -> Result<Vec<Operation>> {
+        let tree = self.db.open_tree(OPERATIONS_TREE)?;
+        let mut operations = Vec::new();
+        for item in tree.iter().rev() {
+            let (_, value) = item?;
+            operations.push(decode(&value)?);
+        }
+        Ok(operations)
+    }
+
+    /// Replace the undo snapshot with the one of the latest operation
+    pub fn save_undo_snapshot(&self, snapshot: &UndoSnapshot) -> Result<()> {
+        let tree = self.db.open_tree(UNDO_TREE)?;
+        tree.insert(UNDO_KEY, encode(snapshot)?)?;
+        Ok(())
+    }
+
+    pub fn get_undo_snapshot(&self) -> Result<Option<UndoSnapshot>> {
+        let tree = self.db.open_tree(UNDO_TREE)?;
+        match tree.get(UNDO_KEY)? {
+            Some(value) => Ok(Some(decode(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn clear_undo_snapshot(&self) -> Result<()> {
+        let tree = self.db.open_tree(UNDO_TREE)?;
+        tree.remove(UNDO_KEY)?;
+        Ok(())
+    }
+
+    /// Replace the pending restore with the failures of the latest restore
+    pub fn save_pending_restore(&self, pending: &PendingRestore) -> Result<()> {
+        let tree = self.db.open_tree(RESTORE_TREE)?;
+        tree.insert(PENDING_RESTORE_KEY, encode(pending)?)?;
+        Ok(())
+    }
+
+    pub fn get_pending_restore(&self) -> Result<Option<PendingRestore>> {
+        let tree = self.db.open_tree(RESTORE_TREE)?;
+        match tree.get(PENDING_RESTORE_KEY)? {
+            Some(value) => Ok(Some(decode(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn clear_pending_restore(&self) -> Result<()> {
+        let tree = self.db.open_tree(RESTORE_TREE)?;
+        tree.remove(PENDING_RESTORE_KEY)?;
+        Ok(())
+    }
+
+    /// Copy the whole database into a [`DbExport`]
+    pub fn export(&self) -> Result<DbExport> {
+        let mut operations = self.list_operations()?;
+        operations.reverse();
+        Ok(DbExport {
+            version: EXPORT_VERSION,
+            exported_at: Utc::now(),
+            repositories: self.list_all_states()?,
+            operations,
+            undo: self.get_undo_snapshot()?,
+            pending_restore: self.get_pending_restore()?,
+        })
+    }
+
+    /// Merge an export into the database
+    /// Repository states replace the stored ones of the same name. Operations missing from the log
+    /// are added in chronological order. The undo snapshot and the pending restore are taken over
+    /// when they are newer than the stored ones.
+    pub fn import(&self, export: DbExport) -> Result<ImportSummary> {
+        if export.version > EXPORT_VERSION {
+            anyhow::bail!(
+                "The export has format version {}, this mgit reads up to version {}; update mgit first",
+                export.version,
+                EXPORT_VERSION
+            );
+        }
+        let mut summary = ImportSummary::default();
+        for state in &export.repositories {
+            self.save_repo_state(state)?;
+            summary.repositories += 1;
+        }
+
+        // The log is ordered by key, so it is rewritten with the imported operations in place
+        let mut operations = self.list_operations()?;
+        let known = |operation: &Operation, log: &[Operation]| {
+            log.iter().any(|o| o.started_at == operation.started_at && o.command == operation.command)
+        };
+        let added: Vec<Operation> = export.operations.into_iter().filter(|o| !known(o, &operations)).collect();
+        if !added.is_empty() {
+            summary.operations = added.len();
+            operations.extend(added);
+            operations.sort_by_key(|o| o.started_at);
+            let tree = self.db.open_tree(OPERATIONS_TREE)?;
+            tree.clear()?;
+            for operation in &operations {
+                tree.insert(self.db.generate_id()?.to_be_bytes(), encode(operation)?)?;
+            }
+        }
+
+        if let Some(undo) = export.undo {
+            if self.get_undo_snapshot()?.is_none_or(|current| current.created_at < undo.created_at) {
+                self.save_undo_snapshot(&undo)?;
+                summary.undo = true;
+            }
+        }
+        if let Some(pending) = export.pending_restore {
+            if self.get_pending_restore()?.is_none_or(|current| current.created_at < pending.created_at) {
+                self.save_pending_restore(&pending)?;
+                summary.pending_restore = true;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Move everything stored under a repository's name to `new`: its state, its entry in the
+    /// undo snapshot and in a pending restore (the operation log keeps the names of the time)
+    pub fn rename_repository(&self, old: &str, new: &str) -> Result<()> {
+        if let Some(mut state) = self.get_repo_state(old)? {
+            state.name = new.to_string();
+            self.save_repo_state(&state)?;
+            self.remove_repo_state(old)?;
+        }
+        if let Some(mut snapshot) = self.get_undo_snapshot()? {
+            if let Some(head) = snapshot.repos.iter_mut().find(|head| head.name == old) {
+                head.name = new.to_string();
+                self.save_undo_snapshot(&snapshot)?;
+            }
+        }
+        if let Some(mut pending) = self.get_pending_restore()? {
+            if let Some(branch) = pending.remaining.remove(old) {
+                pending.remaining.insert(new.to_string(), branch);
+                self.save_pending_restore(&pending)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_round_trip_and_read_json() {
+        let json = r#"{"name":"api","current_branch":"main","last_updated":"2025-03-01T09:30:00Z",
+            "branches":[{"name":"main","owner":"Jane","last_updated":"2025-03-01T09:30:00Z"}]}"#;
+        let legacy: RepoState = decode(json.as_bytes()).unwrap();
+        assert_eq!(legacy.branches[0].owner, "Jane");
+        assert!(legacy.refreshed_at.is_none());
+
+        let encoded = encode(&legacy).unwrap();
+        assert!(encoded.len() < json.len());
+        let decoded: RepoState = decode(&encoded).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&legacy).unwrap());
+    }
+}
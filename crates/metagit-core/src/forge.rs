@@ -0,0 +1,397 @@
+use anyhow::{anyhow, Result};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::git::{credential_keys, extract_hostname};
+use crate::keychain::{get_secret, SecretKind};
+use crate::models::{CiState, Config, ForgeKind, Repository};
+use crate::ssh_config::{lookup_ssh_host, SshUrl};
+
+/// Timeout of a hosting provider API request
+const API_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Grant type of the OAuth device authorization flow (RFC 8628)
+const DEVICE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// A repository on a hosting provider, as addressed by its API
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForgeRepo {
+    pub kind: ForgeKind,
+    /// Web host of the provider (e.g., "github.com")
+    pub host: String,
+    /// Repository path on the host without ".git" (e.g., "acme/api" or "group/subgroup/api")
+    pub path: String,
+}
+
+impl ForgeRepo {
+    /// Find the provider of a remote URL
+    /// github.com and hosts named "github"/"gitlab" are detected; `forges` in the config maps other
+    /// (self-hosted) hosts to their kind. SSH host aliases are resolved through ~/.ssh/config.
+    pub fn from_url(url: &str, config: &Config) -> Option<ForgeRepo> {
+        let (host, path) = match SshUrl::parse(url) {
+            Some(ssh) => (lookup_ssh_host(&ssh.host).hostname.unwrap_or(ssh.host), ssh.path),
+            None => {
+                let host = extract_hostname(url)?;
+                let rest = url.split_once("://")?.1;
+                let path = rest.split_once('/')?.1.to_string();
+                // Drop credentials and ports from the host ("user@host:8443")
+                let host = host.rsplit('@').next()?.split(':').next()?.to_string();
+                (host, path)
+            }
+        };
+        let path = path.trim_matches('/').trim_end_matches(".git").to_string();
+        if path.is_empty() {
+            return None;
+        }
+
+        let kind = config.forges.get(&host).copied().or_else(|| {
+            if host.contains("github") {
+                Some(ForgeKind::GitHub)
+            } else if host.contains("gitlab") {
+                Some(ForgeKind::GitLab)
+            } else {
+                None
+            }
+        })?;
+        Some(ForgeRepo { kind, host, path })
+    }
+
+    /// Base URL of the REST API
+    pub fn api_base(&self) -> String {
+        match (self.kind, self.host.as_str()) {
+            (ForgeKind::GitHub, "github.com") => "https://api.github.com".to_string(),
+            (ForgeKind::GitHub, host) => format!("https://{}/api/v3", host),
+            (ForgeKind::GitLab, host) => format!("https://{}/api/v4", host),
+        }
+    }
+
+    /// Path of the repository in API URLs (GitLab addresses projects by their URL-encoded path)
+    fn api_path(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("repos/{}", self.path),
+            ForgeKind::GitLab => format!("projects/{}", self.path.replace('/', "%2F")),
+        }
+    }
+}
+
+/// API token for a repository: its credential token, the OS keychain token (under the repository's
+/// `keychain` name, or the longest URL prefix like "github.com/acme" down to the host), or the
+/// GITHUB_TOKEN/GITLAB_TOKEN environment variable
+pub fn api_token(repo: &Repository, forge: &ForgeRepo) -> Option<String> {
+    let credential = repo.credential.as_ref();
+    if let Some(token) = credential.and_then(|c| c.token.clone()) {
+        return Some(token);
+    }
+    let keychain_names = match credential.and_then(|c| c.keychain.clone()) {
+        Some(name) => vec![name],
+        None => credential_keys(&forge.host, &forge.path),
+    };
+    if let Some(token) = keychain_names.iter().find_map(|name| get_secret(name, SecretKind::HttpsToken)) {
+        return Some(token);
+    }
+    let variable = match forge.kind {
+        ForgeKind::GitHub => "GITHUB_TOKEN",
+        ForgeKind::GitLab => "GITLAB_TOKEN",
+    };
+    std::env::var(variable).ok().filter(|t| !t.is_empty())
+}
+
+/// Pull request (merge request) to open
+#[derive(Debug, Clone)]
+pub struct PullRequest {
+    pub title: String,
+    pub body: String,
+    /// Branch with the changes
+    pub head: String,
+    /// Branch the changes should be merged into
+    pub base: String,
+    pub draft: bool,
+}
+
+/// Client of a provider's REST API, using the configured proxy
+pub struct ForgeClient {
+    agent: ureq::Agent,
+    forge: ForgeRepo,
+    token: String,
+}
+
+/// HTTP client for a provider's host, using the configured proxy
+fn http_agent(config: &Config, host: &str) -> Result<ureq::Agent> {
+    let mut builder = ureq::AgentBuilder::new()
+        .timeout(API_TIMEOUT)
+        .user_agent(concat!("mgit/", env!("CARGO_PKG_VERSION")))
+        .try_proxy_from_env(true);
+    match config.proxy.as_ref().and_then(|p| p.proxy_for_host(host)) {
+        Some(proxy) if proxy.is_empty() => builder = builder.try_proxy_from_env(false),
+        Some(proxy) => builder = builder.proxy(ureq::Proxy::new(&proxy)?),
+        None => {}
+    }
+    Ok(builder.build())
+}
+
+impl ForgeClient {
+    pub fn new(config: &Config, forge: ForgeRepo, token: String) -> Result<ForgeClient> {
+        let agent = http_agent(config, &forge.host)?;
+        Ok(ForgeClient { agent, forge, token })
+    }
+
+    /// Authenticated request for a path below the repository's API URL
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        let url = format!("{}/{}/{}", self.forge.api_base(), self.forge.api_path(), path);
+        let request = self.agent.request(method, &url);
+        match self.forge.kind {
+            ForgeKind::GitHub => request
+                .set("Authorization", &format!("Bearer {}", self.token))
+                .set("Accept", "application/vnd.github+json"),
+            ForgeKind::GitLab => request.set("PRIVATE-TOKEN", &self.token),
+        }
+    }
+
+    fn get(&self, path: &str) -> Result<serde_json::Value> {
+        self.request("GET", path).call().map_err(api_error)?.into_json().map_err(Into::into)
+    }
+
+    fn post(&self, path: &str, body: serde_json::Value) -> Result<serde_json::Value> {
+        self.request("POST", path).send_json(body).map_err(api_error)?.into_json().map_err(Into::into)
+    }
+
+    /// Open a pull request (GitLab: merge request) from `head` into `base`, returns its web URL
+    pub fn create_pull_request(&self, request: &PullRequest) -> Result<String> {
+        let (path, body, url_field) = match self.forge.kind {
+            ForgeKind::GitHub => (
+                "pulls",
+                serde_json::json!({
+                    "title": request.title,
+                    "body": request.body,
+                    "head": request.head,
+                    "base": request.base,
+                    "draft": request.draft,
+                }),
+                "html_url",
+            ),
+            ForgeKind::GitLab => (
+                "merge_requests",
+                serde_json::json!({
+                    "title": if request.draft { format!("Draft: {}", request.title) } else { request.title.clone() },
+                    "description": request.body,
+                    "source_branch": request.head,
+                    "target_branch": request.base,
+                }),
+                "web_url",
+            ),
+        };
+        let response = self.post(path, body)?;
+        response[url_field]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("The response has no {}", url_field))
+    }
+
+    /// CI state of a commit: GitHub check runs, or the latest GitLab pipeline
+    /// Returns `CiState::None` when no checks or pipelines ran for the commit.
+    pub fn ci_state(&self, sha: &str) -> Result<CiState> {
+        match self.forge.kind {
+            ForgeKind::GitHub => {
+                let response = self.get(&format!("commits/{}/check-runs?per_page=100", sha))?;
+                let runs = response["check_runs"].as_array().cloned().unwrap_or_default();
+                Ok(github_ci_state(&runs))
+            }
+            ForgeKind::GitLab => {
+                let response = self.get(&format!("pipelines?sha={}&per_page=1", sha))?;
+                let status = response.get(0).and_then(|p| p["status"].as_str());
+                Ok(status.map(gitlab_ci_state).unwrap_or(CiState::None))
+            }
+        }
+    }
+}
+
+/// Public web host of a provider
+pub fn default_host(kind: ForgeKind) -> &'static str {
+    match kind {
+        ForgeKind::GitHub => "github.com",
+        ForgeKind::GitLab => "gitlab.com",
+    }
+}
+
+/// A device authorization waiting for the user: `user_code` is entered at `verification_uri`
+#[derive(Debug, Clone)]
+pub struct DeviceAuthorization {
+    pub user_code: String,
+    pub verification_uri: String,
+    device_code: String,
+    interval: Duration,
+    expires_at: Instant,
+}
+
+/// Sign-in to a provider with the OAuth device flow (`mgit login`)
+/// The token grants HTTPS access to repositories and the API used for pull requests and CI.
+pub struct DeviceFlow {
+    agent: ureq::Agent,
+    kind: ForgeKind,
+    host: String,
+    client_id: String,
+}
+
+impl DeviceFlow {
+    pub fn new(config: &Config, kind: ForgeKind, host: &str, client_id: &str) -> Result<DeviceFlow> {
+        let agent = http_agent(config, host)?;
+        Ok(DeviceFlow { agent, kind, host: host.to_string(), client_id: client_id.to_string() })
+    }
+
+    /// Request a code for the user to enter in the browser
+    pub fn start(&self) -> Result<DeviceAuthorization> {
+        let (url, scope) = match self.kind {
+            ForgeKind::GitHub => (format!("https://{}/login/device/code", self.host), "repo"),
+            ForgeKind::GitLab => (format!("https://{}/oauth/authorize_device", self.host), "api write_repository"),
+        };
+        let response: serde_json::Value = self
+            .agent
+            .post(&url)
+            .set("Accept", "application/json")
+            .send_form(&[("client_id", &self.client_id), ("scope", scope)])
+            .map_err(api_error)?
+            .into_json()?;
+        let field = |name: &str| {
+            response[name]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("The response has no {}", name))
+        };
+        Ok(DeviceAuthorization {
+            user_code: field("user_code")?,
+            verification_uri: field("verification_uri")?,
+            device_code: field("device_code")?,
+            interval: Duration::from_secs(response["interval"].as_u64().unwrap_or(5)),
+            expires_at: Instant::now() + Duration::from_secs(response["expires_in"].as_u64().unwrap_or(900)),
+        })
+    }
+
+    /// Wait until the user approved (or denied) the code, returns the access token
+    pub fn wait_for_token(&self, authorization: &DeviceAuthorization) -> Result<String> {
+        let url = match self.kind {
+            ForgeKind::GitHub => format!("https://{}/login/oauth/access_token", self.host),
+            ForgeKind::GitLab => format!("https://{}/oauth/token", self.host),
+        };
+        let mut interval = authorization.interval;
+        loop {
+            if Instant::now() >= authorization.expires_at {
+                return Err(anyhow!("The code expired before it was entered"));
+            }
+            thread::sleep(interval);
+
+            let result = self.agent.post(&url).set("Accept", "application/json").send_form(&[
+                ("client_id", &self.client_id),
+                ("device_code", &authorization.device_code),
+                ("grant_type", DEVICE_GRANT),
+            ]);
+            // GitHub reports a pending authorization with 200, GitLab with 400
+            let response: serde_json::Value = match result {
+                Ok(response) | Err(ureq::Error::Status(400, response)) => response.into_json()?,
+                Err(e) => return Err(api_error(e)),
+            };
+            if let Some(token) = response["access_token"].as_str() {
+                return Ok(token.to_string());
+            }
+            match response["error"].as_str() {
+                Some("authorization_pending") => {}
+                // Polling too fast: RFC 8628 asks for 5 more seconds between requests
+                Some("slow_down") => interval += Duration::from_secs(5),
+                Some("expired_token") => return Err(anyhow!("The code expired before it was entered")),
+                Some("access_denied") => return Err(anyhow!("The authorization was denied")),
+                Some(error) => {
+                    let description = response["error_description"].as_str().unwrap_or(error);
+                    return Err(anyhow!("Sign-in failed: {}", description));
+                }
+                None => return Err(anyhow!("The response has no access_token")),
+            }
+        }
+    }
+}
+
+/// Turn an HTTP error into a readable message (e.g., "401 Unauthorized: Bad credentials")
+fn api_error(error: ureq::Error) -> anyhow::Error {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let status = response.status_text().to_string();
+            let message = response
+                .into_json::<serde_json::Value>()
+                .ok()
+                .and_then(|body| {
+                    // GitHub details validation failures (e.g., an existing pull request) in `errors`,
+                    // GitLab returns a list of messages for them
+                    let detail = body["errors"][0]["message"]
+                        .as_str()
+                        .or(body["message"].as_str())
+                        .or(body["message"][0].as_str());
+                    detail.map(str::to_string)
+                });
+            match message {
+                Some(message) => anyhow!("{} {}: {}", code, status, message),
+                None => anyhow!("{} {}", code, status),
+            }
+        }
+        ureq::Error::Transport(transport) => anyhow!("{}", transport),
+    }
+}
+
+/// Combine GitHub check runs: any failure fails, any unfinished run is running
+fn github_ci_state(runs: &[serde_json::Value]) -> CiState {
+    if runs.is_empty() {
+        return CiState::None;
+    }
+    let failed = runs.iter().any(|run| {
+        matches!(
+            run["conclusion"].as_str(),
+            Some("failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure")
+        )
+    });
+    if failed {
+        CiState::Failed
+    } else if runs.iter().any(|run| run["status"].as_str() != Some("completed")) {
+        CiState::Running
+    } else {
+        CiState::Passed
+    }
+}
+
+/// Map a GitLab pipeline status
+fn gitlab_ci_state(status: &str) -> CiState {
+    match status {
+        "success" => CiState::Passed,
+        "failed" | "canceled" => CiState::Failed,
+        "skipped" | "manual" => CiState::None,
+        _ => CiState::Running,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forge_repo_from_url() {
+        let config = Config {
+            forges: [("git.corp.example.com".to_string(), ForgeKind::GitLab)].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let github = ForgeRepo::from_url("https://github.com/acme/api.git", &config).unwrap();
+        assert_eq!((github.kind, github.path.as_str()), (ForgeKind::GitHub, "acme/api"));
+        assert_eq!(github.api_base(), "https://api.github.com");
+
+        let gitlab = ForgeRepo::from_url("ssh://git@git.corp.example.com:2222/platform/tools/cli.git", &config).unwrap();
+        assert_eq!(gitlab.kind, ForgeKind::GitLab);
+        assert_eq!(gitlab.api_path(), "projects/platform%2Ftools%2Fcli");
+
+        assert!(ForgeRepo::from_url("https://git.unknown.org/acme/api.git", &config).is_none());
+    }
+
+    #[test]
+    fn test_github_ci_state() {
+        let run = |status: &str, conclusion: Option<&str>| serde_json::json!({ "status": status, "conclusion": conclusion });
+        assert_eq!(github_ci_state(&[]), CiState::None);
+        assert_eq!(github_ci_state(&[run("completed", Some("success")), run("completed", Some("skipped"))]), CiState::Passed);
+        assert_eq!(github_ci_state(&[run("completed", Some("success")), run("in_progress", None)]), CiState::Running);
+        assert_eq!(github_ci_state(&[run("completed", Some("failure")), run("queued", None)]), CiState::Failed);
+    }
+}
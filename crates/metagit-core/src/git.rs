@@ -0,0 +1,3143 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use git2::{BranchType, Cred, FetchOptions, Oid, PushOptions, RemoteCallbacks, Repository, Status};
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, instrument, warn, Level};
+
+use crate::models::{
+    matches_no_proxy, BranchInfo, Config, CredentialPrompt, GitBackend, Identity, LineStats, ProxyConfig, RepoCredential, RepoState,
+    StatsOptions, TlsConfig, WorktreeStatus,
+};
+use crate::keychain::{get_secret, SecretKind};
+use crate::ssh_config::{lookup_ssh_host, resolve_ssh_url, SshUrl};
+
+/// Asks for the user name and password of a URL, given the user name from the URL
+pub type CredentialPromptFn = fn(&str, Option<&str>) -> Option<(String, String)>;
+
+/// Terminal prompt for HTTPS user name and password, see [`set_credential_prompt`]
+static CREDENTIAL_PROMPT: OnceLock<CredentialPromptFn> = OnceLock::new();
+
+/// Held while the user is prompted, so parallel operations ask one at a time
+static PROMPT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Credentials entered at the prompt during this run, keyed like [`CREDENTIAL_CACHE`]
+/// Unlike the cache, entries are added before the operation went through, so repositories
+/// waiting for the prompt reuse them instead of asking again.
+static PROMPTED_CREDENTIALS: Mutex<BTreeMap<String, CachedCredential>> = Mutex::new(BTreeMap::new());
+
+/// Receiver for the transfer progress of fetches and pushes, see [`set_progress_handler`]
+static PROGRESS_HANDLER: OnceLock<fn(TransferProgress)> = OnceLock::new();
+
+/// Set by [`cancel_operations`]
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Cancels the operations of this thread only, see [`set_thread_cancel`]
+    static THREAD_CANCELLED: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Whether the TLS settings have been applied to libgit2 (they are process-wide)
+static TLS_APPLIED: Mutex<bool> = Mutex::new(false);
+
+/// Objects received by a fetch, or sent by a push, so far
+#[derive(Debug, Clone, Copy)]
+pub struct TransferProgress {
+    pub objects: usize,
+    pub total_objects: usize,
+    pub bytes: usize,
+}
+
+/// Register the function that receives the transfer progress of network operations
+/// It is called on the thread running the operation, for every update libgit2 reports
+/// (the git CLI backend reports none).
+pub fn set_progress_handler(handler: fn(TransferProgress)) {
+    let _ = PROGRESS_HANDLER.set(handler);
+}
+
+/// Stop the running network operations (e.g., when the user presses Ctrl-C)
+/// Fetches abort at their next progress update and leave the refs as they were, pushes that
+/// haven't sent anything yet don't start, and fetched branches are not fast-forwarded anymore.
+pub fn cancel_operations() {
+    CANCELLED.store(true, Ordering::Relaxed);
+}
+
+/// Let `flag` cancel the network operations of the calling thread, like [`cancel_operations`]
+/// does for all of them (e.g., set by a timeout watching the thread)
+pub fn set_thread_cancel(flag: Arc<AtomicBool>) {
+    THREAD_CANCELLED.with(|cancel| *cancel.borrow_mut() = Some(flag));
+}
+
+pub fn operations_cancelled() -> bool {
+    CANCELLED.load(Ordering::Relaxed)
+        || THREAD_CANCELLED.with(|cancel| cancel.borrow().as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)))
+}
+
+/// Register the function that asks the user for an HTTPS user name and password
+/// It receives the URL and the user name from the URL, and returns None when the user
+/// cancels. Without a prompt (e.g., not on a terminal), HTTPS authentication fails instead.
+pub fn set_credential_prompt(prompt: CredentialPromptFn) {
+    let _ = CREDENTIAL_PROMPT.set(prompt);
+}
+
+/// Represents a unique author identity (name + email)
+/// Stores names and emails in their original case, but uses case-insensitive comparison
+#[derive(Debug, Clone)]
+pub struct AuthorIdentity {
+    pub name: String,
+    pub email: String,
+}
+
+impl PartialEq for AuthorIdentity {
+    fn eq(&self, other: &Self) -> bool {
+        self.name.to_lowercase() == other.name.to_lowercase()
+            && self.email.to_lowercase() == other.email.to_lowercase()
+    }
+}
+
+impl Eq for AuthorIdentity {}
+
+impl std::hash::Hash for AuthorIdentity {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.to_lowercase().hash(state);
+        self.email.to_lowercase().hash(state);
+    }
+}
+
+/// Extract hostname from git URL (e.g., "git@github.com:..." -> "github.com")
+/// User names and ports are dropped ("ssh://git@host:2222/org/repo.git" -> "host")
+pub fn extract_hostname(url: &str) -> Option<String> {
+    // Handle SSH URLs like git@github.com:org/repo.git and ssh://git@host:2222/org/repo.git
+    if url.starts_with("ssh://") || (url.contains('@') && !url.contains("://")) {
+        return SshUrl::parse(url).map(|ssh| ssh.host).filter(|host| !host.is_empty());
+    }
+
+    // Handle HTTPS URLs like https://user@host:8443/org/repo.git
+    if url.starts_with("https://") || url.starts_with("http://") {
+        let without_protocol = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .unwrap_or(url);
+
+        if let Some(slash_pos) = without_protocol.find('/') {
+            let authority = &without_protocol[..slash_pos];
+            let host = authority.rsplit('@').next()?.split(':').next()?;
+            return Some(host.to_string());
+        }
+    }
+
+    None
+}
+
+/// Expand ~ in path to home directory
+fn expand_home(path: &str) -> PathBuf {
+    if path.starts_with("~/") || path == "~" {
+        let home = env::var("HOME")
+            .or_else(|_| env::var("USERPROFILE"))
+            .unwrap_or_else(|_| ".".to_string());
+
+        PathBuf::from(home).join(&path[2..])
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// Get the current branch name from a repository
+/// Returns the branch name if on a branch, or "(detached)" if in detached HEAD state
+fn get_current_branch(repo: &Repository) -> Result<String> {
+    // Try to get the HEAD reference
+    match repo.head() {
+        Ok(head) => {
+            // Check if HEAD is a symbolic reference (points to a branch)
+            if head.is_branch() {
+                // Get the full reference name (e.g., "refs/heads/master")
+                if let Some(name) = head.name() {
+                    // Strip "refs/heads/" prefix to get just the branch name
+                    if let Some(branch_name) = name.strip_prefix("refs/heads/") {
+                        return Ok(branch_name.to_string());
+                    }
+                }
+                // Fallback to shorthand if strip_prefix fails
+                Ok(head.shorthand().unwrap_or("(unknown)").to_string())
+            } else {
+                // Detached HEAD state
+                Ok("(detached)".to_string())
+            }
+        }
+        Err(_) => {
+            // No HEAD (empty repository or corrupt)
+            Ok("(no branch)".to_string())
+        }
+    }
+}
+
+/// Check if SSH agent is running
+fn is_ssh_agent_running() -> bool {
+    // Check for SSH_AUTH_SOCK environment variable (works on all platforms)
+    if env::var("SSH_AUTH_SOCK").is_ok() {
+        return true;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // On Windows, also check if ssh-agent service is running
+        // Or if Git's ssh-agent is available
+        if env::var("GIT_SSH").is_ok() || env::var("SSH_AGENT_PID").is_ok() {
+            return true;
+        }
+
+        // Check if Pageant is running (PuTTY's SSH agent)
+        Command::new("cmd")
+            .args(&["/C", "tasklist", "/FI", "IMAGENAME eq pageant.exe"])
+            .output()
+            .map(|o| {
+                let output = String::from_utf8_lossy(&o.stdout);
+                output.contains("pageant.exe")
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        false
+    }
+}
+
+/// Host and repository path of a remote URL, without user, port and ".git"
+/// ("git@github.com:acme-corp/api.git" -> ("github.com", "acme-corp/api"))
+pub fn url_host_and_path(url: &str) -> Option<(String, String)> {
+    let (host, path) = match SshUrl::parse(url) {
+        Some(ssh) => (ssh.host, ssh.path),
+        None => {
+            let host = extract_hostname(url)?;
+            let rest = url.split_once("://")?.1;
+            let path = rest.split_once('/').map(|(_, path)| path).unwrap_or("");
+            (host, path.to_string())
+        }
+    };
+    Some((host, path.trim_matches('/').trim_end_matches(".git").to_string()))
+}
+
+/// Credential keys matching a host and repository path, longest prefix first
+/// ("github.com", "acme-corp/api" -> "github.com/acme-corp/api", "github.com/acme-corp", "github.com")
+pub fn credential_keys(host: &str, path: &str) -> Vec<String> {
+    let mut keys = vec![host.to_string()];
+    let mut key = host.to_string();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        key = format!("{}/{}", key, segment);
+        keys.push(key.clone());
+    }
+    keys.reverse();
+    keys
+}
+
+/// Look up the SSH key configured in the credentials map for a remote URL
+/// Keys are a host or a URL prefix ("github.com/acme-corp"); the longest matching one wins.
+/// The host as written in the URL (possibly a ~/.ssh/config alias) is tried first,
+/// then the real HostName from ~/.ssh/config
+/// Returns (matched key, key path)
+fn configured_ssh_key(credentials: &HashMap<String, String>, remote_url: &str) -> Option<(String, String)> {
+    let (host, path) = url_host_and_path(remote_url)?;
+    let real_host = lookup_ssh_host(&host).hostname;
+    std::iter::once(host)
+        .chain(real_host)
+        .flat_map(|host| credential_keys(&host, &path))
+        .find_map(|key| credentials.get(&key).map(|key_path| (key.clone(), key_path.clone())))
+}
+
+/// Find the first IdentityFile from ~/.ssh/config for a host that exists on disk
+fn ssh_config_identity(host: &str) -> Option<PathBuf> {
+    lookup_ssh_host(host)
+        .identity_files
+        .iter()
+        .map(|file| expand_home(file))
+        .find(|path| path.exists())
+}
+
+/// Check if we have valid SSH authentication available for the given remote URL
+/// Returns Ok(()) if authentication is available, or an error with helpful suggestions
+fn validate_ssh_auth(
+    remote_url: &str,
+    credentials: &HashMap<String, String>,
+) -> Result<()> {
+    // Only check SSH URLs
+    if !remote_url.starts_with("git@") && !remote_url.starts_with("ssh://") {
+        return Ok(()); // HTTPS or other protocols
+    }
+
+    let hostname = extract_hostname(remote_url);
+    let has_ssh_agent = is_ssh_agent_running();
+
+    debug!("Validating SSH authentication...");
+    debug!("  SSH agent running: {}", has_ssh_agent);
+
+    // If SSH agent is running, we're good
+    if has_ssh_agent {
+        debug!("  ✓ SSH agent available");
+        return Ok(());
+    }
+
+    // Check if we have a configured key
+    if let Some(host) = hostname.as_ref() {
+        if let Some((_, key_path)) = configured_ssh_key(credentials, remote_url) {
+            let private_key = expand_home(&key_path);
+            let public_key = PathBuf::from(format!("{}.pub", private_key.display()));
+
+            debug!("  Checking configured key: {}", key_path);
+            debug!("    Private key: {}", private_key.display());
+            debug!("    Public key: {}", public_key.display());
+
+            // Check if both keys exist
+            if private_key.exists() && public_key.exists() {
+                debug!("  ✓ SSH keys found and valid");
+                return Ok(());
+            }
+
+            // Keys are configured but don't exist - provide specific error
+            let mut error_msg = format!(
+                "SSH authentication will fail: Configured keys not found\n\n\
+                 The key '{}' is configured in .mgitconfig.yaml but doesn't exist on disk.\n\n\
+                 Please choose one of these solutions:\n\n",
+                key_path
+            );
+
+            if !private_key.exists() {
+                error_msg.push_str(&format!(
+                    "  • Private key missing: {}\n",
+                    private_key.display()
+                ));
+            }
+            if !public_key.exists() {
+                error_msg.push_str(&format!(
+                    "  • Public key missing: {}\n",
+                    public_key.display()
+                ));
+            }
+
+            error_msg.push_str(&format!(
+                "\nSolutions:\n\
+                 1. Generate the missing SSH key:\n\
+                    ssh-keygen -t ed25519 -f {}\n\n\
+                 2. Update .mgitconfig.yaml to point to an existing key:\n\
+                    \"credentials\": {{\n\
+                      \"{}\": \"~/.ssh/id_rsa\"  (or your actual key path)\n\
+                    }}\n\n\
+                 3. Start SSH agent and add your key:\n\
+                    ssh-add ~/.ssh/id_rsa\n\
+                    (Then you won't need credentials in .mgitconfig.yaml)",
+                private_key.display(),
+                host
+            ));
+
+            return Err(anyhow::anyhow!(error_msg));
+        }
+
+        // Fall back to IdentityFile entries from ~/.ssh/config
+        if let Some(identity) = ssh_config_identity(host) {
+            debug!("  ✓ Using IdentityFile from ~/.ssh/config: {}", identity.display());
+            return Ok(());
+        }
+    }
+
+    // No SSH agent and no configured keys
+    let hostname_str = hostname.as_deref().unwrap_or("unknown");
+    let error_msg = format!(
+        "SSH authentication not configured\n\n\
+         Repository URL: {}\n\
+         Host: {}\n\n\
+         No SSH authentication method is available. Please choose one solution:\n\n\
+         Solution 1 - Use SSH agent (recommended):\n\
+           • Start SSH agent: eval $(ssh-agent)\n\
+           • Add your key: ssh-add ~/.ssh/id_rsa\n\
+           • Verify keys: ssh-add -l\n\n\
+         Solution 2 - Configure key in .mgitconfig.yaml:\n\
+           Add this to your .mgitconfig.yaml file:\n\
+           \"credentials\": {{\n\
+             \"{}\": \"~/.ssh/id_rsa\"\n\
+           }}\n\n\
+         Solution 3 - Test SSH connection:\n\
+           ssh -T git@{}\n\
+           (This will help verify your SSH setup)",
+        remote_url, hostname_str, hostname_str, hostname_str
+    );
+
+    Err(anyhow::anyhow!(error_msg))
+}
+
+/// Ask the configured git credential helpers for a user name and password
+/// Runs `git credential fill` non-interactively so existing credential managers
+/// (Git Credential Manager, osxkeychain, libsecret, store, ...) are reused
+fn credential_fill(url: &str, username: Option<&str>) -> Option<(String, String)> {
+    let output = run_credential_helper("fill", url, username, None)?;
+    let response = String::from_utf8_lossy(&output);
+    let mut user = None;
+    let mut password = None;
+    for line in response.lines() {
+        if let Some(value) = line.strip_prefix("username=") {
+            user = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("password=") {
+            password = Some(value.to_string());
+        }
+    }
+
+    Some((user?, password?))
+}
+
+/// Tell the credential helpers how the credentials they gave worked out: `approve` stores them,
+/// `reject` erases them, so a stale entry isn't offered again
+fn credential_report(action: &str, credential: &HelperCredential) {
+    debug!("git credential {} for {}", action, credential.url);
+    let _ = run_credential_helper(action, &credential.url, Some(&credential.user), Some(&credential.password));
+}
+
+/// Run `git credential <action>` with a request for `url`, returning its output on success
+fn run_credential_helper(action: &str, url: &str, username: Option<&str>, password: Option<&str>) -> Option<Vec<u8>> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let request = credential_request(url, username, password)?;
+    let mut child = Command::new("git")
+        .args(["credential", action])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    child.stdin.take()?.write_all(request.as_bytes()).ok()?;
+    let output = child.wait_with_output().ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+/// The `key=value` lines `git credential` reads, or None when a value contains a newline or NUL
+/// (like git, which refuses them): it would add keys of its own, e.g. another `url=`
+fn credential_request(url: &str, username: Option<&str>, password: Option<&str>) -> Option<String> {
+    let mut request = String::new();
+    for (key, value) in [("url", Some(url)), ("username", username), ("password", password)] {
+        let Some(value) = value else {
+            continue;
+        };
+        if value.contains(['\n', '\0']) {
+            warn!("Not passing a credential {} containing a newline or NUL to git credential", key);
+            return None;
+        }
+        request.push_str(&format!("{}={}\n", key, value));
+    }
+    request.push('\n');
+    Some(request)
+}
+
+/// Credentials the git credential helpers gave for a URL
+#[derive(Clone)]
+struct HelperCredential {
+    url: String,
+    user: String,
+    password: String,
+}
+
+/// A credential that authenticated successfully, reused for later repositories of the same host
+#[derive(Clone)]
+enum CachedCredential {
+    UserPass { user: String, password: String },
+    SshAgent,
+    SshKey {
+        public_key: Option<PathBuf>,
+        private_key: PathBuf,
+        passphrase: Option<String>,
+    },
+    Default,
+}
+
+impl CachedCredential {
+    fn to_cred(&self, username: &str) -> Result<Cred, git2::Error> {
+        match self {
+            CachedCredential::UserPass { user, password } => Cred::userpass_plaintext(user, password),
+            CachedCredential::SshAgent => Cred::ssh_key_from_agent(username),
+            CachedCredential::SshKey { public_key, private_key, passphrase } => {
+                Cred::ssh_key(username, public_key.as_deref(), private_key, passphrase.as_deref())
+            }
+            CachedCredential::Default => Cred::default(),
+        }
+    }
+
+    fn is_allowed(&self, allowed_types: git2::CredentialType) -> bool {
+        match self {
+            CachedCredential::UserPass { .. } => {
+                allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT)
+            }
+            CachedCredential::SshAgent | CachedCredential::SshKey { .. } => {
+                allowed_types.contains(git2::CredentialType::SSH_KEY)
+            }
+            CachedCredential::Default => allowed_types.contains(git2::CredentialType::DEFAULT),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            CachedCredential::UserPass { .. } => "HTTPS credentials",
+            CachedCredential::SshAgent => "SSH agent",
+            CachedCredential::SshKey { .. } => "SSH key",
+            CachedCredential::Default => "default credential",
+        }
+    }
+}
+
+/// Credentials that worked during this process, keyed by user, host and credential override
+static CREDENTIAL_CACHE: Mutex<BTreeMap<String, CachedCredential>> = Mutex::new(BTreeMap::new());
+
+/// The credential last handed to libgit2 for one operation
+/// It is added to the cache by [`CredentialAttempt::succeeded`] once the operation went through,
+/// so later repositories on the same host skip the helpers/keys that didn't work.
+/// Credentials from the git credential helpers are also approved to the helpers then.
+#[derive(Default)]
+struct CredentialAttempt {
+    last: RefCell<Option<(String, CachedCredential)>>,
+    /// Credentials from `git credential fill`, while they are the ones being tried
+    helper: RefCell<Option<HelperCredential>>,
+}
+
+impl CredentialAttempt {
+    fn succeeded(&self) {
+        if let Some((key, credential)) = self.last.borrow_mut().take() {
+            info!("Caching {} for {}", credential.label(), key);
+            CREDENTIAL_CACHE
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, credential);
+        }
+        if let Some(helper) = self.helper.borrow_mut().take() {
+            credential_report("approve", &helper);
+        }
+    }
+}
+
+/// Cache key of the credentials of a remote: the matching credentials key (the host, or a URL
+/// prefix with its own key) plus the repository's credential override
+fn credential_cache_key(
+    credentials: &HashMap<String, String>,
+    remote_url: &str,
+    repo_credential: Option<&RepoCredential>,
+) -> String {
+    let host = configured_ssh_key(credentials, remote_url)
+        .map(|(key, _)| key)
+        .or_else(|| extract_hostname(remote_url))
+        .unwrap_or_else(|| remote_url.to_string());
+    let keychain = repo_credential.and_then(|c| c.keychain.as_deref()).unwrap_or("");
+    let ssh_key = repo_credential.and_then(|c| c.ssh_key.as_deref()).unwrap_or("");
+    format!("{} [{}|{}]", host, keychain, ssh_key)
+}
+
+/// A source of HTTPS credentials offered by [`create_remote_callbacks`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HttpsCandidate {
+    /// The repository's `credential.token`
+    RepoToken,
+    /// A token stored in the OS keychain under this name
+    Keychain(String),
+    /// The user's git credential helpers (GCM, osxkeychain, store, ...)
+    Helpers,
+    /// The terminal prompt (or, with `credential_prompt: session`, what was entered for the host before)
+    Prompt,
+}
+
+/// HTTPS credential sources to offer for a remote, in order, each once: a token that origin
+/// refuses is not offered again, so the next source gets its turn
+fn https_candidates(
+    repo_credential: Option<&RepoCredential>,
+    keychain_names: &[String],
+    can_prompt: bool,
+) -> Vec<HttpsCandidate> {
+    let mut candidates = Vec::new();
+    if repo_credential.is_some_and(|c| c.token.is_some()) {
+        candidates.push(HttpsCandidate::RepoToken);
+    }
+    candidates.extend(keychain_names.iter().cloned().map(HttpsCandidate::Keychain));
+    candidates.push(HttpsCandidate::Helpers);
+    if can_prompt {
+        candidates.push(HttpsCandidate::Prompt);
+    }
+    candidates
+}
+
+/// An SSH credential offered by [`create_remote_callbacks`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SshCandidate {
+    Agent,
+    /// A key of the credentials map (or the repository's `ssh_key`), with the credentials key it matched
+    Key { key_host: String, private_key: PathBuf },
+    /// An IdentityFile of the host in ~/.ssh/config
+    IdentityFile(PathBuf),
+    Default,
+}
+
+/// SSH credentials to offer for a remote, in order
+/// A repository with its own `ssh_key` is offered that key only: the agent answers every
+/// attempt while it runs, and its key may belong to another account on the same host.
+/// Otherwise the agent comes first, then the configured key (or ~/.ssh/config's IdentityFile),
+/// then the default credential.
+fn ssh_candidates(
+    credentials: &HashMap<String, String>,
+    repo_credential: Option<&RepoCredential>,
+    remote_url: &str,
+    agent_running: bool,
+) -> Vec<SshCandidate> {
+    if let Some(ssh_key) = repo_credential.and_then(|c| c.ssh_key.as_deref()) {
+        let key_host = url_host_and_path(remote_url)
+            .map(|(host, path)| credential_keys(&host, &path).remove(0))
+            .unwrap_or_else(|| remote_url.to_string());
+        return vec![SshCandidate::Key { key_host, private_key: expand_home(ssh_key) }];
+    }
+
+    let mut candidates = Vec::new();
+    if agent_running {
+        candidates.push(SshCandidate::Agent);
+    }
+    match configured_ssh_key(credentials, remote_url) {
+        Some((key_host, key_path)) => {
+            let private_key = expand_home(&key_path);
+            if private_key.exists() {
+                candidates.push(SshCandidate::Key { key_host, private_key });
+            } else {
+                debug!("Configured key for {} not found at {}", key_host, private_key.display());
+            }
+        }
+        None => {
+            if let Some(private_key) = extract_hostname(remote_url).and_then(|host| ssh_config_identity(&host)) {
+                candidates.push(SshCandidate::IdentityFile(private_key));
+            }
+        }
+    }
+    candidates.push(SshCandidate::Default);
+    candidates
+}
+
+/// Create remote callbacks with SSH authentication support
+/// Credentials that worked for the same host earlier in this process are tried first;
+/// the credential used is recorded in `attempt`
+fn create_remote_callbacks<'a>(
+    credentials: &'a HashMap<String, String>,
+    repo_credential: Option<&'a RepoCredential>,
+    remote_url: &'a str,
+    prompt_mode: CredentialPrompt,
+    attempt: &'a CredentialAttempt,
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    debug!("Setting up SSH authentication for: {}", remote_url);
+
+    if tracing::enabled!(Level::DEBUG) {
+        // Check SSH agent status
+        if is_ssh_agent_running() {
+            debug!("SSH agent: RUNNING");
+        } else {
+            debug!("SSH agent: NOT DETECTED");
+        }
+
+        // Show environment variables
+        if let Ok(sock) = env::var("SSH_AUTH_SOCK") {
+            debug!("SSH_AUTH_SOCK: {}", sock);
+        } else {
+            debug!("SSH_AUTH_SOCK: Not set");
+        }
+
+        if let Ok(git_ssh) = env::var("GIT_SSH") {
+            debug!("GIT_SSH: {}", git_ssh);
+        }
+
+        // Show configured credentials
+        if credentials.is_empty() {
+            debug!("No credentials configured in .mgitconfig.yaml");
+        } else {
+            debug!(
+                "Configured credentials for: {:?}",
+                credentials.keys().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    // Numbers the requests; each one offers the next credential, until there are none left
+    let attempt_counter = Cell::new(0);
+    // Credentials entered for another repository are only tried once, then the user is asked
+    let reused_prompted = Cell::new(false);
+
+    // Keychain entry names to look up, longest URL prefix first, unless the repository names its own
+    let keychain_names = match repo_credential.and_then(|c| c.keychain.clone()) {
+        Some(name) => vec![name],
+        None => url_host_and_path(remote_url)
+            .map(|(host, path)| credential_keys(&host, &path))
+            .unwrap_or_default(),
+    };
+    let cache_key = credential_cache_key(credentials, remote_url, repo_credential);
+    // Worked out on the first SSH request (looking for an agent can run a process on Windows)
+    let ssh_candidates_cell = OnceCell::new();
+    let next_ssh_candidate = Cell::new(0);
+    let prompt = CREDENTIAL_PROMPT.get().filter(|_| prompt_mode != CredentialPrompt::Never);
+    let https_candidates = https_candidates(repo_credential, &keychain_names, prompt.is_some());
+    let next_https_candidate = Cell::new(0);
+
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        let attempts = attempt_counter.get() + 1;
+        attempt_counter.set(attempts);
+
+        debug!(
+            "Credentials requested for URL: {} (attempt {})",
+            url,
+            attempts
+        );
+        debug!("Username from URL: {:?}", username_from_url);
+        debug!("Allowed auth types: {:?}", allowed_types);
+
+        // Hand the credential to libgit2, remembering it for the cache
+        let key = format!("{}@{}", username_from_url.unwrap_or(""), cache_key);
+        let use_credential = |credential: CachedCredential, username: &str| {
+            let cred = credential.to_cred(username)?;
+            *attempt.last.borrow_mut() = Some((key.clone(), credential));
+            Ok(cred)
+        };
+
+        // Asked again for the same URL after the helpers' credentials: origin refused them
+        // (for another URL, e.g. after a redirect, they were not tried there and are not erased)
+        let previous = attempt.helper.borrow_mut().take();
+        if let Some(rejected) = previous.filter(|helper| helper.url == url) {
+            debug!("✗ Credentials from git credential helpers rejected");
+            credential_report("reject", &rejected);
+        }
+
+        // Reuse the credential that worked for this host before
+        if attempts == 1 {
+            let cached = CREDENTIAL_CACHE
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(&key)
+                .cloned();
+            if let Some(cached) = cached.filter(|c| c.is_allowed(allowed_types)) {
+                info!("Reusing cached {} for {}", cached.label(), key);
+                if let Ok(cred) = use_credential(cached, username_from_url.unwrap_or("git")) {
+                    return Ok(cred);
+                }
+            }
+        }
+
+        // HTTPS remotes: use the repository's token, or a token stored in the OS keychain
+        if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            // GitLab requires "oauth2" as user name for tokens; GitHub accepts any
+            let token_user = username_from_url.unwrap_or("oauth2");
+
+            // Each attempt offers the next source, since libgit2 asks again when one is rejected
+            while let Some(candidate) = https_candidates.get(next_https_candidate.get()) {
+                next_https_candidate.set(next_https_candidate.get() + 1);
+                match candidate {
+                    HttpsCandidate::RepoToken => {
+                        debug!("Using HTTPS token from repository credential");
+                        let token = repo_credential.and_then(|c| c.token.clone()).unwrap_or_default();
+                        return use_credential(
+                            CachedCredential::UserPass { user: token_user.to_string(), password: token },
+                            token_user,
+                        );
+                    }
+                    HttpsCandidate::Keychain(name) => {
+                        if let Some(token) = get_secret(name, SecretKind::HttpsToken) {
+                            debug!("Using HTTPS token from OS keychain for {}", name);
+                            return use_credential(
+                                CachedCredential::UserPass { user: token_user.to_string(), password: token },
+                                token_user,
+                            );
+                        }
+                        debug!("No HTTPS token in OS keychain for {}", name);
+                    }
+                    // Asked once: after a rejection they would offer the same entry again
+                    HttpsCandidate::Helpers => {
+                        debug!("Asking git credential helpers...");
+                        if let Some((user, password)) = credential_fill(url, username_from_url) {
+                            debug!("✓ Credential helper provided credentials for {}", user);
+                            let credential =
+                                CachedCredential::UserPass { user: user.clone(), password: password.clone() };
+                            let cred = use_credential(credential, token_user)?;
+                            *attempt.helper.borrow_mut() = Some(HelperCredential { url: url.to_string(), user, password });
+                            return Ok(cred);
+                        }
+                        debug!("✗ No credentials from git credential helpers");
+                    }
+                    // Last resort: ask on the terminal
+                    HttpsCandidate::Prompt => {
+                        let Some(prompt) = prompt else {
+                            continue;
+                        };
+                        let _prompting = PROMPT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+                        if prompt_mode == CredentialPrompt::Session && !reused_prompted.replace(true) {
+                            let prompted = PROMPTED_CREDENTIALS
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .get(&key)
+                                .cloned();
+                            if let Some(prompted) = prompted {
+                                debug!("Reusing the credentials entered for {}", key);
+                                // The prompt is still to come if these are refused
+                                next_https_candidate.set(next_https_candidate.get() - 1);
+                                return use_credential(prompted, token_user);
+                            }
+                        }
+
+                        debug!("Prompting for credentials...");
+                        if let Some((user, password)) = prompt(url, username_from_url) {
+                            let credential = CachedCredential::UserPass { user, password };
+                            if prompt_mode == CredentialPrompt::Always {
+                                // Not cached: the next repository prompts again
+                                return credential.to_cred(token_user);
+                            }
+                            PROMPTED_CREDENTIALS
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .insert(key.clone(), credential.clone());
+                            return use_credential(credential, token_user);
+                        }
+                        debug!("✗ Prompt cancelled");
+                    }
+                }
+            }
+        }
+
+        let username = username_from_url.unwrap_or("git");
+
+        // SSH: each attempt offers the next credential, since libgit2 asks again when one is rejected
+        let ssh_candidates = ssh_candidates_cell.get_or_init(|| {
+            let candidates = ssh_candidates(credentials, repo_credential, remote_url, is_ssh_agent_running());
+            debug!("SSH credentials to try: {:?}", candidates);
+            candidates
+        });
+        while let Some(candidate) = ssh_candidates.get(next_ssh_candidate.get()) {
+            next_ssh_candidate.set(next_ssh_candidate.get() + 1);
+            let credential = match candidate {
+                SshCandidate::Agent => {
+                    debug!("Attempting SSH agent authentication...");
+                    CachedCredential::SshAgent
+                }
+                SshCandidate::Key { key_host, private_key } => {
+                    debug!("Attempting SSH key {} (configured for {})...", private_key.display(), key_host);
+                    let public_key = PathBuf::from(format!("{}.pub", private_key.display()));
+                    let passphrase = keychain_names
+                        .iter()
+                        .chain(std::iter::once(key_host))
+                        .find_map(|name| get_secret(name, SecretKind::SshPassphrase));
+                    if passphrase.is_some() {
+                        debug!("Using SSH key passphrase from OS keychain");
+                    }
+                    CachedCredential::SshKey { public_key: Some(public_key), private_key: private_key.clone(), passphrase }
+                }
+                SshCandidate::IdentityFile(private_key) => {
+                    debug!("Attempting IdentityFile from ~/.ssh/config: {}", private_key.display());
+                    let public_key = PathBuf::from(format!("{}.pub", private_key.display()));
+                    let public_key = public_key.exists().then_some(public_key);
+                    let passphrase = keychain_names
+                        .iter()
+                        .find_map(|name| get_secret(name, SecretKind::SshPassphrase));
+                    CachedCredential::SshKey { public_key, private_key: private_key.clone(), passphrase }
+                }
+                SshCandidate::Default => {
+                    debug!("Attempting default credential fallback...");
+                    CachedCredential::Default
+                }
+            };
+            // An HTTPS remote has no use for SSH keys
+            if !credential.is_allowed(allowed_types) {
+                continue;
+            }
+            match use_credential(credential, username) {
+                Ok(cred) => return Ok(cred),
+                Err(e) => info!("✗ {:?} failed: {}", candidate, e),
+            }
+        }
+
+        warn!("❌ All authentication methods exhausted");
+        Err(git2::Error::from_str(match repo_credential.and_then(|c| c.ssh_key.as_deref()) {
+            _ if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) => {
+                "Authentication failed: no token, credential helper entry or entered password was accepted"
+            }
+            Some(_) => "Authentication failed with the repository's ssh_key (the SSH agent is not used for it)",
+            None => "Authentication failed: no SSH agent key, configured key or default credential was accepted",
+        }))
+    });
+
+    // Returning false aborts the fetch, see `cancel_operations`
+    let handler = PROGRESS_HANDLER.get().copied();
+    callbacks.transfer_progress(move |progress| {
+        if let Some(handler) = handler {
+            handler(TransferProgress {
+                objects: progress.received_objects(),
+                total_objects: progress.total_objects(),
+                bytes: progress.received_bytes(),
+            });
+        }
+        !operations_cancelled()
+    });
+    if let Some(handler) = handler {
+        callbacks.push_transfer_progress(move |objects, total_objects, bytes| {
+            handler(TransferProgress { objects, total_objects, bytes });
+        });
+    }
+
+    callbacks
+}
+
+/// Read a repository's branches, with their owners normalized with `user_aliases`
+pub fn get_repo_state(
+    repo_path: &Path,
+    repo_name: &str,
+    user_aliases: &HashMap<String, Vec<String>>,
+) -> Result<RepoState> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+
+    let current_branch = get_current_branch(&repo)?;
+
+    let mut branches = Vec::new();
+
+    // Get all local branches
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = branch.name()?.unwrap_or("(invalid utf8)").to_string();
+
+        // Get the last commit time and author for this branch
+        let reference = branch.get();
+        let commit = reference.peel_to_commit()?;
+        let time = commit.time();
+        let timestamp = time.seconds();
+        let last_updated = DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now);
+
+        // Use the commit author as the owner
+        let author = commit.author();
+        let author_name = author.name().unwrap_or("Unknown");
+        let author_email = author.email().unwrap_or("");
+
+        // Try to normalize using name first, then email if name doesn't match
+        let normalized_name = normalize_author(author_name, user_aliases);
+        let owner = if normalized_name == author_name && !author_email.is_empty() {
+            // Name wasn't normalized, try email
+            normalize_author(author_email, user_aliases)
+        } else {
+            normalized_name
+        };
+
+        branches.push(BranchInfo {
+            name: name.clone(),
+            owner,
+            last_updated,
+            commit_stats: HashMap::new(),
+            line_stats: HashMap::new(),
+            last_commit_sha: None,
+        });
+    }
+
+    // Sort branches by last updated (most recent first)
+    branches.sort_by_key(|b| std::cmp::Reverse(b.last_updated));
+
+    let last_updated = branches
+        .first()
+        .map(|b| b.last_updated)
+        .unwrap_or_else(Utc::now);
+
+    Ok(RepoState {
+        name: repo_name.to_string(),
+        worktree: Some(get_worktree_status(repo_path, &current_branch)),
+        current_branch,
+        last_updated,
+        branches,
+        refreshed_at: None,
+        stats_options: StatsOptions::default(),
+        ci: HashMap::new(),
+    })
+}
+
+/// Find the main branch (master or main)
+fn find_main_branch(repo: &Repository) -> Option<Oid> {
+    // Try "master" first, then "main"
+    for branch_name in &["master", "main"] {
+        let ref_name = format!("refs/heads/{}", branch_name);
+        if let Ok(reference) = repo.find_reference(&ref_name) {
+            if let Some(oid) = reference.target() {
+                return Some(oid);
+            }
+        }
+    }
+    None
+}
+
+/// Normalize a commit author to its canonical user name
+/// Tries the author name first, then the email
+pub fn normalize_signature(
+    signature: &git2::Signature,
+    user_aliases: &HashMap<String, Vec<String>>,
+) -> String {
+    let author_name = signature.name().unwrap_or("Unknown");
+    let author_email = signature.email().unwrap_or("");
+
+    let normalized_name = normalize_author(author_name, user_aliases);
+    if normalized_name == author_name && !author_email.is_empty() {
+        let normalized_email = normalize_author(author_email, user_aliases);
+        if normalized_email != author_email {
+            return normalized_email;
+        }
+    }
+    normalized_name
+}
+
+/// Normalize a user name or email to its canonical form
+fn normalize_author(author: &str, user_aliases: &HashMap<String, Vec<String>>) -> String {
+    let author_lower = author.to_lowercase();
+
+    // Check each canonical user and their aliases
+    for (canonical, aliases) in user_aliases {
+        // Check if matches canonical name
+        if canonical.to_lowercase() == author_lower {
+            return canonical.clone();
+        }
+
+        // Check if matches any alias
+        for alias in aliases {
+            if alias.to_lowercase() == author_lower {
+                return canonical.clone();
+            }
+        }
+    }
+
+    // No match found, return original
+    author.to_string()
+}
+
+/// Collect all unique author identities from all branches in a repository
+/// Returns a set of author identities (name + email pairs)
+pub fn collect_all_author_identities(repo_path: &Path) -> Result<HashSet<AuthorIdentity>> {
+    let repo = Repository::open(repo_path)?;
+    let mut identities = HashSet::new();
+
+    // Iterate through all branches
+    let branches = repo.branches(Some(BranchType::Local))?;
+
+    for branch_result in branches {
+        let (branch, _) = branch_result?;
+        let branch_ref = branch.get();
+
+        if let Some(branch_oid) = branch_ref.target() {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push(branch_oid)?;
+
+            // Walk through all commits in this branch
+            for oid in revwalk.flatten() {
+                if let Ok(commit) = repo.find_commit(oid) {
+                    let author = commit.author();
+                    let name = author.name().unwrap_or("Unknown").to_string();
+                    let email = author.email().unwrap_or("").to_string();
+
+                    // Only add if we have both name and email
+                    if !name.is_empty() && !email.is_empty() {
+                        identities.insert(AuthorIdentity { name, email });
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(identities)
+}
+
+/// Get the current commit SHA for a branch
+pub fn get_branch_commit_sha(repo_path: &Path, branch_name: &str) -> Result<String> {
+    let repo = Repository::open(repo_path)?;
+    let branch = repo.find_branch(branch_name, BranchType::Local)?;
+    let reference = branch.get();
+    let oid = reference
+        .target()
+        .with_context(|| format!("Branch '{}' has no target", branch_name))?;
+    Ok(oid.to_string())
+}
+
+/// Current branch (None if HEAD is detached) and HEAD commit of a repository
+pub fn get_head(repo_path: &Path) -> Result<(Option<String>, String)> {
+    let repo = Repository::open(repo_path)?;
+    let head = repo.head()?;
+    let oid = head
+        .peel_to_commit()
+        .context("HEAD does not point to a commit")?
+        .id();
+    let branch = head
+        .is_branch()
+        .then(|| head.shorthand().map(|s| s.to_string()))
+        .flatten();
+    Ok((branch, oid.to_string()))
+}
+
+/// Get branch info with stats for a specific branch
+/// This is used for on-demand caching when status command encounters a new current branch
+pub fn get_branch_info_with_stats(
+    repo_path: &Path,
+    branch_name: &str,
+    user_aliases: &HashMap<String, Vec<String>>,
+    options: &StatsOptions,
+) -> Result<BranchInfo> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+
+    // Find the branch
+    let branch = repo
+        .find_branch(branch_name, BranchType::Local)
+        .with_context(|| format!("Branch '{}' not found", branch_name))?;
+
+    // Get the branch reference
+    let reference = branch.get();
+    let branch_oid = reference
+        .target()
+        .with_context(|| format!("Branch '{}' has no target", branch_name))?;
+
+    // Collect commit stats
+    let (commit_stats, line_stats, latest_author, last_sha, last_updated) =
+        collect_branch_stats(&repo, branch_name, branch_oid, user_aliases, options)?;
+
+    // Calculate owner based on commit stats, or use branch HEAD commit author if no commits
+    let owner = if commit_stats.is_empty() {
+        // No unmerged commits - use the author of the commit the branch points to
+        let branch_head_commit = repo.find_commit(branch_oid)?;
+        let author = branch_head_commit.author();
+        let author_name = author.name().unwrap_or("Unknown");
+        let author_email = author.email().unwrap_or("");
+
+        // Try to normalize using name first, then email if name doesn't match
+        let normalized_name = normalize_author(author_name, user_aliases);
+        if normalized_name == author_name && !author_email.is_empty() {
+            // Name wasn't normalized, try email
+            normalize_author(author_email, user_aliases)
+        } else {
+            normalized_name
+        }
+    } else {
+        // Use commit stats to calculate owner
+        let temp_branch = BranchInfo {
+            name: branch_name.to_string(),
+            owner: String::new(), // Will be calculated
+            last_updated,
+            commit_stats: commit_stats.clone(),
+            line_stats: HashMap::new(),
+            last_commit_sha: Some(last_sha.clone()),
+        };
+        temp_branch.calculate_owner(options, latest_author.as_deref())
+    };
+
+    Ok(BranchInfo {
+        name: branch_name.to_string(),
+        owner,
+        last_updated,
+        commit_stats,
+        line_stats,
+        last_commit_sha: Some(last_sha),
+    })
+}
+
+/// Per-author commit counts and line counts of a branch, the author of its most recent counted commit,
+/// its last commit SHA and time
+type BranchStats = (HashMap<String, usize>, HashMap<String, LineStats>, Option<String>, String, DateTime<Utc>);
+
+/// Collect commit statistics for a branch
+/// Only counts commits that are NOT in the main branch (master/main) and inside the options' time window,
+/// skipping merge commits unless `options.merge_commits` is set and following only first parents with `options.first_parent`
+/// Line counts are only collected when `options.line_stats` is set, since diffing every commit is slow
+/// Returns (commit_stats, line_stats, latest_author, last_commit_sha, last_updated_time)
+fn collect_branch_stats(
+    repo: &Repository,
+    branch_name: &str,
+    branch_oid: Oid,
+    user_aliases: &HashMap<String, Vec<String>>,
+    options: &StatsOptions,
+) -> Result<BranchStats> {
+    let mut commit_stats = HashMap::new();
+    let mut author_lines: HashMap<String, LineStats> = HashMap::new();
+    let mut revwalk = repo.revwalk()?;
+
+    // Start from the branch tip
+    revwalk.push(branch_oid)?;
+    if options.first_parent {
+        // Commits brought in by merges (e.g., main merged into a long-lived branch) aren't the branch's work
+        revwalk.simplify_first_parent()?;
+    }
+
+    // Find and hide commits from main branch (to only count unmerged commits)
+    // Skip this for the main branch itself
+    let main_branch_names = ["master", "main"];
+    if !main_branch_names.contains(&branch_name) {
+        if let Some(main_oid) = find_main_branch(repo) {
+            // Hide all commits in main branch
+            revwalk.hide(main_oid)?;
+        }
+    }
+
+    // Note: We don't use incremental updates (last_commit_sha) for unmerged commits
+    // because the main branch can change, making the old counts invalid.
+    // We always recalculate unmerged commits from scratch.
+
+    // Get the branch HEAD commit for fallback timestamp (used when no unmerged commits)
+    let branch_head_commit = repo.find_commit(branch_oid)?;
+    let branch_head_time = branch_head_commit.time();
+    let branch_head_timestamp =
+        DateTime::from_timestamp(branch_head_time.seconds(), 0).unwrap_or_else(Utc::now);
+
+    let mut last_commit_time = branch_head_timestamp;
+    let mut last_sha = branch_oid.to_string();
+    let mut first_commit = true;
+    let mut latest_author = None;
+
+    // Walk through commits
+    for oid_result in revwalk {
+        let oid = oid_result?;
+        let commit = repo.find_commit(oid)?;
+
+        // Get author name and normalize it
+        let author = commit.author();
+        let author_name = author.name().unwrap_or("Unknown");
+        let author_email = author.email().unwrap_or("");
+
+        // Try to normalize using name first, then email if name doesn't match
+        let normalized_name = normalize_author(author_name, user_aliases);
+        let normalized_name = if normalized_name == author_name && !author_email.is_empty() {
+            // Name wasn't normalized, try email
+            normalize_author(author_email, user_aliases)
+        } else {
+            normalized_name
+        };
+
+        let commit_time = DateTime::from_timestamp(commit.time().seconds(), 0).unwrap_or_else(Utc::now);
+
+        // Capture the time of the first (most recent) unmerged commit, even outside the time window
+        if first_commit {
+            last_commit_time = commit_time;
+            last_sha = oid.to_string();
+            first_commit = false;
+        }
+
+        if !options.in_window(commit_time) {
+            continue;
+        }
+
+        // Merges credit whoever merged them, not the authors of the merged work
+        if !options.merge_commits && commit.parent_count() > 1 {
+            continue;
+        }
+
+        if options.line_stats {
+            let lines = commit_line_stats(repo, &commit)?;
+            author_lines.entry(normalized_name.clone()).or_default().add(lines);
+        }
+
+        if latest_author.is_none() {
+            latest_author = Some(normalized_name.clone());
+        }
+
+        // Increment commit count for this author
+        *commit_stats.entry(normalized_name).or_insert(0) += 1;
+    }
+
+    Ok((commit_stats, author_lines, latest_author, last_sha, last_commit_time))
+}
+
+/// Lines added and removed by a commit relative to its parent
+/// Merge commits count as zero: their changes are already counted in the merged commits
+fn commit_line_stats(repo: &Repository, commit: &git2::Commit) -> Result<LineStats> {
+    if commit.parent_count() > 1 {
+        return Ok(LineStats::default());
+    }
+
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent_count() {
+        1 => Some(commit.parent(0)?.tree()?),
+        _ => None,
+    };
+    let stats = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?
+        .stats()?;
+
+    Ok(LineStats {
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+/// Refresh repository state with commit statistics
+/// Note: Always recalculates from scratch to ensure accurate unmerged commit counts
+pub fn refresh_repo_state(
+    repo_path: &Path,
+    repo_name: &str,
+    previous_state: Option<&RepoState>,
+    user_aliases: &HashMap<String, Vec<String>>,
+    options: &StatsOptions,
+) -> Result<RepoState> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+
+    let current_branch = get_current_branch(&repo)?;
+
+    let mut branches = Vec::new();
+
+    // Get all local branches
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = branch.name()?.unwrap_or("(invalid utf8)").to_string();
+
+        // Get the branch reference
+        let reference = branch.get();
+        let branch_oid = reference.target().context("Branch has no target")?;
+
+        // Collect commit stats (only unmerged commits from main branch)
+        // We always recalculate from scratch since main branch can change
+        let (commit_stats, line_stats, latest_author, last_sha, last_updated) =
+            collect_branch_stats(&repo, &name, branch_oid, user_aliases, options)?;
+
+        // Calculate owner based on commit stats, or use branch HEAD commit author if no commits
+        let owner = if commit_stats.is_empty() {
+            // No unmerged commits - use the author of the commit the branch points to
+            let branch_head_commit = repo.find_commit(branch_oid)?;
+            let author = branch_head_commit.author();
+            let author_name = author.name().unwrap_or("Unknown");
+            let author_email = author.email().unwrap_or("");
+
+            // Try to normalize using name first, then email if name doesn't match
+            let normalized_name = normalize_author(author_name, user_aliases);
+            if normalized_name == author_name && !author_email.is_empty() {
+                // Name wasn't normalized, try email
+                normalize_author(author_email, user_aliases)
+            } else {
+                normalized_name
+            }
+        } else {
+            // Use commit stats to calculate owner
+            let temp_branch = BranchInfo {
+                name: name.clone(),
+                owner: String::new(), // Will be calculated
+                last_updated,
+                commit_stats: commit_stats.clone(),
+                line_stats: HashMap::new(),
+                last_commit_sha: Some(last_sha.clone()),
+            };
+            temp_branch.calculate_owner(options, latest_author.as_deref())
+        };
+
+        branches.push(BranchInfo {
+            name,
+            owner,
+            last_updated,
+            commit_stats,
+            line_stats,
+            last_commit_sha: Some(last_sha),
+        });
+    }
+
+    // Sort branches by last updated (most recent first)
+    branches.sort_by_key(|b| std::cmp::Reverse(b.last_updated));
+
+    let last_updated = branches
+        .first()
+        .map(|b| b.last_updated)
+        .unwrap_or_else(Utc::now);
+
+    Ok(RepoState {
+        name: repo_name.to_string(),
+        worktree: Some(get_worktree_status(repo_path, &current_branch)),
+        current_branch,
+        last_updated,
+        branches,
+        refreshed_at: Some(Utc::now()),
+        stats_options: *options,
+        // CI results don't depend on the statistics
+        ci: previous_state.map(|s| s.ci.clone()).unwrap_or_default(),
+    })
+}
+
+/// Build proxy options for a remote URL
+/// Priority: config `proxy` (per host, then workspace-wide), then HTTPS_PROXY/HTTP_PROXY/ALL_PROXY
+/// environment variables (honoring NO_PROXY), then git's own `http.proxy` setting
+fn proxy_options_for(
+    proxy: Option<&ProxyConfig>,
+    remote_url: &str,
+) -> git2::ProxyOptions<'static> {
+    let mut options = git2::ProxyOptions::new();
+
+    // libgit2 only proxies HTTP(S) transports
+    let is_https = remote_url.starts_with("https://");
+    if !is_https && !remote_url.starts_with("http://") {
+        return options;
+    }
+
+    let host = extract_hostname(remote_url).unwrap_or_default();
+    let env_var = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()))
+    };
+
+    let proxy_url = match proxy.and_then(|p| p.proxy_for_host(&host)) {
+        Some(url) => Some(url),
+        None if env_var(&["NO_PROXY", "no_proxy"])
+            .is_some_and(|list| matches_no_proxy(&list, &host)) =>
+        {
+            Some(String::new())
+        }
+        None if is_https => env_var(&["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]),
+        None => env_var(&["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]),
+    };
+
+    match proxy_url {
+        Some(url) if url.is_empty() => {
+            debug!("Proxy: none (excluded by no_proxy)");
+        }
+        Some(url) => {
+            debug!("Proxy: {}", url);
+            options.url(&url);
+        }
+        None => {
+            debug!("Proxy: auto (git http.proxy)");
+            options.auto();
+        }
+    }
+
+    options
+}
+
+/// Register the configured CA certificates with libgit2
+/// CA locations are process-wide settings, they are applied before each network operation
+fn apply_tls_config(tls: Option<&TlsConfig>) -> Result<()> {
+    let Some(tls) = tls else {
+        return Ok(());
+    };
+
+    // The settings are process-wide and identical for every repository: apply them once,
+    // before the first connection, while other threads wait
+    let mut applied = TLS_APPLIED.lock().unwrap_or_else(|e| e.into_inner());
+    if *applied {
+        return Ok(());
+    }
+
+    if let Some(ca_file) = &tls.ca_file {
+        let path = expand_home(ca_file);
+        if !path.is_file() {
+            return Err(anyhow!("CA certificate file not found: {}", path.display()));
+        }
+        debug!("TLS CA file: {}", path.display());
+        // SAFETY: guarded by TLS_APPLIED, no connection has been opened with TLS settings yet
+        unsafe { git2::opts::set_ssl_cert_file(path.as_path()) }.map_err(|e| {
+            anyhow!("Failed to load CA certificate file {}: {}", path.display(), e.message())
+        })?;
+    }
+
+    if let Some(ca_dir) = &tls.ca_dir {
+        let path = expand_home(ca_dir);
+        if !path.is_dir() {
+            return Err(anyhow!("CA certificate directory not found: {}", path.display()));
+        }
+        debug!("TLS CA directory: {}", path.display());
+        // SAFETY: see above
+        unsafe { git2::opts::set_ssl_cert_dir(path.as_path()) }.map_err(|e| {
+            anyhow!("Failed to load CA certificate directory {}: {}", path.display(), e.message())
+        })?;
+    }
+
+    *applied = true;
+    Ok(())
+}
+
+/// Accept certificates of hosts listed in `tls.insecure_hosts` without verification
+/// Other hosts use libgit2's built-in checks
+fn add_certificate_check<'a>(
+    callbacks: &mut RemoteCallbacks<'a>,
+    tls: Option<&'a TlsConfig>,
+) {
+    let Some(tls) = tls.filter(|t| !t.insecure_hosts.is_empty()) else {
+        return;
+    };
+
+    callbacks.certificate_check(move |_cert, host| {
+        if tls.insecure_hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+            debug!("TLS: skipping certificate verification for {}", host);
+            Ok(git2::CertificateCheckStatus::CertificateOk)
+        } else {
+            Ok(git2::CertificateCheckStatus::CertificatePassthrough)
+        }
+    });
+}
+
+/// Turn TLS certificate errors from libgit2 into an actionable message
+fn explain_network_error(error: git2::Error, remote_url: &str) -> anyhow::Error {
+    if operations_cancelled() {
+        return anyhow!("Interrupted");
+    }
+    let is_tls_error = error.class() == git2::ErrorClass::Ssl
+        || error.code() == git2::ErrorCode::Certificate;
+    if !is_tls_error {
+        return error.into();
+    }
+
+    let host = extract_hostname(remote_url).unwrap_or_else(|| remote_url.to_string());
+    anyhow!(
+        "TLS certificate verification failed for {}: {}\n\
+         If the server uses a private CA, add it to .mgitconfig.yaml:\n\
+         tls:\n  ca_file: ~/certs/company-ca.pem",
+        host,
+        error.message()
+    )
+}
+
+/// Build the credentials map for a repository: the configured map, with the
+/// repository's own SSH key (if any) under its full URL, so it beats every prefix
+fn credentials_for_repo(
+    credentials: &HashMap<String, String>,
+    repo_credential: Option<&RepoCredential>,
+    remote_url: &str,
+) -> HashMap<String, String> {
+    let mut credentials = credentials.clone();
+    if let Some(ssh_key) = repo_credential.and_then(|c| c.ssh_key.as_ref()) {
+        if let Some((host, path)) = url_host_and_path(remote_url) {
+            credentials.insert(credential_keys(&host, &path).remove(0), ssh_key.clone());
+        }
+    }
+    credentials
+}
+
+/// Open the "origin" remote for a network operation
+/// If ~/.ssh/config maps the URL's host alias to another HostName/Port, or `ssh_ports` gives
+/// the host's port, an anonymous remote with the resolved URL is returned instead
+/// (libssh2 doesn't read ~/.ssh/config)
+/// Returns (remote, is_anonymous)
+fn open_origin<'r>(
+    repo: &'r Repository,
+    remote_url: &str,
+    ssh_ports: &HashMap<String, u16>,
+) -> Result<(git2::Remote<'r>, bool)> {
+    match resolve_ssh_url(remote_url, ssh_ports) {
+        Some(resolved_url) => {
+            debug!("Resolved via ~/.ssh/config and ssh_ports: {}", resolved_url);
+            Ok((repo.remote_anonymous(&resolved_url)?, true))
+        }
+        None => Ok((repo.find_remote("origin")?, false)),
+    }
+}
+
+/// A commit brought in by a pull
+#[derive(Debug, Clone)]
+pub struct PulledCommit {
+    pub short_sha: String,
+    pub summary: String,
+}
+
+/// Outcome of [`pull_repo`]
+#[derive(Debug, Clone)]
+pub struct PullOutcome {
+    /// What happened ("Already up-to-date", "Fast-forwarded", ...)
+    pub message: String,
+    /// Commits brought in, newest first
+    pub new_commits: Vec<PulledCommit>,
+    /// Remote-tracking branches removed because their branch was deleted on origin
+    pub pruned: Vec<String>,
+}
+
+impl PullOutcome {
+    fn status(message: &str) -> Self {
+        Self { message: message.to_string(), new_commits: Vec::new(), pruned: Vec::new() }
+    }
+
+    /// One-line description, e.g. "Fast-forwarded, 3 new commits, pruned 1 remote branch"
+    pub fn summary(&self) -> String {
+        let mut summary = match self.new_commits.len() {
+            0 => self.message.clone(),
+            1 => format!("{}, 1 new commit", self.message),
+            count => format!("{}, {} new commits", self.message, count),
+        };
+        match self.pruned.len() {
+            0 => {}
+            1 => summary.push_str(", pruned 1 remote branch"),
+            count => summary.push_str(&format!(", pruned {} remote branches", count)),
+        }
+        summary
+    }
+}
+
+/// Names of the remote-tracking branches of origin (e.g., "origin/main")
+fn origin_tracking_branches(repo: &Repository) -> HashSet<String> {
+    repo.references_glob("refs/remotes/origin/*")
+        .map(|refs| {
+            refs.flatten()
+                .filter_map(|r| r.shorthand().map(str::to_string))
+                .filter(|name| name != "origin/HEAD")
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Delete the remote-tracking branches of origin whose branch is not in `remote_branches`
+fn prune_tracking_branches(repo: &Repository, remote_branches: &HashSet<String>) -> Result<()> {
+    let stale: Vec<String> = repo
+        .references_glob("refs/remotes/origin/*")?
+        .flatten()
+        .filter_map(|r| r.name().map(str::to_string))
+        .filter(|name| {
+            let branch = &name["refs/remotes/origin/".len()..];
+            branch != "HEAD" && !remote_branches.contains(branch)
+        })
+        .collect();
+    for name in stale {
+        repo.find_reference(&name)?.delete()?;
+    }
+    Ok(())
+}
+
+/// Name of a repository in log spans: its directory name
+fn repo_label(repo_path: &Path) -> String {
+    repo_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| repo_path.display().to_string())
+}
+
+/// Fetch the current branch from origin and fast-forward to it
+/// With `prune`, remote-tracking branches of branches deleted on origin are removed.
+#[instrument(skip_all, fields(repo = %repo_label(repo_path)))]
+pub fn pull_repo(
+    config: &Config,
+    repo_path: &Path,
+    repo_credential: Option<&RepoCredential>,
+    prune: bool,
+) -> Result<PullOutcome> {
+    let repo = Repository::open(repo_path)?;
+    let tracking_before = if prune { origin_tracking_branches(&repo) } else { HashSet::new() };
+    let mut outcome = fetch_and_fast_forward(config, repo_path, &repo, repo_credential, prune)?;
+    if prune {
+        let tracking_after = origin_tracking_branches(&repo);
+        outcome.pruned = tracking_before.difference(&tracking_after).cloned().collect();
+        outcome.pruned.sort();
+    }
+    Ok(outcome)
+}
+
+fn fetch_and_fast_forward(
+    config: &Config,
+    repo_path: &Path,
+    repo: &Repository,
+    repo_credential: Option<&RepoCredential>,
+    prune: bool,
+) -> Result<PullOutcome> {
+    // Get the current branch
+    let branch_name = get_current_branch(repo)?;
+
+    debug!("Repository: {:?}", repo_path);
+    debug!("Current branch: {}", branch_name);
+
+    if config.backend == GitBackend::Cli {
+        debug!("Backend: git CLI");
+        run_git_network(
+            repo_path,
+            &["fetch", "--quiet", "origin", &branch_name],
+            config.ssh_multiplex,
+        )?;
+        // `fetch --prune` with a branch only prunes that branch
+        if prune {
+            run_git_network(repo_path, &["remote", "prune", "origin"], config.ssh_multiplex)?;
+        }
+        return fast_forward_to_fetch_head(repo, &branch_name);
+    }
+
+    // Get remote URL
+    let remote = repo.find_remote("origin")?;
+    let remote_url = remote.url().unwrap_or("");
+
+    debug!("Remote URL: {}", remote_url);
+
+    // Apply the repository's SSH key override on top of the host-level credentials
+    let credentials = credentials_for_repo(&config.credentials, repo_credential, remote_url);
+
+    // Validate SSH authentication early to provide helpful error messages
+    validate_ssh_auth(remote_url, &credentials)?;
+
+    // Setup SSH callbacks for fetch
+    apply_tls_config(config.tls.as_ref())?;
+    let attempt = CredentialAttempt::default();
+    let mut callbacks = create_remote_callbacks(
+        &credentials,
+        repo_credential,
+        remote_url,
+        config.credential_prompt,
+        &attempt,
+    );
+    add_certificate_check(&mut callbacks, config.tls.as_ref());
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    fetch_options.proxy_options(proxy_options_for(config.proxy.as_ref(), remote_url));
+
+    debug!("Starting fetch operation...");
+
+    // Fetch (anonymous remotes need an explicit refspec to update origin/<branch>)
+    let (mut remote, anonymous) = open_origin(repo, remote_url, &config.ssh_ports)?;
+    if anonymous {
+        let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch_name);
+        remote
+            .fetch(&[refspec.as_str()], Some(&mut fetch_options), None)
+            .map_err(|e| explain_network_error(e, remote_url))?;
+    } else {
+        remote
+            .fetch(&[branch_name.as_str()], Some(&mut fetch_options), None)
+            .map_err(|e| explain_network_error(e, remote_url))?;
+    }
+    attempt.succeeded();
+    if prune {
+        // libgit2 only prunes refs matching the fetched refspecs, so compare with the full ref list
+        let mut callbacks = create_remote_callbacks(
+            &credentials,
+            repo_credential,
+            remote_url,
+            config.credential_prompt,
+            &attempt,
+        );
+        add_certificate_check(&mut callbacks, config.tls.as_ref());
+        let proxy = proxy_options_for(config.proxy.as_ref(), remote_url);
+        let connection = remote
+            .connect_auth(git2::Direction::Fetch, Some(callbacks), Some(proxy))
+            .map_err(|e| explain_network_error(e, remote_url))?;
+        let remote_branches: HashSet<String> = connection
+            .list()?
+            .iter()
+            .filter_map(|head| head.name().strip_prefix("refs/heads/").map(str::to_string))
+            .collect();
+        drop(connection);
+        prune_tracking_branches(repo, &remote_branches)?;
+    }
+
+    fast_forward_to_fetch_head(repo, &branch_name)
+}
+
+/// Merge FETCH_HEAD into the current branch when it is a fast-forward
+fn fast_forward_to_fetch_head(repo: &Repository, branch_name: &str) -> Result<PullOutcome> {
+    if operations_cancelled() {
+        return Err(anyhow!("Interrupted after the fetch, {} left as it was", branch_name));
+    }
+
+    // Get fetch head
+    let fetch_head = repo.find_reference("FETCH_HEAD")?;
+    let fetch_commit = repo.reference_to_annotated_commit(&fetch_head)?;
+
+    // Merge
+    let analysis = repo.merge_analysis(&[&fetch_commit])?;
+
+    if analysis.0.is_up_to_date() {
+        return Ok(PullOutcome::status("Already up-to-date"));
+    } else if analysis.0.is_fast_forward() {
+        // Fast-forward merge
+        let refname = format!("refs/heads/{}", branch_name);
+        let mut reference = repo.find_reference(&refname)?;
+        let new_commits = match reference.target() {
+            Some(old_head) => commits_between(repo, old_head, fetch_commit.id())?,
+            None => Vec::new(),
+        };
+        reference.set_target(fetch_commit.id(), "Fast-forward")?;
+        repo.set_head(&refname)?;
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
+        return Ok(PullOutcome { message: "Fast-forwarded".to_string(), new_commits, pruned: Vec::new() });
+    } else if analysis.0.is_normal() {
+        return Ok(PullOutcome::status("Normal merge required (not implemented)"));
+    }
+
+    Ok(PullOutcome::status("Unknown state"))
+}
+
+/// Commits reachable from `to` but not from `from`, newest first
+fn commits_between(repo: &Repository, from: Oid, to: Oid) -> Result<Vec<PulledCommit>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk.push(to)?;
+    revwalk.hide(from)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk.flatten() {
+        let commit = repo.find_commit(oid)?;
+        commits.push(PulledCommit {
+            short_sha: oid.to_string()[..7].to_string(),
+            summary: commit.summary().unwrap_or("").to_string(),
+        });
+    }
+    Ok(commits)
+}
+
+/// Outcome of [`push_repo`]
+#[derive(Debug, Clone)]
+pub struct PushOutcome {
+    pub branch: String,
+    /// The branch did not exist on origin before the push
+    pub created: bool,
+    /// Commits the remote did not have before the push
+    pub commits: usize,
+}
+
+impl PushOutcome {
+    /// One-line description, e.g. "Pushed main, 3 new commits"
+    pub fn summary(&self) -> String {
+        let commits = match self.commits {
+            1 => "1 new commit".to_string(),
+            count => format!("{} new commits", count),
+        };
+        if self.created {
+            format!("Created {} on origin, {}", self.branch, commits)
+        } else if self.commits == 0 {
+            "Already up-to-date".to_string()
+        } else {
+            format!("Pushed {}, {}", self.branch, commits)
+        }
+    }
+}
+
+/// Remote ref before and after a push, as reported by the remote
+#[derive(Debug, Clone, Copy, Default)]
+struct RefUpdate {
+    /// None when the ref was created
+    old: Option<Oid>,
+    /// None when the ref was deleted
+    new: Option<Oid>,
+}
+
+#[instrument(skip_all, fields(repo = %repo_label(repo_path)))]
+pub fn push_repo(config: &Config, repo_path: &Path, repo_credential: Option<&RepoCredential>) -> Result<PushOutcome> {
+    let repo = Repository::open(repo_path)?;
+
+    let branch_name = get_current_branch(&repo)?;
+
+    debug!("Repository: {:?}", repo_path);
+    debug!("Current branch: {}", branch_name);
+
+    // The remote-tracking branch already points to the pushed commit, so count against the
+    // old remote commit, or against the other remote branches for a new branch
+    let tracking = format!("refs/remotes/origin/{}", branch_name);
+    let others: Vec<Oid> = repo
+        .references_glob("refs/remotes/origin/*")?
+        .flatten()
+        .filter(|r| r.name() != Some(tracking.as_str()))
+        .filter_map(|r| r.target())
+        .collect();
+
+    let update = push_refspec(config, &repo, &format!("refs/heads/{}", branch_name), repo_credential)?;
+    let commits = match update.new {
+        Some(new) if update.old != Some(new) => {
+            let hide = update.old.map(|old| vec![old]).unwrap_or(others);
+            count_commits(&repo, new, &hide)?
+        }
+        _ => 0,
+    };
+    debug!("Remote ref: {:?} -> {:?}, {} new commits", update.old, update.new, commits);
+
+    Ok(PushOutcome {
+        branch: branch_name,
+        created: update.old.is_none(),
+        commits,
+    })
+}
+
+/// Number of commits reachable from `to` but not from any of `hide`
+fn count_commits(repo: &Repository, to: Oid, hide: &[Oid]) -> Result<usize> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(to)?;
+    for oid in hide {
+        // Commits we don't have locally can't hide anything
+        let _ = revwalk.hide(*oid);
+    }
+    Ok(revwalk.count())
+}
+
+/// How a branch compares to the base branch a pull request would target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseComparison {
+    /// Base branch: the requested one, or origin's default branch (master/main)
+    pub base: String,
+    /// Commits of the branch that are not in the base
+    pub ahead: usize,
+    /// Whether origin has the branch with all its local commits
+    pub pushed: bool,
+}
+
+/// Compare a local branch with `base` (or the default branch), on origin when fetched, else locally
+pub fn compare_with_base(repo_path: &Path, branch: &str, base: Option<&str>) -> Result<BaseComparison> {
+    let repo = Repository::open(repo_path)?;
+    let tip = repo
+        .find_reference(&format!("refs/heads/{}", branch))?
+        .target()
+        .ok_or_else(|| anyhow!("Branch '{}' has no target", branch))?;
+    let branch_tip = |name: &str| {
+        [format!("refs/remotes/origin/{}", name), format!("refs/heads/{}", name)]
+            .iter()
+            .find_map(|r| repo.find_reference(r).ok().and_then(|r| r.target()))
+    };
+
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => repo
+            .find_reference("refs/remotes/origin/HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(|t| t.trim_start_matches("refs/remotes/origin/").to_string()))
+            .or_else(|| ["main", "master"].iter().find(|b| branch_tip(b).is_some()).map(|b| b.to_string()))
+            .ok_or_else(|| anyhow!("No main or master branch found"))?,
+    };
+    let base_tip = branch_tip(&base).ok_or_else(|| anyhow!("Base branch '{}' not found", base))?;
+    let pushed = match repo.find_reference(&format!("refs/remotes/origin/{}", branch)).ok().and_then(|r| r.target()) {
+        Some(remote) => count_commits(&repo, tip, &[remote])? == 0,
+        None => false,
+    };
+
+    Ok(BaseComparison { ahead: count_commits(&repo, tip, &[base_tip])?, base, pushed })
+}
+
+/// Fetch a branch from `origin` into `refs/remotes/origin/<branch>` without touching local branches
+#[instrument(skip_all, fields(repo = %repo_label(repo_path), branch = %branch))]
+pub fn fetch_remote_branch(
+    config: &Config,
+    repo_path: &Path,
+    branch: &str,
+    repo_credential: Option<&RepoCredential>,
+) -> Result<()> {
+    let repo = Repository::open(repo_path)?;
+    let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch);
+
+    debug!("Repository: {:?}", repo_path);
+    debug!("Fetching branch: {}", branch);
+
+    if config.backend == GitBackend::Cli {
+        debug!("Backend: git CLI");
+        run_git_network(repo_path, &["fetch", "--quiet", "origin", &refspec], config.ssh_multiplex)?;
+        return Ok(());
+    }
+
+    let remote = repo.find_remote("origin")?;
+    let remote_url = remote.url().unwrap_or("");
+    let credentials = credentials_for_repo(&config.credentials, repo_credential, remote_url);
+    validate_ssh_auth(remote_url, &credentials)?;
+
+    apply_tls_config(config.tls.as_ref())?;
+    let attempt = CredentialAttempt::default();
+    let mut callbacks = create_remote_callbacks(
+        &credentials,
+        repo_credential,
+        remote_url,
+        config.credential_prompt,
+        &attempt,
+    );
+    add_certificate_check(&mut callbacks, config.tls.as_ref());
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    fetch_options.proxy_options(proxy_options_for(config.proxy.as_ref(), remote_url));
+
+    let (mut remote, _) = open_origin(&repo, remote_url, &config.ssh_ports)?;
+    remote
+        .fetch(&[refspec.as_str()], Some(&mut fetch_options), None)
+        .map_err(|e| explain_network_error(e, remote_url))?;
+    attempt.succeeded();
+
+    Ok(())
+}
+
+/// What deleting a branch on `origin` would lose, judged from its remote-tracking branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteBranchState {
+    /// There is no `origin/<branch>`
+    Missing,
+    /// `origin/<branch>` points at the local branch or is merged into master/main
+    Merged(Oid),
+    /// `origin/<branch>` has commits that are neither on the local branch nor in master/main
+    Diverged(Oid),
+}
+
+/// Compare `origin/<branch>` with the local branch and master/main (fetch it first to be current)
+pub fn remote_branch_state(repo_path: &Path, branch: &str) -> Result<RemoteBranchState> {
+    let repo = Repository::open(repo_path)?;
+    let Some(remote) = repo
+        .find_reference(&format!("refs/remotes/origin/{}", branch))
+        .ok()
+        .and_then(|r| r.target())
+    else {
+        return Ok(RemoteBranchState::Missing);
+    };
+    let local = repo.find_reference(&format!("refs/heads/{}", branch)).ok().and_then(|r| r.target());
+    let merged = match find_main_branch(&repo) {
+        Some(main) => main == remote || repo.graph_descendant_of(main, remote)?,
+        None => false,
+    };
+    if local == Some(remote) || merged {
+        Ok(RemoteBranchState::Merged(remote))
+    } else {
+        Ok(RemoteBranchState::Diverged(remote))
+    }
+}
+
+/// Delete a branch on `origin`, provided it still points to `expected` (see [`remote_branch_state`])
+/// Commits pushed to the branch since it was fetched are never deleted with it.
+#[instrument(skip_all, fields(repo = %repo_label(repo_path), branch = %branch))]
+pub fn delete_remote_branch(
+    config: &Config,
+    repo_path: &Path,
+    branch: &str,
+    expected: Oid,
+    repo_credential: Option<&RepoCredential>,
+) -> Result<()> {
+    let repo = Repository::open(repo_path)?;
+
+    debug!("Repository: {:?}", repo_path);
+    debug!("Deleting remote branch: {} (expected at {})", branch, expected);
+
+    let remote_ref = format!("refs/heads/{}", branch);
+    push_refspecs(config, &repo, &[&format!(":{}", remote_ref)], &[(&remote_ref, expected)], repo_credential)?;
+
+    // The CLI backend prunes the tracking branch itself; make sure libgit2 pushes do too
+    if let Ok(mut tracking) = repo.find_reference(&format!("refs/remotes/origin/{}", branch)) {
+        tracking.delete()?;
+    }
+    Ok(())
+}
+
+/// Push a tag to origin
+#[instrument(skip_all, fields(repo = %repo_label(repo_path), tag = %tag))]
+pub fn push_tag(config: &Config, repo_path: &Path, tag: &str, repo_credential: Option<&RepoCredential>) -> Result<()> {
+    let repo = Repository::open(repo_path)?;
+
+    debug!("Repository: {:?}", repo_path);
+    debug!("Tag: {}", tag);
+
+    push_refspec(config, &repo, &format!("refs/tags/{}", tag), repo_credential)?;
+    Ok(())
+}
+
+/// Push all local tags (annotated and lightweight) to origin
+/// Returns the names of the tags origin did not have yet.
+#[instrument(skip_all, fields(repo = %repo_label(repo_path)))]
+pub fn push_tags(config: &Config, repo_path: &Path, repo_credential: Option<&RepoCredential>) -> Result<Vec<String>> {
+    let repo = Repository::open(repo_path)?;
+    let refspecs: Vec<String> = repo
+        .tag_names(None)?
+        .iter()
+        .flatten()
+        .map(|tag| format!("refs/tags/{}", tag))
+        .collect();
+
+    debug!("Repository: {:?}", repo_path);
+    debug!("Tags: {}", refspecs.len());
+
+    if refspecs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let refspecs: Vec<&str> = refspecs.iter().map(String::as_str).collect();
+    let mut created: Vec<String> = push_refspecs(config, &repo, &refspecs, &[], repo_credential)?
+        .into_iter()
+        .filter(|(_, update)| update.old.is_none() && update.new.is_some())
+        .filter_map(|(name, _)| name.strip_prefix("refs/tags/").map(str::to_string))
+        .collect();
+    created.sort();
+    Ok(created)
+}
+
+/// Checkout of a submodule compared to the commit recorded by its parent repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmoduleState {
+    /// Not cloned yet
+    Uninitialized,
+    /// Checked out at another commit than the recorded one
+    OutOfDate,
+    UpToDate,
+}
+
+impl SubmoduleState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SubmoduleState::Uninitialized => "not initialized",
+            SubmoduleState::OutOfDate => "out of date",
+            SubmoduleState::UpToDate => "up to date",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubmoduleStatus {
+    /// Path relative to the top-level repository (e.g., "vendor/lib" or "vendor/lib/deps/zlib")
+    pub path: String,
+    pub state: SubmoduleState,
+}
+
+/// State of a repository's submodules, including the submodules of initialized submodules
+pub fn submodule_status(repo_path: &Path) -> Result<Vec<SubmoduleStatus>> {
+    let repo = Repository::open(repo_path)?;
+    let mut statuses = Vec::new();
+    collect_submodule_status(&repo, "", &mut statuses)?;
+    Ok(statuses)
+}
+
+fn collect_submodule_status(repo: &Repository, prefix: &str, statuses: &mut Vec<SubmoduleStatus>) -> Result<()> {
+    for submodule in repo.submodules()? {
+        let path = format!("{}{}", prefix, submodule.path().display());
+        let nested = submodule.open().ok();
+        let state = match (submodule.head_id(), submodule.workdir_id()) {
+            _ if nested.is_none() => SubmoduleState::Uninitialized,
+            (Some(recorded), Some(checked_out)) if recorded != checked_out => SubmoduleState::OutOfDate,
+            _ => SubmoduleState::UpToDate,
+        };
+        statuses.push(SubmoduleStatus { path: path.clone(), state });
+        if let Some(nested) = nested {
+            collect_submodule_status(&nested, &format!("{}/", path), statuses)?;
+        }
+    }
+    Ok(())
+}
+
+/// Initialize and update a repository's submodules, recursively, to the commits it records
+/// Returns the paths of the submodules that were initialized or moved.
+#[instrument(skip_all, fields(repo = %repo_label(repo_path)))]
+pub fn update_submodules(
+    config: &Config,
+    repo_path: &Path,
+    repo_credential: Option<&RepoCredential>,
+) -> Result<Vec<String>> {
+    let before = submodule_status(repo_path)?;
+
+    debug!("Repository: {:?}", repo_path);
+
+    if config.backend == GitBackend::Cli {
+        debug!("Backend: git CLI");
+        run_git_network(
+            repo_path,
+            &["submodule", "update", "--init", "--recursive", "--quiet"],
+            config.ssh_multiplex,
+        )?;
+    } else {
+        let repo = Repository::open(repo_path)?;
+        let origin_url = repo.find_remote("origin").ok().and_then(|r| r.url().map(str::to_string));
+        update_submodules_recursive(&repo, origin_url.as_deref().unwrap_or(""), config, repo_credential)?;
+    }
+
+    let changed = submodule_status(repo_path)?
+        .into_iter()
+        .filter(|after| {
+            let previous = before.iter().find(|b| b.path == after.path).map(|b| b.state);
+            after.state == SubmoduleState::UpToDate && previous != Some(SubmoduleState::UpToDate)
+        })
+        .map(|after| after.path)
+        .collect();
+    Ok(changed)
+}
+
+fn update_submodules_recursive(
+    repo: &Repository,
+    parent_url: &str,
+    config: &Config,
+    repo_credential: Option<&RepoCredential>,
+) -> Result<()> {
+    for mut submodule in repo.submodules()? {
+        // Relative submodule URLs ("../lib.git") live on the parent's host
+        let url = match submodule.url() {
+            Some(url) if !url.starts_with('.') => url.to_string(),
+            _ => parent_url.to_string(),
+        };
+        debug!("Submodule: {} ({})", submodule.path().display(), url);
+
+        let credentials = credentials_for_repo(&config.credentials, repo_credential, &url);
+        apply_tls_config(config.tls.as_ref())?;
+        let attempt = CredentialAttempt::default();
+        let mut callbacks = create_remote_callbacks(
+            &credentials,
+            repo_credential,
+            &url,
+            config.credential_prompt,
+            &attempt,
+        );
+        add_certificate_check(&mut callbacks, config.tls.as_ref());
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(callbacks);
+        fetch_options.proxy_options(proxy_options_for(config.proxy.as_ref(), &url));
+
+        let mut update_options = git2::SubmoduleUpdateOptions::new();
+        update_options.fetch(fetch_options);
+        let path = submodule.path().display().to_string();
+        relink_submodule(repo, &submodule)?;
+        submodule
+            .update(true, Some(&mut update_options))
+            .map_err(|e| anyhow!("Could not update submodule {}: {}", path, explain_network_error(e, &url)))?;
+        attempt.succeeded();
+
+        let nested = submodule.open()?;
+        update_submodules_recursive(&nested, &url, config, repo_credential)?;
+    }
+    Ok(())
+}
+
+/// Link a submodule cloned before into `.git/modules` back to its directory when that is gone
+/// (e.g., removed by a checkout); libgit2 refuses to initialize such a submodule again
+fn relink_submodule(repo: &Repository, submodule: &git2::Submodule) -> Result<()> {
+    let (Some(name), Some(workdir)) = (submodule.name(), repo.workdir()) else {
+        return Ok(());
+    };
+    let clone_dir = repo.path().join("modules").join(name);
+    let submodule_dir = workdir.join(submodule.path());
+    if !clone_dir.exists() || submodule_dir.join(".git").exists() {
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&submodule_dir)?;
+    std::fs::write(submodule_dir.join(".git"), format!("gitdir: {}\n", clone_dir.display()))?;
+    let clone = Repository::open(&clone_dir)?;
+    clone.set_workdir(&submodule_dir, false)?;
+    // The directory is new, nothing to overwrite
+    clone.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
+    Ok(())
+}
+
+/// Push a single ref (e.g., "refs/heads/main") to origin with the configured backend
+/// Returns the remote ref before and after the push; a rejected ref is an error.
+fn push_refspec(
+    config: &Config,
+    repo: &Repository,
+    refspec: &str,
+    repo_credential: Option<&RepoCredential>,
+) -> Result<RefUpdate> {
+    let updates = push_refspecs(config, repo, &[refspec], &[], repo_credential)?;
+    Ok(updates.into_iter().next().map(|(_, update)| update).unwrap_or_default())
+}
+
+/// Push several refs to origin in one connection
+/// `leases` are remote refs with the commit they must still point to (like `--force-with-lease`):
+/// the push is refused when one of them moved.
+/// Returns the remote refs that changed (libgit2) or were reported (git CLI), by remote ref name.
+fn push_refspecs(
+    config: &Config,
+    repo: &Repository,
+    refspecs: &[&str],
+    leases: &[(&str, Oid)],
+    repo_credential: Option<&RepoCredential>,
+) -> Result<Vec<(String, RefUpdate)>> {
+    if config.backend == GitBackend::Cli {
+        debug!("Backend: git CLI");
+        let workdir = repo.workdir().unwrap_or(repo.path());
+        let leases: Vec<String> = leases
+            .iter()
+            .map(|(name, oid)| format!("--force-with-lease={}:{}", name, oid))
+            .collect();
+        let mut args = vec!["push", "--porcelain"];
+        args.extend(leases.iter().map(String::as_str));
+        args.push("origin");
+        args.extend_from_slice(refspecs);
+        let output = run_git_network(workdir, &args, config.ssh_multiplex)?;
+        return Ok(parse_porcelain_push(repo, &output));
+    }
+
+    // Get remote URL
+    let remote = repo.find_remote("origin")?;
+    let remote_url = remote.url().unwrap_or("");
+
+    debug!("Remote URL: {}", remote_url);
+
+    // Apply the repository's SSH key override on top of the host-level credentials
+    let credentials = credentials_for_repo(&config.credentials, repo_credential, remote_url);
+
+    // Validate SSH authentication early to provide helpful error messages
+    validate_ssh_auth(remote_url, &credentials)?;
+
+    // Filled in by the push callbacks
+    let updates = RefCell::new(Vec::new());
+    let rejected = RefCell::new(None);
+
+    // Setup SSH callbacks for push
+    apply_tls_config(config.tls.as_ref())?;
+    let attempt = CredentialAttempt::default();
+    let mut callbacks = create_remote_callbacks(
+        &credentials,
+        repo_credential,
+        remote_url,
+        config.credential_prompt,
+        &attempt,
+    );
+    add_certificate_check(&mut callbacks, config.tls.as_ref());
+    // The last point at which a push can be stopped: nothing is sent before it returns
+    callbacks.push_negotiation(|negotiated| {
+        if operations_cancelled() {
+            return Err(git2::Error::from_str("Interrupted before the push"));
+        }
+        for update in negotiated {
+            let name = update.dst_refname().unwrap_or("");
+            if let Some((_, expected)) = leases.iter().find(|(lease, _)| *lease == name) {
+                if update.src() != *expected {
+                    return Err(git2::Error::from_str(&format!(
+                        "{} changed on origin since it was fetched (now {}), not pushed",
+                        name,
+                        update.src()
+                    )));
+                }
+            }
+        }
+        updates.borrow_mut().extend(negotiated.iter().map(|u| {
+            let update = RefUpdate {
+                old: Some(u.src()).filter(|oid| !oid.is_zero()),
+                new: Some(u.dst()).filter(|oid| !oid.is_zero()),
+            };
+            (u.dst_refname().unwrap_or("").to_string(), update)
+        }));
+        Ok(())
+    });
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(status) = status {
+            *rejected.borrow_mut() = Some(format!("{} rejected by origin: {}", refname, status));
+        }
+        Ok(())
+    });
+
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+    push_options.proxy_options(proxy_options_for(config.proxy.as_ref(), remote_url));
+
+    debug!("Starting push operation...");
+
+    let (mut remote, anonymous) = open_origin(repo, remote_url, &config.ssh_ports)?;
+
+    remote
+        .push(refspecs, Some(&mut push_options))
+        .map_err(|e| explain_network_error(e, remote_url))?;
+    attempt.succeeded();
+    if let Some(rejected) = rejected.take() {
+        return Err(anyhow!(rejected));
+    }
+
+    // Anonymous remotes don't update remote-tracking refs, do it ourselves
+    for refspec in refspecs.iter().filter(|_| anonymous) {
+        let Some(branch_name) = refspec.strip_prefix("refs/heads/") else {
+            continue;
+        };
+        if let Ok(local) = repo.find_reference(refspec) {
+            if let Some(oid) = local.target() {
+                repo.reference(
+                    &format!("refs/remotes/origin/{}", branch_name),
+                    oid,
+                    true,
+                    "mgit push: update remote-tracking branch",
+                )?;
+            }
+        }
+    }
+
+    let updates = updates.take();
+    Ok(updates)
+}
+
+/// Read the ref updates from `git push --porcelain` output
+/// Lines look like `<flag>\t<src>:<dst>\t<summary>`: `*` creates the ref, `-` deletes it,
+/// `=` leaves it up to date and ` `/`+` update it, with an `old..new` summary.
+fn parse_porcelain_push(repo: &Repository, output: &str) -> Vec<(String, RefUpdate)> {
+    let resolve = |rev: &str| repo.revparse_single(rev).ok().map(|o| o.id());
+    let mut updates = Vec::new();
+    for line in output.lines() {
+        let mut fields = line.splitn(3, '\t');
+        let (Some(flag), Some(refs), Some(summary)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let (src, dst) = refs.split_once(':').unwrap_or((refs, refs));
+        let update = match flag {
+            "*" => RefUpdate { old: None, new: resolve(src) },
+            "-" => RefUpdate { old: None, new: None },
+            "=" => {
+                let oid = resolve(src);
+                RefUpdate { old: oid, new: oid }
+            }
+            _ => {
+                let range = summary.split(' ').next().unwrap_or("");
+                let old = range.split("..").next().filter(|_| range.contains("..")).and_then(resolve);
+                RefUpdate { old, new: resolve(src) }
+            }
+        };
+        updates.push((dst.to_string(), update));
+    }
+    updates
+}
+
+/// Run the installed `git` executable inside a repository
+/// Used for operations libgit2 does not support (e.g., bundles) and for the `cli` backend
+/// Returns stdout on success, or an error containing git's stderr
+pub fn run_git(repo_path: &Path, args: &[&str]) -> Result<String> {
+    run_git_command(Command::new("git"), repo_path, args, false)
+}
+
+/// Run a fetch/push with the installed `git` executable (`cli` backend)
+/// With `ssh_multiplex`, SSH connections are shared through an OpenSSH ControlMaster socket
+/// that stays open for a minute, so repositories on the same host skip the handshake
+fn run_git_network(repo_path: &Path, args: &[&str], ssh_multiplex: bool) -> Result<String> {
+    let mut command = Command::new("git");
+    // GIT_SSH/GIT_SSH_COMMAND set by the user take precedence
+    if ssh_multiplex
+        && cfg!(unix)
+        && env::var_os("GIT_SSH_COMMAND").is_none()
+        && env::var_os("GIT_SSH").is_none()
+    {
+        // Keep the socket path short, Unix sockets are limited to ~100 characters
+        let dir = dirs::runtime_dir().unwrap_or_else(|| PathBuf::from("/tmp"));
+        let ssh_command = format!(
+            "ssh -o ControlMaster=auto -o 'ControlPath={}/mgit-ssh-%C' -o ControlPersist=60",
+            dir.display()
+        );
+        debug!("SSH multiplexing: {}", ssh_command);
+        command.env("GIT_SSH_COMMAND", ssh_command);
+    }
+    run_git_command(command, repo_path, args, true)
+}
+
+const GIT_NOT_FOUND: &str = "Failed to execute git (is it installed and on PATH?)";
+
+fn run_git_command(mut command: Command, repo_path: &Path, args: &[&str], cancellable: bool) -> Result<String> {
+    command.args(args).current_dir(repo_path);
+    let output = if cancellable {
+        output_unless_cancelled(command)?
+    } else {
+        command.output().context(GIT_NOT_FOUND)?
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            stderr.trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Run `command` like `Command::output`, but kill it when the operation is cancelled
+/// (see [`operations_cancelled`])
+fn output_unless_cancelled(mut command: Command) -> Result<std::process::Output> {
+    use std::io::Read;
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context(GIT_NOT_FOUND)?;
+    let mut stdout_pipe = child.stdout.take();
+    let mut stderr_pipe = child.stderr.take();
+    let stdout = thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(pipe) = stdout_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    });
+    let stderr = thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(pipe) = stderr_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    });
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if operations_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("Interrupted"));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+pub fn is_git_repo(path: &Path) -> bool {
+    Repository::open(path).is_ok()
+}
+
+pub fn get_repo_url(repo_path: &Path) -> Result<String> {
+    let repo = Repository::open(repo_path)?;
+    let remote = repo.find_remote("origin")?;
+    let url = remote.url().unwrap_or("(no url)").to_string();
+    Ok(url)
+}
+
+/// Get the current git user's name from global config
+#[allow(dead_code)]
+pub fn get_current_user() -> Result<String> {
+    let config = git2::Config::open_default()?;
+    let name = config
+        .get_string("user.name")
+        .context("Failed to get user.name from git config")?;
+    Ok(name)
+}
+
+/// The user.name/user.email a repository commits as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitIdentity {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    /// Config file the email (or, without one, the name) comes from: "local", "global", "system", ...
+    pub source: Option<&'static str>,
+}
+
+/// Read the identity a repository commits as, from its config and the ones it inherits
+pub fn commit_identity(repo_path: &Path) -> Result<CommitIdentity> {
+    let config = Repository::open(repo_path)?.config()?.snapshot()?;
+    let entry = |key: &str| {
+        let entry = config.get_entry(key).ok()?;
+        Some((entry.value()?.to_string(), entry.level()))
+    };
+    let name = entry("user.name");
+    let email = entry("user.email");
+    let source = email.as_ref().or(name.as_ref()).map(|(_, level)| match level {
+        git2::ConfigLevel::Local | git2::ConfigLevel::Worktree => "local",
+        git2::ConfigLevel::Global | git2::ConfigLevel::XDG => "global",
+        git2::ConfigLevel::System | git2::ConfigLevel::ProgramData => "system",
+        git2::ConfigLevel::App | git2::ConfigLevel::Highest => "app",
+    });
+    Ok(CommitIdentity { name: name.map(|(value, _)| value), email: email.map(|(value, _)| value), source })
+}
+
+impl CommitIdentity {
+    /// Whether the identity has the configured name and email (emails compare case-insensitively)
+    pub fn matches(&self, expected: &Identity) -> bool {
+        let name_matches = expected.name.is_none() || self.name == expected.name;
+        let email_matches = match (&expected.email, &self.email) {
+            (None, _) => true,
+            (Some(expected), Some(email)) => expected.eq_ignore_ascii_case(email),
+            (Some(_), None) => false,
+        };
+        name_matches && email_matches
+    }
+}
+
+/// Write the configured name and email into a repository's local config
+/// Returns the keys that changed (e.g., ["user.email"])
+pub fn set_commit_identity(repo_path: &Path, identity: &Identity) -> Result<Vec<&'static str>> {
+    let mut config = Repository::open(repo_path)?.config()?.open_level(git2::ConfigLevel::Local)?;
+    let mut changed = Vec::new();
+    for (key, value) in [("user.name", &identity.name), ("user.email", &identity.email)] {
+        let Some(value) = value else {
+            continue;
+        };
+        if config.get_string(key).ok().as_ref() != Some(value) {
+            config.set_str(key, value)?;
+            changed.push(key);
+        }
+    }
+    Ok(changed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchStatus {
+    /// Branch is fully synced (green)
+    Synced,
+    /// Branch has local changes or commits to push (red)
+    NeedsPush,
+    /// Branch has remote commits to pull (orange)
+    NeedsPull,
+}
+
+/// Check if repository has uncommitted changes
+pub fn has_uncommitted_changes(repo_path: &Path) -> Result<bool> {
+    let repo = Repository::open(repo_path)?;
+
+    // Check for changes in working directory and index
+    let statuses = repo.statuses(None)?;
+
+    // Check if there are any changes that would need to be committed before pushing
+    // We ignore untracked files (WT_NEW) since they don't affect push status
+    for entry in statuses.iter() {
+        let status = entry.status();
+
+        // Check for staged changes (anything in the index)
+        if status.intersects(
+            Status::INDEX_NEW
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_RENAMED
+                | Status::INDEX_TYPECHANGE,
+        ) {
+            return Ok(true);
+        }
+
+        // Check for unstaged changes to tracked files (but NOT untracked files)
+        if status.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE | Status::WT_RENAMED,
+        ) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Stash uncommitted changes to tracked files (untracked files stay in place)
+/// Returns false when there was nothing to stash
+pub fn stash_changes(repo_path: &Path, message: &str) -> Result<bool> {
+    let mut repo = Repository::open(repo_path)?;
+    let signature = repo
+        .signature()
+        .or_else(|_| git2::Signature::now("mgit", "mgit@localhost"))?;
+
+    match repo.stash_save(&signature, message, None) {
+        Ok(_) => Ok(true),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(false),
+        Err(e) => Err(anyhow!("Could not stash changes: {}", e)),
+    }
+}
+
+/// Reapply and drop the most recent stash
+/// If it doesn't apply cleanly, the stash is kept so nothing is lost
+pub fn pop_stash(repo_path: &Path) -> Result<()> {
+    let mut repo = Repository::open(repo_path)?;
+    repo.stash_apply(0, None)
+        .map_err(|e| anyhow!("Could not reapply stashed changes (kept in stash@{{0}}): {}", e.message()))?;
+
+    // libgit2 applies conflicting changes as index conflicts instead of failing
+    if repo.index()?.has_conflicts() {
+        return Err(anyhow!(
+            "Stashed changes conflict with the checked out branch (resolve them; the stash is kept in stash@{{0}})"
+        ));
+    }
+    repo.stash_drop(0)?;
+    Ok(())
+}
+
+/// Get the sync status of a branch relative to its remote
+/// Returns (commits_ahead, commits_behind)
+pub fn get_branch_sync_status(repo_path: &Path, branch_name: &str) -> Result<(usize, usize)> {
+    let repo = Repository::open(repo_path)?;
+
+    // Get local branch reference
+    let local_ref_name = format!("refs/heads/{}", branch_name);
+    let local_ref = match repo.find_reference(&local_ref_name) {
+        Ok(r) => r,
+        Err(_) => return Ok((0, 0)), // Branch doesn't exist locally
+    };
+
+    let local_oid = match local_ref.target() {
+        Some(oid) => oid,
+        None => return Ok((0, 0)),
+    };
+
+    // Try to find remote tracking branch
+    let remote_ref_name = format!("refs/remotes/origin/{}", branch_name);
+    let remote_oid = match repo.find_reference(&remote_ref_name) {
+        Ok(remote_ref) => match remote_ref.target() {
+            Some(oid) => oid,
+            None => return Ok((0, 0)),
+        },
+        Err(_) => return Ok((0, 0)), // No remote tracking branch
+    };
+
+    // Use git2 to count commits ahead and behind
+    let (ahead, behind) = repo.graph_ahead_behind(local_oid, remote_oid)?;
+
+    Ok((ahead, behind))
+}
+
+/// Uncommitted changes and ahead/behind counts of a repository's current branch
+/// Errors count as clean and in sync; the summary is only informational.
+pub fn get_worktree_status(repo_path: &Path, current_branch: &str) -> WorktreeStatus {
+    let (ahead, behind) = get_branch_sync_status(repo_path, current_branch).unwrap_or((0, 0));
+    WorktreeStatus {
+        dirty: has_uncommitted_changes(repo_path).unwrap_or(false),
+        ahead,
+        behind,
+    }
+}
+
+/// Determine the overall status of a branch for coloring
+pub fn get_branch_status(repo_path: &Path, branch_name: &str) -> Result<BranchStatus> {
+    // Check for uncommitted changes first
+    if has_uncommitted_changes(repo_path)? {
+        return Ok(BranchStatus::NeedsPush);
+    }
+
+    // Check sync status with remote
+    let (ahead, behind) = get_branch_sync_status(repo_path, branch_name)?;
+
+    if behind > 0 {
+        // Has remote commits to pull (takes priority)
+        Ok(BranchStatus::NeedsPull)
+    } else if ahead > 0 {
+        // Has local commits to push
+        Ok(BranchStatus::NeedsPush)
+    } else {
+        // Fully synced
+        Ok(BranchStatus::Synced)
+    }
+}
+
+/// A local branch whose last commit is old and that is merged or was never pushed
+#[derive(Debug, Clone)]
+pub struct StaleBranch {
+    pub name: String,
+    /// Normalized author of the branch's last commit
+    pub author: String,
+    pub last_commit: DateTime<Utc>,
+    /// Fully merged into master/main
+    pub merged: bool,
+    /// Has an upstream (remote-tracking) branch
+    pub has_upstream: bool,
+    /// Checked out in the working tree
+    pub is_current: bool,
+}
+
+/// Find local branches (other than master/main) whose last commit is older than `cutoff`
+/// and that are fully merged into master/main or have no upstream
+pub fn find_stale_branches(
+    repo_path: &Path,
+    cutoff: DateTime<Utc>,
+    user_aliases: &HashMap<String, Vec<String>>,
+) -> Result<Vec<StaleBranch>> {
+    let repo = Repository::open(repo_path)
+        .with_context(|| format!("Failed to open repository at {:?}", repo_path))?;
+    let main_oid = find_main_branch(&repo);
+    let current_branch = get_current_branch(&repo)?;
+
+    let mut stale = Vec::new();
+    for branch in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = match branch.name()? {
+            Some(name) if name != "master" && name != "main" => name.to_string(),
+            _ => continue,
+        };
+
+        let commit = branch.get().peel_to_commit()?;
+        let last_commit = DateTime::from_timestamp(commit.time().seconds(), 0).unwrap_or_else(Utc::now);
+        if last_commit >= cutoff {
+            continue;
+        }
+
+        let merged = match main_oid {
+            Some(main_oid) => main_oid == commit.id() || repo.graph_descendant_of(main_oid, commit.id())?,
+            None => false,
+        };
+        let has_upstream = branch.upstream().is_ok();
+        if !merged && has_upstream {
+            continue;
+        }
+
+        stale.push(StaleBranch {
+            author: normalize_signature(&commit.author(), user_aliases),
+            is_current: name == current_branch,
+            name,
+            last_commit,
+            merged,
+            has_upstream,
+        });
+    }
+
+    stale.sort_by_key(|b| b.last_commit);
+    Ok(stale)
+}
+
+/// Corruption found (and, when repairing, fixed) by [`repair_repository`] / [`find_corruption`]
+#[derive(Debug, Default)]
+pub struct RepairResult {
+    /// FETCH_HEAD was empty or unreadable
+    pub corrupted_fetch_head: bool,
+    /// Loose refs holding neither a SHA nor a symbolic ref
+    pub corrupted_refs: Vec<CorruptedRef>,
+    /// Where the corrupted ref files were copied before they were replaced or removed
+    pub backup_dir: Option<PathBuf>,
+}
+
+impl RepairResult {
+    pub fn has_fixes(&self) -> bool {
+        self.corrupted_fetch_head || !self.corrupted_refs.is_empty()
+    }
+}
+
+/// A corrupted loose ref and how it is repaired
+#[derive(Debug)]
+pub struct CorruptedRef {
+    /// Path of the ref file, e.g. ".git/refs/heads/main"
+    pub path: String,
+    /// Where the ref's commit is recovered from, with the commit; None when the ref is removed
+    pub recovery: Option<(RefSource, String)>,
+}
+
+/// Where a corrupted ref's commit is recovered from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefSource {
+    /// The ref's entry in packed-refs, which takes over once the loose file is gone
+    PackedRefs,
+    /// The newest commit in the ref's reflog that still exists
+    Reflog,
+}
+
+impl RefSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RefSource::PackedRefs => "packed-refs",
+            RefSource::Reflog => "reflog",
+        }
+    }
+}
+
+/// Attempt to repair common git repository corruption issues
+/// A corrupted FETCH_HEAD is removed. Corrupted loose refs are backed up under .git/mgit-backup,
+/// then restored from the reflog or packed-refs when possible, and removed otherwise.
+pub fn repair_repository(repo_path: &Path) -> Result<RepairResult> {
+    scan_repository(repo_path, true)
+}
+
+/// Find the corruption [`repair_repository`] would fix, without changing anything
+pub fn find_corruption(repo_path: &Path) -> Result<RepairResult> {
+    scan_repository(repo_path, false)
+}
+
+fn scan_repository(repo_path: &Path, fix: bool) -> Result<RepairResult> {
+    let mut result = RepairResult::default();
+    let git_dir = repo_path.join(".git");
+
+    if !git_dir.exists() {
+        return Err(anyhow::anyhow!("Not a git repository"));
+    }
+
+    // 1. Check FETCH_HEAD: empty, binary data or unreadable means corrupted
+    let fetch_head = git_dir.join("FETCH_HEAD");
+    if fetch_head.exists() {
+        let corrupted = std::fs::read_to_string(&fetch_head)
+            .map_or(true, |content| content.is_empty() || content.contains('\0'));
+        if corrupted {
+            if fix {
+                std::fs::remove_file(&fetch_head).context("Failed to remove corrupted FETCH_HEAD")?;
+            }
+            result.corrupted_fetch_head = true;
+        }
+    }
+
+    // 2. Check for corrupted loose references in .git/refs
+    let mut corrupted = Vec::new();
+    let refs_dir = git_dir.join("refs");
+    if refs_dir.exists() {
+        find_corrupted_refs(&refs_dir, &mut corrupted)?;
+    }
+    if corrupted.is_empty() {
+        // Note: Skipping git fsck as it can be very slow on large repositories
+        // and the critical corruptions (FETCH_HEAD, loose refs) are already handled; see fsck_repository
+        return Ok(result);
+    }
+
+    // 3. Recover them from the reflog or packed-refs, keeping a copy of each file
+    // The reflog comes first: a loose ref overrides its packed entry, which is usually older,
+    // so recovering from packed-refs would silently rewind the branch.
+    let packed = read_packed_refs(&git_dir);
+    let repo = Repository::open(repo_path).ok();
+    let backup_dir = git_dir.join("mgit-backup").join(Utc::now().format("%Y%m%d-%H%M%S").to_string());
+    for file in corrupted {
+        let ref_name = file.strip_prefix(&git_dir).unwrap_or(&file).to_string_lossy().replace('\\', "/");
+        let path = file.strip_prefix(repo_path).unwrap_or(&file).to_string_lossy().to_string();
+        let recovery = match reflog_commit(&git_dir, &ref_name, repo.as_ref()) {
+            Some(commit) => Some((RefSource::Reflog, commit)),
+            None => packed.get(&ref_name).map(|commit| (RefSource::PackedRefs, commit.clone())),
+        };
+
+        if fix {
+            // An unreadable file can't be copied, and there is nothing in it to lose
+            let backup = backup_dir.join(&ref_name);
+            if let Some(parent) = backup.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if std::fs::copy(&file, &backup).is_ok() {
+                result.backup_dir = Some(backup_dir.clone());
+            }
+
+            match &recovery {
+                Some((RefSource::Reflog, commit)) => std::fs::write(&file, format!("{}\n", commit))
+                    .with_context(|| format!("Failed to restore corrupted ref: {}", path))?,
+                _ => std::fs::remove_file(&file)
+                    .with_context(|| format!("Failed to remove corrupted ref: {}", path))?,
+            }
+        }
+        result.corrupted_refs.push(CorruptedRef { path, recovery });
+    }
+
+    Ok(result)
+}
+
+/// Recursively collect the ref files that are unreadable or hold neither a SHA nor a symbolic ref
+fn find_corrupted_refs(dir: &Path, corrupted: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            find_corrupted_refs(&path, corrupted)?;
+        } else if path.is_file() {
+            let valid = std::fs::read_to_string(&path).is_ok_and(|content| is_valid_ref_content(content.trim()));
+            if !valid {
+                corrupted.push(path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Ref name to commit of the entries in .git/packed-refs (peeled "^" lines are skipped)
+fn read_packed_refs(git_dir: &Path) -> HashMap<String, String> {
+    let content = std::fs::read_to_string(git_dir.join("packed-refs")).unwrap_or_default();
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(sha, _)| is_hex_sha(sha))
+        .map(|(sha, name)| (name.trim().to_string(), sha.to_string()))
+        .collect()
+}
+
+/// Newest commit a ref pointed to according to its reflog that still exists in the repository
+fn reflog_commit(git_dir: &Path, ref_name: &str, repo: Option<&Repository>) -> Option<String> {
+    let reflog = std::fs::read_to_string(git_dir.join("logs").join(ref_name)).ok()?;
+    reflog
+        .lines()
+        .rev()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter(|sha| is_hex_sha(sha) && sha.chars().any(|c| c != '0'))
+        .find(|sha| match (repo, Oid::from_str(sha)) {
+            (Some(repo), Ok(oid)) => repo.find_commit(oid).is_ok(),
+            (None, _) => true,
+            (_, Err(_)) => false,
+        })
+        .map(str::to_string)
+}
+
+/// Run `git fsck` on a repository and return the problems it reports (missing or corrupt objects, ...)
+/// Dangling objects are not reported: they are normal leftovers, not damage.
+pub fn fsck_repository(repo_path: &Path) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["fsck", "--no-progress", "--no-dangling"])
+        .current_dir(repo_path)
+        .output()
+        .context("Failed to execute git (is it installed and on PATH?)")?;
+
+    let findings: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("Checking "))
+        .map(str::to_string)
+        .collect();
+    if findings.is_empty() && !output.status.success() {
+        return Ok(vec![format!("git fsck failed ({})", output.status)]);
+    }
+    Ok(findings)
+}
+
+/// Check if reference content is valid
+fn is_valid_ref_content(content: &str) -> bool {
+    if content.is_empty() {
+        return false;
+    }
+
+    // Check for symbolic refs (e.g., "ref: refs/heads/main")
+    if content.starts_with("ref:") {
+        return true;
+    }
+
+    is_hex_sha(content)
+}
+
+/// SHA-1 (40 hex chars) or SHA-256 (64 hex chars)
+fn is_hex_sha(content: &str) -> bool {
+    let len = content.len();
+    (len == 40 || len == 64) && content.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Commit an empty tree on `reference` (created if missing), on top of its current commit
+    fn commit(repo: &Repository, reference: &str, message: &str) -> Oid {
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(repo.treebuilder(None).unwrap().write().unwrap()).unwrap();
+        let parent = repo.find_reference(reference).ok().and_then(|r| r.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some(reference), &signature, &signature, message, &tree, &parents).unwrap()
+    }
+
+    /// A bare origin and a clone of it with `main` pushed
+    fn origin_and_clone() -> (TempDir, Repository, Repository) {
+        let dir = TempDir::new().unwrap();
+        let origin = Repository::init_bare(dir.path().join("origin.git")).unwrap();
+        let clone = Repository::init(dir.path().join("clone")).unwrap();
+        clone.remote("origin", dir.path().join("origin.git").to_str().unwrap()).unwrap();
+        commit(&clone, "refs/heads/main", "initial");
+        (dir, origin, clone)
+    }
+
+    #[test]
+    fn test_remote_branch_state() {
+        let (_dir, _origin, repo) = origin_and_clone();
+        let path = repo.workdir().unwrap().to_path_buf();
+        let main = repo.refname_to_id("refs/heads/main").unwrap();
+
+        let topic = commit(&repo, "refs/heads/topic", "topic");
+        assert_eq!(remote_branch_state(&path, "topic").unwrap(), RemoteBranchState::Missing);
+
+        repo.reference("refs/remotes/origin/topic", topic, true, "test").unwrap();
+        assert_eq!(remote_branch_state(&path, "topic").unwrap(), RemoteBranchState::Merged(topic));
+
+        // Someone pushed another commit to origin's branch
+        let pushed = commit(&repo, "refs/remotes/origin/topic", "pushed by someone else");
+        assert_eq!(remote_branch_state(&path, "topic").unwrap(), RemoteBranchState::Diverged(pushed));
+
+        // Behind the local branch but merged into main
+        repo.reference("refs/remotes/origin/topic", main, true, "test").unwrap();
+        assert_eq!(remote_branch_state(&path, "topic").unwrap(), RemoteBranchState::Merged(main));
+    }
+
+    #[test]
+    fn test_delete_remote_branch_refuses_a_moved_branch() {
+        let (_dir, origin, repo) = origin_and_clone();
+        let path = repo.workdir().unwrap().to_path_buf();
+        let fetched = commit(&repo, "refs/heads/topic", "topic");
+        repo.find_remote("origin").unwrap().push(&["refs/heads/topic"], None).unwrap();
+
+        // Pushed to origin after our fetch
+        let pushed = commit(&origin, "refs/heads/topic", "pushed by someone else");
+        let error = delete_remote_branch(&Config::default(), &path, "topic", fetched, None).unwrap_err();
+        assert!(error.to_string().contains("changed on origin"), "{}", error);
+        assert_eq!(origin.refname_to_id("refs/heads/topic").unwrap(), pushed);
+
+        delete_remote_branch(&Config::default(), &path, "topic", pushed, None).unwrap();
+        assert!(origin.find_reference("refs/heads/topic").is_err());
+    }
+
+    #[test]
+    fn test_cancelled_push_is_not_sent() {
+        let (_dir, origin, repo) = origin_and_clone();
+        set_thread_cancel(Arc::new(AtomicBool::new(true)));
+        let error = push_refspec(&Config::default(), &repo, "refs/heads/main", None).unwrap_err();
+        set_thread_cancel(Arc::new(AtomicBool::new(false)));
+
+        assert_eq!(error.to_string(), "Interrupted");
+        assert!(origin.find_reference("refs/heads/main").is_err());
+    }
+
+    #[test]
+    fn test_credential_request_refuses_injected_keys() {
+        assert_eq!(
+            credential_request("https://github.com/a.git", Some("me"), Some("secret")).unwrap(),
+            "url=https://github.com/a.git\nusername=me\npassword=secret\n\n"
+        );
+        assert_eq!(credential_request("https://github.com/a.git", None, None).unwrap(), "url=https://github.com/a.git\n\n");
+        assert!(credential_request("https://github.com/a.git", Some("me"), Some("x\nurl=https://evil.com")).is_none());
+        assert!(credential_request("https://github.com/a.git", Some("me\0"), Some("secret")).is_none());
+    }
+
+    #[test]
+    fn test_https_sources_are_offered_once_in_order() {
+        let names = vec!["github.com/acme/api".to_string(), "github.com".to_string()];
+        let token = RepoCredential { token: Some("stale".to_string()), ..Default::default() };
+        assert_eq!(
+            https_candidates(Some(&token), &names, true),
+            [
+                HttpsCandidate::RepoToken,
+                HttpsCandidate::Keychain("github.com/acme/api".to_string()),
+                HttpsCandidate::Keychain("github.com".to_string()),
+                HttpsCandidate::Helpers,
+                HttpsCandidate::Prompt,
+            ]
+        );
+        assert_eq!(https_candidates(None, &[], false), [HttpsCandidate::Helpers]);
+    }
+
+    #[test]
+    fn test_repository_ssh_key_is_offered_before_and_instead_of_the_agent() {
+        let dir = TempDir::new().unwrap();
+        let host_key = dir.path().join("id_personal");
+        std::fs::write(&host_key, "key").unwrap();
+        let credentials = HashMap::from([("github.com".to_string(), host_key.display().to_string())]);
+        let url = "git@github.com:acme-corp/api.git";
+
+        assert_eq!(
+            ssh_candidates(&credentials, None, url, true),
+            [
+                SshCandidate::Agent,
+                SshCandidate::Key { key_host: "github.com".to_string(), private_key: host_key.clone() },
+                SshCandidate::Default,
+            ]
+        );
+
+        let work = RepoCredential { ssh_key: Some("/keys/id_work".to_string()), ..Default::default() };
+        let credentials = credentials_for_repo(&credentials, Some(&work), url);
+        let expected = [SshCandidate::Key {
+            key_host: "github.com/acme-corp/api".to_string(),
+            private_key: PathBuf::from("/keys/id_work"),
+        }];
+        assert_eq!(ssh_candidates(&credentials, Some(&work), url, true), expected);
+        assert_eq!(ssh_candidates(&credentials, Some(&work), url, false), expected);
+    }
+
+    /// A repository whose `topic` branch went from `first` to `second` (both in its reflog),
+    /// with the loose ref file of `topic` overwritten with garbage
+    fn corrupted_topic() -> (TempDir, PathBuf, Oid, Oid) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let first = commit(&repo, "refs/heads/topic", "first");
+        let second = commit(&repo, "refs/heads/topic", "second");
+        let git_dir = dir.path().join(".git");
+        std::fs::write(git_dir.join("refs/heads/topic"), "\0\0garbage").unwrap();
+        (dir, git_dir, first, second)
+    }
+
+    fn repaired_topic(dir: &TempDir) -> (Option<(RefSource, String)>, Option<Oid>) {
+        let mut result = repair_repository(dir.path()).unwrap();
+        assert_eq!(result.corrupted_refs.len(), 1);
+        let backup = result.backup_dir.as_ref().unwrap().join("refs/heads/topic");
+        assert_eq!(std::fs::read(backup).unwrap(), b"\0\0garbage");
+        assert!(find_corruption(dir.path()).unwrap().corrupted_refs.is_empty());
+        let topic = Repository::open(dir.path()).unwrap().refname_to_id("refs/heads/topic").ok();
+        (result.corrupted_refs.remove(0).recovery, topic)
+    }
+
+    #[test]
+    fn test_repair_recovers_from_reflog() {
+        let (dir, _, _, second) = corrupted_topic();
+        let (recovery, topic) = repaired_topic(&dir);
+        assert_eq!(recovery, Some((RefSource::Reflog, second.to_string())));
+        assert_eq!(topic, Some(second));
+    }
+
+    #[test]
+    fn test_repair_recovers_from_packed_refs() {
+        let (dir, git_dir, first, _) = corrupted_topic();
+        std::fs::remove_file(git_dir.join("logs/refs/heads/topic")).unwrap();
+        std::fs::write(git_dir.join("packed-refs"), format!("# pack-refs with: peeled\n{} refs/heads/topic\n", first))
+            .unwrap();
+        let (recovery, topic) = repaired_topic(&dir);
+        assert_eq!(recovery, Some((RefSource::PackedRefs, first.to_string())));
+        assert_eq!(topic, Some(first));
+        assert!(!git_dir.join("refs/heads/topic").exists());
+    }
+
+    #[test]
+    fn test_repair_prefers_reflog_over_older_packed_ref() {
+        let (dir, git_dir, first, second) = corrupted_topic();
+        std::fs::write(git_dir.join("packed-refs"), format!("{} refs/heads/topic\n", first)).unwrap();
+        let (recovery, topic) = repaired_topic(&dir);
+        assert_eq!(recovery, Some((RefSource::Reflog, second.to_string())));
+        assert_eq!(topic, Some(second), "the branch must not be rewound to its packed entry");
+    }
+
+    #[test]
+    fn test_repair_removes_unrecoverable_ref() {
+        let (dir, git_dir, _, _) = corrupted_topic();
+        std::fs::remove_file(git_dir.join("logs/refs/heads/topic")).unwrap();
+        let (recovery, topic) = repaired_topic(&dir);
+        assert_eq!(recovery, None);
+        assert_eq!(topic, None);
+        assert!(!git_dir.join("refs/heads/topic").exists());
+    }
+}
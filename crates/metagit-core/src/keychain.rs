@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use keyring::Entry;
+
+/// Service name under which all mgit secrets are stored in the OS keychain
+const SERVICE: &str = "mgit";
+
+/// Kind of secret stored for a host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretKind {
+    /// Passphrase protecting the SSH private key configured for the host
+    SshPassphrase,
+    /// HTTPS access token (personal access token, app password, ...)
+    HttpsToken,
+}
+
+impl SecretKind {
+    fn suffix(&self) -> &'static str {
+        match self {
+            SecretKind::SshPassphrase => "ssh-passphrase",
+            SecretKind::HttpsToken => "https-token",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SecretKind::SshPassphrase => "SSH passphrase",
+            SecretKind::HttpsToken => "HTTPS token",
+        }
+    }
+}
+
+/// Build the keychain entry for a host (e.g., "github.com:https-token")
+fn entry(host: &str, kind: SecretKind) -> Result<Entry> {
+    Entry::new(SERVICE, &format!("{}:{}", host, kind.suffix()))
+        .context("Failed to access the OS keychain")
+}
+
+/// Store a secret for a host in the OS keychain
+/// (macOS Keychain, Windows Credential Manager, or the Secret Service on Linux)
+pub fn set_secret(host: &str, kind: SecretKind, secret: &str) -> Result<()> {
+    entry(host, kind)?
+        .set_password(secret)
+        .with_context(|| format!("Failed to store {} for {} in the OS keychain", kind.label(), host))
+}
+
+/// Retrieve a secret for a host from the OS keychain
+/// Returns None if no secret is stored or the keychain is unavailable
+pub fn get_secret(host: &str, kind: SecretKind) -> Option<String> {
+    entry(host, kind).ok()?.get_password().ok()
+}
+
+/// Remove a secret for a host from the OS keychain
+/// Returns true if a secret was removed, false if none was stored
+pub fn delete_secret(host: &str, kind: SecretKind) -> Result<bool> {
+    match entry(host, kind)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(anyhow::anyhow!(
+            "Failed to remove {} for {} from the OS keychain: {}",
+            kind.label(),
+            host,
+            e
+        )),
+    }
+}
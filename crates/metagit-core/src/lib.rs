@@ -0,0 +1,24 @@
+//! Workspace logic of MetaGit, shared by the `mgit` CLI and other front-ends
+//!
+//! - [`models`]: the `.mgitconfig.yaml` configuration and cached repository state
+//! - [`db`]: the sled-backed [`db::StateDb`] cache of repository states
+//! - [`forge`]: hosting provider APIs (GitHub, GitLab)
+//! - [`git`]: git operations (status, statistics, pull/push with credential handling)
+//! - [`keychain`]: secrets stored in the OS keychain
+//! - [`paths`]: XDG locations of the global config and the state databases
+//! - [`secrets`]: age encryption of the secrets kept in the config file
+//! - [`ssh_config`]: `~/.ssh/config` parsing and SSH URL resolution
+//! - [`vars`]: `$(NAME)` variable substitution in tasks and config values
+//!
+//! The library never writes to stdout/stderr. Results and errors are returned to the caller;
+//! network operations log through `tracing` (debug level), with a span per repository.
+
+pub mod db;
+pub mod forge;
+pub mod git;
+pub mod keychain;
+pub mod models;
+pub mod paths;
+pub mod secrets;
+pub mod ssh_config;
+pub mod vars;
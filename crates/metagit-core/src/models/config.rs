@@ -0,0 +1,1619 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::OwnershipMode;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Config {
+    /// Left out of a global configuration that only holds settings or tasks
+    #[serde(default)]
+    pub repositories: Vec<Repository>,
+    /// URL of repositories that don't set `url`, with `{name}` replaced by the repository name
+    /// Example: "git@github.com:myorg/{name}.git"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_template: Option<String>,
+    #[serde(default)]
+    pub tasks: Vec<Task>,
+    /// Short names of tasks for `mgit run`: maps alias to a task name or a namespace ("build:*")
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub task_aliases: HashMap<String, String>,
+    /// Reusable steps, referenced by name from the `template` of task steps
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub step_templates: HashMap<String, StepTemplate>,
+    #[serde(default)]
+    pub shells: ShellConfig,
+    /// SSH credentials: maps hostname (e.g., "github.com") to SSH private key path (e.g., "~/.ssh/id_github")
+    #[serde(default)]
+    pub credentials: HashMap<String, String>,
+    /// User aliases: maps canonical user name to list of aliases (names and emails)
+    /// Example: `"John" -> ["John Crammer", "JC", "john.crammer@company.com"]`
+    #[serde(default)]
+    pub users: HashMap<String, Vec<String>>,
+    /// Saved tags: maps tag name to repository branches
+    /// Example: "release-1.0" -> {"frontend" -> "release/1.0", "backend" -> "release/1.0"}
+    #[serde(default)]
+    pub tags: HashMap<String, HashMap<String, String>>,
+    /// Metadata of saved tags (when, by whom and why they were saved): maps tag name to its info
+    /// Tags saved before metadata was recorded have no entry
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tag_info: HashMap<String, TagInfo>,
+    /// HTTP(S) proxy settings for network operations (HTTPS_PROXY/NO_PROXY are honored as fallback)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// TLS settings for HTTPS remotes (custom CA certificates, verification overrides)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// Git hooks distributed by `mgit hooks install`: maps hook name (e.g., "pre-commit") to a script path
+    /// Relative paths are resolved from the directory containing .mgitconfig.yaml
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub hooks: HashMap<String, String>,
+    /// Icon settings (Nerd Font switch and per-icon overrides)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icons: Option<IconConfig>,
+    /// Default timestamp format for status/refresh output: "relative" (default), "absolute" or "iso"
+    #[serde(default, skip_serializing_if = "TimestampFormat::is_default")]
+    pub timestamps: TimestampFormat,
+    /// Age after which cached branch statistics are shown as stale by `mgit status` (e.g., "12h", "3d", "2w")
+    /// Defaults to 7 days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl: Option<String>,
+    /// Age of the last commit after which merged or unpushed branches are listed by `mgit stale` (e.g., "90d", "12w")
+    /// Defaults to 90 days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_after: Option<String>,
+    /// Hosting providers of self-hosted hosts: maps host to "github" (Enterprise) or "gitlab"
+    /// github.com and hosts with "github"/"gitlab" in their name are detected without an entry
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub forges: HashMap<String, ForgeKind>,
+    /// SSH ports of hosts that don't listen on 22 (e.g., a self-hosted Gitea): maps host to port
+    /// Applied to SSH URLs without a port when ~/.ssh/config sets none for the host
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub ssh_ports: HashMap<String, u16>,
+    /// Client IDs of the OAuth apps `mgit login` signs in with: maps host to client ID
+    /// The app must have the device flow enabled
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub oauth_client_ids: HashMap<String, String>,
+    /// Identity the repositories should commit as, with overrides per group (see `mgit identity apply`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<IdentityConfig>,
+    /// Age after which unfinished or missing CI results shown by `mgit status` are checked again (default "5m")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_ttl: Option<String>,
+    /// How `mgit refresh` builds the branch statistics (merge commits, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<StatsConfig>,
+    /// Number of new commit subjects listed per repository by pull and sync (default 5, 0 to list none)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_commits: Option<usize>,
+    /// Stash local changes before `mgit pull` updates a repository and reapply them afterwards
+    /// (default false; `--autostash`/`--no-autostash` override it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autostash: Option<bool>,
+    /// Remove remote-tracking branches of branches deleted on origin when pull/sync fetch
+    /// (default false; `mgit pull --prune`/`--no-prune` override it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prune: Option<bool>,
+    /// Time after which a repository's fetch or push in pull/push/sync is given up, e.g. "2m"
+    /// (default: no limit); repositories can set their own `network_timeout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_timeout: Option<String>,
+    /// Backend used for network operations: "libgit2" (default) or "cli" (the installed git executable)
+    #[serde(default, skip_serializing_if = "GitBackend::is_default")]
+    pub backend: GitBackend,
+    /// Share SSH connections between repositories through OpenSSH's ControlMaster (`cli` backend, Unix only)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ssh_multiplex: bool,
+    /// When HTTPS remotes without a token ask for a user name and password on the terminal:
+    /// "session" (default, entered once per host for the run), "always" (for every repository) or "never"
+    #[serde(default, skip_serializing_if = "CredentialPrompt::is_default")]
+    pub credential_prompt: CredentialPrompt,
+    /// Parallelism of network operations (pull, push, sync): total jobs and per-host connection caps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<ConcurrencyConfig>,
+    /// Variables available to task steps as `$(NAME)` (overridden by `-D NAME=value`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+    /// Dotenv files providing defaults for task variables, relative to the config file (default: `.env`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_files: Option<Vec<String>>,
+    /// Named contexts overriding the repository set, credentials and variables,
+    /// selected with `--profile <name>` or the MGIT_PROFILE environment variable
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, Profile>,
+    /// age-encrypted credentials and repository tokens (see `mgit secrets encrypt`), decrypted at load time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_secrets: Option<String>,
+    /// age identity file decrypting `encrypted_secrets` (MGIT_AGE_IDENTITY takes precedence;
+    /// MGIT_AGE_PASSPHRASE is used when neither is set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_identity: Option<String>,
+    /// Secrets decrypted from `encrypted_secrets` and merged into `credentials` and the repositories,
+    /// so `save` can leave them out of the file
+    #[serde(skip)]
+    pub decrypted_secrets: Option<Secrets>,
+    /// Name of the applied profile and the values it replaced, so `save` writes the file unchanged
+    #[serde(skip)]
+    pub active_profile: Option<(String, Box<ProfileBase>)>,
+    /// Interpolated config values by their path, with the value as written in the file,
+    /// so `save` keeps the references
+    #[serde(skip)]
+    pub interpolated: HashMap<String, (String, String)>,
+    /// Directory where the config file was loaded from (used to resolve relative paths)
+    /// Not serialized - this is metadata about where we loaded from
+    #[serde(skip)]
+    pub config_dir: Option<std::path::PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShellConfig {
+    /// Shell executable to use for .sh scripts (default: "sh" on Unix, "bash" if available)
+    #[serde(default = "default_shell")]
+    pub sh: String,
+    /// Command prompt executable to use for .bat/.cmd scripts (default: "cmd")
+    #[serde(default = "default_cmd")]
+    pub cmd: String,
+    /// PowerShell executable to use for .ps1 scripts (default: "powershell")
+    #[serde(default = "default_powershell")]
+    pub powershell: String,
+}
+
+fn default_shell() -> String {
+    "sh".to_string()
+}
+
+fn default_cmd() -> String {
+    "cmd".to_string()
+}
+
+fn default_powershell() -> String {
+    "powershell".to_string()
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            sh: default_shell(),
+            cmd: default_cmd(),
+            powershell: default_powershell(),
+        }
+    }
+}
+
+impl ShellConfig {
+    /// Copy of these shells with the executables set in `overrides` replaced
+    pub fn with_overrides(&self, overrides: Option<&ShellOverrides>) -> ShellConfig {
+        let mut shells = self.clone();
+        if let Some(overrides) = overrides {
+            if let Some(sh) = &overrides.sh {
+                shells.sh = sh.clone();
+            }
+            if let Some(cmd) = &overrides.cmd {
+                shells.cmd = cmd.clone();
+            }
+            if let Some(powershell) = &overrides.powershell {
+                shells.powershell = powershell.clone();
+            }
+        }
+        shells
+    }
+}
+
+/// Shell executables overriding the workspace `shells` for one repository or task step
+/// Example: { "sh": "C:\\Windows\\System32\\wsl.exe" } for a repository that needs WSL bash
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ShellOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sh: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cmd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub powershell: Option<String>,
+}
+
+/// Icon configuration, shared with the workspace so everyone sees the same glyphs
+/// Example: { "nerd_font": true, "overrides": { "success": "✔", "branch": "" } }
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IconConfig {
+    /// Use Nerd Font glyphs (the NERD_FONT environment variable takes precedence)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nerd_font: Option<bool>,
+    /// Plain ASCII instead of emoji/Unicode glyphs (e.g., for log files or Windows consoles)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ascii: Option<bool>,
+    /// Per-icon replacements: maps icon name (e.g., "success", "branch", "folder") to a glyph
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub overrides: HashMap<String, String>,
+}
+
+/// How timestamps are displayed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampFormat {
+    /// Human friendly, e.g., "2 hours ago"
+    #[default]
+    Relative,
+    /// Local date and time, e.g., "2025-03-14 09:26"
+    Absolute,
+    /// ISO 8601 / RFC 3339 in UTC, e.g., "2025-03-14T08:26:53Z"
+    Iso,
+}
+
+impl TimestampFormat {
+    pub fn is_default(&self) -> bool {
+        *self == TimestampFormat::Relative
+    }
+}
+
+/// Hosting provider API flavor, for hosts that aren't recognized by name (see `forges`)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    #[cfg_attr(feature = "clap", value(name = "github"))]
+    GitHub,
+    #[cfg_attr(feature = "clap", value(name = "gitlab"))]
+    GitLab,
+}
+
+/// Implementation used for network operations (pull, push)
+/// Read-only analysis (status, refresh) always uses libgit2
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBackend {
+    /// Built-in libgit2 with mgit's own credential handling
+    #[default]
+    Libgit2,
+    /// Shell out to the installed `git`, inheriting the user's auth, config and protocols
+    Cli,
+}
+
+impl GitBackend {
+    pub fn is_default(&self) -> bool {
+        *self == GitBackend::Libgit2
+    }
+}
+
+/// Terminal prompt for HTTPS credentials, the last resort after tokens and git credential helpers
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialPrompt {
+    /// Prompt once per host; the credentials are reused for the rest of the run
+    #[default]
+    Session,
+    /// Prompt for every repository
+    Always,
+    /// Never prompt, fail like in a non-interactive run
+    Never,
+}
+
+impl CredentialPrompt {
+    pub fn is_default(&self) -> bool {
+        *self == CredentialPrompt::Session
+    }
+}
+
+/// Proxy configuration for HTTP(S) remotes
+/// Example: `{ "url": "http://proxy.corp:8080", "hosts": { "github.com": "http://gh-proxy:3128" }, "no_proxy": ["gitlab.internal"] }`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProxyConfig {
+    /// Proxy used for all hosts without a specific entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Per-host proxies: maps hostname to proxy URL
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub hosts: HashMap<String, String>,
+    /// Hosts (or domain suffixes like ".corp.example.com") that are reached directly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Get the configured proxy for a host, if any
+    /// Returns Some("") when the host is explicitly excluded via no_proxy
+    pub fn proxy_for_host(&self, host: &str) -> Option<String> {
+        if matches_no_proxy(&self.no_proxy.join(","), host) {
+            return Some(String::new());
+        }
+        self.hosts.get(host).or(self.url.as_ref()).cloned()
+    }
+}
+
+/// Settings of the branch statistics collected by `mgit refresh`
+/// Example: `{ "merge_commits": true, "first_parent": true, "ownership": "recent", "owner_threshold": 10 }`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StatsConfig {
+    /// Count merge commits towards authorship (by default they are skipped, so whoever merges isn't credited)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub merge_commits: bool,
+    /// Only follow the first parent of merges, so commits merged in from main aren't counted for the branch
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub first_parent: bool,
+    /// How branch owners are determined: "commits" (most commits, default) or "recent" (latest commit)
+    #[serde(default, skip_serializing_if = "OwnershipMode::is_default")]
+    pub ownership: OwnershipMode,
+    /// Share of a branch's commits, in percent, from which other authors mark the owner with `*` (default 5)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_threshold: Option<u32>,
+}
+
+/// Concurrency limits for network operations
+/// Example: `{ "jobs": 8, "per_host": 4, "hosts": { "git.internal.example.com": 2 } }`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConcurrencyConfig {
+    /// Number of repositories processed at once (overridden by `--jobs`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<usize>,
+    /// Maximum simultaneous connections to a single host (default: 4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_host: Option<usize>,
+    /// Per-host connection caps: maps hostname to its limit (takes precedence over `per_host`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub hosts: HashMap<String, usize>,
+}
+
+/// Default cap of simultaneous connections to one host
+pub const DEFAULT_CONNECTIONS_PER_HOST: usize = 4;
+
+impl ConcurrencyConfig {
+    /// Get the connection cap for a host (at least 1)
+    pub fn limit_for_host(&self, host: &str) -> usize {
+        self.hosts
+            .get(host)
+            .copied()
+            .or(self.per_host)
+            .unwrap_or(DEFAULT_CONNECTIONS_PER_HOST)
+            .max(1)
+    }
+}
+
+/// TLS configuration for HTTPS remotes
+/// Example: { "ca_file": "~/certs/corp-ca.pem", "insecure_hosts": ["gitlab.dev.local"] }
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TlsConfig {
+    /// PEM file with additional CA certificates (e.g., a company's private CA)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<String>,
+    /// Directory of PEM CA certificates (hashed names, as created by `openssl rehash`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_dir: Option<String>,
+    /// Hosts whose certificates are accepted without verification (use with care)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub insecure_hosts: Vec<String>,
+}
+
+/// Substitute the variables in every string of a config value, recording each changed string
+/// by its path (see [`child_path`]) with its new value and its value as written
+fn interpolate_value(
+    value: &mut serde_yaml::Value,
+    ctx: &crate::vars::VarContext,
+    path: &str,
+    interpolated: &mut HashMap<String, (String, String)>,
+) {
+    match value {
+        serde_yaml::Value::String(raw) => {
+            let substituted = ctx.substitute_defined(raw);
+            if substituted != *raw {
+                let raw = std::mem::replace(raw, substituted.clone());
+                interpolated.insert(path.to_string(), (substituted, raw));
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let path = child_path(path, index, item);
+                interpolate_value(item, ctx, &path, interpolated);
+            }
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (key, item) in map.iter_mut() {
+                let path = format!("{}.{}", path, key.as_str().unwrap_or_default());
+                interpolate_value(item, ctx, &path, interpolated);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Path of a sequence item: `[<name>]` for items with a `name` (e.g. repositories), so removing
+/// one doesn't move the others, `[<index>]` otherwise
+fn child_path(path: &str, index: usize, item: &serde_yaml::Value) -> String {
+    match item.get("name").and_then(|name| name.as_str()) {
+        Some(name) => format!("{}[{}]", path, name),
+        None => format!("{}[{}]", path, index),
+    }
+}
+
+/// Put the variable references back into the strings that still hold their interpolated value
+/// at the same path; other strings are kept, even when they have the same text
+fn restore_references(value: &mut serde_yaml::Value, path: &str, interpolated: &HashMap<String, (String, String)>) {
+    match value {
+        serde_yaml::Value::String(text) => {
+            if let Some((substituted, raw)) = interpolated.get(path) {
+                if text == substituted {
+                    *text = raw.clone();
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                let path = child_path(path, index, item);
+                restore_references(item, &path, interpolated);
+            }
+        }
+        serde_yaml::Value::Mapping(map) => {
+            for (key, item) in map.iter_mut() {
+                let path = format!("{}.{}", path, key.as_str().unwrap_or_default());
+                restore_references(item, &path, interpolated);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Check a host against a NO_PROXY-style comma separated list
+/// Entries match the host exactly or as a domain suffix; "*" matches everything
+pub fn matches_no_proxy(no_proxy: &str, host: &str) -> bool {
+    let host = host.to_lowercase();
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().to_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host == entry.trim_start_matches('.')
+                || host.ends_with(&format!(".{}", entry.trim_start_matches('.')))
+        })
+}
+
+/// A named context, e.g. "work" or "oss", sharing the workspace layout
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Profile {
+    /// Names of the repositories in this profile (all repositories when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repositories: Option<Vec<String>>,
+    /// SSH keys per host, taking precedence over the top-level `credentials`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub credentials: HashMap<String, String>,
+    /// Task variables, taking precedence over the top-level `variables`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+}
+
+/// Configuration values as they were before a profile was applied
+#[derive(Debug, Clone, Default)]
+pub struct ProfileBase {
+    pub repositories: Vec<Repository>,
+    pub credentials: HashMap<String, String>,
+    pub variables: HashMap<String, String>,
+}
+
+/// Secrets kept encrypted in `encrypted_secrets`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Secrets {
+    /// SSH key paths per host, like the top-level `credentials`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub credentials: HashMap<String, String>,
+    /// HTTPS tokens per repository name, like a repository's `credential.token`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tokens: HashMap<String, String>,
+}
+
+/// Metadata recorded alongside a saved tag
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagInfo {
+    pub created_at: DateTime<Utc>,
+    /// Git user name of whoever saved the tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Repository {
+    pub name: String,
+    /// Remote URL of origin (defaults to the workspace `url_template`)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+    /// Directory of the repository, relative to the config file or absolute (defaults to the name)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Group used to organize the status view; '/' nests groups (e.g., "backend/services")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Credential override for this repository (takes precedence over the host-level `credentials` map)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<RepoCredential>,
+    /// Pinned revision (branch, tag or commit), e.g. from an imported repo manifest; see `mgit restore --pinned`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    /// Shell executables used for this repository's task steps instead of the workspace `shells`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shells: Option<ShellOverrides>,
+    /// Shell commands run in the repository after `mgit restore` switches its branch
+    /// (e.g., "npm ci"); `${BRANCH}` and `${REPO}` refer to the new branch and the repository
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_checkout: Vec<String>,
+    /// Identity this repository should commit as, overriding the workspace and group `identity`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<Identity>,
+    /// Time after which this repository's fetch or push is given up, overriding the workspace `network_timeout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_timeout: Option<String>,
+    /// Retired repository kept for reference: skipped by status/pull/push/sync/refresh unless `--all-repos`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+}
+
+/// Commit identity (git's user.name and user.email); unset fields are inherited
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct Identity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// Workspace identity with overrides for groups
+/// Example: `{ "name": "Jane Doe", "email": "jane@acme.com", "groups": { "oss": { "email": "jane@home.me" } } }`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct IdentityConfig {
+    #[serde(flatten)]
+    pub default: Identity,
+    /// Identities of groups, applied to their nested groups too: maps group to identity
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub groups: HashMap<String, Identity>,
+}
+
+/// Per-repository credential, for workspaces mixing accounts on the same host
+/// Example: { "ssh_key": "~/.ssh/id_work" } or { "keychain": "work-account" }
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RepoCredential {
+    /// SSH private key path used instead of the host's configured key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<String>,
+    /// HTTPS access token (prefer `keychain` to keep tokens out of the config file)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Name under which the token/passphrase is stored in the OS keychain
+    /// (via `mgit credential set <name>`), used instead of the hostname
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keychain: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Task {
+    pub name: String,
+    pub steps: Vec<TaskStep>,
+    /// Comes from the global configuration: runnable in every workspace, never saved in the project's
+    #[serde(skip)]
+    pub global: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskStep {
+    #[serde(rename = "type", default = "default_type")]
+    pub step_type: String,
+    pub repo: String,
+    /// Left out when the step uses a template
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cmd: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Name of a step template the step is based on (see `Config::step_templates`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Values of the template's `{param}` placeholders
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, String>,
+    /// Platform(s) this step should run on: "windows", "linux", "macos", or "all" (default)
+    #[serde(default = "default_platform")]
+    pub platform: String,
+    /// Shell executables for this step, taking precedence over the repository's and workspace's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shells: Option<ShellOverrides>,
+    /// Run with mgit's own stdin, stdout and stderr (the terminal), for scripts that ask for input
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interactive: bool,
+    /// A failure of the step is reported but doesn't stop the task
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_failure: bool,
+}
+
+/// A step without its repository, shared by the steps that name it as their `template`
+/// `{param}` in `cmd`, `args` and `platform` is replaced by the step's value for `param`,
+/// `{repo}` by the step's repository.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StepTemplate {
+    #[serde(rename = "type", default = "default_type")]
+    pub step_type: String,
+    pub cmd: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "default_platform")]
+    pub platform: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shells: Option<ShellOverrides>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interactive: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_failure: bool,
+}
+
+fn default_type() -> String {
+    String::new() // Empty string means infer from extension
+}
+
+fn default_platform() -> String {
+    "all".to_string()
+}
+
+impl TaskStep {
+    /// Check if this step should run on the current platform
+    #[allow(dead_code)]
+    pub fn should_run_on_current_platform(&self) -> bool {
+        if self.platform == "all" {
+            return true;
+        }
+
+        let current_platform = std::env::consts::OS;
+
+        // Handle comma-separated platform list (e.g., "windows,linux")
+        self.platform
+            .split(',')
+            .map(|s| s.trim())
+            .any(|p| p == current_platform || p == "all")
+    }
+}
+
+impl Config {
+    /// Get the path to the global configuration file
+    /// `$XDG_CONFIG_HOME/mgit/config.yaml`, or the legacy `~/.mgitconfig.yaml` while only that one exists
+    pub fn global_config_path() -> Option<std::path::PathBuf> {
+        let path = crate::paths::global_config_file();
+        if path.as_ref().is_some_and(|p| p.exists()) {
+            return path;
+        }
+        crate::paths::legacy_global_config_file()
+            .filter(|legacy| legacy.exists())
+            .or(path)
+    }
+
+    /// Repositories the everyday commands work on: all but the archived ones, unless `include_archived`
+    pub fn active_repositories(&self, include_archived: bool) -> impl Iterator<Item = &Repository> {
+        self.repositories.iter().filter(move |r| include_archived || !r.archived)
+    }
+
+    /// Every repository in the config file, including those an applied profile leaves out
+    pub fn configured_repositories(&self) -> &[Repository] {
+        match &self.active_profile {
+            Some((_, base)) => &base.repositories,
+            None => &self.repositories,
+        }
+    }
+
+    /// Resolve a repository path relative to the config file's directory
+    /// The repository's `path` is used when set, otherwise the directory is named after the repository.
+    /// `path` may point outside the workspace ("../sibling", "/mnt/data/api"); the repository keeps
+    /// its name in the state database and the output either way.
+    /// If config_dir is not set, returns the path as-is
+    pub fn resolve_repo_path(&self, repo_name: &str) -> std::path::PathBuf {
+        let custom_path = self
+            .repositories
+            .iter()
+            .find(|r| r.name == repo_name)
+            .and_then(|r| r.path.as_deref());
+        if let Some(path) = custom_path {
+            return self.resolve_path(path);
+        }
+        if let Some(config_dir) = &self.config_dir {
+            config_dir.join(repo_name)
+        } else {
+            std::path::PathBuf::from(repo_name)
+        }
+    }
+
+    /// Resolve a path from the config file relative to the config file's directory
+    /// Absolute paths and paths starting with ~ are returned as-is (with ~ expanded)
+    /// `.` and `..` are resolved, so "../sibling" becomes the sibling of the workspace directory.
+    pub fn resolve_path(&self, path: &str) -> std::path::PathBuf {
+        if let Some(rest) = path.strip_prefix("~/") {
+            if let Some(home) = dirs::home_dir() {
+                return home.join(rest);
+            }
+        }
+        match &self.config_dir {
+            Some(config_dir) => crate::paths::normalize_path(&config_dir.join(path)),
+            None => crate::paths::normalize_path(std::path::Path::new(path)),
+        }
+    }
+
+    /// Directory of a repository that lives outside the workspace (its `path` leads out of the
+    /// config file's directory), or None for repositories inside it
+    pub fn external_repo_path(&self, repo_name: &str) -> Option<std::path::PathBuf> {
+        let absolute = |path: &std::path::Path| {
+            crate::paths::normalize_path(&std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
+        };
+        let root = absolute(self.config_dir.as_deref().unwrap_or(std::path::Path::new(".")));
+        let path = absolute(&self.resolve_repo_path(repo_name));
+        (!path.starts_with(&root)).then_some(path)
+    }
+
+    /// Dotenv files read into the task variables; files that don't exist are skipped
+    pub fn env_file_paths(&self) -> Vec<std::path::PathBuf> {
+        match &self.env_files {
+            Some(files) => files.iter().map(|f| self.resolve_path(f)).collect(),
+            None => vec![self.resolve_path(".env")],
+        }
+    }
+
+    /// Get the state database of the workspace: the one given by MGIT_DB (set by `--db`), else `db`
+    /// in its XDG cache directory, or the legacy ".mgitdb" next to .mgitconfig.yaml while only that one exists
+    pub fn get_db_path(&self) -> std::path::PathBuf {
+        use crate::paths::{workspace_cache_dir, LEGACY_DB_NAME};
+
+        if let Some(path) = Self::db_override() {
+            return path;
+        }
+
+        let Some(config_dir) = &self.config_dir else {
+            return std::path::PathBuf::from(LEGACY_DB_NAME);
+        };
+        let legacy = config_dir.join(LEGACY_DB_NAME);
+        match workspace_cache_dir(config_dir).map(|dir| dir.join("db")) {
+            Some(db) if db.exists() || !legacy.exists() => db,
+            _ => legacy,
+        }
+    }
+
+    /// State database given by the MGIT_DB environment variable (set by `--db`), made absolute
+    pub fn db_override() -> Option<std::path::PathBuf> {
+        let path = std::path::PathBuf::from(std::env::var_os("MGIT_DB").filter(|p| !p.is_empty())?);
+        if path.is_absolute() {
+            Some(path)
+        } else {
+            std::env::current_dir().ok().map(|cwd| cwd.join(path))
+        }
+    }
+
+    /// Config file given by the MGIT_CONFIG environment variable (set by `--config`), made absolute
+    pub fn config_override() -> Option<std::path::PathBuf> {
+        let path = std::env::var_os("MGIT_CONFIG").filter(|p| !p.is_empty())?;
+        let path = std::path::PathBuf::from(path);
+        if path.is_absolute() {
+            Some(path)
+        } else {
+            std::env::current_dir().ok().map(|cwd| cwd.join(path))
+        }
+    }
+
+    /// Search for .mgitconfig.yaml starting from current directory and walking up
+    /// Stops at $HOME (does not use $HOME/.mgitconfig.yaml as project config).
+    /// MGIT_CONFIG bypasses the search.
+    pub fn find_project_config() -> Option<std::path::PathBuf> {
+        use std::env;
+
+        if let Some(path) = Self::config_override() {
+            return path.exists().then_some(path);
+        }
+
+        // Get home directory to know when to stop
+        let home_dir = dirs::home_dir()?;
+
+        // Start from current directory
+        let mut current_dir = env::current_dir().ok()?;
+
+        loop {
+            // Check if .mgitconfig.yaml exists in current directory
+            let config_path = current_dir.join(".mgitconfig.yaml");
+            if config_path.exists() {
+                // Don't use $HOME/.mgitconfig.yaml as project config
+                if current_dir != home_dir {
+                    return Some(config_path);
+                }
+            }
+
+            // Try to go up one directory
+            match current_dir.parent() {
+                Some(parent) => {
+                    // Stop if we've reached home directory
+                    if current_dir == home_dir {
+                        break;
+                    }
+                    current_dir = parent.to_path_buf();
+                }
+                None => break, // Reached filesystem root
+            }
+        }
+
+        None
+    }
+
+    /// Load configuration by discovering project config (searching upward from current directory)
+    /// Falls back to global config if no project config is found
+    pub fn load_from_project() -> anyhow::Result<Self> {
+        if let Some(path) = Self::config_override() {
+            if !path.exists() {
+                anyhow::bail!("Configuration file '{}' (from --config/MGIT_CONFIG) not found", path.display());
+            }
+        }
+
+        // Try to find project config by searching upward
+        if let Some(project_config_path) = Self::find_project_config() {
+            // Use the discovered project config path
+            return Self::load(project_config_path.to_str().unwrap_or(".mgitconfig.yaml"));
+        }
+
+        // No project config found - error out
+        anyhow::bail!("No .mgitconfig.yaml found in current directory or parent directories.\nRun 'mgit init' to create one.")
+    }
+
+    /// Load configuration with fallback hierarchy:
+    /// 1. Try local project config
+    /// 2. If not found or if only loading shells, try global config
+    /// 3. Fall back to defaults
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        // Get the directory containing the config file for resolving relative paths
+        let config_path = std::path::Path::new(path);
+        let config_dir = config_path.parent().map(|p| p.to_path_buf());
+
+        // Try to load local config
+        let local_config = if config_path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            let mut config: Config = serde_yaml::from_str(&content)?;
+            config.config_dir = config_dir.clone();
+            Some(config)
+        } else {
+            None
+        };
+
+        // Try to load global config for shell settings
+        let global_config = if let Some(global_path) = Self::global_config_path() {
+            if global_path.exists() {
+                match std::fs::read_to_string(&global_path) {
+                    Ok(content) => serde_yaml::from_str::<Config>(&content).ok(),
+                    Err(_) => None,
+                }
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        // Merge configurations: local takes precedence, but use global shells and credentials if local doesn't specify
+        match (local_config, global_config) {
+            (Some(mut local), Some(global)) => {
+                // If local config has default shells, use global shells
+                if local.shells.sh == "sh" && global.shells.sh != "sh" {
+                    local.shells.sh = global.shells.sh;
+                }
+                if local.shells.cmd == "cmd" && global.shells.cmd != "cmd" {
+                    local.shells.cmd = global.shells.cmd;
+                }
+                if local.shells.powershell == "powershell" && global.shells.powershell != "powershell" {
+                    local.shells.powershell = global.shells.powershell;
+                }
+                // Merge credentials from global config (global credentials as fallback)
+                for (host, key_path) in global.credentials {
+                    local.credentials.entry(host).or_insert(key_path);
+                }
+                // Use global proxy settings if the project doesn't define any
+                if local.proxy.is_none() {
+                    local.proxy = global.proxy;
+                }
+                if local.tls.is_none() {
+                    local.tls = global.tls;
+                }
+                local.ssh_multiplex |= global.ssh_multiplex;
+                if local.concurrency.is_none() {
+                    local.concurrency = global.concurrency;
+                }
+                if local.icons.is_none() {
+                    local.icons = global.icons;
+                }
+                if local.timestamps.is_default() {
+                    local.timestamps = global.timestamps;
+                }
+                if local.cache_ttl.is_none() {
+                    local.cache_ttl = global.cache_ttl;
+                }
+                if local.stale_after.is_none() {
+                    local.stale_after = global.stale_after;
+                }
+                for (host, kind) in global.forges {
+                    local.forges.entry(host).or_insert(kind);
+                }
+                for (host, port) in global.ssh_ports {
+                    local.ssh_ports.entry(host).or_insert(port);
+                }
+                for (host, client_id) in global.oauth_client_ids {
+                    local.oauth_client_ids.entry(host).or_insert(client_id);
+                }
+                if local.identity.is_none() {
+                    local.identity = global.identity;
+                }
+                if local.ci_ttl.is_none() {
+                    local.ci_ttl = global.ci_ttl;
+                }
+                if local.stats.is_none() {
+                    local.stats = global.stats;
+                }
+                if local.pull_commits.is_none() {
+                    local.pull_commits = global.pull_commits;
+                }
+                if local.autostash.is_none() {
+                    local.autostash = global.autostash;
+                }
+                if local.prune.is_none() {
+                    local.prune = global.prune;
+                }
+                if local.network_timeout.is_none() {
+                    local.network_timeout = global.network_timeout;
+                }
+                if local.backend.is_default() {
+                    local.backend = global.backend;
+                }
+                if local.credential_prompt.is_default() {
+                    local.credential_prompt = global.credential_prompt;
+                }
+                // Merge users from global config (global users as fallback)
+                for (canonical, aliases) in global.users {
+                    local.users.entry(canonical).or_insert(aliases);
+                }
+                if local.age_identity.is_none() {
+                    local.age_identity = global.age_identity;
+                }
+                for (name, value) in global.variables {
+                    local.variables.entry(name).or_insert(value);
+                }
+                for (name, profile) in global.profiles {
+                    local.profiles.entry(name).or_insert(profile);
+                }
+                for (name, template) in global.step_templates {
+                    local.step_templates.entry(name).or_insert(template);
+                }
+                for (alias, target) in global.task_aliases {
+                    local.task_aliases.entry(alias).or_insert(target);
+                }
+                local.interpolate()?;
+                // Global tasks come after the project's, which win when both have a task of the same name;
+                // added after the interpolation, whose round trip through YAML would lose their `global` flag
+                for mut task in global.tasks {
+                    if !local.tasks.iter().any(|t| t.name == task.name) {
+                        task.global = true;
+                        local.tasks.push(task);
+                    }
+                }
+                local.expand_url_template()?;
+                local.unlock_secrets()?;
+                local.apply_profile_from_env()?;
+                Ok(local)
+            }
+            (Some(mut local), None) => {
+                local.interpolate()?;
+                local.expand_url_template()?;
+                local.unlock_secrets()?;
+                local.apply_profile_from_env()?;
+                Ok(local)
+            }
+            (None, _) => anyhow::bail!("Configuration file '{}' not found", path),
+        }
+    }
+
+    /// Substitute the `$(NAME)`/`${NAME}` references in the config values with the variables known
+    /// at load time: env files, environment, predefined and config variables
+    /// Tasks and step templates are left to `mgit run`, so `-D` defines can override them;
+    /// references to unknown variables are kept as written.
+    fn interpolate(&mut self) -> anyhow::Result<()> {
+        let Some(config_dir) = self.config_dir.clone() else {
+            return Ok(());
+        };
+        let ctx = crate::vars::VarContext::new(&config_dir, &self.env_file_paths(), &self.variables, vec![])?;
+
+        // Task steps are substituted when they run, where `-D` defines take precedence over `variables`
+        let tasks = std::mem::take(&mut self.tasks);
+        let step_templates = std::mem::take(&mut self.step_templates);
+        let mut value = serde_yaml::to_value(&*self)?;
+        let mut interpolated = HashMap::new();
+        interpolate_value(&mut value, &ctx, "", &mut interpolated);
+        if !interpolated.is_empty() {
+            *self = serde_yaml::from_value(value)
+                .map_err(|e| anyhow::anyhow!("Invalid configuration after variable substitution: {}", e))?;
+            self.config_dir = Some(config_dir);
+            self.interpolated = interpolated;
+        }
+        self.tasks = tasks;
+        self.step_templates = step_templates;
+        Ok(())
+    }
+
+    /// Identity a repository should commit as, field by field from its own `identity`, its group's
+    /// (the innermost group first), then the workspace `identity`
+    /// Returns None when no identity is configured for it
+    pub fn expected_identity(&self, repo: &Repository) -> Option<Identity> {
+        let workspace = self.identity.as_ref();
+        let mut layers: Vec<&Identity> = repo.identity.iter().collect();
+        if let (Some(workspace), Some(group)) = (workspace, repo.group.as_deref()) {
+            let group = group.trim_matches('/');
+            let parents = group.match_indices('/').map(|(index, _)| &group[..index]).rev();
+            layers.extend(std::iter::once(group).chain(parents).filter_map(|g| workspace.groups.get(g)));
+        }
+        layers.extend(workspace.map(|w| &w.default));
+
+        let identity = Identity {
+            name: layers.iter().find_map(|layer| layer.name.clone()),
+            email: layers.iter().find_map(|layer| layer.email.clone()),
+        };
+        (identity != Identity::default()).then_some(identity)
+    }
+
+    /// URL of a repository according to `url_template`
+    pub fn template_url(&self, name: &str) -> Option<String> {
+        self.url_template.as_ref().map(|template| template.replace("{name}", name))
+    }
+
+    /// Fill in the `url` of repositories that don't set one from `url_template`
+    fn expand_url_template(&mut self) -> anyhow::Result<()> {
+        let template = self.url_template.clone();
+        for repo in self.repositories.iter_mut().filter(|r| r.url.is_empty()) {
+            let template = template.as_ref().ok_or_else(|| {
+                anyhow::anyhow!("Repository '{}' has no url (set its `url` or a workspace `url_template`)", repo.name)
+            })?;
+            repo.url = template.replace("{name}", &repo.name);
+        }
+        Ok(())
+    }
+
+    /// Decrypt `encrypted_secrets` and merge them into `credentials` and the repositories' tokens
+    /// Without a configured key the secrets stay locked (network operations fall back to other credentials).
+    fn unlock_secrets(&mut self) -> anyhow::Result<()> {
+        let (armored, key) = match (&self.encrypted_secrets, crate::secrets::SecretsKey::from_config(self)) {
+            (Some(armored), Some(key)) => (armored.clone(), key),
+            _ => return Ok(()),
+        };
+        let secrets = crate::secrets::decrypt_secrets(&armored, &key)
+            .map_err(|e| anyhow::anyhow!("Could not decrypt encrypted_secrets: {}", e))?;
+        self.merge_secrets(secrets);
+        Ok(())
+    }
+
+    /// Merge decrypted secrets into `credentials` and the repositories' tokens
+    pub fn merge_secrets(&mut self, secrets: Secrets) {
+        for (host, key_path) in &secrets.credentials {
+            self.credentials.insert(host.clone(), key_path.clone());
+        }
+        for (name, token) in &secrets.tokens {
+            if let Some(repo) = self.repositories.iter_mut().find(|r| &r.name == name) {
+                repo.credential.get_or_insert_with(Default::default).token = Some(token.clone());
+            }
+        }
+        self.decrypted_secrets = Some(secrets);
+    }
+
+    /// Move the plaintext credentials and repository tokens, plus the already decrypted secrets, into one set
+    pub fn collect_secrets(&self) -> Secrets {
+        let mut secrets = self.decrypted_secrets.clone().unwrap_or_default();
+        secrets.credentials.extend(self.credentials.clone());
+        for repo in &self.repositories {
+            if let Some(token) = repo.credential.as_ref().and_then(|c| c.token.clone()) {
+                secrets.tokens.insert(repo.name.clone(), token);
+            }
+        }
+        secrets
+    }
+
+    /// Remove the decrypted secrets from a copy of the config about to be written
+    fn strip_secrets(&mut self) {
+        let Some(secrets) = self.decrypted_secrets.take() else {
+            return;
+        };
+        for (host, key_path) in &secrets.credentials {
+            if self.credentials.get(host) == Some(key_path) {
+                self.credentials.remove(host);
+            }
+        }
+        for repo in &mut self.repositories {
+            let Some(credential) = repo.credential.as_mut() else {
+                continue;
+            };
+            if credential.token.is_some() && credential.token.as_ref() == secrets.tokens.get(&repo.name) {
+                credential.token = None;
+            }
+            if credential.ssh_key.is_none() && credential.token.is_none() && credential.keychain.is_none() {
+                repo.credential = None;
+            }
+        }
+    }
+
+    /// Apply the profile named by the MGIT_PROFILE environment variable (set by `--profile`), if any
+    fn apply_profile_from_env(&mut self) -> anyhow::Result<()> {
+        match std::env::var("MGIT_PROFILE") {
+            Ok(name) if !name.is_empty() => self.apply_profile(&name),
+            _ => Ok(()),
+        }
+    }
+
+    /// Restrict the repositories to the profile's and override credentials and variables with its own
+    pub fn apply_profile(&mut self, name: &str) -> anyhow::Result<()> {
+        let profile = match self.profiles.get(name) {
+            Some(profile) => profile.clone(),
+            None => {
+                let mut names: Vec<&str> = self.profiles.keys().map(|n| n.as_str()).collect();
+                names.sort();
+                anyhow::bail!(
+                    "Profile '{}' not found. Available profiles: {}",
+                    name,
+                    if names.is_empty() { "none".to_string() } else { names.join(", ") }
+                );
+            }
+        };
+
+        let base = ProfileBase {
+            repositories: self.repositories.clone(),
+            credentials: self.credentials.clone(),
+            variables: self.variables.clone(),
+        };
+        if let Some(names) = &profile.repositories {
+            self.repositories.retain(|r| names.contains(&r.name));
+        }
+        self.credentials.extend(profile.credentials);
+        self.variables.extend(profile.variables);
+        self.active_profile = Some((name.to_string(), Box::new(base)));
+        Ok(())
+    }
+
+    /// Tasks `mgit run` runs for `name`: the task of that name, else an alias's target;
+    /// "build:*" stands for every task in the `build` namespace ("build:web", "build:web:prod", ...),
+    /// in configuration order
+    pub fn resolve_tasks(&self, name: &str) -> anyhow::Result<Vec<&Task>> {
+        if let Some(task) = self.tasks.iter().find(|t| t.name == name) {
+            return Ok(vec![task]);
+        }
+        let target = self.task_aliases.get(name).map(String::as_str).unwrap_or(name);
+        let tasks: Vec<&Task> = match target.strip_suffix('*') {
+            Some(prefix) if prefix.is_empty() || prefix.ends_with(':') => {
+                self.tasks.iter().filter(|t| t.name.starts_with(prefix)).collect()
+            }
+            _ => self.tasks.iter().filter(|t| t.name == target).collect(),
+        };
+        if tasks.is_empty() {
+            return Err(match target == name {
+                true => anyhow::anyhow!("Task '{}' not found", name),
+                false => anyhow::anyhow!("Task '{}' (alias '{}') not found", target, name),
+            });
+        }
+        Ok(tasks)
+    }
+
+    /// The step to run for `step`: with a `template`, the template's fields where the step leaves
+    /// them out, and its `{param}` placeholders filled in from the step's `params`
+    pub fn resolve_step(&self, step: &TaskStep) -> anyhow::Result<TaskStep> {
+        let Some(name) = &step.template else {
+            if step.cmd.is_empty() {
+                anyhow::bail!("A step of repository '{}' has neither a cmd nor a template", step.repo);
+            }
+            return Ok(step.clone());
+        };
+        let template = self
+            .step_templates
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("Step template '{}' not found (step of repository '{}')", name, step.repo))?;
+
+        let fill = |text: &str| {
+            let mut text = text.replace("{repo}", &step.repo);
+            for (param, value) in &step.params {
+                text = text.replace(&format!("{{{}}}", param), value);
+            }
+            text
+        };
+        let mut resolved = step.clone();
+        if resolved.step_type.is_empty() {
+            resolved.step_type = template.step_type.clone();
+        }
+        if resolved.cmd.is_empty() {
+            resolved.cmd = template.cmd.clone();
+        }
+        if resolved.args.is_empty() {
+            resolved.args = template.args.clone();
+        }
+        if resolved.platform == "all" {
+            resolved.platform = template.platform.clone();
+        }
+        if resolved.shells.is_none() {
+            resolved.shells = template.shells.clone();
+        }
+        resolved.interactive |= template.interactive;
+        resolved.allow_failure |= template.allow_failure;
+        resolved.cmd = fill(&resolved.cmd);
+        resolved.args = resolved.args.iter().map(|arg| fill(arg)).collect();
+        resolved.platform = fill(&resolved.platform);
+        Ok(resolved)
+    }
+
+    /// Rename a repository and every reference to it: task steps, saved tags and profiles
+    /// Its directory is not touched; see `resolve_repo_path` for where it is expected afterwards.
+    pub fn rename_repository(&mut self, old: &str, new: &str) -> anyhow::Result<()> {
+        if !self.repositories.iter().any(|r| r.name == old) {
+            anyhow::bail!("Repository '{}' not found in configuration", old);
+        }
+        let base = self.active_profile.as_ref().map(|(_, base)| &base.repositories);
+        if self.repositories.iter().chain(base.into_iter().flatten()).any(|r| r.name == new) {
+            anyhow::bail!("A repository named '{}' already exists", new);
+        }
+
+        let rename = |name: &mut String| {
+            if name == old {
+                *name = new.to_string();
+            }
+        };
+        self.repositories.iter_mut().for_each(|r| rename(&mut r.name));
+        if let Some((_, base)) = self.active_profile.as_mut() {
+            base.repositories.iter_mut().for_each(|r| rename(&mut r.name));
+        }
+        for task in &mut self.tasks {
+            task.steps.iter_mut().for_each(|step| rename(&mut step.repo));
+        }
+        for branches in self.tags.values_mut() {
+            if let Some(branch) = branches.remove(old) {
+                branches.insert(new.to_string(), branch);
+            }
+        }
+        for profile in self.profiles.values_mut() {
+            profile.repositories.iter_mut().flatten().for_each(rename);
+        }
+        Ok(())
+    }
+
+    /// Load only global configuration
+    #[allow(dead_code)]
+    pub fn load_global() -> anyhow::Result<Option<Self>> {
+        if let Some(global_path) = Self::global_config_path() {
+            if global_path.exists() {
+                let content = std::fs::read_to_string(&global_path)?;
+                let config: Config = serde_yaml::from_str(&content)?;
+                return Ok(Some(config));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Icon settings of the workspace config, or else of the global config
+    /// Only the `icons` section is read: no interpolation or secret decryption (and no passphrase prompt).
+    pub fn load_icons() -> Option<IconConfig> {
+        #[derive(Deserialize)]
+        struct IconsOnly {
+            #[serde(default)]
+            icons: Option<IconConfig>,
+        }
+        let read = |path: std::path::PathBuf| {
+            let content = std::fs::read_to_string(path).ok()?;
+            serde_yaml::from_str::<IconsOnly>(&content).ok()?.icons
+        };
+        Self::find_project_config()
+            .and_then(read)
+            .or_else(|| Self::global_config_path().and_then(read))
+    }
+
+    /// Write the configuration to `path`
+    /// With a profile applied, its overrides are left out and repositories outside it are kept;
+    /// decrypted secrets are never written in plain text.
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        let mut config = self.clone();
+        if let Some((_, base)) = &self.active_profile {
+            config.credentials = base.credentials.clone();
+            config.variables = base.variables.clone();
+            config.repositories = base
+                .repositories
+                .iter()
+                .map(|r| self.repositories.iter().find(|p| p.name == r.name).unwrap_or(r).clone())
+                .collect();
+            for repo in &self.repositories {
+                if !base.repositories.iter().any(|r| r.name == repo.name) {
+                    config.repositories.push(repo.clone());
+                }
+            }
+        }
+        config.strip_secrets();
+        config.tasks.retain(|t| !t.global);
+        // URLs that follow the template are left to it
+        for repo in config.repositories.iter_mut() {
+            if self.template_url(&repo.name).as_deref() == Some(repo.url.as_str()) {
+                repo.url.clear();
+            }
+        }
+        let mut value = serde_yaml::to_value(&config)?;
+        if !self.interpolated.is_empty() {
+            restore_references(&mut value, "", &self.interpolated);
+        }
+        let content = serde_yaml::to_string(&value)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Normalize a user name or email to its canonical form
+    /// Returns the canonical name if a match is found, otherwise returns the input unchanged
+    #[allow(dead_code)]
+    pub fn normalize_user(&self, author: &str) -> String {
+        // Check each canonical user and their aliases
+        for (canonical, aliases) in &self.users {
+            // Case-insensitive comparison
+            let author_lower = author.to_lowercase();
+
+            // Check if the author matches the canonical name itself
+            if canonical.to_lowercase() == author_lower {
+                return canonical.clone();
+            }
+
+            // Check if the author matches any alias
+            for alias in aliases {
+                if alias.to_lowercase() == author_lower {
+                    return canonical.clone();
+                }
+            }
+        }
+
+        // No match found, return original
+        author.to_string()
+    }
+
+    /// Check if an author identity (name or email) is already mapped in users config
+    #[allow(dead_code)]
+    pub fn is_author_mapped(&self, author_name: &str, author_email: &str) -> bool {
+        let name_lower = author_name.to_lowercase();
+        let email_lower = author_email.to_lowercase();
+
+        for (canonical, aliases) in &self.users {
+            // Check if name matches canonical
+            if canonical.to_lowercase() == name_lower {
+                return true;
+            }
+
+            // Check if name or email matches any alias
+            for alias in aliases {
+                let alias_lower = alias.to_lowercase();
+                if alias_lower == name_lower || alias_lower == email_lower {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Add new unmapped author identities to the users section
+    /// Uses case-insensitive matching to avoid duplicates
+    /// Returns true if a new entry or alias was added, false if it already existed
+    pub fn add_unmapped_authors(&mut self, name: String, email: String) -> bool {
+        let name_lower = name.to_lowercase();
+        let email_lower = email.to_lowercase();
+
+        // First pass: find which canonical entry (if any) this name/email belongs to
+        let mut target_canonical: Option<String> = None;
+        let mut should_add_email = false;
+        let mut should_add_name = false;
+
+        for (canonical_key, aliases) in &self.users {
+            // Check if name matches canonical key
+            if canonical_key.to_lowercase() == name_lower {
+                target_canonical = Some(canonical_key.clone());
+                // Check if we need to add the email
+                should_add_email = !email_lower.is_empty() &&
+                                 !aliases.iter().any(|a| a.to_lowercase() == email_lower);
+                break;
+            }
+
+            // Check if name matches any alias
+            if aliases.iter().any(|a| a.to_lowercase() == name_lower) {
+                target_canonical = Some(canonical_key.clone());
+                // Check if we need to add the email
+                should_add_email = !email_lower.is_empty() &&
+                                 !aliases.iter().any(|a| a.to_lowercase() == email_lower);
+                break;
+            }
+
+            // Check if email matches any alias (and email is not empty)
+            if !email_lower.is_empty() && aliases.iter().any(|a| a.to_lowercase() == email_lower) {
+                target_canonical = Some(canonical_key.clone());
+                // Check if we need to add the name
+                should_add_name = canonical_key.to_lowercase() != name_lower &&
+                                !aliases.iter().any(|a| a.to_lowercase() == name_lower);
+                break;
+            }
+        }
+
+        // Second pass: perform the mutation if we found a matching entry
+        if let Some(canonical) = target_canonical {
+            let mut added = false;
+            if let Some(aliases) = self.users.get_mut(&canonical) {
+                if should_add_email {
+                    aliases.push(email);
+                    added = true;
+                }
+                if should_add_name {
+                    aliases.push(name);
+                    added = true;
+                }
+            }
+            return added;
+        }
+
+        // Neither name nor email is mapped anywhere, create new entry
+        self.users.insert(name, vec![email]);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The tests loading config files set XDG_CONFIG_HOME to their own global config
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_resolve_tasks() {
+        let task = |name: &str| Task { name: name.to_string(), steps: Vec::new(), global: false };
+        let config = Config {
+            tasks: vec![task("build:frontend"), task("test"), task("build:backend"), task("builder")],
+            task_aliases: HashMap::from([
+                ("b".to_string(), "build:*".to_string()),
+                ("fe".to_string(), "build:frontend".to_string()),
+                ("gone".to_string(), "deploy".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let names = |name: &str| -> Vec<String> {
+            config.resolve_tasks(name).unwrap().iter().map(|t| t.name.clone()).collect()
+        };
+
+        assert_eq!(names("test"), ["test"]);
+        assert_eq!(names("build:*"), ["build:frontend", "build:backend"]);
+        assert_eq!(names("b"), ["build:frontend", "build:backend"]);
+        assert_eq!(names("fe"), ["build:frontend"]);
+        assert!(config.resolve_tasks("build").is_err());
+        assert!(config.resolve_tasks("deploy:*").is_err());
+        assert!(config.resolve_tasks("gone").unwrap_err().to_string().contains("alias 'gone'"));
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let mut config: Config = serde_yaml::from_str(
+            r#"
+repositories:
+  - { name: api, url: u }
+  - { name: web, url: u }
+  - { name: blog, url: u }
+credentials:
+  github.com: ~/.ssh/personal
+  gitlab.com: ~/.ssh/gitlab
+variables: { ENV: dev, REGION: eu }
+profiles:
+  work:
+    repositories: [api, web]
+    credentials: { github.com: ~/.ssh/work }
+    variables: { ENV: prod }
+"#,
+        )
+        .unwrap();
+        assert!(config.apply_profile("oss").unwrap_err().to_string().contains("Available profiles: work"));
+
+        config.apply_profile("work").unwrap();
+        let names = |repos: &[Repository]| repos.iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&config.repositories), ["api", "web"]);
+        assert_eq!(names(config.configured_repositories()), ["api", "web", "blog"]);
+        assert_eq!(config.credentials["github.com"], "~/.ssh/work");
+        assert_eq!(config.credentials["gitlab.com"], "~/.ssh/gitlab");
+        assert_eq!((config.variables["ENV"].as_str(), config.variables["REGION"].as_str()), ("prod", "eu"));
+        assert_eq!(config.active_profile.as_ref().map(|(name, _)| name.as_str()), Some("work"));
+    }
+
+    #[test]
+    fn test_save_leaves_out_global_tasks() {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("mgit-test-global-tasks-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("home/mgit")).unwrap();
+        std::fs::write(dir.join("home/mgit/config.yaml"), "tasks:\n  - { name: hello, steps: [] }\n").unwrap();
+        let path = dir.join(".mgitconfig.yaml");
+        std::fs::write(
+            &path,
+            "variables: { ORG: acme }\nrepositories:\n  - { name: api, url: \"git@github.com:$(ORG)/api.git\" }\ntasks:\n  - { name: build, steps: [] }\n",
+        )
+        .unwrap();
+
+        std::env::set_var("XDG_CONFIG_HOME", dir.join("home"));
+        let config = Config::load(path.to_str().unwrap());
+        std::env::remove_var("XDG_CONFIG_HOME");
+        let config = config.unwrap();
+        assert_eq!(config.tasks.iter().map(|t| (t.name.as_str(), t.global)).collect::<Vec<_>>(), [("build", false), ("hello", true)]);
+        assert_eq!(config.repositories[0].url, "git@github.com:acme/api.git");
+
+        config.save(path.to_str().unwrap()).unwrap();
+        let saved: Config = serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(saved.tasks.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), ["build"]);
+        assert_eq!(saved.repositories[0].url, "git@github.com:$(ORG)/api.git");
+    }
+
+    #[test]
+    fn test_load_icons_falls_back_to_global_config() {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("mgit-test-icons-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("home/mgit")).unwrap();
+        std::fs::write(dir.join("home/mgit/config.yaml"), "icons: { ascii: true }\n").unwrap();
+        let path = dir.join(".mgitconfig.yaml");
+        std::env::set_var("XDG_CONFIG_HOME", dir.join("home"));
+        std::env::set_var("MGIT_CONFIG", &path);
+
+        std::fs::write(&path, "icons: { nerd_font: true }\nrepositories: []\n").unwrap();
+        let workspace = Config::load_icons();
+        std::fs::write(&path, "repositories: []\n").unwrap();
+        let global = Config::load_icons();
+
+        std::env::remove_var("MGIT_CONFIG");
+        std::env::remove_var("XDG_CONFIG_HOME");
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(workspace.map(|icons| (icons.nerd_font, icons.ascii)), Some((Some(true), None)));
+        assert_eq!(global.map(|icons| (icons.nerd_font, icons.ascii)), Some((None, Some(true))));
+    }
+
+    #[test]
+    fn test_save_restores_references_by_path() {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join(format!("mgit-test-references-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("home")).unwrap();
+        let path = dir.join(".mgitconfig.yaml");
+        std::fs::write(
+            &path,
+            r#"
+variables: { ORG: acme }
+repositories:
+  - { name: api, url: "git@github.com:$(ORG)/api.git" }
+  - { name: fork, url: "git@github.com:acme/api.git" }
+  - { name: web, url: "git@github.com:$(ORG)/web.git" }
+"#,
+        )
+        .unwrap();
+
+        std::env::set_var("XDG_CONFIG_HOME", dir.join("home"));
+        let config = Config::load(path.to_str().unwrap());
+        std::env::remove_var("XDG_CONFIG_HOME");
+        let mut config = config.unwrap();
+        config.repositories.remove(0);
+        config.save(path.to_str().unwrap()).unwrap();
+        let saved: Config = serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let urls: Vec<&str> = saved.repositories.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["git@github.com:acme/api.git", "git@github.com:$(ORG)/web.git"]);
+    }
+
+    #[test]
+    fn test_resolve_step_from_template() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+step_templates:
+  rust_service:
+    type: exe
+    cmd: cargo
+    args: [build, --release, -p, "{crate}", --target-dir, "../target/{repo}"]
+tasks:
+  - name: build
+    steps:
+      - { repo: billing, template: rust_service, params: { crate: billing-api } }
+      - { repo: users, template: rust_service, args: [test], params: { crate: users } }
+      - { repo: users, template: missing }
+"#,
+        )
+        .unwrap();
+        let steps = &config.tasks[0].steps;
+
+        let billing = config.resolve_step(&steps[0]).unwrap();
+        assert_eq!((billing.step_type.as_str(), billing.cmd.as_str()), ("exe", "cargo"));
+        assert_eq!(billing.args, ["build", "--release", "-p", "billing-api", "--target-dir", "../target/billing"]);
+        assert_eq!(config.resolve_step(&steps[1]).unwrap().args, ["test"]);
+        assert!(config.resolve_step(&steps[2]).is_err());
+    }
+}
@@ -0,0 +1,11 @@
+pub mod config;
+pub mod operation;
+pub mod repo_state;
+pub mod restore;
+pub mod undo;
+
+pub use config::*;
+pub use operation::*;
+pub use repo_state::*;
+pub use restore::*;
+pub use undo::*;
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Receiver of every recorded repository outcome, see [`set_outcome_handler`]
+static OUTCOME_HANDLER: OnceLock<fn(&RepoOutcome)> = OnceLock::new();
+
+/// Register the function that is told about each outcome as soon as it is recorded
+/// (e.g., to report it while the rest of the operation is still running)
+pub fn set_outcome_handler(handler: fn(&RepoOutcome)) {
+    let _ = OUTCOME_HANDLER.set(handler);
+}
+
+/// A command recorded in the operation log (browsed with `mgit log-ops`)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Operation {
+    /// Command with its main argument (e.g., "pull", "restore release-1.0", "run build")
+    pub command: String,
+    /// git user.name (or the OS user) of whoever ran the command
+    pub user: String,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Outcome per repository (or per task step for `run`)
+    #[serde(default)]
+    pub repos: Vec<RepoOutcome>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepoOutcome {
+    pub name: String,
+    pub success: bool,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+impl Operation {
+    pub fn new(command: impl Into<String>, user: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            user: user.into(),
+            started_at: Utc::now(),
+            duration_ms: 0,
+            repos: Vec::new(),
+        }
+    }
+
+    /// Record the outcome of one repository
+    pub fn add_outcome(&mut self, name: &str, success: bool, message: impl Into<String>, duration: Duration) {
+        let outcome = RepoOutcome {
+            name: name.to_string(),
+            success,
+            message: message.into(),
+            duration_ms: duration.as_millis() as u64,
+        };
+        if let Some(handler) = OUTCOME_HANDLER.get() {
+            handler(&outcome);
+        }
+        self.repos.push(outcome);
+    }
+
+    /// Set the total duration from the start time
+    pub fn finish(&mut self) {
+        self.duration_ms = (Utc::now() - self.started_at).num_milliseconds().max(0) as u64;
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.repos.iter().filter(|r| !r.success).count()
+    }
+}
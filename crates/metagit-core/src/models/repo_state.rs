@@ -0,0 +1,256 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepoState {
+    pub name: String,
+    pub current_branch: String,
+    pub last_updated: DateTime<Utc>,
+    pub branches: Vec<BranchInfo>,
+    /// When the commit statistics were last collected by `mgit refresh` (None if never)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed_at: Option<DateTime<Utc>>,
+    /// How the statistics were collected (line counts, merge handling, time window)
+    #[serde(default, skip_serializing_if = "StatsOptions::is_default")]
+    pub stats_options: StatsOptions,
+    /// Latest CI result of branch tips by branch name, from the hosting provider (see `mgit status`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub ci: HashMap<String, CiStatus>,
+    /// Working tree summary as of the last status, refresh, pull, push or sync (read by `mgit prompt`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<WorktreeStatus>,
+}
+
+/// CI result of a commit as reported by the hosting provider
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CiStatus {
+    /// Commit the result belongs to
+    pub sha: String,
+    pub state: CiState,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl CiStatus {
+    /// Whether the result should be fetched again for `sha`: the branch moved, or the result may
+    /// still change (running or no CI yet) and is older than `ttl`
+    pub fn is_outdated(&self, sha: &str, ttl: chrono::Duration) -> bool {
+        self.sha != sha || (!self.state.is_final() && Utc::now() - self.checked_at > ttl)
+    }
+}
+
+/// Combined state of the checks or pipelines of a commit
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CiState {
+    Passed,
+    Failed,
+    Running,
+    /// No checks or pipelines ran
+    None,
+}
+
+impl CiState {
+    /// Finished results don't change for the same commit
+    pub fn is_final(&self) -> bool {
+        matches!(self, CiState::Passed | CiState::Failed)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CiState::Passed => "passed",
+            CiState::Failed => "failed",
+            CiState::Running => "running",
+            CiState::None => "",
+        }
+    }
+}
+
+/// Uncommitted changes and sync state of the current branch
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorktreeStatus {
+    pub dirty: bool,
+    /// Commits to push, against the last fetched state of origin
+    pub ahead: usize,
+    /// Commits to pull, against the last fetched state of origin
+    pub behind: usize,
+}
+
+impl RepoState {
+    /// Whether the cached statistics are older than `max_age` (or were never collected)
+    pub fn is_stale(&self, max_age: chrono::Duration) -> bool {
+        self.refreshed_at
+            .map(|refreshed| Utc::now() - refreshed > max_age)
+            .unwrap_or(true)
+    }
+}
+
+/// Options of `mgit refresh` that shape the statistics
+/// Stored with the state so that `mgit status` updates branches the same way.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsOptions {
+    /// Also count lines added/removed per author (`--lines`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub line_stats: bool,
+    /// Also count merge commits (`stats.merge_commits` in the config)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub merge_commits: bool,
+    /// Only follow the first parent of merges (`stats.first_parent` in the config)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub first_parent: bool,
+    /// How branch owners are determined (`stats.ownership` in the config)
+    #[serde(default, skip_serializing_if = "OwnershipMode::is_default")]
+    pub ownership: OwnershipMode,
+    /// Share of a branch's commits, in percent, from which other authors mark the owner with `*`
+    /// (`stats.owner_threshold` in the config, 5 when not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_threshold: Option<u32>,
+    /// Only count commits made at or after this time (`--since`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+    /// Only count commits made before this time (`--until`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl StatsOptions {
+    pub fn is_default(&self) -> bool {
+        *self == StatsOptions::default()
+    }
+
+    /// Whether a commit made at `time` falls inside the `since`/`until` window
+    pub fn in_window(&self, time: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time < until)
+    }
+}
+
+/// Share of commits (percent) from which other authors are significant contributors of a branch
+pub const DEFAULT_OWNER_THRESHOLD: u32 = 5;
+
+/// How the owner of a branch is determined from its counted commits
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnershipMode {
+    /// The author with the most commits
+    #[default]
+    Commits,
+    /// The author of the most recent commit
+    Recent,
+}
+
+impl OwnershipMode {
+    pub fn is_default(&self) -> bool {
+        *self == OwnershipMode::Commits
+    }
+}
+
+/// Lines added and removed by an author
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineStats {
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl LineStats {
+    pub fn add(&mut self, other: LineStats) {
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BranchInfo {
+    pub name: String,
+    pub owner: String, // Could be "me" or author name
+    pub last_updated: DateTime<Utc>,
+    /// Number of commits per author on this branch
+    #[serde(default)]
+    pub commit_stats: HashMap<String, usize>,
+    /// Lines added/removed per author on this branch (only collected with `StatsOptions::line_stats`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub line_stats: HashMap<String, LineStats>,
+    /// SHA of the last commit we processed (for incremental updates)
+    #[serde(default)]
+    pub last_commit_sha: Option<String>,
+}
+
+impl BranchInfo {
+    /// Calculate the owner based on commit statistics
+    /// The owner is the author with the most commits, or with `OwnershipMode::Recent` the author of
+    /// the most recent counted commit (`latest_author`). A `*` is appended when other authors made at
+    /// least `options.owner_threshold` percent of the commits.
+    pub fn calculate_owner(&self, options: &StatsOptions, latest_author: Option<&str>) -> String {
+        if self.commit_stats.is_empty() {
+            return "Unknown".to_string();
+        }
+
+        let total_commits: usize = self.commit_stats.values().sum();
+        if total_commits == 0 {
+            return "Unknown".to_string();
+        }
+
+        // Find the author with the most commits
+        let mut authors: Vec<_> = self.commit_stats.iter().collect();
+        authors.sort_by(|a, b| b.1.cmp(a.1));
+
+        let primary_author = match (options.ownership, latest_author) {
+            (OwnershipMode::Recent, Some(latest)) => latest,
+            _ => authors[0].0.as_str(),
+        };
+
+        // Check if there are other significant contributors
+        let percent = options.owner_threshold.unwrap_or(DEFAULT_OWNER_THRESHOLD);
+        let threshold = (total_commits as f64 * percent as f64 / 100.0).ceil() as usize;
+        let has_other_contributors = authors
+            .iter()
+            .any(|(author, &count)| author.as_str() != primary_author && count >= threshold.max(1));
+
+        if has_other_contributors {
+            format!("{}*", primary_author)
+        } else {
+            primary_author.to_string()
+        }
+    }
+
+    /// Get the number of commits by the primary owner
+    pub fn get_owner_commit_count(&self) -> usize {
+        if self.commit_stats.is_empty() {
+            return 0;
+        }
+
+        // Find the author with the most commits
+        let mut authors: Vec<_> = self.commit_stats.iter().collect();
+        authors.sort_by(|a, b| b.1.cmp(a.1));
+
+        *authors[0].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branch(commit_stats: &[(&str, usize)]) -> BranchInfo {
+        BranchInfo {
+            name: "feature".to_string(),
+            owner: String::new(),
+            last_updated: Utc::now(),
+            commit_stats: commit_stats.iter().map(|(a, c)| (a.to_string(), *c)).collect(),
+            line_stats: HashMap::new(),
+            last_commit_sha: None,
+        }
+    }
+
+    #[test]
+    fn test_calculate_owner_threshold_and_mode() {
+        let branch = branch(&[("Jane", 90), ("John", 10)]);
+        let options = StatsOptions::default();
+        assert_eq!(branch.calculate_owner(&options, Some("John")), "Jane*");
+
+        let options = StatsOptions { owner_threshold: Some(20), ..Default::default() };
+        assert_eq!(branch.calculate_owner(&options, Some("John")), "Jane");
+
+        let options = StatsOptions { ownership: OwnershipMode::Recent, ..Default::default() };
+        assert_eq!(branch.calculate_owner(&options, Some("John")), "John*");
+    }
+}
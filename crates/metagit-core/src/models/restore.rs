@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A restore that failed for some repositories, retried by `mgit restore --continue`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PendingRestore {
+    /// Restored tag ("--pinned" for pinned revisions)
+    pub tag: String,
+    /// Whether `remaining` holds pinned revisions rather than branch names
+    #[serde(default)]
+    pub pinned: bool,
+    pub created_at: DateTime<Utc>,
+    /// Repositories that failed, with the branch (or revision) they should be on
+    pub remaining: BTreeMap<String, String>,
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Repository HEADs recorded around the last pull/restore, used by `mgit undo`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UndoSnapshot {
+    /// Command that changed the repositories (e.g., "pull", "restore release-1.0")
+    pub command: String,
+    pub created_at: DateTime<Utc>,
+    pub repos: Vec<RepoHead>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepoHead {
+    pub name: String,
+    /// Checked out branch before the operation (None if HEAD was detached)
+    pub branch: Option<String>,
+    /// HEAD commit before the operation
+    pub before: String,
+    /// Branch and HEAD commit after the operation; undo refuses to touch repositories
+    /// that changed since (e.g., new commits) unless forced
+    #[serde(default)]
+    pub after_branch: Option<String>,
+    #[serde(default)]
+    pub after: Option<String>,
+}
@@ -0,0 +1,147 @@
+//! Locations of mgit's user-level files, following the XDG base directory specification
+//!
+//! - global config: `$XDG_CONFIG_HOME/mgit/config.yaml` (default `~/.config/mgit/config.yaml`)
+//! - state databases: `$XDG_CACHE_HOME/mgit/workspaces/<workspace>/db` (default `~/.cache/mgit/...`)
+//!
+//! The legacy `~/.mgitconfig.yaml` and per-workspace `.mgitdb` are still used while they exist;
+//! [`migrate_legacy_files`] moves them to the new locations.
+
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the global config file before XDG support
+const LEGACY_GLOBAL_CONFIG: &str = ".mgitconfig.yaml";
+
+/// Name of the state database inside the workspace before XDG support
+pub const LEGACY_DB_NAME: &str = ".mgitdb";
+
+/// Base directory from an XDG variable (only absolute values count), else `fallback` under the home directory
+/// Windows has no XDG convention; the platform's own directory is used there.
+fn xdg_home(variable: &str, fallback: &str, platform: fn() -> Option<PathBuf>) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(variable).map(PathBuf::from).filter(|dir| dir.is_absolute()) {
+        return Some(dir);
+    }
+    if cfg!(windows) {
+        return platform();
+    }
+    dirs::home_dir().map(|home| home.join(fallback))
+}
+
+/// Directory of the global configuration (`$XDG_CONFIG_HOME/mgit`)
+pub fn config_home() -> Option<PathBuf> {
+    xdg_home("XDG_CONFIG_HOME", ".config", dirs::config_dir).map(|dir| dir.join("mgit"))
+}
+
+/// Directory of mgit's caches (`$XDG_CACHE_HOME/mgit`)
+pub fn cache_home() -> Option<PathBuf> {
+    xdg_home("XDG_CACHE_HOME", ".cache", dirs::cache_dir).map(|dir| dir.join("mgit"))
+}
+
+/// Global config file at its XDG location (whether or not it exists)
+pub fn global_config_file() -> Option<PathBuf> {
+    config_home().map(|dir| dir.join("config.yaml"))
+}
+
+/// Global config file before XDG support (`~/.mgitconfig.yaml`)
+pub fn legacy_global_config_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(LEGACY_GLOBAL_CONFIG))
+}
+
+/// Cache directory of a workspace, named after its directory plus a hash of the full path
+/// so that workspaces with the same directory name don't share it
+pub fn workspace_cache_dir(workspace_dir: &Path) -> Option<PathBuf> {
+    // The same workspace must map to the same directory however its path was spelled
+    let workspace_dir = if workspace_dir.as_os_str().is_empty() { Path::new(".") } else { workspace_dir };
+    let workspace_dir = fs::canonicalize(workspace_dir)
+        .or_else(|_| std::path::absolute(workspace_dir))
+        .unwrap_or_else(|_| workspace_dir.to_path_buf());
+    let name = workspace_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "root".to_string());
+    let key = format!("{}-{:016x}", name, fnv1a(workspace_dir.to_string_lossy().as_bytes()));
+    cache_home().map(|dir| dir.join("workspaces").join(key))
+}
+
+/// FNV-1a: a hash that stays the same across Rust versions, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3))
+}
+
+/// Remove `.` and `..` components without touching the filesystem
+/// `..` at the start of a relative path is kept ("../sibling" stays as it is).
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // The parent of the root is the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            other => normalized.push(other),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Move the legacy global config and the workspace's legacy database to their XDG locations
+/// Files are only moved when nothing exists at the new location yet. Returns a note per moved file;
+/// a file that can't be moved (e.g., across file systems) stays where it is and keeps being used.
+pub fn migrate_legacy_files(workspace_dir: Option<&Path>) -> Vec<String> {
+    let mut moved = Vec::new();
+    let mut migrate = |from: Option<PathBuf>, to: Option<PathBuf>| {
+        let (Some(from), Some(to)) = (from, to) else {
+            return;
+        };
+        if !from.exists() || to.exists() {
+            return;
+        }
+        let renamed = to.parent().is_some_and(|parent| fs::create_dir_all(parent).is_ok()) && fs::rename(&from, &to).is_ok();
+        if renamed {
+            moved.push(format!("Moved {} to {}", from.display(), to.display()));
+        }
+    };
+
+    migrate(legacy_global_config_file(), global_config_file());
+    if let Some(workspace_dir) = workspace_dir {
+        migrate(
+            Some(workspace_dir.join(LEGACY_DB_NAME)),
+            workspace_cache_dir(workspace_dir).map(|dir| dir.join("db")),
+        );
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_cache_dir_is_stable_and_distinct() {
+        let a = workspace_cache_dir(Path::new("/work/a/project")).unwrap();
+        let b = workspace_cache_dir(Path::new("/work/b/project")).unwrap();
+        assert_eq!(a, workspace_cache_dir(Path::new("/work/a/project")).unwrap());
+        assert_ne!(a, b);
+        assert!(a.file_name().unwrap().to_string_lossy().starts_with("project-"));
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(Path::new("/work/project/../data/./api")), Path::new("/work/data/api"));
+        assert_eq!(normalize_path(Path::new("/..")), Path::new("/"));
+        assert_eq!(normalize_path(Path::new("../sibling/x/..")), Path::new("../sibling"));
+        assert_eq!(normalize_path(Path::new("a/../../b")), Path::new("../b"));
+        assert_eq!(normalize_path(Path::new("a/..")), Path::new("."));
+    }
+}
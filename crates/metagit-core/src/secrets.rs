@@ -0,0 +1,123 @@
+use age::armor::{ArmoredReader, ArmoredWriter, Format};
+use age::secrecy::SecretString;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::models::{Config, Secrets};
+
+/// Decrypted secrets by ciphertext: the config is loaded many times per run and
+/// passphrase decryption is deliberately slow
+static DECRYPTED: OnceLock<Mutex<HashMap<String, Secrets>>> = OnceLock::new();
+
+/// Key that encrypts and decrypts the `encrypted_secrets` of a config
+pub enum SecretsKey {
+    /// age identity file, as created by `age-keygen`
+    IdentityFile(PathBuf),
+    /// Passphrase (scrypt)
+    Passphrase(String),
+}
+
+impl SecretsKey {
+    /// Key configured for a workspace: the MGIT_AGE_IDENTITY file, the `age_identity` file,
+    /// or the MGIT_AGE_PASSPHRASE passphrase, in that order
+    pub fn from_config(config: &Config) -> Option<SecretsKey> {
+        if let Some(path) = std::env::var_os("MGIT_AGE_IDENTITY").filter(|p| !p.is_empty()) {
+            return Some(SecretsKey::IdentityFile(PathBuf::from(path)));
+        }
+        if let Some(path) = &config.age_identity {
+            return Some(SecretsKey::IdentityFile(config.resolve_path(path)));
+        }
+        std::env::var("MGIT_AGE_PASSPHRASE")
+            .ok()
+            .filter(|p| !p.is_empty())
+            .map(SecretsKey::Passphrase)
+    }
+
+    fn identity_file(path: &Path) -> Result<age::IdentityFile<age::NoCallbacks>> {
+        age::IdentityFile::from_file(path.to_string_lossy().to_string())
+            .with_context(|| format!("Could not read age identity file {}", path.display()))
+    }
+}
+
+/// Encrypt secrets into an ASCII-armored age file
+pub fn encrypt_secrets(secrets: &Secrets, key: &SecretsKey) -> Result<String> {
+    let plaintext = serde_yaml::to_string(secrets)?;
+
+    let encryptor = match key {
+        SecretsKey::IdentityFile(path) => {
+            let recipients = SecretsKey::identity_file(path)?
+                .to_recipients()
+                .map_err(|e| anyhow!("Invalid age identity file {}: {}", path.display(), e))?;
+            age::Encryptor::with_recipients(recipients.iter().map(|r| r.as_ref() as &dyn age::Recipient))
+                .map_err(|e| anyhow!("Could not encrypt secrets: {}", e))?
+        }
+        SecretsKey::Passphrase(passphrase) => {
+            age::Encryptor::with_user_passphrase(SecretString::from(passphrase.clone()))
+        }
+    };
+
+    let mut ciphertext = Vec::new();
+    let mut writer = encryptor.wrap_output(ArmoredWriter::wrap_output(&mut ciphertext, Format::AsciiArmor)?)?;
+    writer.write_all(plaintext.as_bytes())?;
+    writer.finish()?.finish()?;
+
+    Ok(String::from_utf8(ciphertext)?)
+}
+
+/// Decrypt the ASCII-armored age file written by [`encrypt_secrets`]
+pub fn decrypt_secrets(armored: &str, key: &SecretsKey) -> Result<Secrets> {
+    let cache = DECRYPTED.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(secrets) = cache.lock().unwrap().get(armored) {
+        return Ok(secrets.clone());
+    }
+
+    let decryptor = age::Decryptor::new_buffered(ArmoredReader::new(armored.as_bytes()))
+        .map_err(|e| anyhow!("Invalid encrypted secrets: {}", e))?;
+    let identities: Vec<Box<dyn age::Identity>> = match key {
+        SecretsKey::IdentityFile(path) => SecretsKey::identity_file(path)?
+            .into_identities()
+            .map_err(|e| anyhow!("Invalid age identity file {}: {}", path.display(), e))?,
+        SecretsKey::Passphrase(passphrase) => {
+            vec![Box::new(age::scrypt::Identity::new(SecretString::from(passphrase.clone())))]
+        }
+    };
+
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i.as_ref()))
+        .map_err(|e| anyhow!("Could not decrypt secrets: {}", e))?;
+    let mut plaintext = String::new();
+    reader.read_to_string(&mut plaintext)?;
+    let secrets: Secrets = serde_yaml::from_str(&plaintext)?;
+
+    cache.lock().unwrap().insert(armored.to_string(), secrets.clone());
+    Ok(secrets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use age::secrecy::ExposeSecret;
+
+    #[test]
+    fn test_secrets_round_trip_with_identity_file() {
+        let identity = age::x25519::Identity::generate();
+        let path = std::env::temp_dir().join(format!("mgit-test-identity-{}.txt", std::process::id()));
+        std::fs::write(&path, format!("{}\n", identity.to_string().expose_secret())).unwrap();
+
+        let secrets = Secrets {
+            credentials: HashMap::from([("github.com".to_string(), "~/.ssh/id_work".to_string())]),
+            tokens: HashMap::from([("api".to_string(), "ghp_secret".to_string())]),
+        };
+        let key = SecretsKey::IdentityFile(path.clone());
+        let armored = encrypt_secrets(&secrets, &key).unwrap();
+        assert!(armored.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+        assert!(!armored.contains("ghp_secret"));
+
+        let decrypted = decrypt_secrets(&armored, &key).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(decrypted, secrets);
+    }
+}
@@ -1,44 +1,112 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use std::collections::HashMap;
 use std::env;
+use std::path::PathBuf;
+
+/// Where a variable comes from, from lowest to highest precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VarSource {
+    /// A dotenv file from `env_files`
+    EnvFile,
+    Environment,
+    /// CWD, PROJECT_DIR and HOME
+    Predefined,
+    /// `variables` in the config (including the active profile's)
+    Config,
+    /// `-D NAME=value`
+    Define,
+}
+
+impl VarSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VarSource::EnvFile => "env file",
+            VarSource::Environment => "environment",
+            VarSource::Predefined => "predefined",
+            VarSource::Config => "config",
+            VarSource::Define => "-D",
+        }
+    }
+}
+
+/// Parse a dotenv file: `NAME=value` lines, optionally prefixed with `export`;
+/// blank lines and `#` comments are skipped and matching quotes around a value are removed
+pub fn parse_env_file(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=')?;
+            let value = value.trim();
+            let unquoted = ['"', '\'']
+                .iter()
+                .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+                .unwrap_or(value);
+            Some((name.trim().to_string(), unquoted.to_string()))
+        })
+        .collect()
+}
 
 /// Variable context that holds all available variables for substitution
 #[derive(Debug)]
 pub struct VarContext {
     vars: HashMap<String, String>,
+    /// Every source that defined a variable, in precedence order (the last one wins)
+    sources: HashMap<String, Vec<VarSource>>,
 }
 
 impl VarContext {
-    /// Create a new variable context with:
+    /// Create a new variable context with, from lowest to highest precedence:
+    /// - Variables from the dotenv `env_files` that exist
     /// - All current environment variables
     /// - Predefined variables (CWD, PROJECT_DIR, HOME)
+    /// - Variables from the config (`variables`, including the active profile's)
     /// - User-defined variables from -D flags
     pub fn new(
         project_dir: &std::path::Path,
+        env_files: &[PathBuf],
+        config_variables: &HashMap<String, String>,
         user_defines: Vec<String>,
     ) -> Result<Self> {
-        let mut vars = HashMap::new();
+        let mut ctx = Self { vars: HashMap::new(), sources: HashMap::new() };
+
+        // Dotenv files only provide defaults
+        for path in env_files.iter().filter(|path| path.exists()) {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Could not read env file {}", path.display()))?;
+            for (key, value) in parse_env_file(&content) {
+                ctx.set(key, value, VarSource::EnvFile);
+            }
+        }
 
         // Add all environment variables
         for (key, value) in env::vars() {
-            vars.insert(key, value);
+            ctx.set(key, value, VarSource::Environment);
         }
 
         // Add predefined variables
         // CWD - current working directory where mgit was invoked
         if let Ok(cwd) = env::current_dir() {
-            vars.insert("CWD".to_string(), cwd.to_string_lossy().to_string());
+            ctx.set("CWD".to_string(), cwd.to_string_lossy().to_string(), VarSource::Predefined);
         }
 
         // PROJECT_DIR - location of .mgitconfig.yaml
-        vars.insert(
+        ctx.set(
             "PROJECT_DIR".to_string(),
             project_dir.to_string_lossy().to_string(),
+            VarSource::Predefined,
         );
 
         // HOME - user's home directory (also available from env, but ensure it's set)
         if let Some(home) = dirs::home_dir() {
-            vars.insert("HOME".to_string(), home.to_string_lossy().to_string());
+            ctx.set("HOME".to_string(), home.to_string_lossy().to_string(), VarSource::Predefined);
+        }
+
+        // Variables from the config file
+        for (key, value) in config_variables {
+            ctx.set(key.clone(), value.clone(), VarSource::Config);
         }
 
         // Parse user-defined variables from -D flags
@@ -50,10 +118,25 @@ impl VarContext {
                     define
                 ));
             }
-            vars.insert(parts[0].to_string(), parts[1].to_string());
+            ctx.set(parts[0].to_string(), parts[1].to_string(), VarSource::Define);
         }
 
-        Ok(Self { vars })
+        Ok(ctx)
+    }
+
+    fn set(&mut self, key: String, value: String, source: VarSource) {
+        self.sources.entry(key.clone()).or_default().push(source);
+        self.vars.insert(key, value);
+    }
+
+    /// Sources that defined a variable, the one whose value is used last
+    pub fn sources(&self, key: &str) -> &[VarSource] {
+        self.sources.get(key).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// All variables available for substitution
+    pub fn vars(&self) -> &HashMap<String, String> {
+        &self.vars
     }
 
     /// Substitute variables in a string
@@ -80,21 +163,43 @@ impl VarContext {
             iteration += 1;
 
             // Pattern 1: $(VAR)
-            result = self.substitute_pattern(&result, "$(", ")", &mut changed)?;
+            result = self.substitute_pattern(&result, "$(", ")", true, &mut changed)?;
 
             // Pattern 2: ${VAR}
-            result = self.substitute_pattern(&result, "${", "}", &mut changed)?;
+            result = self.substitute_pattern(&result, "${", "}", true, &mut changed)?;
         }
 
         Ok(result)
     }
 
+    /// Substitute the variables that are defined and keep every other reference as written
+    /// Used for config values, whose task arguments may still refer to `-D` variables.
+    pub fn substitute_defined(&self, input: &str) -> String {
+        let mut result = input.to_string();
+        let mut changed = true;
+        let mut iteration = 0;
+
+        while changed && iteration < 10 {
+            changed = false;
+            iteration += 1;
+            for (start_marker, end_marker) in [("$(", ")"), ("${", "}")] {
+                if let Ok(substituted) = self.substitute_pattern(&result, start_marker, end_marker, false, &mut changed) {
+                    result = substituted;
+                }
+            }
+        }
+
+        result
+    }
+
     /// Helper function to substitute a specific pattern (either $(...) or ${...})
+    /// Unless `strict`, undefined and unclosed references are kept instead of failing.
     fn substitute_pattern(
         &self,
         input: &str,
         start_marker: &str,
         end_marker: &str,
+        strict: bool,
         changed: &mut bool,
     ) -> Result<String> {
         let mut result = String::new();
@@ -113,6 +218,8 @@ impl VarContext {
                 if let Some(value) = self.vars.get(var_name) {
                     result.push_str(value);
                     *changed = true;
+                } else if !strict {
+                    result.push_str(&remaining[start_pos..start_pos + start_marker.len() + end_pos + end_marker.len()]);
                 } else {
                     return Err(anyhow!(
                         "Undefined variable: {}{}{}",
@@ -124,6 +231,9 @@ impl VarContext {
 
                 // Move past the closing marker
                 remaining = &after_marker[end_pos + end_marker.len()..];
+            } else if !strict {
+                remaining = &remaining[start_pos..];
+                break;
             } else {
                 return Err(anyhow!(
                     "Unclosed variable reference: {}",
@@ -153,7 +263,7 @@ mod tests {
     #[test]
     fn test_basic_substitution() {
         let project_dir = Path::new("/project");
-        let ctx = VarContext::new(project_dir, vec![
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![
             "VAR1=value1".to_string(),
             "VAR2=value2".to_string(),
         ])
@@ -170,7 +280,7 @@ mod tests {
     #[test]
     fn test_predefined_vars() {
         let project_dir = Path::new("/project");
-        let ctx = VarContext::new(project_dir, vec![]).unwrap();
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![]).unwrap();
 
         let result = ctx.substitute("$(PROJECT_DIR)").unwrap();
         assert!(result.contains("project"));
@@ -182,7 +292,7 @@ mod tests {
     #[test]
     fn test_tilde_expansion() {
         let project_dir = Path::new("/project");
-        let ctx = VarContext::new(project_dir, vec![]).unwrap();
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![]).unwrap();
 
         let result = ctx.substitute("~/Documents").unwrap();
         assert!(!result.starts_with("~"));
@@ -192,7 +302,7 @@ mod tests {
     #[test]
     fn test_mixed_syntax() {
         let project_dir = Path::new("/project");
-        let ctx = VarContext::new(project_dir, vec![
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![
             "A=hello".to_string(),
             "B=world".to_string(),
         ])
@@ -204,7 +314,7 @@ mod tests {
     #[test]
     fn test_undefined_variable() {
         let project_dir = Path::new("/project");
-        let ctx = VarContext::new(project_dir, vec![]).unwrap();
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![]).unwrap();
 
         let result = ctx.substitute("$(UNDEFINED_VAR)");
         assert!(result.is_err());
@@ -217,7 +327,7 @@ mod tests {
     #[test]
     fn test_unclosed_variable() {
         let project_dir = Path::new("/project");
-        let ctx = VarContext::new(project_dir, vec![]).unwrap();
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![]).unwrap();
 
         let result = ctx.substitute("$(UNCLOSED");
         assert!(result.is_err());
@@ -227,10 +337,21 @@ mod tests {
             .contains("Unclosed variable"));
     }
 
+    #[test]
+    fn test_substitute_defined_keeps_unknown_references() {
+        let project_dir = Path::new("/project");
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![]).unwrap();
+
+        assert_eq!(
+            ctx.substitute_defined("$(PROJECT_DIR)/keys ${NOT_DEFINED_ANYWHERE} $(git rev-parse HEAD) ${open"),
+            "/project/keys ${NOT_DEFINED_ANYWHERE} $(git rev-parse HEAD) ${open"
+        );
+    }
+
     #[test]
     fn test_invalid_define_format() {
         let project_dir = Path::new("/project");
-        let result = VarContext::new(project_dir, vec!["INVALID".to_string()]);
+        let result = VarContext::new(project_dir, &[], &HashMap::new(), vec!["INVALID".to_string()]);
 
         assert!(result.is_err());
         assert!(result
@@ -239,13 +360,28 @@ mod tests {
             .contains("Invalid variable definition"));
     }
 
+    #[test]
+    fn test_env_file_precedence() {
+        let project_dir = Path::new("/project");
+        let path = env::temp_dir().join(format!("mgit-test-{}.env", std::process::id()));
+        std::fs::write(&path, "# defaults\nexport FROM_FILE=\"file value\"\nOVERRIDDEN=file\n\nHOME=/nowhere\n").unwrap();
+        let config_variables = HashMap::from([("OVERRIDDEN".to_string(), "config".to_string())]);
+
+        let ctx = VarContext::new(project_dir, std::slice::from_ref(&path), &config_variables, vec![]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ctx.substitute("$(FROM_FILE)").unwrap(), "file value");
+        assert_eq!(ctx.substitute("$(OVERRIDDEN)").unwrap(), "config");
+        assert_eq!(ctx.sources("OVERRIDDEN"), [VarSource::EnvFile, VarSource::Config]);
+        assert_ne!(ctx.substitute("$(HOME)").unwrap(), "/nowhere");
+    }
+
     #[test]
     fn test_env_vars() {
         let project_dir = Path::new("/project");
         // Set a test environment variable
         env::set_var("TEST_VAR_12345", "test_value");
 
-        let ctx = VarContext::new(project_dir, vec![]).unwrap();
+        let ctx = VarContext::new(project_dir, &[], &HashMap::new(), vec![]).unwrap();
         assert_eq!(ctx.substitute("$(TEST_VAR_12345)").unwrap(), "test_value");
 
         // Clean up
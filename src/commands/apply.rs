@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use std::path::Path;
+use std::time::Instant;
+
+use metagit_core::models::Config;
+use crate::utils::{has_uncommitted_changes, icons, record_operation, run_git, start_operation};
+
+/// Apply a unified diff to every repository (or the given ones) with `git apply`
+/// With `check`, only reports whether the patch applies; with `three_way`, falls back to a 3-way merge;
+/// with `message`, the applied changes are committed
+pub fn apply_command(
+    patch: &str,
+    repos: &[String],
+    check: bool,
+    three_way: bool,
+    message: Option<&str>,
+) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let patch_path = Path::new(patch)
+        .canonicalize()
+        .with_context(|| format!("Patch file '{}' not found", patch))?;
+
+    for name in repos {
+        if !config.repositories.iter().any(|r| &r.name == name) {
+            return Err(anyhow!("Repository '{}' not found in .mgitconfig.yaml", name));
+        }
+    }
+
+    println!(
+        "{} {} {}...\n",
+        icons::status::info(),
+        if check { "Checking patch" } else { "Applying patch" },
+        patch.cyan().bold()
+    );
+
+    let mut success_count = 0;
+    let mut error_count = 0;
+    let mut operation = start_operation(format!("apply {}", patch));
+
+    for repo_config in &config.repositories {
+        if !repos.is_empty() && !repos.contains(&repo_config.name) {
+            continue;
+        }
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        let started = Instant::now();
+
+        match apply_patch(&repo_path, &patch_path, check, three_way, message) {
+            Ok(result) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::success(),
+                    repo_config.name.cyan(),
+                    result.green()
+                );
+                operation.add_outcome(&repo_config.name, true, result, started.elapsed());
+                success_count += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    e
+                );
+                operation.add_outcome(&repo_config.name, false, e.to_string(), started.elapsed());
+                error_count += 1;
+            }
+        }
+    }
+
+    if !check {
+        record_operation(&config, operation);
+    }
+
+    println!();
+    println!(
+        "{} Patch {}! ({} repositories, {} errors)",
+        icons::status::success(),
+        if check { "checked" } else { "applied" },
+        success_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} The patch does not apply to some repositories. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Apply the patch to one repository, returns what was done
+fn apply_patch(
+    repo_path: &Path,
+    patch: &Path,
+    check: bool,
+    three_way: bool,
+    message: Option<&str>,
+) -> Result<String> {
+    if !repo_path.exists() {
+        return Err(anyhow!("repository not found"));
+    }
+    // The commit must only contain the patch
+    if message.is_some() && !check && has_uncommitted_changes(repo_path)? {
+        return Err(anyhow!("has uncommitted changes, commit or stash them first"));
+    }
+
+    let patch = patch.to_string_lossy();
+    let mut args = vec!["apply"];
+    if three_way {
+        args.push("--3way");
+    }
+
+    // Check first so a failing patch leaves the repository untouched
+    let mut check_args = args.clone();
+    check_args.extend(["--check", patch.as_ref()]);
+    run_git(repo_path, &check_args).map_err(|e| anyhow!("does not apply: {}", first_line(&e)))?;
+    if check {
+        return Ok("applies cleanly".to_string());
+    }
+
+    args.extend(["--index", patch.as_ref()]);
+    run_git(repo_path, &args).map_err(|e| anyhow!("apply failed: {}", first_line(&e)))?;
+
+    let Some(message) = message else {
+        return Ok("applied (changes staged)".to_string());
+    };
+    run_git(repo_path, &["commit", "--quiet", "-m", message])
+        .map_err(|e| anyhow!("applied, but commit failed: {}", first_line(&e)))?;
+    let sha = run_git(repo_path, &["rev-parse", "--short", "HEAD"])?;
+
+    Ok(format!("applied and committed {}", sha.trim()))
+}
+
+/// First line of a git error (the rest repeats details per hunk)
+fn first_line(error: &anyhow::Error) -> String {
+    error.to_string().lines().next().unwrap_or("").to_string()
+}
@@ -0,0 +1,245 @@
+use metagit_core::models::Config;
+use crate::utils::icons;
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use git2::Repository;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+pub fn archive_command(output: &str, tag: Option<&str>) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    // Resolve which branch to archive for each repository (None = current HEAD)
+    let saved_branches = match tag {
+        Some(tag) => Some(
+            config
+                .tags
+                .get(tag)
+                .cloned()
+                .ok_or_else(|| anyhow!("Tag '{}' not found. Use 'mgit save {}' to create it.", tag, tag))?,
+        ),
+        None => None,
+    };
+
+    match tag {
+        Some(tag) => println!(
+            "{} Archiving repositories at tag '{}' into {}...\n",
+            icons::status::info(),
+            tag.cyan().bold(),
+            output.cyan()
+        ),
+        None => println!(
+            "{} Archiving repositories into {}...\n",
+            icons::status::info(),
+            output.cyan()
+        ),
+    }
+
+    let file = File::create(output).with_context(|| format!("Failed to create '{}'", output))?;
+
+    // Plain .tar when explicitly requested, gzip-compressed otherwise
+    let (success_count, error_count) = if output.ends_with(".tar") {
+        let mut builder = tar::Builder::new(file);
+        let counts = archive_all(&mut builder, &config, saved_branches.as_ref());
+        builder.into_inner()?.flush()?;
+        counts
+    } else {
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let counts = archive_all(&mut builder, &config, saved_branches.as_ref());
+        builder.into_inner()?.finish()?;
+        counts
+    };
+
+    if success_count == 0 {
+        let _ = std::fs::remove_file(output);
+        return Err(anyhow!("No repositories could be archived"));
+    }
+
+    println!();
+    println!(
+        "{} Archive '{}' created! ({} repositories, {} errors)",
+        icons::status::success(),
+        output.green().bold(),
+        success_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} Some repositories could not be archived. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Archive every configured repository into the builder
+/// Returns (success_count, error_count)
+fn archive_all<W: Write>(
+    builder: &mut tar::Builder<W>,
+    config: &Config,
+    saved_branches: Option<&HashMap<String, String>>,
+) -> (usize, usize) {
+    let mut success_count = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+
+        let branch_name = match saved_branches {
+            Some(branches) => match branches.get(&repo_config.name) {
+                Some(name) => Some(name.as_str()),
+                None => {
+                    println!(
+                        "  {} {} - no branch saved in tag",
+                        icons::status::warning(),
+                        repo_config.name.yellow()
+                    );
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        if !repo_path.exists() {
+            println!(
+                "  {} {} - repository not found",
+                icons::status::error(),
+                repo_config.name.yellow()
+            );
+            error_count += 1;
+            continue;
+        }
+
+        match archive_repository(builder, &repo_path, &repo_config.name, branch_name) {
+            Ok((revision, file_count)) => {
+                println!(
+                    "  {} {} - {} ({} files)",
+                    icons::status::success(),
+                    repo_config.name.cyan(),
+                    revision.green(),
+                    file_count
+                );
+                success_count += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    e
+                );
+                error_count += 1;
+            }
+        }
+    }
+
+    (success_count, error_count)
+}
+
+/// Write a repository revision into the archive under `<prefix>/`, as `git archive` produces it
+/// (`export-ignore` and `export-subst` in .gitattributes are honoured)
+/// Archives the given local branch, or the current HEAD when no branch is given
+/// Returns (revision description, number of files written)
+fn archive_repository<W: Write>(
+    builder: &mut tar::Builder<W>,
+    repo_path: &Path,
+    prefix: &str,
+    branch_name: Option<&str>,
+) -> Result<(String, usize)> {
+    let repo = Repository::open(repo_path)?;
+
+    let (reference, revision) = match branch_name {
+        Some(name) => {
+            let branch = repo
+                .find_branch(name, git2::BranchType::Local)
+                .map_err(|e| anyhow!("Branch '{}' not found: {}", name, e))?;
+            (branch.into_reference(), name.to_string())
+        }
+        None => {
+            let head = repo.head().map_err(|e| anyhow!("Could not read HEAD: {}", e))?;
+            let revision = head.shorthand().unwrap_or("HEAD").to_string();
+            (head, revision)
+        }
+    };
+
+    let commit = reference.peel_to_commit()?;
+    let revision = format!("{} @ {}", revision, &commit.id().to_string()[..7]);
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["archive", "--format=tar", &format!("--prefix={}/", prefix), &commit.id().to_string()])
+        .output()
+        .context("Failed to run git archive")?;
+    if !output.status.success() {
+        return Err(anyhow!("git archive failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let file_count = append_archive(builder, &output.stdout)?;
+    Ok((revision, file_count))
+}
+
+/// Copy the entries of a tar archive into the builder, so the repositories end up in one archive
+/// Returns the number of files and symlinks copied.
+fn append_archive<W: Write>(builder: &mut tar::Builder<W>, archive: &[u8]) -> Result<usize> {
+    let mut file_count = 0;
+    for entry in tar::Archive::new(archive).entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        // The global header only records the commit ID, and belongs to a single repository
+        if entry_type == tar::EntryType::XGlobalHeader {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let mut header = entry.header().clone();
+        match entry_type {
+            tar::EntryType::Symlink => {
+                let target = entry.link_name()?.ok_or_else(|| anyhow!("Symlink without target: {}", path.display()))?;
+                builder.append_link(&mut header, &path, &target)?;
+            }
+            _ => builder.append_data(&mut header, &path, &mut entry)?,
+        }
+        if matches!(entry_type, tar::EntryType::Regular | tar::EntryType::Symlink) {
+            file_count += 1;
+        }
+    }
+    Ok(file_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_repository_honours_export_ignore() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        std::fs::write(dir.path().join(".gitattributes"), "notes.txt export-ignore\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "internal").unwrap();
+        std::fs::write(dir.path().join("readme.txt"), "hello").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], git2::IndexAddOption::DEFAULT, None).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[]).unwrap();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let (_, file_count) = archive_repository(&mut builder, dir.path(), "api", None).unwrap();
+        let archive = builder.into_inner().unwrap();
+
+        let mut files = Vec::new();
+        for entry in tar::Archive::new(archive.as_slice()).entries().unwrap() {
+            files.push(entry.unwrap().path().unwrap().display().to_string());
+        }
+        assert_eq!(files, ["api/", "api/.gitattributes", "api/readme.txt"]);
+        assert_eq!(file_count, 2);
+    }
+}
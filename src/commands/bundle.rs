@@ -0,0 +1,270 @@
+use metagit_core::models::Config;
+use crate::utils::{icons, run_git};
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use git2::Repository;
+use std::fs;
+use std::path::Path;
+
+/// Create a git bundle for every repository in the given directory
+pub fn bundle_create_command(dir: &str) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let bundle_dir = Path::new(dir);
+
+    fs::create_dir_all(bundle_dir)
+        .with_context(|| format!("Failed to create bundle directory '{}'", dir))?;
+    let bundle_dir = bundle_dir.canonicalize()?;
+
+    println!(
+        "{} Creating bundles in {}...\n",
+        icons::status::info(),
+        bundle_dir.display().to_string().cyan()
+    );
+
+    let mut success_count = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+
+        if !repo_path.exists() {
+            println!(
+                "  {} {} - repository not found",
+                icons::status::error(),
+                repo_config.name.yellow()
+            );
+            error_count += 1;
+            continue;
+        }
+
+        let bundle_path = bundle_dir.join(format!("{}.bundle", repo_config.name));
+        match create_bundle(&repo_path, &bundle_path) {
+            Ok(()) => {
+                let size = fs::metadata(&bundle_path).map(|m| m.len()).unwrap_or(0);
+                println!(
+                    "  {} {} - {} ({} KB)",
+                    icons::status::success(),
+                    repo_config.name.cyan(),
+                    bundle_path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                        .green(),
+                    size.div_ceil(1024)
+                );
+                success_count += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    e
+                );
+                error_count += 1;
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} Bundles created! ({} repositories, {} errors)",
+        icons::status::success(),
+        success_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} Some repositories could not be bundled. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Import bundles from the given directory into every repository
+/// Bundle branches are fetched into refs/remotes/origin/* (as if fetched from the remote),
+/// then the current branch is fast-forwarded when possible
+pub fn bundle_apply_command(dir: &str) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let bundle_dir = Path::new(dir)
+        .canonicalize()
+        .with_context(|| format!("Bundle directory '{}' not found", dir))?;
+
+    println!(
+        "{} Applying bundles from {}...\n",
+        icons::status::info(),
+        bundle_dir.display().to_string().cyan()
+    );
+
+    let mut success_count = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        let bundle_path = bundle_dir.join(format!("{}.bundle", repo_config.name));
+
+        if !bundle_path.exists() {
+            println!(
+                "  {} {} - no bundle found",
+                icons::status::warning(),
+                repo_config.name.yellow()
+            );
+            continue;
+        }
+
+        if !repo_path.exists() {
+            println!(
+                "  {} {} - repository not found",
+                icons::status::error(),
+                repo_config.name.yellow()
+            );
+            error_count += 1;
+            continue;
+        }
+
+        match apply_bundle(&repo_path, &bundle_path) {
+            Ok(msg) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::success(),
+                    repo_config.name.cyan(),
+                    msg.green()
+                );
+                success_count += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    e
+                );
+                error_count += 1;
+            }
+        }
+    }
+
+    println!();
+    println!(
+        "{} Bundles applied! ({} repositories, {} errors)",
+        icons::status::success(),
+        success_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} Some bundles could not be applied. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Bundle all local branches and tags of a repository, so the receiver gets the full workspace state
+fn create_bundle(repo_path: &Path, bundle_path: &Path) -> Result<()> {
+    run_git(repo_path, &["bundle", "create", &bundle_path.to_string_lossy(), "--branches", "--tags"])?;
+    Ok(())
+}
+
+/// Verify and fetch a single bundle, then fast-forward the current branch
+fn apply_bundle(repo_path: &Path, bundle_path: &Path) -> Result<String> {
+    let bundle_str = bundle_path.to_string_lossy();
+
+    // Verify first so we fail with git's explanation (e.g., missing prerequisite commits)
+    run_git(repo_path, &["bundle", "verify", "--quiet", &bundle_str])?;
+    run_git(
+        repo_path,
+        &[
+            "fetch",
+            "--quiet",
+            &bundle_str,
+            "+refs/heads/*:refs/remotes/origin/*",
+            "refs/tags/*:refs/tags/*",
+        ],
+    )?;
+
+    fast_forward_current_branch(repo_path)
+}
+
+/// Fast-forward the current branch to refs/remotes/origin/<branch> if possible
+fn fast_forward_current_branch(repo_path: &Path) -> Result<String> {
+    let repo = Repository::open(repo_path)?;
+    let head = repo.head()?;
+
+    if !head.is_branch() {
+        return Ok("imported (detached HEAD, not updated)".to_string());
+    }
+
+    let branch_name = head
+        .shorthand()
+        .ok_or_else(|| anyhow!("Could not get branch name"))?
+        .to_string();
+
+    let remote_ref = match repo.find_reference(&format!("refs/remotes/origin/{}", branch_name)) {
+        Ok(r) => r,
+        Err(_) => return Ok(format!("imported ({} not in bundle)", branch_name)),
+    };
+    let bundle_commit = repo.reference_to_annotated_commit(&remote_ref)?;
+    let analysis = repo.merge_analysis(&[&bundle_commit])?;
+
+    if analysis.0.is_up_to_date() {
+        Ok(format!("imported ({} already up-to-date)", branch_name))
+    } else if analysis.0.is_fast_forward() {
+        let refname = format!("refs/heads/{}", branch_name);
+        let target = repo.find_commit(bundle_commit.id())?;
+
+        // Safe checkout: refuses to overwrite local modifications
+        repo.checkout_tree(target.as_object(), None)
+            .map_err(|e| anyhow!("Could not fast-forward {}: {}", branch_name, e))?;
+        repo.find_reference(&refname)?
+            .set_target(bundle_commit.id(), "mgit bundle apply: fast-forward")?;
+        repo.set_head(&refname)?;
+
+        Ok(format!("imported, fast-forwarded {}", branch_name))
+    } else {
+        Ok(format!("imported ({} has diverged, merge origin/{} manually)", branch_name, branch_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Commit an empty tree on top of HEAD
+    fn commit(repo: &Repository, message: &str) -> git2::Oid {
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(repo.treebuilder(None).unwrap().write().unwrap()).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let source = Repository::init(dir.path().join("source")).unwrap();
+        commit(&source, "initial");
+        let target = Repository::clone(dir.path().join("source").to_str().unwrap(), dir.path().join("target")).unwrap();
+
+        // Work done on the connected side after the target was cloned
+        let latest = commit(&source, "offline work");
+        source.tag_lightweight("v1.0", &source.find_object(latest, None).unwrap(), false).unwrap();
+        let bundle = dir.path().join("source.bundle");
+        create_bundle(source.workdir().unwrap(), &bundle).unwrap();
+
+        let message = apply_bundle(target.workdir().unwrap(), &bundle).unwrap();
+        let branch = target.head().unwrap().shorthand().unwrap().to_string();
+        assert_eq!(message, format!("imported, fast-forwarded {}", branch));
+        assert_eq!(target.head().unwrap().target(), Some(latest));
+        assert_eq!(target.refname_to_id("refs/tags/v1.0").unwrap(), latest);
+
+        let message = apply_bundle(target.workdir().unwrap(), &bundle).unwrap();
+        assert_eq!(message, format!("imported ({} already up-to-date)", branch));
+    }
+}
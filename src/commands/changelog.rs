@@ -0,0 +1,103 @@
+use metagit_core::models::Config;
+use crate::utils::{collect_commits, icons, resolve_tag_commit, tag_branches};
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use git2::Repository;
+use std::fmt::Write as _;
+use std::fs;
+
+/// Render a Markdown changelog of the commits between two saved tags (or a tag and HEAD)
+/// Commits are grouped per repository, authors are normalized using the `users` config
+pub fn changelog_command(from: &str, to: Option<&str>, output: Option<&str>) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    let from_branches = tag_branches(&config, from)?;
+    let to_branches = match to {
+        Some(tag) => Some(tag_branches(&config, tag)?),
+        None => None,
+    };
+    let to_label = to.unwrap_or("HEAD");
+
+    let mut markdown = String::new();
+    writeln!(markdown, "# Changelog: {} → {}", from, to_label)?;
+
+    let mut total_commits = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let name = &repo_config.name;
+        let repo_path = config.resolve_repo_path(name);
+
+        let result = Repository::open(&repo_path)
+            .map_err(|_| anyhow!("repository not found"))
+            .and_then(|repo| {
+                let from_oid = resolve_tag_commit(&repo, from, from_branches.as_ref(), name)?;
+                let to_oid = match &to_branches {
+                    Some(branches) => resolve_tag_commit(&repo, to_label, branches.as_ref(), name)?,
+                    None => repo
+                        .head()?
+                        .target()
+                        .ok_or_else(|| anyhow!("HEAD does not point to a commit"))?,
+                };
+                collect_commits(&repo, from_oid, to_oid, &config.users)
+            });
+
+        match result {
+            Ok(entries) if entries.is_empty() => {}
+            Ok(entries) => {
+                total_commits += entries.len();
+                writeln!(markdown)?;
+                writeln!(
+                    markdown,
+                    "## {} ({} commit{})",
+                    name,
+                    entries.len(),
+                    if entries.len() == 1 { "" } else { "s" }
+                )?;
+                writeln!(markdown)?;
+                for entry in entries {
+                    writeln!(
+                        markdown,
+                        "- {} (`{}`, {})",
+                        entry.summary, entry.short_sha, entry.author
+                    )?;
+                }
+            }
+            Err(e) => {
+                error_count += 1;
+                writeln!(markdown)?;
+                writeln!(markdown, "## {}", name)?;
+                writeln!(markdown)?;
+                writeln!(markdown, "_Skipped: {}_", e)?;
+            }
+        }
+    }
+
+    if total_commits == 0 && error_count == 0 {
+        writeln!(markdown)?;
+        writeln!(markdown, "_No changes._")?;
+    }
+
+    match output {
+        Some(path) => {
+            fs::write(path, &markdown)
+                .with_context(|| format!("Failed to write changelog to '{}'", path))?;
+            println!(
+                "{} Changelog written to {} ({} commits, {} errors)",
+                icons::status::success(),
+                path.cyan(),
+                total_commits,
+                error_count
+            );
+            if error_count > 0 {
+                println!(
+                    "\n{} Some repositories were skipped. See the changelog for details.",
+                    icons::status::warning()
+                );
+            }
+        }
+        None => print!("{}", markdown),
+    }
+
+    Ok(())
+}
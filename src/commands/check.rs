@@ -0,0 +1,136 @@
+use anyhow::Result;
+use colored::*;
+
+use metagit_core::models::{Config, Repository};
+use crate::utils::{fetch_remote_branch, get_branch_sync_status, get_head, has_uncommitted_changes, icons};
+
+/// Exit code bits, combined when several checks fail (1 means a repository could not be checked)
+const EXIT_DIRTY: i32 = 2;
+const EXIT_BEHIND: i32 = 4;
+const EXIT_AHEAD: i32 = 8;
+const EXIT_BRANCH: i32 = 16;
+const EXIT_MISSING: i32 = 32;
+
+/// Which checks `mgit check` runs; none selected means all of them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckCriteria {
+    pub dirty: bool,
+    pub behind: bool,
+    pub ahead: bool,
+    pub branch: bool,
+}
+
+impl CheckCriteria {
+    fn or_all(self) -> Self {
+        if self.dirty || self.behind || self.ahead || self.branch {
+            self
+        } else {
+            CheckCriteria { dirty: true, behind: true, ahead: true, branch: true }
+        }
+    }
+}
+
+/// Branch a repository is expected to be on: from `expect` (a saved tag, or else a branch name
+/// for every repository), or the repository's pinned `revision`
+fn expected_branch(config: &Config, repo: &Repository, expect: Option<&str>) -> Option<String> {
+    match expect {
+        Some(tag) if config.tags.contains_key(tag) => config.tags[tag].get(&repo.name).cloned(),
+        Some(branch) => Some(branch.to_string()),
+        None => repo.revision.clone(),
+    }
+}
+
+/// Failed checks of one repository and the exit code bits they set
+fn check_repo(
+    config: &Config,
+    repo: &Repository,
+    criteria: CheckCriteria,
+    expect: Option<&str>,
+    fetch: bool,
+) -> Result<(Vec<String>, i32)> {
+    let repo_path = config.resolve_repo_path(&repo.name);
+    if !repo_path.exists() {
+        return Ok((vec!["not found".to_string()], EXIT_MISSING));
+    }
+
+    let (branch, head) = get_head(&repo_path)?;
+    let mut failures = Vec::new();
+    let mut code = 0;
+
+    if criteria.dirty && has_uncommitted_changes(&repo_path)? {
+        failures.push("uncommitted changes".to_string());
+        code |= EXIT_DIRTY;
+    }
+
+    if let Some(branch) = &branch {
+        if fetch && (criteria.behind || criteria.ahead) {
+            fetch_remote_branch(config, &repo_path, branch, repo.credential.as_ref())?;
+        }
+        let (ahead, behind) = get_branch_sync_status(&repo_path, branch)?;
+        if criteria.behind && behind > 0 {
+            failures.push(format!("{} behind origin", behind));
+            code |= EXIT_BEHIND;
+        }
+        if criteria.ahead && ahead > 0 {
+            failures.push(format!("{} ahead of origin", ahead));
+            code |= EXIT_AHEAD;
+        }
+    }
+
+    if criteria.branch {
+        if let Some(expected) = expected_branch(config, repo, expect) {
+            // A pinned revision may also be a commit
+            let matches = match &branch {
+                Some(branch) => *branch == expected,
+                None => expected.len() >= 7 && head.starts_with(&expected),
+            };
+            if !matches {
+                let current = branch.unwrap_or_else(|| format!("detached at {}", &head[..7]));
+                failures.push(format!("on {} (expected {})", current, expected));
+                code |= EXIT_BRANCH;
+            }
+        }
+    }
+
+    Ok((failures, code))
+}
+
+/// Check every active repository (archived ones too with `all_repos`) for uncommitted changes,
+/// commits to pull or push, and an unexpected branch, then exit with a non-zero code if any check failed
+/// The exit code combines one bit per kind of failure so scripts can tell them apart.
+pub fn check_command(criteria: CheckCriteria, expect: Option<&str>, fetch: bool, all_repos: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let criteria = CheckCriteria { branch: criteria.branch || expect.is_some(), ..criteria }.or_all();
+
+    println!("{} Checking repositories...\n", icons::status::info());
+
+    let mut exit_code = 0;
+    let (mut total, mut failed) = (0, 0);
+    for repo in config.active_repositories(all_repos) {
+        total += 1;
+        let (failures, code) = check_repo(&config, repo, criteria, expect, fetch)
+            .unwrap_or_else(|e| (vec![format!("could not check: {}", e)], 1));
+        if failures.is_empty() {
+            println!("  {} {}", icons::status::success(), repo.name);
+        } else {
+            failed += 1;
+            exit_code |= code;
+            println!("  {} {} - {}", icons::status::error(), repo.name, failures.join(", ").red());
+        }
+    }
+
+    println!();
+    if failed == 0 {
+        println!("{} All {} repositories pass", icons::status::success(), total);
+        return Ok(());
+    }
+
+    println!(
+        "{} {} of {} repositor{} failed the checks",
+        icons::status::error(),
+        failed,
+        total,
+        if total == 1 { "y" } else { "ies" }
+    );
+    std::process::exit(exit_code);
+}
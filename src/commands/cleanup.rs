@@ -0,0 +1,238 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use colored::*;
+use git2::{BranchType, Repository};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use metagit_core::db::StateDb;
+use metagit_core::models::{Config, RepoCredential};
+use crate::commands::{stale_branch_owner, DEFAULT_STALE_AFTER};
+use crate::utils::{
+    delete_remote_branch, fetch_remote_branch, find_stale_branches, format_time, icons, parse_duration,
+    record_operation, remote_branch_state, select_items, start_operation, RemoteBranchState, StaleBranch,
+};
+
+/// A stale branch offered for deletion
+struct Candidate<'a> {
+    repo: &'a str,
+    path: PathBuf,
+    credential: Option<&'a RepoCredential>,
+    branch: StaleBranch,
+    owner: String,
+    /// `origin/<branch>` exists
+    on_remote: bool,
+}
+
+/// Delete stale branches chosen interactively (or all of them with `yes`)
+/// Unmerged branches are only deleted with `force`; `remote` also deletes `origin/<branch>`
+pub fn cleanup_command(older_than: Option<&str>, yes: bool, remote: bool, force: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let threshold = older_than
+        .or(config.stale_after.as_deref())
+        .unwrap_or(DEFAULT_STALE_AFTER);
+    let cutoff = Utc::now() - parse_duration(threshold)?;
+    let db_path = config.get_db_path();
+    let db = StateDb::open(&db_path).ok();
+
+    println!(
+        "{} Looking for branches without commits for {} that are merged or have no upstream...\n",
+        icons::status::info(),
+        threshold.cyan().bold()
+    );
+
+    let mut candidates = Vec::new();
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        if !repo_path.exists() {
+            continue;
+        }
+
+        let branches = match find_stale_branches(&repo_path, cutoff, &config.users) {
+            Ok(branches) => branches,
+            Err(e) => {
+                println!("  {} {} - {}", icons::status::error(), repo_config.name.yellow(), e);
+                continue;
+            }
+        };
+        let state = db
+            .as_ref()
+            .and_then(|db| db.get_repo_state(&repo_config.name).ok().flatten());
+        let repo = Repository::open(&repo_path)?;
+
+        for branch in branches {
+            // The checked out branch can't be deleted
+            if branch.is_current {
+                println!(
+                    "  {} {} - {} is checked out, skipped",
+                    icons::status::warning(),
+                    repo_config.name.yellow(),
+                    branch.name
+                );
+                continue;
+            }
+            candidates.push(Candidate {
+                repo: &repo_config.name,
+                path: repo_path.clone(),
+                credential: repo_config.credential.as_ref(),
+                owner: stale_branch_owner(state.as_ref(), &branch),
+                on_remote: repo
+                    .find_branch(&format!("origin/{}", branch.name), BranchType::Remote)
+                    .is_ok(),
+                branch,
+            });
+        }
+    }
+
+    // Release the state database so the operation log can be written
+    drop(db);
+
+    if candidates.is_empty() {
+        println!("{} No stale branches to clean up", icons::status::success());
+        return Ok(());
+    }
+
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|c| {
+            let mut notes = vec![if c.branch.merged { "merged" } else { "not merged" }];
+            if c.on_remote && remote {
+                notes.push("also on origin");
+            }
+            format!(
+                "{}: {} - {}, last commit {} ({})",
+                c.repo.cyan(),
+                c.branch.name.yellow(),
+                c.owner,
+                format_time(c.branch.last_commit, config.timestamps),
+                notes.join(", ").bright_black()
+            )
+        })
+        .collect();
+
+    let selected: Vec<usize> = if yes {
+        for label in &labels {
+            println!("  {}", label);
+        }
+        (0..candidates.len()).collect()
+    } else {
+        select_items("Branches to delete", &labels)?
+    };
+
+    if selected.is_empty() {
+        println!("\n{} Nothing deleted", icons::status::info());
+        return Ok(());
+    }
+
+    println!();
+    let mut deleted_count = 0;
+    let mut error_count = 0;
+    let mut operation = start_operation("cleanup");
+
+    for candidate in selected.into_iter().map(|i| &candidates[i]) {
+        let started = Instant::now();
+        let name = &candidate.branch.name;
+
+        if !candidate.branch.merged && !force {
+            println!(
+                "  {} {} - {} is not merged, use --force to delete it",
+                icons::status::warning(),
+                candidate.repo.yellow(),
+                name
+            );
+            continue;
+        }
+
+        match delete_branch(&config, candidate, remote, force) {
+            Ok((message, kept)) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::success(),
+                    candidate.repo.cyan(),
+                    message
+                );
+                if let Some(kept) = kept {
+                    println!("  {} {} - {}", icons::status::warning(), candidate.repo.yellow(), kept);
+                }
+                operation.add_outcome(candidate.repo, true, message, started.elapsed());
+                deleted_count += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} - failed to delete {}: {}",
+                    icons::status::error(),
+                    candidate.repo.yellow(),
+                    name,
+                    e
+                );
+                let message = format!("failed to delete {}: {}", name, e);
+                operation.add_outcome(candidate.repo, false, message, started.elapsed());
+                error_count += 1;
+            }
+        }
+    }
+
+    record_operation(&config, operation);
+
+    println!();
+    println!(
+        "{} Cleanup complete! ({} branches deleted, {} errors)",
+        icons::status::success(),
+        deleted_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} Some branches could not be deleted. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Delete the local branch, and with `remote` also `origin/<branch>`
+/// origin's branch is fetched first and only deleted when that loses no commits (or with `force`),
+/// and only if nobody pushed to it since the fetch. Returns the message, and why origin's branch
+/// was kept when it was.
+fn delete_branch(config: &Config, candidate: &Candidate, remote: bool, force: bool) -> Result<(String, Option<String>)> {
+    let repo = Repository::open(&candidate.path)?;
+    let name = &candidate.branch.name;
+
+    let mut branch = repo
+        .find_branch(name, BranchType::Local)
+        .map_err(|e| anyhow!("branch not found: {}", e))?;
+    let sha = branch
+        .get()
+        .target()
+        .map(|oid| oid.to_string()[..7].to_string())
+        .unwrap_or_default();
+
+    let mut message = format!("deleted {} (was {})", name, sha);
+    let mut kept = None;
+    if remote && candidate.on_remote {
+        fetch_remote_branch(config, &candidate.path, name, candidate.credential)
+            .map_err(|e| anyhow!("could not fetch origin/{}: {}", name, e))?;
+        let expected = match remote_branch_state(&candidate.path, name)? {
+            RemoteBranchState::Missing => None,
+            RemoteBranchState::Merged(oid) => Some(oid),
+            RemoteBranchState::Diverged(oid) if force => Some(oid),
+            RemoteBranchState::Diverged(_) => {
+                kept = Some(format!(
+                    "origin/{} kept: it has commits that are neither on {} nor in master/main, use --force to delete it",
+                    name, name
+                ));
+                None
+            }
+        };
+        if let Some(expected) = expected {
+            delete_remote_branch(config, &candidate.path, name, expected, candidate.credential)
+                .map_err(|e| anyhow!("could not delete origin/{}: {}", name, e))?;
+            message.push_str(&format!(" and origin/{}", name));
+        }
+    }
+
+    branch.delete()?;
+    Ok((message, kept))
+}
@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use git2::{BranchType, Oid, Repository};
+use std::path::Path;
+
+use metagit_core::models::Config;
+use crate::utils::{format_time, icons};
+
+/// A commit found in a repository, with the branches containing it
+struct Found {
+    oid: Oid,
+    summary: String,
+    author: String,
+    time: chrono::DateTime<chrono::Utc>,
+    local: Vec<String>,
+    remote: Vec<String>,
+}
+
+/// Find which repositories have a commit, and which local/remote branches contain it
+pub fn contains_command(sha: &str) -> Result<()> {
+    if sha.len() < 4 || !sha.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow!(
+            "'{}' is not a commit SHA (expected at least 4 hexadecimal characters)",
+            sha
+        ));
+    }
+
+    let config = Config::load_from_project()?;
+
+    println!(
+        "{} Searching for commit '{}' in repositories...\n",
+        icons::status::info(),
+        sha.cyan().bold()
+    );
+
+    let mut found_count = 0;
+    let mut searched_count = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+
+        if !repo_path.exists() {
+            println!(
+                "  {} {} - repository not found",
+                icons::status::error(),
+                repo_config.name.yellow()
+            );
+            error_count += 1;
+            continue;
+        }
+        searched_count += 1;
+
+        let found = match find_commit(&repo_path, sha) {
+            Ok(Some(found)) => found,
+            Ok(None) => continue,
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    e
+                );
+                error_count += 1;
+                continue;
+            }
+        };
+
+        println!(
+            "  {} {} - {} {} ({}, {})",
+            icons::status::success(),
+            repo_config.name.cyan(),
+            found.oid.to_string()[..7].yellow(),
+            found.summary,
+            found.author,
+            format_time(found.time, config.timestamps)
+        );
+        if found.local.is_empty() && found.remote.is_empty() {
+            println!("      {}", "not on any branch".bright_black());
+        }
+        if !found.local.is_empty() {
+            println!("      local:  {}", found.local.join(", ").green());
+        }
+        if !found.remote.is_empty() {
+            println!("      remote: {}", found.remote.join(", ").green());
+        }
+        found_count += 1;
+    }
+
+    if found_count + error_count > 0 {
+        println!();
+    }
+    if found_count == 0 {
+        println!(
+            "{} Commit '{}' not found ({} repositories searched, {} errors)",
+            icons::status::warning(),
+            sha,
+            searched_count,
+            error_count
+        );
+    } else {
+        println!(
+            "{} Found in {} repositor{} ({} searched, {} errors)",
+            icons::status::success(),
+            found_count,
+            if found_count == 1 { "y" } else { "ies" },
+            searched_count,
+            error_count
+        );
+    }
+
+    Ok(())
+}
+
+/// Look up a (possibly abbreviated) commit SHA, None if the repository doesn't have it
+fn find_commit(repo_path: &Path, sha: &str) -> Result<Option<Found>> {
+    let repo = Repository::open(repo_path)?;
+
+    let commit = match repo.revparse_single(sha).and_then(|obj| obj.peel_to_commit()) {
+        Ok(commit) => commit,
+        Err(e) if e.code() == git2::ErrorCode::Ambiguous => {
+            return Err(anyhow!("'{}' is ambiguous, use more characters", sha));
+        }
+        Err(_) => return Ok(None),
+    };
+    let oid = commit.id();
+
+    let mut local = Vec::new();
+    let mut remote = Vec::new();
+    for branch in repo.branches(None)? {
+        let (branch, branch_type) = branch?;
+        let (Some(name), Some(tip)) = (branch.name()?, branch.get().target()) else {
+            continue;
+        };
+        // Skip symbolic refs like origin/HEAD
+        if name.ends_with("/HEAD") {
+            continue;
+        }
+        if tip == oid || repo.graph_descendant_of(tip, oid)? {
+            match branch_type {
+                BranchType::Local => local.push(name.to_string()),
+                BranchType::Remote => remote.push(name.to_string()),
+            }
+        }
+    }
+    local.sort();
+    remote.sort();
+
+    let author = commit.author().name().unwrap_or("Unknown").to_string();
+    Ok(Some(Found {
+        oid,
+        summary: commit.summary().unwrap_or("").to_string(),
+        author,
+        time: chrono::DateTime::from_timestamp(commit.time().seconds(), 0).unwrap_or_default(),
+        local,
+        remote,
+    }))
+}
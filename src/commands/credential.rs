@@ -0,0 +1,57 @@
+use crate::utils::icons;
+use metagit_core::keychain::{delete_secret, set_secret, SecretKind};
+use anyhow::{anyhow, Result};
+use colored::*;
+
+/// Pick the secret kind from the --token / --passphrase flags
+fn secret_kind(token: bool, passphrase: bool) -> Result<SecretKind> {
+    match (token, passphrase) {
+        (true, false) => Ok(SecretKind::HttpsToken),
+        (false, true) => Ok(SecretKind::SshPassphrase),
+        _ => Err(anyhow!("Specify exactly one of --token or --passphrase")),
+    }
+}
+
+/// Prompt for a secret (without echo) and store it in the OS keychain
+pub fn credential_set_command(host: &str, token: bool, passphrase: bool) -> Result<()> {
+    let kind = secret_kind(token, passphrase)?;
+
+    let secret = rpassword::prompt_password(format!("{} for {}: ", kind.label(), host))?;
+    if secret.is_empty() {
+        return Err(anyhow!("Empty {} - nothing stored", kind.label()));
+    }
+
+    set_secret(host, kind, &secret)?;
+
+    println!(
+        "{} Stored {} for {} in the OS keychain",
+        icons::status::success(),
+        kind.label(),
+        host.cyan().bold()
+    );
+
+    Ok(())
+}
+
+/// Remove a secret from the OS keychain
+pub fn credential_delete_command(host: &str, token: bool, passphrase: bool) -> Result<()> {
+    let kind = secret_kind(token, passphrase)?;
+
+    if delete_secret(host, kind)? {
+        println!(
+            "{} Removed {} for {} from the OS keychain",
+            icons::status::success(),
+            kind.label(),
+            host.cyan().bold()
+        );
+    } else {
+        println!(
+            "{} No {} stored for {}",
+            icons::status::warning(),
+            kind.label(),
+            host.yellow()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::Path;
+
+use metagit_core::db::{DbExport, StateDb};
+use metagit_core::models::Config;
+use crate::utils::icons;
+
+fn open_db(config: &Config) -> Result<StateDb> {
+    let db_path = config.get_db_path();
+    StateDb::open(&db_path)
+}
+
+/// "3 repository states" / "1 operation"
+fn count(n: usize, what: &str) -> String {
+    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+}
+
+/// Write the workspace's state database (cached repository states, operation log, undo snapshot,
+/// pending restore) to a JSON file that doesn't depend on the database format
+pub fn db_export_command(file: &Path) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let export = open_db(&config)?.export()?;
+
+    fs::write(file, serde_json::to_string_pretty(&export)? + "\n")
+        .map_err(|e| anyhow!("Failed to write {}: {}", file.display(), e))?;
+    println!(
+        "{} Exported {} and {} to {}",
+        icons::status::success(),
+        count(export.repositories.len(), "repository state"),
+        count(export.operations.len(), "operation"),
+        file.display()
+    );
+    Ok(())
+}
+
+/// Merge a file written by `mgit db export` into the workspace's state database
+pub fn db_import_command(file: &Path) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let content = fs::read_to_string(file).map_err(|e| anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let export: DbExport = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("{} is not an mgit database export: {}", file.display(), e))?;
+
+    let db = open_db(&config)?;
+    let summary = db.import(export)?;
+    let mut imported = vec![
+        count(summary.repositories, "repository state"),
+        count(summary.operations, "new operation"),
+    ];
+    if summary.undo {
+        imported.push("the undo snapshot".to_string());
+    }
+    if summary.pending_restore {
+        imported.push("an incomplete restore".to_string());
+    }
+    println!("{} Imported {} from {}", icons::status::success(), imported.join(", "), file.display());
+
+    let unknown: Vec<String> = db
+        .list_all_states()?
+        .into_iter()
+        .map(|state| state.name)
+        .filter(|name| !config.configured_repositories().iter().any(|r| &r.name == name))
+        .collect();
+    if !unknown.is_empty() {
+        println!(
+            "{} Not in .mgitconfig.yaml: {}. Remove them with 'mgit refresh --prune-db'.",
+            icons::status::info(),
+            unknown.join(", ")
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+
+use metagit_core::models::Config;
+use crate::utils::{icons, VarContext, VarSource};
+
+/// Print the variables a task would see, grouped by the source that wins, with their
+/// substituted values
+/// `names` limits the output to these variables; `defines` are the `-D` flags of `mgit run`.
+pub fn env_command(names: Vec<String>, defines: Vec<String>) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let project_dir = config
+        .config_dir
+        .as_ref()
+        .ok_or_else(|| anyhow!("Could not determine project directory"))?;
+
+    let env_files = config.env_file_paths();
+    let ctx = VarContext::new(project_dir, &env_files, &config.variables, defines)?;
+
+    println!("{} Env files:", icons::status::info());
+    for path in &env_files {
+        let note = if path.exists() { String::new() } else { " (not found)".bright_black().to_string() };
+        println!("  {}{}", path.display(), note);
+    }
+
+    let mut selected: Vec<&String> = if names.is_empty() {
+        ctx.vars().keys().collect()
+    } else {
+        names.iter().collect()
+    };
+    selected.sort();
+
+    let groups = [
+        (VarSource::Define, "-D defines"),
+        (VarSource::Config, "Config variables"),
+        (VarSource::Predefined, "Predefined variables"),
+        (VarSource::EnvFile, "Env file variables"),
+        (VarSource::Environment, "Environment variables"),
+    ];
+    for (source, title) in groups {
+        let group: Vec<&String> = selected
+            .iter()
+            .copied()
+            .filter(|name| ctx.sources(name).last() == Some(&source))
+            .collect();
+        if group.is_empty() {
+            continue;
+        }
+
+        println!("\n{}", title.bold());
+        for name in group {
+            let value = match ctx.substitute(&ctx.vars()[name]) {
+                Ok(value) => value,
+                Err(e) => e.to_string().red().to_string(),
+            };
+            let overridden: Vec<&str> = ctx.sources(name).iter().rev().skip(1).map(VarSource::label).collect();
+            let note = if overridden.is_empty() {
+                String::new()
+            } else {
+                format!(" (overrides {})", overridden.join(", ")).bright_black().to_string()
+            };
+            println!("  {}={}{}", name.cyan(), value, note);
+        }
+    }
+
+    let missing: Vec<&String> = selected.iter().copied().filter(|name| ctx.get(name).is_none()).collect();
+    if !missing.is_empty() {
+        println!();
+        for name in missing {
+            println!("{} {} is not defined", icons::status::error(), name.cyan());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use metagit_core::models::Config;
+use crate::utils::icons;
+use anyhow::{anyhow, Result};
+use colored::*;
+use git2::Repository;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Client-side hooks that can be distributed with `mgit hooks install`
+const SUPPORTED_HOOKS: &[&str] = &[
+    "applypatch-msg",
+    "commit-msg",
+    "post-checkout",
+    "post-commit",
+    "post-merge",
+    "post-rewrite",
+    "pre-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "pre-push",
+    "pre-rebase",
+    "prepare-commit-msg",
+];
+
+/// Outcome of installing one hook into one repository
+enum HookResult {
+    Installed,
+    UpToDate,
+    /// A different hook already exists (kept unless --force)
+    Conflict,
+}
+
+/// Install the hooks declared in the `hooks` config section into every repository
+/// Hooks are symlinked to the shared scripts (copied with `copy` or on Windows)
+pub fn hooks_install_command(copy: bool, force: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    if config.hooks.is_empty() {
+        println!(
+            "{} No hooks defined. Add a 'hooks' section to .mgitconfig.yaml.",
+            icons::status::warning()
+        );
+        return Ok(());
+    }
+
+    // Validate the hook declarations before touching any repository
+    let mut hooks: Vec<(&str, PathBuf)> = Vec::new();
+    for (name, script) in &config.hooks {
+        if !SUPPORTED_HOOKS.contains(&name.as_str()) {
+            return Err(anyhow!(
+                "Unknown hook '{}'. Supported hooks: {}",
+                name,
+                SUPPORTED_HOOKS.join(", ")
+            ));
+        }
+        let script_path = config.resolve_path(script);
+        if !script_path.is_file() {
+            return Err(anyhow!(
+                "Hook script for '{}' not found: {}",
+                name,
+                script_path.display()
+            ));
+        }
+        hooks.push((name.as_str(), script_path.canonicalize()?));
+    }
+    hooks.sort();
+
+    let copy = copy || cfg!(windows);
+
+    println!(
+        "{} Installing {} hook(s) into repositories...\n",
+        icons::status::info(),
+        hooks.len()
+    );
+
+    let mut success_count = 0;
+    let mut error_count = 0;
+    let mut conflict_count = 0;
+
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+
+        let hooks_dir = match hooks_dir(&repo_path) {
+            Ok(dir) => dir,
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    e
+                );
+                error_count += 1;
+                continue;
+            }
+        };
+
+        let mut installed = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut failed = None;
+
+        for (name, script) in &hooks {
+            match install_hook(&hooks_dir.join(name), script, copy, force) {
+                Ok(HookResult::Installed) => installed.push(*name),
+                Ok(HookResult::UpToDate) => {}
+                Ok(HookResult::Conflict) => conflicts.push(*name),
+                Err(e) => {
+                    failed = Some(format!("{}: {}", name, e));
+                    break;
+                }
+            }
+        }
+
+        if let Some(e) = failed {
+            println!(
+                "  {} {} - {}",
+                icons::status::error(),
+                repo_config.name.yellow(),
+                e
+            );
+            error_count += 1;
+        } else if !conflicts.is_empty() {
+            println!(
+                "  {} {} - existing hook(s) kept: {}",
+                icons::status::warning(),
+                repo_config.name.yellow(),
+                conflicts.join(", ")
+            );
+            conflict_count += 1;
+        } else if installed.is_empty() {
+            println!(
+                "  {} {} - {}",
+                icons::status::success(),
+                repo_config.name.cyan(),
+                "up-to-date".green()
+            );
+            success_count += 1;
+        } else {
+            println!(
+                "  {} {} - {}",
+                icons::status::success(),
+                repo_config.name.cyan(),
+                format!("installed {}", installed.join(", ")).green()
+            );
+            success_count += 1;
+        }
+    }
+
+    println!();
+    println!(
+        "{} Hooks installed! ({} repositories, {} errors)",
+        icons::status::success(),
+        success_count,
+        error_count
+    );
+
+    if conflict_count > 0 {
+        println!(
+            "\n{} Some repositories already have different hooks. Use --force to replace them.",
+            icons::status::warning()
+        );
+    }
+    if error_count > 0 {
+        println!(
+            "\n{} Some hooks could not be installed. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Get the hooks directory of a repository, honoring core.hooksPath
+fn hooks_dir(repo_path: &Path) -> Result<PathBuf> {
+    if !repo_path.exists() {
+        return Err(anyhow!("repository not found"));
+    }
+    let repo = Repository::open(repo_path)?;
+
+    let dir = match repo.config()?.get_path("core.hooksPath") {
+        // A relative core.hooksPath is relative to the working tree
+        Ok(path) if path.is_relative() => repo.workdir().unwrap_or(repo.path()).join(path),
+        Ok(path) => path,
+        Err(_) => repo.path().join("hooks"),
+    };
+
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Link or copy a hook script into place
+fn install_hook(target: &Path, script: &Path, copy: bool, force: bool) -> Result<HookResult> {
+    if let Ok(metadata) = fs::symlink_metadata(target) {
+        let up_to_date = if metadata.file_type().is_symlink() {
+            !copy && fs::read_link(target).ok().as_deref() == Some(script)
+        } else {
+            copy && fs::read(target).ok() == fs::read(script).ok()
+        };
+
+        if up_to_date {
+            return Ok(HookResult::UpToDate);
+        }
+        if !force {
+            return Ok(HookResult::Conflict);
+        }
+        fs::remove_file(target)?;
+    }
+
+    if copy {
+        fs::copy(script, target)?;
+        make_executable(target)?;
+    } else {
+        symlink(script, target)?;
+    }
+
+    Ok(HookResult::Installed)
+}
+
+#[cfg(unix)]
+fn symlink(script: &Path, target: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(script, target)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn symlink(script: &Path, target: &Path) -> Result<()> {
+    fs::copy(script, target)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_install_hook() {
+        let dir = TempDir::new().unwrap();
+        let (script, other, target) = (dir.path().join("check.sh"), dir.path().join("other.sh"), dir.path().join("pre-commit"));
+        fs::write(&script, "#!/bin/sh\nexit 0\n").unwrap();
+        fs::write(&other, "#!/bin/sh\necho other\n").unwrap();
+
+        assert!(matches!(install_hook(&target, &script, true, false).unwrap(), HookResult::Installed));
+        assert!(matches!(install_hook(&target, &script, true, false).unwrap(), HookResult::UpToDate));
+        assert!(matches!(install_hook(&target, &other, true, false).unwrap(), HookResult::Conflict));
+        assert_eq!(fs::read(&target).unwrap(), fs::read(&script).unwrap());
+        assert!(matches!(install_hook(&target, &other, true, true).unwrap(), HookResult::Installed));
+        assert_eq!(fs::read(&target).unwrap(), fs::read(&other).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_installed_hook_stops_the_commit() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path().join("api")).unwrap();
+        let script = dir.path().join("pre-commit.sh");
+        fs::write(&script, "#!/bin/sh\necho 'lint failed' >&2\nexit 1\n").unwrap();
+        make_executable(&script).unwrap();
+
+        let hooks_dir = hooks_dir(repo.workdir().unwrap()).unwrap();
+        install_hook(&hooks_dir.join("pre-commit"), &script, false, false).unwrap();
+
+        let output = Command::new("git")
+            .current_dir(repo.workdir().unwrap())
+            .args(["commit", "--allow-empty", "-m", "blocked"])
+            .env("GIT_AUTHOR_NAME", "Test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "Test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("lint failed"));
+        assert!(repo.head().is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use colored::*;
+
+use metagit_core::models::{Config, Identity};
+use crate::utils::{commit_identity, icons, set_commit_identity};
+
+/// "Name <email>", leaving out what isn't set
+pub fn format_identity(name: Option<&str>, email: Option<&str>) -> String {
+    match (name, email) {
+        (Some(name), Some(email)) => format!("{} <{}>", name, email),
+        (Some(name), None) => name.to_string(),
+        (None, Some(email)) => format!("<{}>", email),
+        (None, None) => "(none)".to_string(),
+    }
+}
+
+/// Repositories whose commit identity differs from the configured one, with the expected identity
+pub fn deviating_identities(config: &Config, all_repos: bool) -> Vec<(String, Identity)> {
+    config
+        .active_repositories(all_repos)
+        .filter_map(|repo_config| {
+            let expected = config.expected_identity(repo_config)?;
+            let repo_path = config.resolve_repo_path(&repo_config.name);
+            let actual = commit_identity(&repo_path).ok()?;
+            (!actual.matches(&expected)).then(|| (repo_config.name.clone(), expected))
+        })
+        .collect()
+}
+
+/// Write the configured identity into the local git config of every repository
+pub fn identity_apply_command(all_repos: bool, dry_run: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    println!(
+        "{} {} commit identities...\n",
+        icons::status::info(),
+        if dry_run { "Checking" } else { "Applying" }
+    );
+
+    let mut changed = 0;
+    let mut unconfigured = 0;
+    let mut errors = 0;
+    for repo_config in config.active_repositories(all_repos) {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        if !repo_path.exists() {
+            continue;
+        }
+        let Some(expected) = config.expected_identity(repo_config) else {
+            unconfigured += 1;
+            continue;
+        };
+        let identity = format_identity(expected.name.as_deref(), expected.email.as_deref());
+
+        if dry_run {
+            match commit_identity(&repo_path) {
+                Ok(actual) if actual.matches(&expected) => {
+                    println!("{:<30} {}", repo_config.name.cyan(), identity.bright_black())
+                }
+                Ok(actual) => {
+                    let current = format_identity(actual.name.as_deref(), actual.email.as_deref());
+                    println!("{:<30} {} -> {}", repo_config.name.cyan(), current.yellow(), identity.green());
+                    changed += 1;
+                }
+                Err(e) => {
+                    println!("{:<30} {}: {}", repo_config.name.yellow(), "failed".red(), e);
+                    errors += 1;
+                }
+            }
+            continue;
+        }
+
+        match set_commit_identity(&repo_path, &expected) {
+            Ok(keys) if keys.is_empty() => println!("{:<30} {}", repo_config.name.cyan(), identity.bright_black()),
+            Ok(keys) => {
+                println!("{:<30} {} ({} set)", repo_config.name.cyan(), identity.green(), keys.join(", "));
+                changed += 1;
+            }
+            Err(e) => {
+                println!("{:<30} {}: {}", repo_config.name.yellow(), "failed".red(), e);
+                errors += 1;
+            }
+        }
+    }
+
+    if unconfigured > 0 {
+        println!(
+            "\n{} {} repositor{} without a configured identity {} left alone",
+            icons::status::info(),
+            unconfigured,
+            if unconfigured == 1 { "y" } else { "ies" },
+            if unconfigured == 1 { "was" } else { "were" }
+        );
+    }
+    let verb = if dry_run { "would change" } else { "updated" };
+    println!("\n{} Identities {}: {} ({} errors)", icons::status::success(), verb, changed, errors);
+    Ok(())
+}
@@ -1,43 +1,28 @@
-use anyhow::Result;
-use std::collections::HashMap;
+use anyhow::{anyhow, Result};
+use colored::*;
+use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use crate::commands::refresh_command;
-use crate::models::{Config, Repository};
-use crate::utils::{get_repo_url, is_git_repo};
+use metagit_core::models::{Config, Repository};
+use metagit_core::paths::normalize_path;
+use crate::utils::{get_repo_url, icons, is_git_repo, ImportTool};
 
-pub fn init_command() -> Result<()> {
-    let config_path = ".mgitconfig.yaml";
+/// Create the workspace configuration from the repositories found in the current directory or
+/// imported from another tool; with `url_template`, URLs that follow it are not written
+pub fn init_command(import: Option<(&str, &Path)>, url_template: Option<String>) -> Result<()> {
+    let config_path = Config::config_override().unwrap_or_else(|| PathBuf::from(".mgitconfig.yaml"));
+    let config_path = config_path.to_string_lossy();
 
-    if Path::new(config_path).exists() {
+    if Path::new(config_path.as_ref()).exists() {
         println!("Configuration file already exists at {}", config_path);
         return Ok(());
     }
 
-    println!("Scanning current directory for git repositories...");
-
-    let mut repositories = Vec::new();
-
-    // Walk through immediate subdirectories
-    for entry in fs::read_dir(".")? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            if is_git_repo(&path) {
-                let name = path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("unknown")
-                    .to_string();
-
-                if let Ok(url) = get_repo_url(&path) {
-                    println!("  Found repository: {} ({})", name, url);
-                    repositories.push(Repository { name, url });
-                }
-            }
-        }
-    }
+    let repositories = match import {
+        Some((tool, file)) => import_repositories(ImportTool::from_name(tool)?, file)?,
+        None => scan_repositories()?,
+    };
 
     if repositories.is_empty() {
         println!("No git repositories found in current directory.");
@@ -52,22 +37,141 @@ pub fn init_command() -> Result<()> {
 
     let config = Config {
         repositories,
-        tasks: Vec::new(),
-        shells: Default::default(),
-        credentials: HashMap::new(),
-        users: HashMap::new(),
-        tags: HashMap::new(),
-        config_dir: None,
+        url_template,
+        ..Default::default()
     };
 
-    config.save(config_path)?;
+    config.save(&config_path)?;
     println!("Configuration saved to {}", config_path);
 
     // Automatically refresh repository states if we found any repositories
     if !config.repositories.is_empty() {
         println!();
-        refresh_command()?;
+        refresh_command(None, false, None, None, false, false)?;
     }
 
     Ok(())
 }
+
+fn scan_repositories() -> Result<Vec<Repository>> {
+    println!("Scanning current directory for git repositories...");
+
+    let mut repositories = Vec::new();
+
+    // Walk through immediate subdirectories
+    for entry in fs::read_dir(".")? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.is_dir() && is_git_repo(&path) {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+
+            if let Ok(url) = get_repo_url(&path) {
+                println!("  Found repository: {} ({})", name, url);
+                repositories.push(Repository {
+                    name,
+                    url,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    Ok(repositories)
+}
+
+/// Convert another tool's configuration file into repository entries
+/// Repository paths are resolved against the file's directory and stored relative to the current one;
+/// repositories outside the current directory are named after their directory and keep a `path`.
+fn import_repositories(tool: ImportTool, file: &Path) -> Result<Vec<Repository>> {
+    let content = fs::read_to_string(file)
+        .map_err(|e| anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let imported = tool.parse(&content)?;
+
+    println!("Importing repositories from {}...", file.display());
+
+    let cwd = env::current_dir()?;
+    let base = cwd.join(file.parent().unwrap_or(Path::new("")));
+    let mut repositories = Vec::new();
+
+    for repo in imported {
+        let path = normalize_path(&base.join(expand_home(&repo.path)));
+        let (name, location) = match path.strip_prefix(&cwd) {
+            Ok(relative) if !relative.as_os_str().is_empty() => (relative.to_string_lossy().to_string(), None),
+            Ok(_) => {
+                println!(
+                    "  {} {} - the current directory itself, skipped",
+                    icons::status::warning(),
+                    repo.path.yellow()
+                );
+                continue;
+            }
+            // Repositories outside the workspace keep their location in `path`
+            Err(_) => match path.file_name() {
+                Some(name) => (name.to_string_lossy().to_string(), Some(external_path(&cwd, &path))),
+                None => continue,
+            },
+        };
+        if repositories.iter().any(|r: &Repository| r.name == name) {
+            println!(
+                "  {} {} - another repository is named {}, skipped",
+                icons::status::warning(),
+                repo.path.yellow(),
+                name
+            );
+            continue;
+        }
+
+        // gita doesn't record URLs, and an mr section may lack a checkout command
+        let url = match repo.url.or_else(|| get_repo_url(&path).ok()) {
+            Some(url) => url,
+            None => {
+                println!(
+                    "  {} {} - no remote URL found, skipped",
+                    icons::status::warning(),
+                    name.yellow()
+                );
+                continue;
+            }
+        };
+
+        match &repo.revision {
+            Some(revision) => println!("  Found repository: {} ({} @ {})", name, url, revision),
+            None => println!("  Found repository: {} ({})", name, url),
+        }
+        if let Some(location) = &location {
+            println!("    outside the workspace, at {}", location);
+        }
+        repositories.push(Repository {
+            name,
+            url,
+            path: location,
+            revision: repo.revision,
+            ..Default::default()
+        });
+    }
+
+    Ok(repositories)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// `path` of a repository outside the workspace `cwd`: "../name" for siblings of the workspace,
+/// the absolute path for anything further away (e.g., on another drive)
+fn external_path(cwd: &Path, path: &Path) -> String {
+    match (cwd.parent(), path.parent(), path.file_name()) {
+        (Some(parent), Some(repo_parent), Some(name)) if parent == repo_parent => {
+            format!("../{}", name.to_string_lossy())
+        }
+        _ => path.to_string_lossy().to_string(),
+    }
+}
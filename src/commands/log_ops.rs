@@ -0,0 +1,70 @@
+use anyhow::Result;
+use colored::*;
+
+use metagit_core::db::StateDb;
+use metagit_core::models::{Config, TimestampFormat};
+use crate::utils::{format_duration, format_time, icons};
+
+/// Show the operation log: who ran which pull/push/sync/restore/run, when, and what happened per repository
+/// `command` keeps operations of one command, `repo` those touching a repository (showing only its line),
+/// `failed` those with at least one error
+pub fn log_ops_command(
+    limit: usize,
+    command: Option<&str>,
+    repo: Option<&str>,
+    failed: bool,
+    timestamps: Option<TimestampFormat>,
+) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let timestamps = timestamps.unwrap_or(config.timestamps);
+    let db_path = config.get_db_path();
+    let db = StateDb::open(&db_path)?;
+
+    let operations: Vec<_> = db
+        .list_operations()?
+        .into_iter()
+        .filter(|op| command.is_none_or(|c| op.command.split_whitespace().next() == Some(c)))
+        .filter(|op| repo.is_none_or(|r| op.repos.iter().any(|o| o.name == r)))
+        .filter(|op| !failed || op.error_count() > 0)
+        .take(limit)
+        .collect();
+
+    if operations.is_empty() {
+        println!("{} No operations recorded.", icons::status::info());
+        return Ok(());
+    }
+
+    for op in &operations {
+        let errors = op.error_count();
+        let summary = format!(
+            "{} repositories, {} error{}, {}",
+            op.repos.len(),
+            errors,
+            if errors == 1 { "" } else { "s" },
+            format_duration(op.duration_ms)
+        );
+        println!(
+            "{} {}  {}  by {}  ({})",
+            if errors == 0 { icons::status::success() } else { icons::status::warning() },
+            format_time(op.started_at, timestamps),
+            op.command.cyan().bold(),
+            op.user,
+            if errors == 0 { summary.normal() } else { summary.yellow() }
+        );
+
+        for outcome in op.repos.iter().filter(|o| repo.is_none_or(|r| o.name == r)) {
+            // Errors can span several lines (e.g., SSH setup hints), only show the first
+            let message = outcome.message.lines().next().unwrap_or("");
+            println!(
+                "    {} {} - {} ({})",
+                if outcome.success { icons::status::success() } else { icons::status::error() },
+                if outcome.success { outcome.name.cyan() } else { outcome.name.yellow() },
+                message,
+                format_duration(outcome.duration_ms)
+            );
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::utils::icons;
+use anyhow::{anyhow, Result};
+use colored::*;
+use metagit_core::forge::{default_host, DeviceFlow};
+use metagit_core::keychain::{set_secret, SecretKind};
+use metagit_core::models::{Config, ForgeKind};
+
+/// Sign in to GitHub or GitLab with the OAuth device flow and store the token in the OS keychain
+/// The token is stored under the host, where HTTPS authentication and the PR/CI integrations look it up.
+pub fn login_command(kind: ForgeKind, host: Option<&str>, client_id: Option<&str>) -> Result<()> {
+    // Works outside of a workspace too: the proxy and client IDs may come from the global config
+    let config = Config::load_from_project()
+        .ok()
+        .or_else(|| Config::load_global().ok().flatten())
+        .unwrap_or_default();
+    let host = host.unwrap_or(default_host(kind));
+    let client_id = client_id
+        .map(str::to_string)
+        .or_else(|| config.oauth_client_ids.get(host).cloned())
+        .ok_or_else(|| {
+            anyhow!(
+                "No OAuth client ID for {}. Register an OAuth app with the device flow enabled, then pass \
+                 --client-id or add it to oauth_client_ids in the config",
+                host
+            )
+        })?;
+
+    let flow = DeviceFlow::new(&config, kind, host, &client_id)?;
+    let authorization = flow.start()?;
+    println!(
+        "{} Open {} and enter the code {}\n",
+        icons::status::info(),
+        authorization.verification_uri.cyan(),
+        authorization.user_code.bold().yellow()
+    );
+    println!("{} Waiting for authorization...", icons::status::waiting());
+
+    let token = flow.wait_for_token(&authorization)?;
+    set_secret(host, SecretKind::HttpsToken, &token)?;
+
+    println!(
+        "{} Logged in to {}. The token is stored in the OS keychain",
+        icons::status::success(),
+        host.cyan().bold()
+    );
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use colored::*;
+
+use metagit_core::models::Config;
+use crate::utils::{icons, porcelain_flag, porcelain_line};
+
+/// List the configured repositories (archived ones too with `all_repos`)
+/// `porcelain` prints one tab-separated record per repository instead:
+/// name, path, url, group, cloned (1/0), archived (1/0)
+pub fn ls_command(porcelain: bool, all_repos: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    if porcelain {
+        for repo in config.active_repositories(all_repos) {
+            let path = config.resolve_repo_path(&repo.name);
+            println!(
+                "{}",
+                porcelain_line(&[
+                    repo.name.as_str(),
+                    &path.display().to_string(),
+                    repo.url.as_str(),
+                    repo.group.as_deref().unwrap_or(""),
+                    porcelain_flag(path.exists()),
+                    porcelain_flag(repo.archived),
+                ])
+            );
+        }
+        return Ok(());
+    }
+
+    if config.repositories.is_empty() {
+        println!("{} No repositories configured", icons::status::info());
+        return Ok(());
+    }
+
+    println!(
+        "{:<28} {:<20} {}",
+        format!("{} REPOSITORY", icons::files::folder()).bold(),
+        "GROUP".bold(),
+        "URL".bold()
+    );
+    for repo in config.active_repositories(all_repos) {
+        let mut notes = Vec::new();
+        if !config.resolve_repo_path(&repo.name).exists() {
+            notes.push("not cloned".red().to_string());
+        }
+        if repo.archived {
+            notes.push("archived".bright_black().to_string());
+        }
+        if let Some(path) = config.external_repo_path(&repo.name) {
+            notes.push(format!("at {}", path.display()).bright_black().to_string());
+        }
+        println!(
+            "  {:<26} {:<20} {}{}",
+            repo.name,
+            repo.group.as_deref().unwrap_or("-"),
+            repo.url,
+            if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) }
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::utils::icons;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Generate man pages from the CLI definition
+/// Without an output directory, the mgit(1) page is written to stdout;
+/// with one, a page is written for every subcommand (mgit-status.1, mgit-bundle-create.1, ...)
+pub fn man_command(cmd: clap::Command, output: Option<&str>) -> Result<()> {
+    let Some(dir) = output else {
+        clap_mangen::Man::new(cmd)
+            .render(&mut io::stdout())
+            .context("Failed to render man page")?;
+        return Ok(());
+    };
+
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create directory '{}'", dir))?;
+    clap_mangen::generate_to(cmd, dir).context("Failed to generate man pages")?;
+
+    let mut pages: Vec<String> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("mgit") && name.ends_with(".1"))
+        .collect();
+    pages.sort();
+
+    for page in &pages {
+        println!("  {} {}", icons::files::file(), page);
+    }
+    println!();
+    println!(
+        "{} Generated {} man pages in {}",
+        icons::status::success(),
+        pages.len(),
+        Path::new(dir).display().to_string().cyan()
+    );
+
+    Ok(())
+}
@@ -1,19 +1,83 @@
+pub mod apply;
+pub mod archive;
+pub mod bundle;
+pub mod changelog;
+pub mod check;
+pub mod cleanup;
+pub mod contains;
+pub mod credential;
+pub mod db;
+pub mod env;
+pub mod hooks;
+pub mod identity;
 pub mod init;
+pub mod log_ops;
+pub mod login;
+pub mod ls;
+pub mod man;
+pub mod mv;
+pub mod owners;
+pub mod plugin;
+pub mod pr;
+pub mod prompt;
 pub mod pull;
 pub mod push;
 pub mod refresh;
+pub mod release;
+pub mod remote;
 pub mod restore;
 pub mod run;
 pub mod save;
+pub mod secrets;
+pub mod stale;
 pub mod status;
+pub mod submodule;
+pub mod summary;
 pub mod sync;
+pub mod tag;
+pub mod undo;
+pub mod verify;
+pub mod whoami;
+pub mod workspace;
 
+pub use apply::*;
+pub use archive::*;
+pub use bundle::*;
+pub use changelog::*;
+pub use check::*;
+pub use cleanup::*;
+pub use contains::*;
+pub use credential::*;
+pub use db::*;
+pub use env::*;
+pub use hooks::*;
+pub use identity::*;
 pub use init::*;
+pub use log_ops::*;
+pub use login::*;
+pub use ls::*;
+pub use man::*;
+pub use mv::*;
+pub use owners::*;
+pub use plugin::*;
+pub use pr::*;
+pub use prompt::*;
 pub use pull::*;
 pub use push::*;
 pub use refresh::*;
+pub use release::*;
+pub use remote::*;
 pub use restore::*;
 pub use run::*;
 pub use save::*;
+pub use secrets::*;
+pub use stale::*;
 pub use status::*;
+pub use submodule::*;
+pub use summary::*;
 pub use sync::*;
+pub use tag::*;
+pub use undo::*;
+pub use verify::*;
+pub use whoami::*;
+pub use workspace::*;
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use std::fs;
+use std::path::{Component, Path};
+use std::time::Instant;
+
+use metagit_core::db::StateDb;
+use metagit_core::models::Config;
+use crate::commands::update_workspace_files;
+use crate::utils::{icons, record_operation, start_operation};
+
+/// Rename a repository: its directory, its config entry with the task steps, saved tags and
+/// profiles referring to it, and what the state database stores under its name
+/// A repository with a `path` keeps its directory; only its name changes.
+pub fn mv_command(old: &str, new: &str) -> Result<()> {
+    let new_path = Path::new(new);
+    if new.is_empty()
+        || new_path.is_absolute()
+        || new_path.components().any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(anyhow!(
+            "'{}' is not a directory inside the workspace; set the repository's `path` to check it out elsewhere",
+            new
+        ));
+    }
+    let mut config = Config::load_from_project()?;
+    let config_path = Config::find_project_config().ok_or_else(|| anyhow!("Could not find .mgitconfig.yaml"))?;
+
+    let repo_config = config
+        .repositories
+        .iter()
+        .find(|r| r.name == old)
+        .ok_or_else(|| anyhow!("Repository '{}' not found in configuration", old))?;
+    // Encrypted tokens are stored by repository name; the renamed one would be written in plain text
+    if config.decrypted_secrets.as_ref().is_some_and(|secrets| secrets.tokens.contains_key(old)) {
+        return Err(anyhow!(
+            "The token of '{}' is in encrypted_secrets. Run '{}' first and '{}' after the rename.",
+            old,
+            "mgit secrets decrypt".cyan(),
+            "mgit secrets encrypt".cyan()
+        ));
+    }
+    let keeps_path = repo_config.path.is_some();
+    let old_dir = config.resolve_repo_path(old);
+    let steps = config.tasks.iter().flat_map(|t| &t.steps).filter(|s| s.repo == old).count();
+    let tags = config.tags.values().filter(|branches| branches.contains_key(old)).count();
+    let profiles = config
+        .profiles
+        .values()
+        .filter(|p| p.repositories.as_ref().is_some_and(|names| names.iter().any(|n| n == old)))
+        .count();
+    config.rename_repository(old, new)?;
+    let new_dir = config.resolve_repo_path(new);
+
+    let mut operation = start_operation(format!("mv {} {}", old, new));
+    let started = Instant::now();
+    println!("{} Renaming '{}' to '{}'...\n", icons::status::info(), old.cyan(), new.cyan().bold());
+
+    // Opened before anything moves: another mgit command may hold it
+    let db_path = config.get_db_path();
+    let db = StateDb::open(&db_path)?;
+
+    if keeps_path {
+        println!("  {} directory stays at {} (set by `path`)", icons::status::info(), old_dir.display());
+    } else if !old_dir.exists() {
+        println!("  {} not cloned, no directory to move", icons::status::info());
+    } else {
+        if new_dir.exists() {
+            return Err(anyhow!("{} already exists", new_dir.display()));
+        }
+        if let Some(parent) = new_dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&old_dir, &new_dir)
+            .map_err(|e| anyhow!("Could not move {} to {}: {}", old_dir.display(), new_dir.display(), e))?;
+        println!("  {} moved {} to {}", icons::status::success(), old_dir.display(), new_dir.display());
+    }
+
+    db.rename_repository(old, new)?;
+    drop(db);
+    println!("  {} moved the cached state", icons::status::success());
+
+    config.save(config_path.to_str().unwrap())?;
+    let references = [(steps, "task step"), (tags, "saved tag"), (profiles, "profile")]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}{}", count, label, if *count == 1 { "" } else { "s" }))
+        .collect::<Vec<_>>();
+    if references.is_empty() {
+        println!("  {} updated .mgitconfig.yaml", icons::status::success());
+    } else {
+        println!("  {} updated .mgitconfig.yaml ({})", icons::status::success(), references.join(", "));
+    }
+    match update_workspace_files(&config) {
+        Ok(updated) => {
+            for path in updated {
+                println!("  {} updated {}", icons::status::success(), path.display());
+            }
+        }
+        Err(e) => println!("  {} editor workspace not updated: {}", icons::status::warning(), e),
+    }
+
+    operation.add_outcome(new, true, format!("renamed from {}", old), started.elapsed());
+    record_operation(&config, operation);
+    println!("\n{} Renamed '{}' to '{}'", icons::status::success(), old, new);
+    Ok(())
+}
@@ -0,0 +1,343 @@
+use metagit_core::db::StateDb;
+use metagit_core::models::{Config, LineStats, RepoState};
+use crate::utils::icons;
+use anyhow::{Context, Result};
+use chrono::{SecondsFormat, Utc};
+use colored::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+
+/// Output formats of `mgit owners`
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum OwnersFormat {
+    /// Markdown tables, for team wikis
+    Md,
+    /// JSON, for scripts
+    Json,
+}
+
+#[derive(Serialize)]
+struct Contributor {
+    name: String,
+    commits: usize,
+    /// Percentage of the commits, rounded to one decimal
+    share: f64,
+    /// Lines added/removed, when collected by `mgit refresh --lines`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lines: Option<LineStats>,
+}
+
+#[derive(Serialize)]
+struct BranchOwner {
+    name: String,
+    owner: String,
+    /// Commits counted for the branch (unmerged commits for branches other than main/master)
+    commits: usize,
+    last_updated: String,
+}
+
+#[derive(Serialize)]
+struct RepoOwnership {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    owner: String,
+    commits: usize,
+    /// Time window of the counted commits (`mgit refresh --since/--until`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    until: Option<String>,
+    contributors: Vec<Contributor>,
+    branches: Vec<BranchOwner>,
+}
+
+#[derive(Serialize)]
+struct GroupOwnership {
+    name: String,
+    owner: String,
+    commits: usize,
+    repositories: usize,
+    contributors: Vec<Contributor>,
+}
+
+#[derive(Serialize)]
+struct OwnershipReport {
+    generated_at: String,
+    groups: Vec<GroupOwnership>,
+    repositories: Vec<RepoOwnership>,
+    /// Configured repositories without refreshed state
+    not_refreshed: Vec<String>,
+}
+
+/// Aggregate the commit statistics collected by `mgit refresh` into an ownership report
+pub fn owners_command(format: OwnersFormat, output: Option<&str>) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let db_path = config.get_db_path();
+    let db = StateDb::open(&db_path)?;
+
+    let mut repositories = Vec::new();
+    let mut not_refreshed = Vec::new();
+    let mut group_stats: BTreeMap<String, (AuthorStats, usize)> = BTreeMap::new();
+
+    for repo_config in &config.repositories {
+        let state = match db.get_repo_state(&repo_config.name)? {
+            Some(state) => state,
+            None => {
+                not_refreshed.push(repo_config.name.clone());
+                continue;
+            }
+        };
+
+        let stats = repo_commit_stats(&state);
+        // Nested groups ("backend/services") also count towards their parents ("backend")
+        if let Some(group) = &repo_config.group {
+            let parts: Vec<&str> = group.split('/').collect();
+            for depth in 1..=parts.len() {
+                let (group_authors, repo_count) = group_stats.entry(parts[..depth].join("/")).or_default();
+                group_authors.merge(&stats);
+                *repo_count += 1;
+            }
+        }
+
+        let contributors = contributors(&stats);
+        repositories.push(RepoOwnership {
+            name: repo_config.name.clone(),
+            group: repo_config.group.clone(),
+            owner: owner_label(&contributors),
+            commits: stats.commits.values().sum(),
+            since: state.stats_options.since.map(|s| s.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            until: state.stats_options.until.map(|u| u.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            contributors,
+            branches: state
+                .branches
+                .iter()
+                .map(|branch| BranchOwner {
+                    name: branch.name.clone(),
+                    owner: branch.owner.clone(),
+                    commits: branch.commit_stats.values().sum(),
+                    last_updated: branch.last_updated.format("%Y-%m-%d").to_string(),
+                })
+                .collect(),
+        });
+    }
+
+    let groups = group_stats
+        .into_iter()
+        .map(|(name, (stats, repo_count))| {
+            let contributors = contributors(&stats);
+            GroupOwnership {
+                name,
+                owner: owner_label(&contributors),
+                commits: stats.commits.values().sum(),
+                repositories: repo_count,
+                contributors,
+            }
+        })
+        .collect();
+
+    let report = OwnershipReport {
+        generated_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        groups,
+        repositories,
+        not_refreshed,
+    };
+
+    let content = match format {
+        OwnersFormat::Md => render_markdown(&report)?,
+        OwnersFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, &content)
+                .with_context(|| format!("Failed to write ownership report to '{}'", path))?;
+            println!(
+                "{} Ownership report written to {} ({} repositories)",
+                icons::status::success(),
+                path.cyan(),
+                report.repositories.len()
+            );
+        }
+        None => print!("{}", content),
+    }
+
+    if !report.not_refreshed.is_empty() {
+        eprintln!(
+            "\n{} {} repositor{} not refreshed yet. Run 'mgit refresh' to include them.",
+            icons::status::warning(),
+            report.not_refreshed.len(),
+            if report.not_refreshed.len() == 1 { "y is" } else { "ies are" }
+        );
+    }
+
+    Ok(())
+}
+
+/// Commits (and lines, if collected) per author
+#[derive(Default)]
+struct AuthorStats {
+    commits: HashMap<String, usize>,
+    lines: HashMap<String, LineStats>,
+}
+
+impl AuthorStats {
+    fn merge(&mut self, other: &AuthorStats) {
+        for (author, count) in &other.commits {
+            *self.commits.entry(author.clone()).or_insert(0) += count;
+        }
+        for (author, lines) in &other.lines {
+            self.lines.entry(author.clone()).or_default().add(*lines);
+        }
+    }
+}
+
+/// Statistics per author over all branches of a repository
+/// Branches other than main/master only count their unmerged commits, so they don't overlap with main.
+fn repo_commit_stats(state: &RepoState) -> AuthorStats {
+    let mut stats = AuthorStats::default();
+    for branch in &state.branches {
+        for (author, count) in &branch.commit_stats {
+            *stats.commits.entry(author.clone()).or_insert(0) += count;
+        }
+        for (author, lines) in &branch.line_stats {
+            stats.lines.entry(author.clone()).or_default().add(*lines);
+        }
+    }
+    stats
+}
+
+/// Contributors sorted by commit count (then name)
+fn contributors(stats: &AuthorStats) -> Vec<Contributor> {
+    let total: usize = stats.commits.values().sum();
+    let has_lines = !stats.lines.is_empty();
+    let mut contributors: Vec<Contributor> = stats
+        .commits
+        .iter()
+        .map(|(name, &commits)| Contributor {
+            name: name.clone(),
+            commits,
+            share: (commits as f64 * 1000.0 / total.max(1) as f64).round() / 10.0,
+            lines: has_lines.then(|| stats.lines.get(name).copied().unwrap_or_default()),
+        })
+        .collect();
+    contributors.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.name.cmp(&b.name)));
+    contributors
+}
+
+fn owner_label(contributors: &[Contributor]) -> String {
+    contributors
+        .first()
+        .map(|c| c.name.clone())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+fn render_markdown(report: &OwnershipReport) -> Result<String> {
+    let mut md = String::new();
+    writeln!(md, "# Ownership Report")?;
+    writeln!(md)?;
+    writeln!(md, "_Generated {} from `mgit refresh` data._", &report.generated_at[..10])?;
+
+    // Windows are per repository; describe them once when they all match
+    let mut windows: Vec<(Option<&str>, Option<&str>)> = report
+        .repositories
+        .iter()
+        .map(|r| (r.since.as_deref(), r.until.as_deref()))
+        .collect();
+    windows.sort();
+    windows.dedup();
+    match windows.as_slice() {
+        [(None, None)] | [] => {}
+        [(since, until)] => {
+            let bound = |label: &str, time: &Option<&str>| time.map(|t| format!(" {} {}", label, &t[..10]));
+            writeln!(md)?;
+            writeln!(
+                md,
+                "_Counting commits{}{}._",
+                bound("since", since).unwrap_or_default(),
+                bound("until", until).unwrap_or_default()
+            )?;
+        }
+        _ => {
+            writeln!(md)?;
+            writeln!(
+                md,
+                "_Repositories were refreshed with different `--since`/`--until` windows; see the JSON format for details._"
+            )?;
+        }
+    }
+
+    if !report.groups.is_empty() {
+        writeln!(md)?;
+        writeln!(md, "## Groups")?;
+        writeln!(md)?;
+        writeln!(md, "| Group | Repositories | Owner | Commits | Top contributors |")?;
+        writeln!(md, "|---|---:|---|---:|---|")?;
+        for group in &report.groups {
+            writeln!(
+                md,
+                "| {} | {} | {} | {} | {} |",
+                group.name,
+                group.repositories,
+                group.owner,
+                group.commits,
+                top_contributors(&group.contributors)
+            )?;
+        }
+    }
+
+    writeln!(md)?;
+    writeln!(md, "## Repositories")?;
+    writeln!(md)?;
+    writeln!(md, "| Repository | Group | Owner | Commits | Top contributors |")?;
+    writeln!(md, "|---|---|---|---:|---|")?;
+    for repo in &report.repositories {
+        writeln!(
+            md,
+            "| {} | {} | {} | {} | {} |",
+            repo.name,
+            repo.group.as_deref().unwrap_or(""),
+            repo.owner,
+            repo.commits,
+            top_contributors(&repo.contributors)
+        )?;
+    }
+
+    for repo in &report.repositories {
+        writeln!(md)?;
+        writeln!(md, "### {}", repo.name)?;
+        writeln!(md)?;
+        writeln!(md, "| Branch | Owner | Commits | Last updated |")?;
+        writeln!(md, "|---|---|---:|---|")?;
+        for branch in &repo.branches {
+            writeln!(
+                md,
+                "| {} | {} | {} | {} |",
+                branch.name, branch.owner, branch.commits, branch.last_updated
+            )?;
+        }
+    }
+
+    if !report.not_refreshed.is_empty() {
+        writeln!(md)?;
+        writeln!(md, "_Not refreshed: {}._", report.not_refreshed.join(", "))?;
+    }
+
+    Ok(md)
+}
+
+/// "Jane (60%), John (30%), Ana (10%)" - the three largest contributors
+/// With line counts: "Jane (60%, +1200/-300), ..."
+fn top_contributors(contributors: &[Contributor]) -> String {
+    contributors
+        .iter()
+        .take(3)
+        .map(|c| match c.lines {
+            Some(lines) => format!("{} ({}%, +{}/-{})", c.name, c.share, lines.insertions, lines.deletions),
+            None => format!("{} ({}%)", c.name, c.share),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
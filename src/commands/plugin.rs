@@ -0,0 +1,86 @@
+use anyhow::{anyhow, Context, Result};
+use metagit_core::models::Config;
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Run an external plugin: `mgit foo args...` executes `mgit-foo args...` from PATH
+/// Workspace context is passed through environment variables:
+/// - MGIT_CONFIG: path of .mgitconfig.yaml
+/// - MGIT_PROJECT_DIR: directory containing .mgitconfig.yaml
+/// - MGIT_DB: path of the state database
+/// - MGIT_REPOS: JSON array of repositories ({"name", "url", "path", "group"})
+///
+/// The variables are only set when a workspace config is found.
+/// Exits with the plugin's exit code.
+pub fn plugin_command(args: Vec<OsString>) -> Result<()> {
+    let (name, plugin_args) = args
+        .split_first()
+        .ok_or_else(|| anyhow!("Missing plugin name"))?;
+    let name = name.to_string_lossy();
+
+    let executable = find_plugin(&name).ok_or_else(|| {
+        anyhow!(
+            "Unknown command '{}'. No 'mgit-{}' executable found on PATH.\nRun 'mgit --help' to list the built-in commands.",
+            name,
+            name
+        )
+    })?;
+
+    let mut command = Command::new(&executable);
+    command.args(plugin_args);
+
+    if let Some(config_path) = Config::find_project_config() {
+        let config = Config::load(config_path.to_str().unwrap_or(".mgitconfig.yaml"))?;
+        let repos: Vec<serde_json::Value> = config
+            .repositories
+            .iter()
+            .map(|repo| {
+                serde_json::json!({
+                    "name": repo.name,
+                    "url": repo.url,
+                    "path": config.resolve_repo_path(&repo.name),
+                    "group": repo.group,
+                })
+            })
+            .collect();
+
+        command
+            .env("MGIT_CONFIG", &config_path)
+            .env("MGIT_DB", config.get_db_path())
+            .env("MGIT_REPOS", serde_json::to_string(&repos)?);
+        if let Some(dir) = &config.config_dir {
+            command.env("MGIT_PROJECT_DIR", dir);
+        }
+    }
+
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run {}", executable.display()))?;
+
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+/// Find the `mgit-<name>` executable in the directories listed in PATH
+fn find_plugin(name: &str) -> Option<PathBuf> {
+    let file_name = format!("mgit-{}{}", name, env::consts::EXE_SUFFIX);
+    let path = env::var_os("PATH")?;
+
+    env::split_paths(&path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use std::time::Instant;
+
+use metagit_core::forge::{api_token, ForgeClient, ForgeRepo, PullRequest};
+use metagit_core::models::Config;
+use crate::utils::{compare_with_base, get_head, icons, record_operation, start_operation, VarContext};
+
+/// Open a pull request (GitLab: merge request) for every repository whose current branch is ahead of
+/// its base branch, and print the created URLs
+/// `title` and `body` are templates: `$(REPO)`, `$(BRANCH)` and `$(BASE)` are set per repository,
+/// next to the usual task variables. `base` overrides the default branch as target.
+/// Branches that aren't pushed to origin with all their commits are skipped.
+pub fn pr_create_command(
+    title: &str,
+    body: Option<String>,
+    body_file: Option<&str>,
+    base: Option<&str>,
+    draft: bool,
+    all_repos: bool,
+) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let project_dir = config
+        .config_dir
+        .clone()
+        .ok_or_else(|| anyhow!("Could not determine project directory"))?;
+    let body = match body_file {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| anyhow!("Could not read {}: {}", path, e))?,
+        None => body.unwrap_or_default(),
+    };
+    let env_files = config.env_file_paths();
+    let mut operation = start_operation("pr create");
+
+    println!("{} Opening pull requests...\n", icons::status::info());
+
+    let (mut created, mut skipped, mut failed) = (0, 0, 0);
+    for repo_config in config.active_repositories(all_repos) {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        if !repo_path.exists() {
+            continue;
+        }
+        let Ok((Some(branch), _)) = get_head(&repo_path) else {
+            continue;
+        };
+        let comparison = match compare_with_base(&repo_path, &branch, base) {
+            Ok(comparison) => comparison,
+            Err(e) => {
+                println!("{:<30} {}: {}", repo_config.name, "skipped".yellow(), e);
+                skipped += 1;
+                continue;
+            }
+        };
+        if branch == comparison.base || comparison.ahead == 0 {
+            continue;
+        }
+        if !comparison.pushed {
+            println!(
+                "{:<30} {} {} has unpushed commits (run {})",
+                repo_config.name,
+                "skipped:".yellow(),
+                branch.cyan(),
+                "mgit push".cyan()
+            );
+            skipped += 1;
+            continue;
+        }
+
+        let started = Instant::now();
+        let defines = vec![
+            format!("REPO={}", repo_config.name),
+            format!("BRANCH={}", branch),
+            format!("BASE={}", comparison.base),
+        ];
+        let result = VarContext::new(&project_dir, &env_files, &config.variables, defines).and_then(|ctx| {
+            let request = PullRequest {
+                title: ctx.substitute(title)?,
+                body: ctx.substitute(&body)?,
+                head: branch.clone(),
+                base: comparison.base.clone(),
+                draft,
+            };
+            let forge = ForgeRepo::from_url(&repo_config.url, &config)
+                .ok_or_else(|| anyhow!("not a GitHub or GitLab repository (see forges)"))?;
+            let token = api_token(repo_config, &forge).ok_or_else(|| anyhow!("no API token configured"))?;
+            ForgeClient::new(&config, forge, token)?.create_pull_request(&request)
+        });
+
+        match result {
+            Ok(url) => {
+                created += 1;
+                println!(
+                    "{:<30} {} {} {} {}",
+                    repo_config.name,
+                    url.green(),
+                    branch.cyan(),
+                    icons::symbol("→", "->"),
+                    comparison.base
+                );
+                operation.add_outcome(&repo_config.name, true, url, started.elapsed());
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{:<30} {}: {}", repo_config.name, "failed".red(), e);
+                operation.add_outcome(&repo_config.name, false, e.to_string(), started.elapsed());
+            }
+        }
+    }
+
+    if created + skipped + failed == 0 {
+        println!("{} No branches ahead of their base branch", icons::status::info());
+    } else {
+        println!(
+            "\n{} {} pull request{} opened, {} skipped, {} failed",
+            if skipped + failed == 0 { icons::status::success() } else { icons::status::warning() },
+            created,
+            if created == 1 { "" } else { "s" },
+            skipped,
+            failed
+        );
+    }
+
+    record_operation(&config, operation);
+    Ok(())
+}
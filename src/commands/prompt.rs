@@ -0,0 +1,66 @@
+use anyhow::Result;
+
+use metagit_core::db::StateDb;
+use metagit_core::models::Config;
+use crate::utils::{get_head, get_worktree_status, icons};
+
+/// Print a compact workspace summary for shell prompts, e.g. "3✗ 2↓ 1↑"
+/// (repositories with uncommitted changes, behind origin, ahead of origin)
+/// Only the state database is read, without git or loading the configuration, so it stays fast.
+/// The counts are as of the last status, refresh, pull, push or sync. Prints nothing outside a
+/// workspace, when everything is clean, or while another mgit command holds the database.
+pub fn prompt_command() -> Result<()> {
+    let Some(config_path) = Config::find_project_config() else {
+        return Ok(());
+    };
+    let db_path = Config {
+        config_dir: config_path.parent().map(|dir| dir.to_path_buf()),
+        ..Default::default()
+    }
+    .get_db_path();
+    // Opening would create an empty database
+    if !db_path.exists() {
+        return Ok(());
+    }
+    let Ok(db) = StateDb::open(&db_path) else {
+        return Ok(());
+    };
+
+    let (mut dirty, mut behind, mut ahead) = (0, 0, 0);
+    for worktree in db.list_all_states()?.iter().filter_map(|state| state.worktree) {
+        dirty += usize::from(worktree.dirty);
+        behind += usize::from(worktree.behind > 0);
+        ahead += usize::from(worktree.ahead > 0);
+    }
+
+    let symbols = [(dirty, ("✗", "*")), (behind, ("↓", "<")), (ahead, ("↑", ">"))];
+    let parts: Vec<String> = symbols
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, (unicode, ascii))| format!("{}{}", count, icons::symbol(unicode, ascii)))
+        .collect();
+    if !parts.is_empty() {
+        println!("{}", parts.join(" "));
+    }
+    Ok(())
+}
+
+/// Store the working tree summary of repositories changed by a pull, push or sync for `mgit prompt`
+/// Repositories without a stored state are left to the next status or refresh.
+pub fn update_worktree_states<'a>(config: &Config, names: impl IntoIterator<Item = &'a str>) {
+    let db_path = config.get_db_path();
+    let Ok(db) = StateDb::open(&db_path) else {
+        return;
+    };
+    for name in names {
+        let Ok(Some(mut state)) = db.get_repo_state(name) else {
+            continue;
+        };
+        let repo_path = config.resolve_repo_path(name);
+        if let Ok((Some(branch), _)) = get_head(&repo_path) {
+            state.worktree = Some(get_worktree_status(&repo_path, &branch));
+            state.current_branch = branch;
+            let _ = db.save_repo_state(&state);
+        }
+    }
+}
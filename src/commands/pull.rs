@@ -1,37 +1,181 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use colored::*;
+use std::path::Path;
+use std::sync::Arc;
 
-use crate::models::Config;
-use crate::utils::pull_repo;
+use metagit_core::models::{Config, Repository};
+use crate::utils::{
+    capture_heads, extract_hostname, human_println, icons, interrupted, network_timeout, pick_repositories, pop_stash,
+    pull_repo, record_operation, report_failures, report_interrupted, resolve_jobs, run_parallel, save_undo,
+    stash_changes, start_operation, still_running, with_timeout, RepoProgress,
+};
+use metagit_core::git::PullOutcome;
+use crate::commands::update_worktree_states;
+use std::time::Instant;
 
-pub fn pull_command(debug: bool) -> Result<()> {
-    let config = Config::load_from_project()?;
+/// New commit subjects listed per repository when `pull_commits` is not configured
+const DEFAULT_PULL_COMMITS: usize = 5;
+
+/// Render the subjects of up to `limit` pulled commits, one indented line each
+pub fn pulled_commit_lines(outcome: &PullOutcome, limit: usize) -> String {
+    let mut lines = String::new();
+    for commit in outcome.new_commits.iter().take(limit) {
+        lines.push_str(&format!(
+            "\n      {} {} ({})",
+            icons::symbol("•", "-"),
+            commit.summary,
+            commit.short_sha.bright_black()
+        ));
+    }
+    if outcome.new_commits.len() > limit && limit > 0 {
+        lines.push_str(&format!(
+            "\n      {} and {} more",
+            icons::symbol("…", "..."),
+            outcome.new_commits.len() - limit
+        ));
+    }
+    lines
+}
+
+/// Number of new commit subjects listed per repository
+pub fn pull_commits_limit(config: &Config) -> usize {
+    config.pull_commits.unwrap_or(DEFAULT_PULL_COMMITS)
+}
+
+/// Pull a repository; with `autostash`, its local changes are stashed before the update and
+/// reapplied afterwards. Returns whether changes were stashed.
+fn pull_with_autostash(
+    config: &Arc<Config>,
+    repo_config: &Repository,
+    repo_path: &Path,
+    autostash: bool,
+    prune: bool,
+) -> Result<(PullOutcome, bool)> {
+    let timeout = network_timeout(config, repo_config)?;
+    let stashed = autostash && stash_changes(repo_path, "mgit pull --autostash")?;
+    let (config, path, credential) = (Arc::clone(config), repo_path.to_path_buf(), repo_config.credential.clone());
+    let pulled = with_timeout(timeout, move || pull_repo(&config, &path, credential.as_ref(), prune));
+    if !stashed {
+        return pulled.map(|outcome| (outcome, false));
+    }
+    // The pull may still be writing to the working tree: applying the stash now would race it
+    if let Err(e) = &pulled {
+        if still_running(e) {
+            return Err(anyhow!("{}; local changes left in the stash, run 'git stash pop' once it is done", e));
+        }
+    }
+
+    // Reapply the changes also when the pull failed
+    match (pulled, pop_stash(repo_path)) {
+        (Ok(outcome), Ok(())) => Ok((outcome, true)),
+        (Ok(outcome), Err(e)) => Err(anyhow!("{}; {}", outcome.summary(), e)),
+        (Err(e), Ok(())) => Err(e),
+        (Err(e), Err(stash_error)) => Err(anyhow!("{} ({})", e, stash_error)),
+    }
+}
+
+/// Pull every active repository (archived ones too with `all_repos`)
+/// With `interactive`, the repositories are picked from a fuzzy-searchable list first.
+/// `autostash` (default: the config's `autostash`) carries local changes over the update,
+/// `prune` (default: the config's `prune`) removes remote-tracking branches deleted on origin.
+pub fn pull_command(
+    jobs: Option<usize>,
+    all_repos: bool,
+    interactive: bool,
+    autostash: Option<bool>,
+    prune: Option<bool>,
+    debug: bool,
+) -> Result<()> {
+    // Shared with the network operations, which may outlive a timed out repository's turn
+    let config = Arc::new(Config::load_from_project()?);
+    let autostash = autostash.or(config.autostash).unwrap_or(false);
+    let prune = prune.or(config.prune).unwrap_or(false);
+    let concurrency = config.concurrency.clone().unwrap_or_default();
+    let jobs = resolve_jobs(jobs, Some(&concurrency), debug);
+    let limit = pull_commits_limit(&config);
+    let mut operation = start_operation("pull");
+
+    let selected: Vec<&Repository> = config.active_repositories(all_repos).collect();
+    let selected = if interactive { pick_repositories(selected)? } else { selected };
+    if interactive && selected.is_empty() {
+        human_println!("{} No repositories selected", icons::status::info());
+        return Ok(());
+    }
 
     if debug {
-        println!("{}", "🔍 DEBUG MODE ENABLED".bright_cyan().bold());
-        println!();
+        human_println!("{}", format!("{}DEBUG MODE ENABLED", icons::symbol("🔍 ", "")).bright_cyan().bold());
+        human_println!();
     }
 
-    println!("Pulling repositories...\n");
+    human_println!("Pulling repositories...\n");
 
-    for repo_config in &config.repositories {
+    let mut repos = Vec::new();
+    for repo_config in selected {
         let repo_path = config.resolve_repo_path(&repo_config.name);
 
         if !repo_path.exists() {
-            println!("{:<30} {}",repo_config.name.yellow(), "not found".red());
+            human_println!("{:<30} {}",repo_config.name.yellow(), "not found".red());
+            operation.add_outcome(&repo_config.name, false, "not found", Default::default());
             continue;
         }
-
-        if debug {
-            println!("{}", repo_config.name);
-        } else {
-            print!("{:<30} ", repo_config.name);
-        }
-        match pull_repo(&repo_path, debug) {
-            Ok(msg) => println!("{}", msg.green()),
-            Err(e) => println!("{}: {}", "failed".red(), e),
-        }
+        repos.push((repo_config, repo_path));
     }
+    let hosts: Vec<String> = repos
+        .iter()
+        .map(|(repo_config, _)| extract_hostname(&repo_config.url).unwrap_or_default())
+        .collect();
+
+    let undo = capture_heads("pull", &config, repos.iter().map(|(repo_config, _)| repo_config.name.as_str()));
 
+    let mut fast_forwarded = 0;
+    let progress = RepoProgress::new(repos.iter().map(|(repo_config, _)| repo_config.name.as_str()), debug);
+    let not_started = run_parallel(
+        &repos,
+        &hosts,
+        jobs,
+        &concurrency,
+        |(repo_config, repo_path)| {
+            if debug {
+                human_println!("{}", repo_config.name);
+            }
+            progress.start(&repo_config.name);
+            let started = Instant::now();
+            (pull_with_autostash(&config, repo_config, repo_path, autostash, prune), started.elapsed())
+        },
+        |index, (result, duration)| {
+            let repo_name = &repos[index].0.name;
+            let name = if debug { String::new() } else { format!("{:<30} ", repo_name) };
+            match result {
+                Ok((outcome, stashed)) => {
+                    let mut summary = outcome.summary();
+                    if stashed {
+                        summary.push_str(", local changes reapplied");
+                    }
+                    let commits = pulled_commit_lines(&outcome, limit);
+                    fast_forwarded += usize::from(!outcome.new_commits.is_empty());
+                    progress.finish(repo_name, format!("{}{}{}", name, summary.green(), commits));
+                    operation.add_outcome(repo_name, true, summary, duration);
+                }
+                Err(e) => {
+                    progress.finish(repo_name, format!("{}{}: {}", name, "failed".red(), e));
+                    operation.add_outcome(repo_name, false, e.to_string(), duration);
+                }
+            }
+        },
+    );
+    progress.done();
+    report_interrupted(
+        repos.len() - not_started.len(),
+        not_started.iter().map(|&index| repos[index].0.name.as_str()),
+    );
+    update_worktree_states(&config, repos.iter().map(|(repo_config, _)| repo_config.name.as_str()));
+
+    save_undo(&config, undo);
+    // The repositories updated before the interruption can be moved back together
+    if interrupted() && fast_forwarded > 0 {
+        human_println!("Run {} to move the updated repositories back", "mgit undo".cyan());
+    }
+    report_failures(&config, &operation);
+    record_operation(&config, operation);
     Ok(())
 }
@@ -1,37 +1,144 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use colored::*;
+use std::path::Path;
+use std::sync::Arc;
 
-use crate::models::Config;
-use crate::utils::push_repo;
+use metagit_core::models::{Config, Repository};
+use crate::utils::{
+    extract_hostname, human_println, icons, network_timeout, pick_repositories, push_repo, push_tags,
+    record_operation, report_failures, report_interrupted, resolve_jobs, run_parallel, start_operation, with_timeout,
+    RepoProgress,
+};
+use crate::commands::update_worktree_states;
+use std::time::Instant;
 
-pub fn push_command(debug: bool) -> Result<()> {
-    let config = Config::load_from_project()?;
+/// What `mgit push` sends to origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushRefs {
+    /// The current branch
+    Branch,
+    /// The current branch and all local tags
+    BranchAndTags,
+    /// All local tags, no branch
+    Tags,
+}
+
+/// Push a repository's current branch and/or tags, returns the summary line
+fn push_refs(config: &Config, repo_config: &Repository, repo_path: &Path, refs: PushRefs) -> Result<String> {
+    let credential = repo_config.credential.as_ref();
+    let branch = match refs {
+        PushRefs::Tags => None,
+        _ => Some(push_repo(config, repo_path, credential)?.summary()),
+    };
+    if refs == PushRefs::Branch {
+        return Ok(branch.unwrap_or_default());
+    }
+
+    let tags = match (push_tags(config, repo_path, credential), &branch) {
+        (Ok(tags), _) => tags,
+        (Err(e), Some(branch)) => return Err(anyhow!("{}; pushing tags failed: {}", branch, e)),
+        (Err(e), None) => return Err(e),
+    };
+    let pushed = match tags.len() {
+        0 => None,
+        1 => Some(format!("1 new tag ({})", tags[0])),
+        count => Some(format!("{} new tags ({})", count, tags.join(", "))),
+    };
+    Ok(match (branch, pushed) {
+        (Some(branch), Some(pushed)) => format!("{}, {}", branch, pushed),
+        (Some(branch), None) => branch,
+        (None, Some(pushed)) => format!("Pushed {}", pushed),
+        (None, None) => "Tags up-to-date".to_string(),
+    })
+}
+
+/// Push every active repository (archived ones too with `all_repos`)
+/// With `interactive`, the repositories are picked from a fuzzy-searchable list first.
+/// `refs` selects whether the current branch, the tags, or both are pushed.
+pub fn push_command(
+    jobs: Option<usize>,
+    all_repos: bool,
+    interactive: bool,
+    refs: PushRefs,
+    debug: bool,
+) -> Result<()> {
+    // Shared with the network operations, which may outlive a timed out repository's turn
+    let config = Arc::new(Config::load_from_project()?);
+    let concurrency = config.concurrency.clone().unwrap_or_default();
+    let jobs = resolve_jobs(jobs, Some(&concurrency), debug);
+    let mut operation = start_operation("push");
+
+    let selected: Vec<&Repository> = config.active_repositories(all_repos).collect();
+    let selected = if interactive { pick_repositories(selected)? } else { selected };
+    if interactive && selected.is_empty() {
+        human_println!("{} No repositories selected", icons::status::info());
+        return Ok(());
+    }
 
     if debug {
-        println!("{}", "🔍 DEBUG MODE ENABLED".bright_cyan().bold());
-        println!();
+        human_println!("{}", format!("{}DEBUG MODE ENABLED", icons::symbol("🔍 ", "")).bright_cyan().bold());
+        human_println!();
     }
 
-    println!("Pushing repositories...\n");
+    human_println!("Pushing repositories...\n");
 
-    for repo_config in &config.repositories {
+    let mut repos = Vec::new();
+    for repo_config in selected {
         let repo_path = config.resolve_repo_path(&repo_config.name);
 
         if !repo_path.exists() {
-            println!("{:<30} {}",repo_config.name.yellow(), "not found".red());
+            human_println!("{:<30} {}",repo_config.name.yellow(), "not found".red());
+            operation.add_outcome(&repo_config.name, false, "not found", Default::default());
             continue;
         }
-
-        if debug {
-            println!("{}", repo_config.name);
-        } else {
-            print!("{:<30} ", repo_config.name);
-        }
-        match push_repo(&repo_path, debug) {
-            Ok(msg) => println!("{}", msg.green()),
-            Err(e) => println!("{}: {}", "failed".red(), e),
-        }
+        repos.push((repo_config, repo_path));
     }
+    let hosts: Vec<String> = repos
+        .iter()
+        .map(|(repo_config, _)| extract_hostname(&repo_config.url).unwrap_or_default())
+        .collect();
+
+    let progress = RepoProgress::new(repos.iter().map(|(repo_config, _)| repo_config.name.as_str()), debug);
+    let not_started = run_parallel(
+        &repos,
+        &hosts,
+        jobs,
+        &concurrency,
+        |(repo_config, repo_path)| {
+            if debug {
+                human_println!("{}", repo_config.name);
+            }
+            progress.start(&repo_config.name);
+            let started = Instant::now();
+            let pushed = network_timeout(&config, repo_config).and_then(|timeout| {
+                let (config, repo_config, repo_path) = (Arc::clone(&config), (*repo_config).clone(), repo_path.clone());
+                with_timeout(timeout, move || push_refs(&config, &repo_config, &repo_path, refs))
+            });
+            (pushed, started.elapsed())
+        },
+        |index, (result, duration)| {
+            let repo_name = &repos[index].0.name;
+            let name = if debug { String::new() } else { format!("{:<30} ", repo_name) };
+            match result {
+                Ok(summary) => {
+                    progress.finish(repo_name, format!("{}{}", name, summary.green()));
+                    operation.add_outcome(repo_name, true, summary, duration);
+                }
+                Err(e) => {
+                    progress.finish(repo_name, format!("{}{}: {}", name, "failed".red(), e));
+                    operation.add_outcome(repo_name, false, e.to_string(), duration);
+                }
+            }
+        },
+    );
+    progress.done();
+    report_interrupted(
+        repos.len() - not_started.len(),
+        not_started.iter().map(|&index| repos[index].0.name.as_str()),
+    );
+    update_worktree_states(&config, repos.iter().map(|(repo_config, _)| repo_config.name.as_str()));
 
+    report_failures(&config, &operation);
+    record_operation(&config, operation);
     Ok(())
 }
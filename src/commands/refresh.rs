@@ -1,32 +1,64 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
 use colored::Colorize;
+use serde_json::json;
 use std::collections::HashSet;
+use std::time::Instant;
 
-use crate::db::StateDb;
-use crate::models::Config;
-use crate::utils::git::{collect_all_author_identities, refresh_repo_state, repair_repository, AuthorIdentity};
-use crate::utils::icons;
+use metagit_core::db::StateDb;
+use metagit_core::models::{Config, LineStats, StatsOptions, TimestampFormat};
+use metagit_core::git::{collect_all_author_identities, refresh_repo_state, repair_repository, AuthorIdentity};
+use crate::commands::{describe_ref_repair, update_workspace_files};
+use crate::utils::{emit_event, emit_started, format_time, human_println, icons, parse_time_bound};
 
-pub fn refresh_command() -> Result<()> {
+/// Refresh the cached state of every repository
+/// `lines` also collects per-author line counts; `since`/`until` limit the counted commits to a time window.
+/// Archived repositories are skipped unless `all_repos` is set. `prune_db` removes the cached states
+/// of repositories that are no longer configured; otherwise they are only pointed out.
+pub fn refresh_command(
+    timestamps: Option<TimestampFormat>,
+    lines: bool,
+    since: Option<&str>,
+    until: Option<&str>,
+    all_repos: bool,
+    prune_db: bool,
+) -> Result<()> {
     let mut config = Config::load_from_project()?;
+    let timestamps = timestamps.unwrap_or(config.timestamps);
+    let now = Utc::now();
+    let stats = config.stats.clone().unwrap_or_default();
+    let options = StatsOptions {
+        line_stats: lines,
+        merge_commits: stats.merge_commits,
+        first_parent: stats.first_parent,
+        ownership: stats.ownership,
+        owner_threshold: stats.owner_threshold,
+        since: since.map(|s| parse_time_bound(s, now)).transpose()?,
+        until: until.map(|u| parse_time_bound(u, now)).transpose()?,
+    };
     let db_path = config.get_db_path();
-    let db = StateDb::open(db_path.to_str().unwrap_or(".mgitdb"))?;
+    let db = StateDb::open(&db_path)?;
 
     let folder_icon = icons::files::folder();
     let check_icon = icons::status::success();
 
-    println!("{}", "Refreshing repository states...".bold());
-    println!();
+    human_println!("{}", "Refreshing repository states...".bold());
+    if let Some(window) = describe_window(&options, timestamps) {
+        human_println!("{}", format!("Counting commits {}", window).cyan());
+    }
+    human_println!();
 
     let mut success_count = 0;
     let mut error_count = 0;
     let mut repair_count = 0;
     let mut all_identities = HashSet::new();
+    let mut refreshed = Vec::new();
 
-    for repo_config in &config.repositories {
+    for repo_config in config.active_repositories(all_repos) {
         let repo_path = config.resolve_repo_path(&repo_config.name);
 
         if !repo_path.exists() {
+            emit_event("error", &repo_config.name, json!({ "message": "not found", "duration_ms": 0 }));
             eprintln!(
                 "  {} {} - {}",
                 folder_icon,
@@ -37,6 +69,9 @@ pub fn refresh_command() -> Result<()> {
             continue;
         }
 
+        emit_started(&repo_config.name);
+        let started = Instant::now();
+
         // Attempt to repair repository before refreshing
         match repair_repository(&repo_path) {
             Ok(repair_result) => {
@@ -44,8 +79,8 @@ pub fn refresh_command() -> Result<()> {
                     repair_count += 1;
 
                     // Report what was fixed
-                    if repair_result.fixed_fetch_head {
-                        println!(
+                    if repair_result.corrupted_fetch_head {
+                        human_println!(
                             "  {} {} - {}",
                             icons::status::info(),
                             repo_config.name.cyan(),
@@ -53,12 +88,12 @@ pub fn refresh_command() -> Result<()> {
                         );
                     }
 
-                    for ref_path in &repair_result.removed_corrupted_refs {
-                        println!(
+                    for corrupted in &repair_result.corrupted_refs {
+                        human_println!(
                             "  {} {} - {}",
                             icons::status::info(),
                             repo_config.name.cyan(),
-                            format!("removed corrupted ref: {}", ref_path).yellow()
+                            describe_ref_repair(corrupted, true).yellow()
                         );
                     }
                 }
@@ -82,10 +117,8 @@ pub fn refresh_command() -> Result<()> {
         // Get previous state from database for incremental updates
         let previous_state = db.get_repo_state(&repo_config.name).ok().flatten();
 
-        match refresh_repo_state(&repo_path, &repo_config.name, previous_state.as_ref(), &config.users) {
+        match refresh_repo_state(&repo_path, &repo_config.name, previous_state.as_ref(), &config.users, &options) {
             Ok(state) => {
-                // Save to database
-                db.save_repo_state(&state)?;
 
                 let branch_count = state.branches.len();
                 let total_commits: usize = state
@@ -94,17 +127,32 @@ pub fn refresh_command() -> Result<()> {
                     .flat_map(|b| b.commit_stats.values())
                     .sum();
 
-                println!(
-                    "  {} {} {:<30} {} branches, {} commits analyzed",
+                let mut line_summary = String::new();
+                if lines {
+                    let mut totals = LineStats::default();
+                    for branch in &state.branches {
+                        branch.line_stats.values().for_each(|l| totals.add(*l));
+                    }
+                    line_summary = format!(" (+{}/-{} lines)", totals.insertions, totals.deletions);
+                }
+
+                let message = format!("{} branches, {} commits{} analyzed", branch_count, total_commits, line_summary);
+                let duration_ms = started.elapsed().as_millis() as u64;
+                emit_event("finished", &repo_config.name, json!({ "message": message, "duration_ms": duration_ms }));
+                human_println!(
+                    "  {} {} {:<30} {}, updated {}",
                     check_icon,
                     folder_icon,
                     repo_config.name.green(),
-                    branch_count,
-                    total_commits
+                    message,
+                    format_time(state.last_updated, timestamps)
                 );
                 success_count += 1;
+                refreshed.push(state);
             }
             Err(e) => {
+                let duration_ms = started.elapsed().as_millis() as u64;
+                emit_event("error", &repo_config.name, json!({ "message": e.to_string(), "duration_ms": duration_ms }));
                 eprintln!(
                     "  {} {} - {}",
                     folder_icon,
@@ -116,6 +164,8 @@ pub fn refresh_command() -> Result<()> {
         }
     }
 
+    db.save_repo_states(&refreshed)?;
+
     // Process author identities - add all identities and track what was actually added
     let mut unmapped_count = 0;
     let mut unmapped_identities: Vec<AuthorIdentity> = all_identities.into_iter().collect();
@@ -131,19 +181,21 @@ pub fn refresh_command() -> Result<()> {
 
     // Save updated config if anything was added
     if unmapped_count > 0 {
-        config.save(".mgitconfig.yaml")?;
+        let config_path = Config::find_project_config()
+            .ok_or_else(|| anyhow!("Could not find .mgitconfig.yaml"))?;
+        config.save(config_path.to_str().unwrap())?;
     }
 
-    println!();
+    human_println!();
     if error_count == 0 {
-        println!(
+        human_println!(
             "{}",
             format!("Successfully refreshed {} repositories", success_count)
                 .green()
                 .bold()
         );
     } else {
-        println!(
+        human_println!(
             "{}",
             format!(
                 "Refreshed {} repositories ({} errors)",
@@ -155,7 +207,7 @@ pub fn refresh_command() -> Result<()> {
     }
 
     if repair_count > 0 {
-        println!(
+        human_println!(
             "{}",
             format!(
                 "Repaired {} repositor{}",
@@ -167,7 +219,7 @@ pub fn refresh_command() -> Result<()> {
     }
 
     if unmapped_count > 0 {
-        println!(
+        human_println!(
             "{}",
             format!("Added {} new author alias{} to .mgitconfig.yaml",
                 unmapped_count,
@@ -177,5 +229,62 @@ pub fn refresh_command() -> Result<()> {
         );
     }
 
+    // States of repositories removed from the config (archived ones and those outside the profile stay)
+    let mut orphaned: Vec<String> = db
+        .list_all_states()?
+        .into_iter()
+        .map(|state| state.name)
+        .filter(|name| !config.configured_repositories().iter().any(|r| &r.name == name))
+        .collect();
+    orphaned.sort();
+    if !orphaned.is_empty() && prune_db {
+        for name in &orphaned {
+            db.remove_repo_state(name)?;
+        }
+        human_println!(
+            "{}",
+            format!(
+                "Removed the cached state of {} repositor{} no longer in .mgitconfig.yaml: {}",
+                orphaned.len(),
+                if orphaned.len() == 1 { "y" } else { "ies" },
+                orphaned.join(", ")
+            )
+            .cyan()
+        );
+    } else if !orphaned.is_empty() {
+        human_println!(
+            "{} {} cached state{} of repositories no longer in .mgitconfig.yaml ({}). Run '{}' to remove them.",
+            icons::status::info(),
+            orphaned.len(),
+            if orphaned.len() == 1 { "" } else { "s" },
+            orphaned.join(", "),
+            "mgit refresh --prune-db".cyan()
+        );
+    }
+
+    // Keep generated editor workspace files in sync with the repository list
+    match update_workspace_files(&config) {
+        Ok(updated) => {
+            for path in updated {
+                human_println!("{}", format!("Updated {}", path.display()).cyan());
+            }
+        }
+        Err(e) => eprintln!(
+            "{} {}",
+            icons::status::warning(),
+            format!("Could not update workspace files: {}", e).yellow()
+        ),
+    }
+
     Ok(())
 }
+
+/// "since 2025-01-01 09:00 until 2025-04-01 09:00", or None without a time window
+pub fn describe_window(options: &StatsOptions, timestamps: TimestampFormat) -> Option<String> {
+    let since = options.since.map(|s| format!("since {}", format_time(s, timestamps)));
+    let until = options.until.map(|u| format!("until {}", format_time(u, timestamps)));
+    match (since, until) {
+        (Some(since), Some(until)) => Some(format!("{} {}", since, until)),
+        (since, until) => since.or(until),
+    }
+}
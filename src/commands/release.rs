@@ -0,0 +1,161 @@
+use metagit_core::models::Config;
+use crate::utils::{icons, push_tag, record_tag};
+use anyhow::{anyhow, Result};
+use colored::*;
+use git2::Repository;
+use std::collections::HashMap;
+
+/// Create an annotated git tag at the current HEAD of every repository
+/// The current branches are recorded as an mgit saved tag with the same name,
+/// so the release can be restored later with `mgit restore <version>`
+pub fn release_command(version: &str, message: Option<&str>, push: bool, debug: bool) -> Result<()> {
+    // Reserved tags cannot be saved (they're virtual)
+    if version == "master" || version == "main" {
+        return Err(anyhow!(
+            "Tag '{}' is reserved and cannot be used for a release. Reserved tags: 'master', 'main'",
+            version
+        ));
+    }
+
+    let mut config = Config::load_from_project()?;
+
+    if config.tags.contains_key(version) {
+        return Err(anyhow!(
+            "Saved tag '{}' already exists. Choose another version or remove it from .mgitconfig.yaml.",
+            version
+        ));
+    }
+
+    let message = message
+        .map(|m| m.to_string())
+        .unwrap_or_else(|| format!("Release {}", version));
+
+    if debug {
+        println!("{}", format!("{}DEBUG MODE ENABLED", icons::symbol("🔍 ", "")).bright_cyan().bold());
+        println!();
+    }
+
+    println!(
+        "{} Creating release '{}'{}...\n",
+        icons::status::info(),
+        version.cyan().bold(),
+        if push { " and pushing tags" } else { "" }
+    );
+
+    let mut branches = HashMap::new();
+    let mut success_count = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+
+        if !repo_path.exists() {
+            println!(
+                "  {} {} - repository not found",
+                icons::status::error(),
+                repo_config.name.yellow()
+            );
+            error_count += 1;
+            continue;
+        }
+
+        let branch_name = match create_tag(&repo_path, version, &message) {
+            Ok(branch_name) => branch_name,
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    e
+                );
+                error_count += 1;
+                continue;
+            }
+        };
+
+        // The local tag exists from here on, record it even if pushing fails
+        branches.insert(repo_config.name.clone(), branch_name.clone());
+
+        if push {
+            if debug {
+                println!("{}", repo_config.name);
+            }
+            if let Err(e) = push_tag(&config, &repo_path, version, repo_config.credential.as_ref()) {
+                println!(
+                    "  {} {} - tagged {} but push failed: {}",
+                    icons::status::error(),
+                    repo_config.name.yellow(),
+                    branch_name,
+                    e
+                );
+                error_count += 1;
+                continue;
+            }
+        }
+
+        println!(
+            "  {} {} - tagged {}{}",
+            icons::status::success(),
+            repo_config.name.cyan(),
+            branch_name.green(),
+            if push { " (pushed)" } else { "" }
+        );
+        success_count += 1;
+    }
+
+    if branches.is_empty() {
+        return Err(anyhow!("No repositories could be tagged"));
+    }
+
+    // Record the release as a saved tag
+    record_tag(&mut config, version, branches, Some(&message));
+
+    let config_path = Config::find_project_config()
+        .ok_or_else(|| anyhow!("Could not find .mgitconfig.yaml"))?;
+
+    config.save(config_path.to_str().unwrap())?;
+
+    println!();
+    println!(
+        "{} Release '{}' created! ({} repositories, {} errors)",
+        icons::status::success(),
+        version.green().bold(),
+        success_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} Some repositories could not be tagged. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Create an annotated tag at HEAD, returns the current branch name
+fn create_tag(repo_path: &std::path::Path, version: &str, message: &str) -> Result<String> {
+    let repo = Repository::open(repo_path)?;
+    let head = repo.head()?;
+
+    if !head.is_branch() {
+        return Err(anyhow!("detached HEAD state"));
+    }
+    let branch_name = head
+        .shorthand()
+        .ok_or_else(|| anyhow!("Could not get branch name"))?
+        .to_string();
+
+    if repo.find_reference(&format!("refs/tags/{}", version)).is_ok() {
+        return Err(anyhow!("git tag '{}' already exists", version));
+    }
+
+    let signature = repo
+        .signature()
+        .map_err(|_| anyhow!("git user.name/user.email are not configured"))?;
+    let target = head.peel(git2::ObjectType::Commit)?;
+    repo.tag(version, &target, &signature, message, false)?;
+
+    Ok(branch_name)
+}
@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use git2::Repository as GitRepository;
+use std::time::Instant;
+
+use crate::utils::{icons, record_operation, start_operation};
+use metagit_core::models::Config;
+use metagit_core::ssh_config::{https_to_ssh_url, ssh_to_https_url};
+
+/// Protocol `mgit remote set-url` converts the URLs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlScheme {
+    Ssh,
+    Https,
+}
+
+/// New URL for a repository: `replace` applied first, then the conversion to `scheme`
+/// Returns None when nothing changes.
+fn rewrite_url(url: &str, replace: Option<(&str, &str)>, scheme: Option<UrlScheme>) -> Option<String> {
+    let mut new_url = match replace {
+        Some((from, to)) => url.replace(from, to),
+        None => url.to_string(),
+    };
+    match scheme {
+        Some(UrlScheme::Https) => new_url = ssh_to_https_url(&new_url).unwrap_or(new_url),
+        Some(UrlScheme::Ssh) => new_url = https_to_ssh_url(&new_url).unwrap_or(new_url),
+        None => {}
+    }
+    (new_url != url).then_some(new_url)
+}
+
+/// Rewrite the origin URL of every repository (or the given ones) in the config and in the git remote
+/// `replace` substitutes text in the URLs (e.g., an organization name), `scheme` switches them
+/// to SSH or HTTPS. With `dry_run`, the new URLs are only shown.
+pub fn remote_set_url_command(
+    replace: Option<(&str, &str)>,
+    scheme: Option<UrlScheme>,
+    repos: &[String],
+    dry_run: bool,
+) -> Result<()> {
+    if replace.is_none() && scheme.is_none() {
+        return Err(anyhow!("Give the text to replace (FROM TO), --ssh or --https"));
+    }
+    let mut config = Config::load_from_project()?;
+    for name in repos {
+        if !config.repositories.iter().any(|r| &r.name == name) {
+            return Err(anyhow!("Repository '{}' not found in configuration", name));
+        }
+    }
+    let mut operation = start_operation("remote set-url");
+
+    println!(
+        "{} {} remote URLs...\n",
+        icons::status::info(),
+        if dry_run { "Previewing" } else { "Rewriting" }
+    );
+
+    let mut changed = 0;
+    let mut errors = 0;
+    for index in 0..config.repositories.len() {
+        let repo_config = &config.repositories[index];
+        if !repos.is_empty() && !repos.contains(&repo_config.name) {
+            continue;
+        }
+        let Some(new_url) = rewrite_url(&repo_config.url, replace, scheme) else {
+            continue;
+        };
+        let name = repo_config.name.clone();
+        println!("{:<30} {} -> {}", name.cyan(), repo_config.url.bright_black(), new_url.green());
+        changed += 1;
+        if dry_run {
+            continue;
+        }
+
+        // Not cloned yet: the new URL is used when it is
+        let started = Instant::now();
+        let repo_path = config.resolve_repo_path(&name);
+        if repo_path.exists() {
+            let updated = GitRepository::open(&repo_path).and_then(|repo| repo.remote_set_url("origin", &new_url));
+            if let Err(e) = updated {
+                println!("{:<30} {} {}", "", icons::status::error(), format!("git remote not updated: {}", e.message()).red());
+                operation.add_outcome(&name, false, e.message(), started.elapsed());
+                errors += 1;
+                continue;
+            }
+        }
+        operation.add_outcome(&name, true, format!("origin set to {}", new_url), started.elapsed());
+        config.repositories[index].url = new_url;
+    }
+
+    if changed == 0 {
+        println!("{} No URLs to change", icons::status::info());
+        return Ok(());
+    }
+    if dry_run {
+        println!("\n{} Dry run: {} URL{} would change", icons::status::info(), changed, if changed == 1 { "" } else { "s" });
+        return Ok(());
+    }
+
+    let config_path = Config::find_project_config()
+        .ok_or_else(|| anyhow!("Could not find .mgitconfig.yaml"))?;
+    config.save(config_path.to_str().unwrap())?;
+    record_operation(&config, operation);
+
+    println!(
+        "\n{} Remote URLs updated! ({} repositories, {} errors)",
+        icons::status::success(),
+        changed - errors,
+        errors
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_url() {
+        let url = "git@github.com:old-org/api.git";
+        assert_eq!(
+            rewrite_url(url, Some(("old-org", "new-org")), None).as_deref(),
+            Some("git@github.com:new-org/api.git")
+        );
+        assert_eq!(
+            rewrite_url(url, Some(("old-org", "new-org")), Some(UrlScheme::Https)).as_deref(),
+            Some("https://github.com/new-org/api.git")
+        );
+        assert_eq!(rewrite_url(url, Some(("other-org", "new-org")), Some(UrlScheme::Ssh)), None);
+    }
+}
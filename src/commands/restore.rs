@@ -1,21 +1,120 @@
-use crate::models::Config;
-use crate::utils::icons;
+use metagit_core::db::StateDb;
+use metagit_core::models::{Config, PendingRestore};
+use crate::utils::{
+    capture_heads, fetch_remote_branch, format_time, has_uncommitted_changes, icons, pick_one, pop_stash,
+    record_operation, run_checkout_hooks, save_undo, stash_changes, start_operation,
+};
 use anyhow::{anyhow, Result};
+use chrono::Utc;
 use colored::*;
 use git2::Repository;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
 
-pub fn restore_command(tag: &str) -> Result<()> {
+/// Where `mgit restore` takes the branch of each repository from
+pub enum RestoreSource<'a> {
+    /// A saved tag, or the reserved 'master'/'main' tags
+    Tag(&'a str),
+    /// Each repository's pinned `revision`
+    Pinned,
+    /// The repositories the last restore failed on
+    Continue,
+    /// A saved tag picked from a list
+    Pick,
+}
+
+/// Let the user pick a saved tag, newest first; None when cancelled
+fn pick_saved_tag(config: &Config) -> Result<Option<String>> {
+    if config.tags.is_empty() {
+        return Err(anyhow!("No saved tags. Use 'mgit save <tag>' to create one."));
+    }
+    let mut names: Vec<&String> = config.tags.keys().collect();
+    names.sort_by(|a, b| {
+        let created = |name: &String| config.tag_info.get(name).map(|info| info.created_at);
+        created(b).cmp(&created(a)).then_with(|| a.cmp(b))
+    });
+
+    let labels: Vec<String> = names
+        .iter()
+        .map(|name| match config.tag_info.get(*name) {
+            Some(info) => format!(
+                "{} - saved {}{}",
+                name,
+                format_time(info.created_at, config.timestamps),
+                info.description.as_deref().map(|d| format!(", {}", d)).unwrap_or_default()
+            ),
+            None => name.to_string(),
+        })
+        .collect();
+    Ok(pick_one("Tag to restore:", &labels)?.map(|index| names[index].clone()))
+}
+
+/// Restore the branches of a saved tag, the pinned revisions, or retry the last restore's failures
+/// Branches missing locally are created from `origin/<branch>`, fetched first with `fetch`.
+/// Repositories with uncommitted changes are skipped unless `stash` (carry the changes over)
+/// or `force` (discard them) is set. Failed repositories are remembered for `--continue`.
+/// `hooks` runs the repositories' `on_checkout` commands after switching their branch.
+pub fn restore_command(source: RestoreSource, fetch: bool, stash: bool, force: bool, hooks: bool) -> Result<()> {
     let config = Config::load_from_project()?;
+    let db_path = config.get_db_path();
 
-    println!(
-        "{} Restoring branches from tag '{}'...\n",
-        icons::status::info(),
-        tag.cyan().bold()
-    );
+    let picked;
+    let source = match source {
+        RestoreSource::Pick => match pick_saved_tag(&config)? {
+            Some(tag) => {
+                picked = tag;
+                RestoreSource::Tag(&picked)
+            }
+            None => {
+                println!("{} No tag selected", icons::status::info());
+                return Ok(());
+            }
+        },
+        source => source,
+    };
+
+    let pending = match source {
+        RestoreSource::Continue => {
+            let db = StateDb::open(&db_path)?;
+            Some(db.get_pending_restore()?.ok_or_else(|| anyhow!("No failed restore to continue"))?)
+        }
+        _ => None,
+    };
+    let (tag, pinned) = match (&source, &pending) {
+        (RestoreSource::Tag(tag), _) => (*tag, false),
+        (_, Some(pending)) => (pending.tag.as_str(), pending.pinned),
+        _ => ("--pinned", true),
+    };
+
+    if let Some(pending) = &pending {
+        println!(
+            "{} Continuing restore of {} from {} ({} repositor{} left)...\n",
+            icons::status::info(),
+            if pending.pinned { "pinned revisions".to_string() } else { format!("tag '{}'", tag.cyan().bold()) },
+            format_time(pending.created_at, config.timestamps),
+            pending.remaining.len(),
+            if pending.remaining.len() == 1 { "y" } else { "ies" }
+        );
+    } else if pinned {
+        println!("{} Restoring pinned revisions...\n", icons::status::info());
+    } else {
+        println!(
+            "{} Restoring branches from tag '{}'...\n",
+            icons::status::info(),
+            tag.cyan().bold()
+        );
+    }
 
-    // Handle reserved tags 'master' and 'main'
-    let branches = if tag == "master" || tag == "main" {
+    let branches: HashMap<String, String> = if let Some(pending) = &pending {
+        pending.remaining.clone().into_iter().collect()
+    } else if pinned {
+        config
+            .repositories
+            .iter()
+            .filter_map(|r| r.revision.clone().map(|revision| (r.name.clone(), revision)))
+            .collect()
+    } else if tag == "master" || tag == "main" {
+        // Handle reserved tags 'master' and 'main'
         // For reserved tags, determine the default branch for each repo
         println!(
             "{} Using reserved tag '{}' - will switch to default branch (master/main) for each repository\n",
@@ -79,24 +178,42 @@ pub fn restore_command(tag: &str) -> Result<()> {
     };
 
     if branches.is_empty() {
+        if pinned {
+            return Err(anyhow!("No repository has a pinned revision"));
+        }
         return Err(anyhow!("No branches to restore for tag '{}'", tag));
     }
 
     let mut success_count = 0;
     let mut error_count = 0;
+    let mut failed = BTreeMap::new();
+    let mut operation = start_operation(format!("restore {}", tag));
+    let undo = capture_heads(
+        format!("restore {}", tag),
+        &config,
+        config
+            .repositories
+            .iter()
+            .map(|r| r.name.as_str())
+            .filter(|name| branches.contains_key(*name)),
+    );
 
     // Restore branches for each repository
     for repo_config in &config.repositories {
         let repo_path = config.resolve_repo_path(&repo_config.name);
+        let started = Instant::now();
 
         // Skip if no branch saved for this repo
         let branch_name = match branches.get(&repo_config.name) {
             Some(name) => name,
+            // Only the failed repositories are retried
+            None if pending.is_some() => continue,
             None => {
                 println!(
-                    "  {} {} - no branch saved in tag",
+                    "  {} {} - {}",
                     icons::status::warning(),
-                    repo_config.name.yellow()
+                    repo_config.name.yellow(),
+                    if pinned { "no pinned revision" } else { "no branch saved in tag" }
                 );
                 continue;
             }
@@ -108,7 +225,9 @@ pub fn restore_command(tag: &str) -> Result<()> {
                 icons::status::error(),
                 repo_config.name.yellow()
             );
+            operation.add_outcome(&repo_config.name, false, "repository not found", started.elapsed());
             error_count += 1;
+            failed.insert(repo_config.name.clone(), branch_name.clone());
             continue;
         }
 
@@ -126,6 +245,8 @@ pub fn restore_command(tag: &str) -> Result<()> {
                                     repo_config.name.cyan(),
                                     branch_name.green()
                                 );
+                                let message = format!("already on {}", branch_name);
+                                operation.add_outcome(&repo_config.name, true, message, started.elapsed());
                                 success_count += 1;
                                 continue;
                             }
@@ -133,15 +254,101 @@ pub fn restore_command(tag: &str) -> Result<()> {
                     }
                 }
 
+                // Never check out over uncommitted changes unless told what to do with them
+                let dirty = has_uncommitted_changes(&repo_path).unwrap_or(false);
+                if dirty && !stash && !force {
+                    println!(
+                        "  {} {} - has uncommitted changes (use --stash to carry them over or --force to discard them)",
+                        icons::status::error(),
+                        repo_config.name.yellow()
+                    );
+                    operation.add_outcome(&repo_config.name, false, "has uncommitted changes", started.elapsed());
+                    error_count += 1;
+                    failed.insert(repo_config.name.clone(), branch_name.clone());
+                    continue;
+                }
+                let stashed = if dirty && stash {
+                    match stash_changes(&repo_path, &format!("mgit restore {}", tag)) {
+                        Ok(stashed) => stashed,
+                        Err(e) => {
+                            println!("  {} {} - {}", icons::status::error(), repo_config.name.yellow(), e);
+                            operation.add_outcome(&repo_config.name, false, e.to_string(), started.elapsed());
+                            error_count += 1;
+                            failed.insert(repo_config.name.clone(), branch_name.clone());
+                            continue;
+                        }
+                    }
+                } else {
+                    false
+                };
+
+                // A branch saved by someone else may not be here yet
+                let is_local = repo.find_branch(branch_name, git2::BranchType::Local).is_ok();
+                if fetch && !is_local {
+                    let fetched = fetch_remote_branch(&config, &repo_path, branch_name, repo_config.credential.as_ref());
+                    // Pinned revisions may be tags or commits, which can't be fetched as branches
+                    if let (Err(e), false) = (fetched, pinned) {
+                        println!(
+                            "  {} {} - could not fetch {}: {}",
+                            icons::status::error(),
+                            repo_config.name.yellow(),
+                            branch_name,
+                            e
+                        );
+                        let mut message = format!("could not fetch {}: {}", branch_name, e);
+                        if stashed {
+                            if let Err(e) = pop_stash(&repo_path) {
+                                println!("  {} {} - {}", icons::status::warning(), repo_config.name.yellow(), e);
+                                message.push_str(&format!(", {}", e));
+                            }
+                        }
+                        operation.add_outcome(&repo_config.name, false, message, started.elapsed());
+                        error_count += 1;
+                        failed.insert(repo_config.name.clone(), branch_name.clone());
+                        continue;
+                    }
+                }
+
                 // Try to checkout the branch
-                match checkout_branch(&repo, branch_name) {
+                let checkout = if pinned {
+                    checkout_revision(&repo, branch_name, force)
+                } else {
+                    checkout_branch(&repo, branch_name, force)
+                };
+
+                // Bring stashed changes back, also when the checkout failed
+                let unstash = if stashed { Some(pop_stash(&repo_path)) } else { None };
+
+                match checkout {
                     Ok(_) => {
+                        let mut note = match &unstash {
+                            Some(Ok(())) => ", stashed changes reapplied".to_string(),
+                            Some(Err(e)) => format!(", {}", e),
+                            None if dirty => ", uncommitted changes discarded".to_string(),
+                            None => String::new(),
+                        };
+                        if !is_local && repo.find_branch(branch_name, git2::BranchType::Local).is_ok() {
+                            note.insert_str(0, &format!(" (created from origin/{})", branch_name));
+                        }
+                        let icon = match unstash {
+                            Some(Err(_)) => icons::status::warning(),
+                            _ => icons::status::success(),
+                        };
                         println!(
-                            "  {} {} - switched to {}",
-                            icons::status::success(),
+                            "  {} {} - switched to {}{}",
+                            icon,
                             repo_config.name.cyan(),
-                            branch_name.green()
+                            branch_name.green(),
+                            note
                         );
+                        let mut message = format!("switched to {}{}", branch_name, note);
+                        if hooks {
+                            if let Err(e) = run_checkout_hooks(&config, repo_config, &repo_path, branch_name) {
+                                println!("  {} {} - {}", icons::status::warning(), repo_config.name.yellow(), e);
+                                message.push_str(&format!(", {}", e));
+                            }
+                        }
+                        operation.add_outcome(&repo_config.name, true, message, started.elapsed());
                         success_count += 1;
                     }
                     Err(e) => {
@@ -152,7 +359,14 @@ pub fn restore_command(tag: &str) -> Result<()> {
                             branch_name,
                             e
                         );
+                        let mut message = format!("failed to checkout {}: {}", branch_name, e);
+                        if let Some(Err(e)) = unstash {
+                            println!("  {} {} - {}", icons::status::warning(), repo_config.name.yellow(), e);
+                            message.push_str(&format!(", {}", e));
+                        }
+                        operation.add_outcome(&repo_config.name, false, message, started.elapsed());
                         error_count += 1;
+                        failed.insert(repo_config.name.clone(), branch_name.clone());
                     }
                 }
             }
@@ -163,36 +377,67 @@ pub fn restore_command(tag: &str) -> Result<()> {
                     repo_config.name.yellow(),
                     e
                 );
+                let message = format!("could not open repository: {}", e);
+                operation.add_outcome(&repo_config.name, false, message, started.elapsed());
                 error_count += 1;
+                failed.insert(repo_config.name.clone(), branch_name.clone());
             }
         }
     }
 
+    save_undo(&config, undo);
+    record_operation(&config, operation);
+
+    // Remember the failures for `mgit restore --continue`
+    let db = StateDb::open(&db_path)?;
+    if failed.is_empty() {
+        db.clear_pending_restore()?;
+    } else {
+        db.save_pending_restore(&PendingRestore {
+            tag: tag.to_string(),
+            pinned,
+            created_at: pending.as_ref().map(|p| p.created_at).unwrap_or_else(Utc::now),
+            remaining: failed,
+        })?;
+    }
+
     println!();
-    println!(
-        "{} Tag '{}' restored! ({} repositories, {} errors)",
-        icons::status::success(),
-        tag.green().bold(),
-        success_count,
-        error_count
-    );
+    if pinned {
+        println!(
+            "{} Pinned revisions restored! ({} repositories, {} errors)",
+            icons::status::success(),
+            success_count,
+            error_count
+        );
+    } else {
+        println!(
+            "{} Tag '{}' restored! ({} repositories, {} errors)",
+            icons::status::success(),
+            tag.green().bold(),
+            success_count,
+            error_count
+        );
+    }
 
     if error_count > 0 {
         println!(
-            "\n{} Some repositories could not be restored. Check the errors above.",
-            icons::status::warning()
+            "\n{} Some repositories could not be restored. Fix the errors above, then run '{}' to retry them.",
+            icons::status::warning(),
+            "mgit restore --continue".cyan()
         );
     }
 
     Ok(())
 }
 
-/// Checkout a branch in a repository
-fn checkout_branch(repo: &Repository, branch_name: &str) -> Result<()> {
+/// Checkout a branch in a repository (`force` overwrites local changes)
+/// A branch that only exists on origin is created locally, tracking `origin/<branch>`
+fn checkout_branch(repo: &Repository, branch_name: &str, force: bool) -> Result<()> {
     // Find the branch
-    let branch = repo
-        .find_branch(branch_name, git2::BranchType::Local)
-        .map_err(|e| anyhow!("Branch '{}' not found: {}", branch_name, e))?;
+    let branch = match repo.find_branch(branch_name, git2::BranchType::Local) {
+        Ok(branch) => branch,
+        Err(_) => create_tracking_branch(repo, branch_name)?,
+    };
 
     // Get the reference
     let reference = branch.get();
@@ -203,7 +448,7 @@ fn checkout_branch(repo: &Repository, branch_name: &str) -> Result<()> {
         .map_err(|e| anyhow!("Could not get tree: {}", e))?;
 
     // Checkout the tree
-    repo.checkout_tree(tree.as_object(), None)
+    repo.checkout_tree(tree.as_object(), Some(&mut checkout_options(force)))
         .map_err(|e| anyhow!("Could not checkout tree: {}", e))?;
 
     // Set HEAD to the branch
@@ -212,3 +457,52 @@ fn checkout_branch(repo: &Repository, branch_name: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Create a local branch from `origin/<branch>`, tracking it
+fn create_tracking_branch<'r>(repo: &'r Repository, branch_name: &str) -> Result<git2::Branch<'r>> {
+    let upstream = format!("origin/{}", branch_name);
+    let commit = repo
+        .find_branch(&upstream, git2::BranchType::Remote)
+        .and_then(|remote_branch| remote_branch.get().peel_to_commit())
+        .map_err(|_| anyhow!("Branch '{}' not found locally or on origin (try --fetch)", branch_name))?;
+
+    let mut branch = repo
+        .branch(branch_name, &commit, false)
+        .map_err(|e| anyhow!("Could not create branch '{}': {}", branch_name, e))?;
+    branch.set_upstream(Some(&upstream))?;
+    Ok(branch)
+}
+
+/// Checkout a pinned revision: a local branch, a branch of `origin` (creating the local
+/// branch), or any other revision such as a tag or commit (detaching HEAD)
+fn checkout_revision(repo: &Repository, revision: &str, force: bool) -> Result<()> {
+    let is_branch = repo.find_branch(revision, git2::BranchType::Local).is_ok()
+        || repo
+            .find_branch(&format!("origin/{}", revision), git2::BranchType::Remote)
+            .is_ok();
+    if is_branch {
+        return checkout_branch(repo, revision, force);
+    }
+
+    let commit = repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| anyhow!("Revision '{}' not found: {}", revision, e))?;
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout_options(force)))
+        .map_err(|e| anyhow!("Could not checkout tree: {}", e))?;
+    repo.set_head_detached(commit.id())
+        .map_err(|e| anyhow!("Could not set HEAD: {}", e))?;
+
+    Ok(())
+}
+
+/// Safe checkout (fails instead of overwriting local changes), or forced
+fn checkout_options(force: bool) -> git2::build::CheckoutBuilder<'static> {
+    let mut options = git2::build::CheckoutBuilder::new();
+    if force {
+        options.force();
+    } else {
+        options.safe();
+    }
+    options
+}
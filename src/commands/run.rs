@@ -1,9 +1,19 @@
-use crate::models::Config;
-use crate::utils::{execute_script, icons, ScriptType, VarContext};
+use metagit_core::models::{Config, TaskStep};
+use crate::utils::{
+    execute_script, format_duration, icons, interrupted, pick_one, record_operation, run_rhai_script, start_operation, RepoOutput, ScriptType,
+    VarContext,
+};
 use anyhow::{anyhow, Result};
 use colored::*;
+use notify::{RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
 use terminal_size::{terminal_size, Width};
 
+/// Quiet time after a change before a watched task runs again, so a save of several files runs it once
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
 /// Display a task execution header with black text on light grey background
 fn display_task_header(task_name: &str, step_num: usize, total_steps: usize, cmd: &str) {
     // Get terminal width, default to 80 if not available
@@ -39,9 +49,34 @@ fn display_task_header(task_name: &str, step_num: usize, total_steps: usize, cmd
     println!(); // Add a blank line after the header
 }
 
-pub fn run_command(task_name: Option<&str>, detailed: bool, defines: Vec<String>) -> Result<()> {
+/// Run a task, list the tasks when no name is given, or pick one from a list with `interactive`
+/// With `watch`, the task runs again whenever files of its repositories change.
+pub fn run_command(
+    task_name: Option<&str>,
+    interactive: bool,
+    detailed: bool,
+    defines: Vec<String>,
+    watch: bool,
+) -> Result<()> {
     let config = Config::load_from_project()?;
 
+    let task_name = if interactive && !config.tasks.is_empty() {
+        let labels: Vec<String> = config
+            .tasks
+            .iter()
+            .map(|t| format!("{} ({} step{})", t.name, t.steps.len(), if t.steps.len() == 1 { "" } else { "s" }))
+            .collect();
+        match pick_one("Task:", &labels)? {
+            Some(index) => Some(config.tasks[index].name.as_str()),
+            None => {
+                println!("{} No task selected", icons::status::info());
+                return Ok(());
+            }
+        }
+    } else {
+        task_name
+    };
+
     // If no task name provided, list all available tasks
     if task_name.is_none() {
         if config.tasks.is_empty() {
@@ -66,29 +101,46 @@ pub fn run_command(task_name: Option<&str>, detailed: bool, defines: Vec<String>
 
             for task in &config.tasks {
                 println!(
-                    "  {} {}({}):",
-                    "•".cyan(),
+                    "  {} {}({}){}:",
+                    icons::symbol("•", "-").cyan(),
                     task.name.green().bold(),
-                    task.steps.len()
+                    task.steps.len(),
+                    if task.global { format!(" {}", "[global]".dimmed()) } else { String::new() }
                 );
 
                 for step in &task.steps {
+                    // A step with a broken template is listed as it is; running it reports the problem
+                    let step = &config.resolve_step(step).unwrap_or_else(|_| step.clone());
                     let platform_info = if step.platform != "all" {
                         format!(" [{}]", step.platform.dimmed())
                     } else {
                         String::new()
                     };
+                    let interactive_info = if step.interactive {
+                        format!(" [{}]", "interactive".dimmed())
+                    } else {
+                        String::new()
+                    };
+                    let failure_info = if step.allow_failure {
+                        format!(" [{}]", "allow_failure".dimmed())
+                    } else {
+                        String::new()
+                    };
                     println!(
-                        "    - {:<width$} {}{}",
+                        "    - {:<width$} {}{}{}{}",
                         format!("{}:", step.repo.cyan()),
                         step.cmd,
                         platform_info,
+                        interactive_info,
+                        failure_info,
                         width = max_repo_len
                     );
                 }
                 println!();
             }
 
+            print_aliases(&config, true);
+
             println!(
                 "Run a task with: {} {} {}",
                 "mgit run".bold(),
@@ -100,9 +152,10 @@ pub fn run_command(task_name: Option<&str>, detailed: bool, defines: Vec<String>
             println!("Available tasks:");
             println!();
             for task in &config.tasks {
-                println!("  • {}", task.name);
+                println!("  {} {}{}", icons::symbol("•", "-"), task.name, if task.global { " (global)" } else { "" });
             }
             println!();
+            print_aliases(&config, false);
             println!("Run a task with: mgit run <task-name>");
             println!("Use -d flag for detailed information: mgit run -d");
         }
@@ -118,19 +171,46 @@ pub fn run_command(task_name: Option<&str>, detailed: bool, defines: Vec<String>
         .ok_or_else(|| anyhow!("Could not determine project directory"))?;
 
     // Create variable context for substitution
-    let var_context = VarContext::new(project_dir, defines)?;
+    let var_context = VarContext::new(project_dir, &config.env_file_paths(), &config.variables, defines)?;
 
-    // Find the task
-    let task = config
-        .tasks
-        .iter()
-        .find(|t| t.name == task_name)
-        .ok_or_else(|| anyhow!("Task '{}' not found", task_name))?;
+    // An exact task, an alias or a whole namespace ("build:*")
+    let tasks = config.resolve_tasks(task_name)?;
+    let mut planned = Vec::new();
+    for task in tasks {
+        planned.push((task.name.as_str(), steps_for_platform(&config, &task.steps, &var_context)?));
+    }
+
+    if watch {
+        watch_task(task_name, &planned, &config, &var_context)
+    } else {
+        run_tasks(&planned, &config, &var_context)
+    }
+}
+
+/// List the task aliases below the tasks
+fn print_aliases(config: &Config, colored: bool) {
+    if config.task_aliases.is_empty() {
+        return;
+    }
+    let mut aliases: Vec<(&String, &String)> = config.task_aliases.iter().collect();
+    aliases.sort();
+    println!("{}", if colored { "Aliases:".bold() } else { "Aliases:".normal() });
+    println!();
+    for (alias, target) in aliases {
+        if colored {
+            println!("  {} {} -> {}", icons::symbol("•", "-").cyan(), alias.green().bold(), target);
+        } else {
+            println!("  {} {} -> {}", icons::symbol("•", "-"), alias, target);
+        }
+    }
+    println!();
+}
 
-    // Filter steps to only those that match the current platform
-    // and apply variable substitution
+/// Steps that run on the current platform, with their templates applied and the variables substituted
+fn steps_for_platform(config: &Config, steps: &[TaskStep], var_context: &VarContext) -> Result<Vec<TaskStep>> {
     let mut steps_to_run = Vec::new();
-    for step in &task.steps {
+    for step in steps {
+        let step = &config.resolve_step(step)?;
         // Apply variable substitution to platform field first
         let substituted_platform = var_context.substitute(&step.platform)?;
 
@@ -158,95 +238,424 @@ pub fn run_command(task_name: Option<&str>, detailed: bool, defines: Vec<String>
             steps_to_run.push(substituted_step);
         }
     }
+    Ok(steps_to_run)
+}
+
+/// Run tasks one after the other (a namespace runs in configuration order), stopping at the first
+/// failing or interrupted one
+fn run_tasks(tasks: &[(&str, Vec<TaskStep>)], config: &Config, var_context: &VarContext) -> Result<()> {
+    for (task_name, steps_to_run) in tasks {
+        run_steps(task_name, steps_to_run, config, var_context)?;
+        if interrupted() {
+            break;
+        }
+    }
+    Ok(())
+}
 
+/// Run the steps of a task in order, stopping at the first failing one unless it has `allow_failure`
+/// A table of the steps, with how each ended and how long it took, is printed at the end.
+fn run_steps(task_name: &str, steps_to_run: &[TaskStep], config: &Config, var_context: &VarContext) -> Result<()> {
     let total_steps = steps_to_run.len();
+    let task_started = Instant::now();
+
+    let mut operation = start_operation(format!("run {}", task_name));
+    // How each step that ran ended and how long it took (the steps after a failure don't run)
+    let mut results: Vec<(ColoredString, Duration)> = Vec::new();
+    let mut ignored = 0;
 
     // Execute tasks sequentially
     for (step_idx, step) in steps_to_run.iter().enumerate() {
-        let repo_path = config.resolve_repo_path(&step.repo);
+        let started = Instant::now();
+        let result = run_step(step, step_idx, total_steps, task_name, config, var_context);
+        let elapsed = started.elapsed();
+        match &result {
+            Ok(()) => operation.add_outcome(&step.repo, true, step.cmd.clone(), elapsed),
+            Err(e) => operation.add_outcome(&step.repo, false, e.to_string(), elapsed),
+        }
+        results.push((step_status(&result, step.allow_failure), elapsed));
+        // Ctrl-C terminated the step (or came while it ran): the remaining steps are skipped
+        if interrupted() {
+            record_operation(config, operation);
+            print_step_table(steps_to_run, &results, task_started.elapsed());
+            println!(
+                "{} Task '{}' interrupted at step {}/{}: {} of {} steps completed",
+                icons::status::warning(),
+                task_name.yellow().bold(),
+                step_idx + 1,
+                total_steps,
+                step_idx + usize::from(result.is_ok()),
+                total_steps
+            );
+            return Ok(());
+        }
+        if result.is_err() {
+            if step.allow_failure {
+                println!(
+                    "{} Step {}/{} failed, continuing (allow_failure)\n",
+                    icons::status::warning(),
+                    step_idx + 1,
+                    total_steps
+                );
+                ignored += 1;
+                continue;
+            }
+            record_operation(config, operation);
+            print_step_table(steps_to_run, &results, task_started.elapsed());
+            return result;
+        }
+    }
+    record_operation(config, operation);
+    print_step_table(steps_to_run, &results, task_started.elapsed());
 
-        // Build command display string
-        let args_display = step.args.join(" ");
-        let cmd_display = if args_display.is_empty() {
-            step.cmd.clone()
-        } else {
-            format!("{} {}", step.cmd, args_display)
-        };
+    if ignored > 0 {
+        println!(
+            "Task '{}' completed with {} ignored failure{}\n",
+            task_name.yellow().bold(),
+            ignored,
+            if ignored == 1 { "" } else { "s" }
+        );
+        return Ok(());
+    }
 
-        // Display the task header
-        display_task_header(task_name, step_idx + 1, total_steps, &cmd_display);
+    println!("Task '{}' completed successfully!\n", task_name.green().bold());
 
-        if !repo_path.exists() {
-            let error_msg = format!("{} repository not found: {}", icons::status::error(), step.repo);
-            println!("{}\n", error_msg.red());
-            return Err(anyhow!("Repository not found: {}", step.repo));
-        }
-
-        // Determine script type
-        // Priority: explicit type > inferred from extension
-        let script_type = if !step.step_type.is_empty() {
-            // Explicit type specified
-            match step.step_type.as_str() {
-                "sh" => ScriptType::Shell,
-                "bat" | "cmd" => ScriptType::Batch,
-                "ps1" => ScriptType::PowerShell,
-                "exe" => ScriptType::Executable,
-                _ => ScriptType::from_path(&step.cmd), // Unknown type, try to infer
-            }
-        } else {
-            // No explicit type, infer from file extension
-            ScriptType::from_path(&step.cmd)
+    Ok(())
+}
+
+/// Error of a step whose process exited with a non-zero code, kept for the summary table
+#[derive(Debug)]
+struct StepExitError {
+    message: String,
+    code: i32,
+}
+
+impl std::fmt::Display for StepExitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StepExitError {}
+
+/// Status column of a step in the summary table
+fn step_status(result: &Result<()>, allow_failure: bool) -> ColoredString {
+    let Err(e) = result else {
+        return "ok".green();
+    };
+    if interrupted() {
+        return "interrupted".yellow();
+    }
+    let status = match e.downcast_ref::<StepExitError>() {
+        Some(failure) => format!("exit {}", failure.code),
+        None => "error".to_string(),
+    };
+    if allow_failure {
+        format!("{}, ignored", status).yellow()
+    } else {
+        status.red()
+    }
+}
+
+/// Print every step of a task with its repository, how it ended and how long it took,
+/// then the wall time of the whole task; steps that didn't get to run are listed as such
+fn print_step_table(steps: &[TaskStep], results: &[(ColoredString, Duration)], total: Duration) {
+    let repo_width = steps.iter().map(|s| s.repo.len()).chain(["Repository".len()]).max().unwrap_or(0);
+    let number_width = steps.len().to_string().len().max(1);
+    let millis = |d: &Duration| d.as_millis() as u64;
+
+    println!(
+        "  {:>nw$}  {:<rw$}  {:<16}  {:>9}  {}",
+        "#".bold(),
+        "Repository".bold(),
+        "Status".bold(),
+        "Duration".bold(),
+        "Step".bold(),
+        nw = number_width,
+        rw = repo_width
+    );
+    for (index, step) in steps.iter().enumerate() {
+        let (status, duration) = match results.get(index) {
+            Some((status, duration)) => (status.clone(), format_duration(millis(duration))),
+            None => ("not run".bright_black(), String::new()),
         };
+        let cmd = std::iter::once(step.cmd.as_str()).chain(step.args.iter().map(String::as_str)).collect::<Vec<_>>();
+        println!(
+            "  {:>nw$}  {:<rw$}  {:<16}  {:>9}  {}",
+            index + 1,
+            step.repo.cyan(),
+            status,
+            duration,
+            cmd.join(" ").lines().next().unwrap_or(""),
+            nw = number_width,
+            rw = repo_width
+        );
+    }
+    println!(
+        "  {:>nw$}  {:<rw$}  {:<16}  {:>9}",
+        "",
+        "Total".bold(),
+        "",
+        format_duration(millis(&total)),
+        nw = number_width,
+        rw = repo_width
+    );
+    println!();
+}
 
-        // Execute
-        match execute_script(
-            script_type,
-            &step.cmd,
-            &step.args,
-            &repo_path,
-            &config.shells,
-        ) {
-            Ok(mut child) => {
-                // Use wait() for real-time output streaming
-                match child.wait() {
-                    Ok(status) => {
-                        if status.success() {
-                            println!("{} {}\n", icons::status::success(), "Completed".green());
-                        } else {
-                            let exit_code = status.code().unwrap_or(-1);
-                            let error_msg = format!("{} script execution failed! (errcode: {})", icons::status::error(), exit_code);
-                            println!("{}\n", error_msg.red());
-                            return Err(anyhow!("Task '{}' failed at step {}/{}: {} (exit code: {})", task_name, step_idx + 1, total_steps, cmd_display, exit_code));
-                        }
-                    }
-                    Err(e) => {
-                        let error_msg = if e.to_string().contains("not found")
-                            || e.to_string().contains("cannot find")
-                        {
-                            format!("{} script not found!", icons::status::error())
-                        } else {
-                            format!("{} {}", icons::status::error(), e)
-                        };
-                        println!("{}\n", error_msg.red());
-                        return Err(anyhow!("Task '{}' failed at step {}/{}: {}", task_name, step_idx + 1, total_steps, e));
+/// Run tasks, then again whenever files in the repositories of their steps change, until Ctrl-C
+/// Files git ignores (build output, dependencies), `.git` and what changes while the tasks run
+/// don't count; they run once the changes have settled for [`WATCH_DEBOUNCE`].
+fn watch_task(
+    task_name: &str,
+    tasks: &[(&str, Vec<TaskStep>)],
+    config: &Config,
+    var_context: &VarContext,
+) -> Result<()> {
+    let mut repos: Vec<(&str, PathBuf, Option<git2::Repository>)> = Vec::new();
+    for step in tasks.iter().flat_map(|(_, steps)| steps) {
+        if !repos.iter().any(|(name, _, _)| *name == step.repo) {
+            let path = config.resolve_repo_path(&step.repo);
+            let repo = git2::Repository::open(&path).ok();
+            repos.push((&step.repo, path, repo));
+        }
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for (name, path, _) in &repos {
+        if path.exists() {
+            watcher
+                .watch(path, RecursiveMode::Recursive)
+                .map_err(|e| anyhow!("Could not watch {}: {}", name, e))?;
+        }
+    }
+    let names: Vec<&str> = repos.iter().map(|(name, _, _)| *name).collect();
+
+    loop {
+        if let Err(e) = run_tasks(tasks, config, var_context) {
+            println!("{} {}\n", icons::status::error(), e);
+        }
+        if interrupted() {
+            return Ok(());
+        }
+        // Forget what the task changed itself
+        while receiver.try_recv().is_ok() {}
+        println!(
+            "{} {}\n",
+            icons::status::waiting(),
+            format!("Watching {} for changes (Ctrl-C to quit)", names.join(", ")).bright_black()
+        );
+
+        let changed = loop {
+            if interrupted() {
+                return Ok(());
+            }
+            match receiver.recv_timeout(Duration::from_millis(200)) {
+                Ok(Ok(event)) => {
+                    if let Some(changed) = watched_change(&event, &repos) {
+                        break changed;
                     }
                 }
+                Ok(Err(e)) => tracing::warn!("File watching error: {}", e),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(anyhow!("File watching stopped")),
+            }
+        };
+        while receiver.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+        println!("{} {} changed, running '{}' again\n", icons::status::info(), changed, task_name);
+    }
+}
+
+/// First file of `event` that should trigger a run, as "<repo>/<path>"
+fn watched_change(event: &notify::Event, repos: &[(&str, PathBuf, Option<git2::Repository>)]) -> Option<String> {
+    if !(event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove()) {
+        return None;
+    }
+    event.paths.iter().find_map(|path| {
+        repos.iter().find_map(|(name, root, repo)| {
+            let relative = path.strip_prefix(root).ok()?;
+            if relative.as_os_str().is_empty() || relative.components().any(|c| c.as_os_str() == ".git") {
+                return None;
+            }
+            if repo.as_ref().is_some_and(|repo| repo.is_path_ignored(relative).unwrap_or(false)) {
+                return None;
+            }
+            Some(format!("{}/{}", name, relative.display()))
+        })
+    })
+}
+
+/// Run one step of a task
+fn run_step(
+    step: &TaskStep,
+    step_idx: usize,
+    total_steps: usize,
+    task_name: &str,
+    config: &Config,
+    var_context: &VarContext,
+) -> Result<()> {
+    let repo_path = config.resolve_repo_path(&step.repo);
+
+    // Build command display string
+    let args_display = step.args.join(" ");
+    let cmd_display = if args_display.is_empty() {
+        step.cmd.clone()
+    } else {
+        format!("{} {}", step.cmd, args_display)
+    };
+
+    // Display the task header
+    display_task_header(task_name, step_idx + 1, total_steps, &cmd_display);
+
+    if !repo_path.exists() {
+        let error_msg = format!("{} repository not found: {}", icons::status::error(), step.repo);
+        println!("{}\n", error_msg.red());
+        return Err(anyhow!("Repository not found: {}", step.repo));
+    }
+
+    // Determine script type
+    // Priority: explicit type > inferred from extension
+    let script_type = if !step.step_type.is_empty() {
+        // Explicit type specified
+        match step.step_type.as_str() {
+            "sh" => ScriptType::Shell,
+            "bat" | "cmd" => ScriptType::Batch,
+            "ps1" => ScriptType::PowerShell,
+            "exe" => ScriptType::Executable,
+            "rhai" => ScriptType::Rhai,
+            _ => ScriptType::from_path(&step.cmd), // Unknown type, try to infer
+        }
+    } else {
+        // No explicit type, infer from file extension
+        ScriptType::from_path(&step.cmd)
+    };
+
+    // Embedded scripts run inside mgit
+    if matches!(script_type, ScriptType::Rhai) {
+        match run_rhai_script(&step.cmd, &step.args, &step.repo, &repo_path, config, var_context) {
+            Ok(()) => {
+                println!("{} {}\n", icons::status::success(), "Completed".green());
+                return Ok(());
             }
             Err(e) => {
-                let error_msg = if e.to_string().contains("not found")
-                    || e.to_string().contains("cannot find")
-                {
-                    format!("{} script not found!", icons::status::error())
-                } else {
-                    format!("{} {}", icons::status::error(), e)
-                };
-                println!("{}\n", error_msg.red());
+                println!("{}\n", format!("{} {}", icons::status::error(), e).red());
                 return Err(anyhow!("Task '{}' failed at step {}/{}: {}", task_name, step_idx + 1, total_steps, e));
             }
         }
     }
 
-    println!("Task '{}' completed successfully!\n", task_name.green().bold());
+    // Step overrides win over repository overrides, which win over the workspace shells
+    let repo_shells = config
+        .repositories
+        .iter()
+        .find(|r| r.name == step.repo)
+        .and_then(|r| r.shells.as_ref());
+    let shells = config
+        .shells
+        .with_overrides(repo_shells)
+        .with_overrides(step.shells.as_ref());
+
+    // Execute
+    match execute_script(
+        script_type,
+        &step.cmd,
+        &step.args,
+        &repo_path,
+        &shells,
+        step.interactive,
+    ) {
+        Ok(child) => {
+            // Lines are printed as they come, prefixed with the repository (interactive steps print themselves)
+            match RepoOutput::new(&step.repo).wait(child) {
+                Ok(status) => {
+                    if status.success() {
+                        println!("{} {}\n", icons::status::success(), "Completed".green());
+                    } else if interrupted() {
+                        println!("{} {}\n", icons::status::warning(), "Interrupted".yellow());
+                        return Err(anyhow!("Interrupted"));
+                    } else {
+                        let exit_code = status.code().unwrap_or(-1);
+                        let error_msg = format!("{} script execution failed! (errcode: {})", icons::status::error(), exit_code);
+                        println!("{}\n", error_msg.red());
+                        return Err(StepExitError {
+                            message: format!(
+                                "Task '{}' failed at step {}/{}: {} (exit code: {})",
+                                task_name,
+                                step_idx + 1,
+                                total_steps,
+                                cmd_display,
+                                exit_code
+                            ),
+                            code: exit_code,
+                        }
+                        .into());
+                    }
+                }
+                Err(e) => {
+                    let error_msg = if e.to_string().contains("not found")
+                        || e.to_string().contains("cannot find")
+                    {
+                        format!("{} script not found!", icons::status::error())
+                    } else {
+                        format!("{} {}", icons::status::error(), e)
+                    };
+                    println!("{}\n", error_msg.red());
+                    return Err(anyhow!("Task '{}' failed at step {}/{}: {}", task_name, step_idx + 1, total_steps, e));
+                }
+            }
+        }
+        Err(e) => {
+            let error_msg = if e.to_string().contains("not found")
+                || e.to_string().contains("cannot find")
+            {
+                format!("{} script not found!", icons::status::error())
+            } else {
+                format!("{} {}", icons::status::error(), e)
+            };
+            println!("{}\n", error_msg.red());
+            return Err(anyhow!("Task '{}' failed at step {}/{}: {}", task_name, step_idx + 1, total_steps, e));
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defines_take_precedence_over_config_variables() {
+        let dir = std::env::temp_dir().join(format!("mgit-test-defines-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".mgitconfig.yaml");
+        std::fs::write(
+            &path,
+            r#"
+variables: { MODE: debug }
+repositories:
+  - { name: api, url: "git@github.com:acme/api-$(MODE).git" }
+step_templates:
+  cargo: { type: exe, cmd: cargo, args: [build, "--profile=$(MODE)"] }
+tasks:
+  - name: build
+    steps:
+      - { repo: api, type: exe, cmd: echo, args: ["mode=$(MODE)"] }
+      - { repo: api, template: cargo }
+"#,
+        )
+        .unwrap();
+        let config = Config::load(path.to_str().unwrap());
+        std::fs::remove_dir_all(&dir).ok();
+        let config = config.unwrap();
+
+        let var_context =
+            VarContext::new(&dir, &[], &config.variables, vec!["MODE=release".to_string()]).unwrap();
+        let steps = steps_for_platform(&config, &config.tasks[0].steps, &var_context).unwrap();
+        assert_eq!(steps[0].args, ["mode=release"]);
+        assert_eq!(steps[1].args, ["build", "--profile=release"]);
+        // Other config values are substituted at load time
+        assert_eq!(config.repositories[0].url, "git@github.com:acme/api-debug.git");
+    }
+}
@@ -1,11 +1,12 @@
-use crate::models::Config;
-use crate::utils::icons;
+use metagit_core::models::Config;
+use crate::utils::{icons, record_tag};
 use anyhow::{anyhow, Result};
 use colored::*;
 use git2::Repository;
 use std::collections::HashMap;
 
-pub fn save_command(tag: &str) -> Result<()> {
+/// Save the current branch of every repository to a tag, with an optional description
+pub fn save_command(tag: &str, description: Option<&str>) -> Result<()> {
     // Reserved tags cannot be saved (they're virtual)
     if tag == "master" || tag == "main" {
         return Err(anyhow!(
@@ -97,7 +98,7 @@ pub fn save_command(tag: &str) -> Result<()> {
     }
 
     // Save to config
-    config.tags.insert(tag.to_string(), branches);
+    record_tag(&mut config, tag, branches, description);
 
     // Find the project config path to save to
     let config_path = Config::find_project_config()
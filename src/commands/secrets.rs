@@ -0,0 +1,98 @@
+use crate::utils::icons;
+use anyhow::{anyhow, Result};
+use colored::*;
+use metagit_core::models::Config;
+use metagit_core::secrets::{encrypt_secrets, SecretsKey};
+
+/// Encrypt the credentials and repository tokens into `encrypted_secrets`
+/// Secrets that are already encrypted are kept, so new plaintext tokens can be added later.
+/// `passphrase` prompts for a passphrase instead of using the configured age identity.
+pub fn secrets_encrypt_command(passphrase: bool) -> Result<()> {
+    let mut config = Config::load_from_project()?;
+    if let Some((profile, _)) = &config.active_profile {
+        return Err(anyhow!("Run without a profile (active: '{}') to encrypt the secrets", profile));
+    }
+    if config.encrypted_secrets.is_some() && config.decrypted_secrets.is_none() {
+        return Err(anyhow!(
+            "The existing encrypted secrets can't be decrypted. Set age_identity, MGIT_AGE_IDENTITY or MGIT_AGE_PASSPHRASE first."
+        ));
+    }
+
+    let key = if passphrase {
+        let first = rpassword::prompt_password("Passphrase: ")?;
+        let second = rpassword::prompt_password("Confirm passphrase: ")?;
+        if first.is_empty() || first != second {
+            return Err(anyhow!("Passphrases are empty or don't match - nothing encrypted"));
+        }
+        SecretsKey::Passphrase(first)
+    } else {
+        SecretsKey::from_config(&config).ok_or_else(|| {
+            anyhow!("No key configured. Set age_identity (or MGIT_AGE_IDENTITY) to an age key file, or use --passphrase.")
+        })?
+    };
+
+    let secrets = config.collect_secrets();
+    if secrets.credentials.is_empty() && secrets.tokens.is_empty() {
+        return Err(anyhow!("No credentials or repository tokens to encrypt"));
+    }
+
+    config.encrypted_secrets = Some(encrypt_secrets(&secrets, &key)?);
+    let (credentials, tokens) = (secrets.credentials.len(), secrets.tokens.len());
+    // Saving leaves the decrypted values out of the file
+    config.decrypted_secrets = Some(secrets);
+
+    let config_path = Config::find_project_config()
+        .ok_or_else(|| anyhow!("Could not find .mgitconfig.yaml"))?;
+    config.save(config_path.to_str().unwrap())?;
+
+    println!(
+        "{} Encrypted {} credential{} and {} repository token{} into {}",
+        icons::status::success(),
+        credentials,
+        if credentials == 1 { "" } else { "s" },
+        tokens,
+        if tokens == 1 { "" } else { "s" },
+        "encrypted_secrets".cyan()
+    );
+    if passphrase {
+        println!(
+            "\n{} Set MGIT_AGE_PASSPHRASE so mgit can decrypt them.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Write the decrypted secrets back to `credentials` and the repositories in plain text
+pub fn secrets_decrypt_command() -> Result<()> {
+    let mut config = Config::load_from_project()?;
+    if let Some((profile, _)) = &config.active_profile {
+        return Err(anyhow!("Run without a profile (active: '{}') to decrypt the secrets", profile));
+    }
+    if config.encrypted_secrets.is_none() {
+        println!("{} No encrypted secrets in the configuration", icons::status::info());
+        return Ok(());
+    }
+    if config.decrypted_secrets.is_none() {
+        return Err(anyhow!(
+            "No key configured. Set age_identity, MGIT_AGE_IDENTITY or MGIT_AGE_PASSPHRASE."
+        ));
+    }
+
+    // The decrypted values were merged at load time; dropping them from the bookkeeping keeps them on save
+    config.encrypted_secrets = None;
+    config.decrypted_secrets = None;
+
+    let config_path = Config::find_project_config()
+        .ok_or_else(|| anyhow!("Could not find .mgitconfig.yaml"))?;
+    config.save(config_path.to_str().unwrap())?;
+
+    println!(
+        "{} Decrypted secrets written to {} in plain text",
+        icons::status::success(),
+        config_path.display().to_string().cyan()
+    );
+
+    Ok(())
+}
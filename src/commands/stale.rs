@@ -0,0 +1,119 @@
+use anyhow::Result;
+use chrono::Utc;
+use colored::*;
+
+use metagit_core::db::StateDb;
+use metagit_core::models::{Config, RepoState};
+use crate::utils::{find_stale_branches, format_time, icons, parse_duration, StaleBranch};
+
+/// Age after which branches are reported when `stale_after` is not configured
+pub const DEFAULT_STALE_AFTER: &str = "90d";
+
+/// List branches whose last commit is older than the threshold and that are merged or have no upstream
+/// Owners come from the refreshed statistics when available, otherwise from the last commit's author
+pub fn stale_command(older_than: Option<&str>) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let threshold = older_than
+        .or(config.stale_after.as_deref())
+        .unwrap_or(DEFAULT_STALE_AFTER);
+    let cutoff = Utc::now() - parse_duration(threshold)?;
+    let db_path = config.get_db_path();
+    let db = StateDb::open(&db_path).ok();
+
+    println!(
+        "{} Branches without commits for {} that are merged or have no upstream...\n",
+        icons::status::info(),
+        threshold.cyan().bold()
+    );
+
+    let mut stale_count = 0;
+    let mut repo_count = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+
+        if !repo_path.exists() {
+            println!(
+                "  {} {} - repository not found",
+                icons::status::error(),
+                repo_config.name.yellow()
+            );
+            error_count += 1;
+            continue;
+        }
+
+        let branches = match find_stale_branches(&repo_path, cutoff, &config.users) {
+            Ok(branches) => branches,
+            Err(e) => {
+                println!("  {} {} - {}", icons::status::error(), repo_config.name.yellow(), e);
+                error_count += 1;
+                continue;
+            }
+        };
+        if branches.is_empty() {
+            continue;
+        }
+
+        let state = db
+            .as_ref()
+            .and_then(|db| db.get_repo_state(&repo_config.name).ok().flatten());
+
+        println!("  {} {}", icons::status::warning(), repo_config.name.cyan());
+        for branch in &branches {
+            let owner = stale_branch_owner(state.as_ref(), branch);
+
+            let mut reasons = Vec::new();
+            if branch.merged {
+                reasons.push("merged");
+            }
+            if !branch.has_upstream {
+                reasons.push("no upstream");
+            }
+            if branch.is_current {
+                reasons.push("checked out");
+            }
+
+            println!(
+                "      {} - {}, last commit {} ({})",
+                branch.name.yellow(),
+                owner,
+                format_time(branch.last_commit, config.timestamps),
+                reasons.join(", ").bright_black()
+            );
+        }
+        stale_count += branches.len();
+        repo_count += 1;
+    }
+
+    if stale_count + error_count > 0 {
+        println!();
+    }
+    if stale_count == 0 {
+        println!(
+            "{} No stale branches ({} errors)",
+            icons::status::success(),
+            error_count
+        );
+    } else {
+        println!(
+            "{} {} stale branch{} in {} repositor{} ({} errors)",
+            icons::status::warning(),
+            stale_count,
+            if stale_count == 1 { "" } else { "es" },
+            repo_count,
+            if repo_count == 1 { "y" } else { "ies" },
+            error_count
+        );
+    }
+
+    Ok(())
+}
+
+/// Owner from the refreshed statistics, falling back to the author of the branch's last commit
+pub fn stale_branch_owner(state: Option<&RepoState>, branch: &StaleBranch) -> String {
+    state
+        .and_then(|s| s.branches.iter().find(|b| b.name == branch.name))
+        .map(|b| b.owner.clone())
+        .unwrap_or_else(|| branch.author.clone())
+}
@@ -1,10 +1,32 @@
 use anyhow::Result;
 use colored::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
 use std::path::Path;
+use std::thread;
 
-use crate::db::StateDb;
-use crate::models::Config;
-use crate::utils::{format_relative_time, get_branch_commit_sha, get_branch_info_with_stats, get_branch_status, get_repo_state, icons, BranchStatus};
+use metagit_core::db::StateDb;
+use chrono::{Duration, Utc};
+use metagit_core::forge::{api_token, ForgeClient, ForgeRepo};
+use crate::commands::{deviating_identities, format_identity};
+use metagit_core::models::{CiState, CiStatus, Config, RepoState, Repository, TimestampFormat};
+use crate::utils::{format_time, get_branch_commit_sha, parse_duration, refresh_repo_state, get_branch_info_with_stats, get_branch_status, get_branch_sync_status, get_repo_state, get_worktree_status, icons, match_saved_tags, porcelain_flag, porcelain_line, porcelain_time, BranchStatus, TagMatch};
+
+/// Label used for repositories without a group in the grouped view
+const UNGROUPED: &str = "(ungrouped)";
+
+/// Cache age after which statistics are marked stale when `cache_ttl` is not configured
+const DEFAULT_CACHE_TTL: &str = "7d";
+
+/// Age after which unfinished or missing CI results are checked again when `ci_ttl` is not configured
+const DEFAULT_CI_TTL: &str = "5m";
+
+/// Per-repository flags aggregated in group summary lines
+#[derive(Default, Clone, Copy)]
+struct RepoSummary {
+    dirty: bool,
+    behind: bool,
+}
 
 /// Color a branch name based on its sync status
 fn color_branch(branch_name: &str, status: BranchStatus) -> ColoredString {
@@ -18,23 +40,39 @@ fn color_branch(branch_name: &str, status: BranchStatus) -> ColoredString {
 #[allow(dead_code)]
 /// Format owner name with " et al" in darker gray
 fn format_owner(owner: &str) -> String {
-    if owner.ends_with("*") {
-        let base = &owner[..owner.len() - 1]; // Remove "*"
+    if let Some(base) = owner.strip_suffix('*') {
         format!("{}{}", base, "*".bright_yellow())
     } else {
         owner.to_string()
     }
 }
 
-pub fn status_command(all: bool) -> Result<()> {
+/// Show the status of all repositories
+/// Statistics cached longer than `cache_ttl` are marked stale; with `max_age`,
+/// repositories whose cache is older than that are refreshed first. Archived repositories are
+/// left out unless `all_repos` is set. `porcelain` prints the stable tab-separated format instead of the table
+pub fn status_command(
+    all: bool,
+    tree: bool,
+    timestamps: Option<TimestampFormat>,
+    max_age: Option<&str>,
+    porcelain: bool,
+    all_repos: bool,
+) -> Result<()> {
     let config = Config::load_from_project()?;
+    let timestamps = timestamps.unwrap_or(config.timestamps);
+    let ttl = parse_duration(config.cache_ttl.as_deref().unwrap_or(DEFAULT_CACHE_TTL))?;
+    let max_age = max_age.map(parse_duration).transpose()?;
+    let ci_ttl = parse_duration(config.ci_ttl.as_deref().unwrap_or(DEFAULT_CI_TTL))?;
     let db_path = config.get_db_path();
-    let db = StateDb::open(db_path.to_str().unwrap_or(".mgitdb"))?;
+    let db = StateDb::open(&db_path)?;
 
     let mut all_states = Vec::new();
+    let mut summaries: HashMap<String, RepoSummary> = HashMap::new();
+    let mut refreshed = Vec::new();
 
     // Collect all repository states
-    for repo_config in &config.repositories {
+    for repo_config in config.active_repositories(all_repos) {
         let repo_path = config.resolve_repo_path(&repo_config.name);
 
         if !repo_path.exists() {
@@ -50,7 +88,7 @@ pub fn status_command(all: bool) -> Result<()> {
             }
             _ => {
                 // Fall back to reading from git if no database entry
-                match get_repo_state(&repo_path, &repo_config.name) {
+                match get_repo_state(&repo_path, &repo_config.name, &config.users) {
                     Ok(state) => {
                         // Save to database
                         let _ = db.save_repo_state(&state);
@@ -64,9 +102,26 @@ pub fn status_command(all: bool) -> Result<()> {
             }
         };
 
+        // Refresh statistics that are older than --max-age
+        if max_age.is_some_and(|max_age| state.is_stale(max_age)) {
+            match refresh_repo_state(&repo_path, &repo_config.name, Some(&state), &config.users, &state.stats_options) {
+                Ok(fresh) => {
+                    let _ = db.save_repo_state(&fresh);
+                    state = fresh;
+                    refreshed.push(repo_config.name.as_str());
+                }
+                Err(e) => {
+                    eprintln!("Warning: Could not refresh '{}': {}", repo_config.name, e);
+                }
+            }
+        }
+
+        // Update branches with the same options (line counts, time window) as the last refresh
+        let stats_options = state.stats_options;
+
         // SMART CACHING: Always update current_branch from live git state
         // Check if master/main changed - if so, invalidate ALL branches
-        match get_repo_state(&repo_path, &repo_config.name) {
+        match get_repo_state(&repo_path, &repo_config.name, &config.users) {
             Ok(live_state) => {
                 let current_branch = live_state.current_branch;
 
@@ -110,7 +165,7 @@ pub fn status_command(all: bool) -> Result<()> {
 
                         // Recalculate all cached branches
                         for cached_branch in &state.branches {
-                            match get_branch_info_with_stats(&repo_path, &cached_branch.name, &config.users) {
+                            match get_branch_info_with_stats(&repo_path, &cached_branch.name, &config.users, &stats_options) {
                                 Ok(branch_info) => {
                                     if branch_info.last_updated > latest_updated {
                                         latest_updated = branch_info.last_updated;
@@ -146,7 +201,7 @@ pub fn status_command(all: bool) -> Result<()> {
 
                         if needs_recalculation {
                             // Calculate or recalculate stats for this branch
-                            match get_branch_info_with_stats(&repo_path, &current_branch, &config.users) {
+                            match get_branch_info_with_stats(&repo_path, &current_branch, &config.users, &stats_options) {
                                 Ok(branch_info) => {
                                     // Remove old cached version if it exists
                                     state.branches.retain(|b| b.name != current_branch);
@@ -179,11 +234,24 @@ pub fn status_command(all: bool) -> Result<()> {
             }
         }
 
+        let worktree = get_worktree_status(&repo_path, &state.current_branch);
+        summaries.insert(
+            state.name.clone(),
+            RepoSummary {
+                dirty: worktree.dirty,
+                behind: worktree.behind > 0,
+            },
+        );
+        let ci_changed = update_ci_status(&config, repo_config, &repo_path, &mut state, all, ci_ttl);
+        if state.worktree != Some(worktree) || ci_changed {
+            state.worktree = Some(worktree);
+            let _ = db.save_repo_state(&state);
+        }
         all_states.push(state);
     }
 
     // Sort by last updated (most recent first)
-    all_states.sort_by(|a, b| b.last_updated.cmp(&a.last_updated));
+    all_states.sort_by_key(|s| std::cmp::Reverse(s.last_updated));
 
     // Filter branches based on -a flag
     if !all {
@@ -195,6 +263,78 @@ pub fn status_command(all: bool) -> Result<()> {
     }
     // With -a: show all branches (no filtering)
 
+    if porcelain {
+        print_porcelain(&config, &all_states, &summaries);
+        return Ok(());
+    }
+
+    if !refreshed.is_empty() {
+        println!(
+            "{} Refreshed {} repositor{} with stale statistics: {}\n",
+            icons::status::info(),
+            refreshed.len(),
+            if refreshed.len() == 1 { "y" } else { "ies" },
+            refreshed.join(", ")
+        );
+    }
+
+    // A half-applied restore leaves the workspace in a mixed state
+    if let Ok(Some(pending)) = db.get_pending_restore() {
+        println!(
+            "{} Restore of {} is incomplete ({} repositor{} failed: {}). Run '{}' after fixing them.\n",
+            icons::status::warning(),
+            if pending.pinned { "pinned revisions".to_string() } else { format!("tag '{}'", pending.tag) },
+            pending.remaining.len(),
+            if pending.remaining.len() == 1 { "y" } else { "ies" },
+            pending.remaining.keys().cloned().collect::<Vec<_>>().join(", "),
+            "mgit restore --continue".cyan()
+        );
+    }
+
+    // Repositories that would commit with another identity than the configured one
+    let deviating = deviating_identities(&config, all_repos);
+    if !deviating.is_empty() {
+        let repos: Vec<String> = deviating
+            .iter()
+            .map(|(name, expected)| {
+                let expected = format_identity(expected.name.as_deref(), expected.email.as_deref());
+                format!("{} (expected {})", name.yellow(), expected)
+            })
+            .collect();
+        println!(
+            "{} Commit identity differs from the configured one: {}. Run '{}'.\n",
+            icons::status::warning(),
+            repos.join(", "),
+            "mgit identity apply".cyan()
+        );
+    }
+
+    // Point out the saved tag the current branch layout corresponds to
+    let current: HashMap<String, String> = all_states
+        .iter()
+        .map(|s| (s.name.clone(), s.current_branch.clone()))
+        .collect();
+    match match_saved_tags(&config.tags, &current) {
+        TagMatch::Exact(tags) => println!(
+            "{} (matches tag: {})\n",
+            icons::git::branch(),
+            tags.join(", ").cyan()
+        ),
+        TagMatch::Partial(tag, deviations) => {
+            let deviations: Vec<String> = deviations
+                .iter()
+                .map(|d| format!("{} ({}, tag has {})", d.repo.yellow(), d.current, d.saved))
+                .collect();
+            println!(
+                "{} (closest tag: {}, differs in {})\n",
+                icons::git::branch(),
+                tag.cyan(),
+                deviations.join(", ")
+            );
+        }
+        TagMatch::None => {}
+    }
+
     // Get icons for header
     let folder_icon = icons::files::folder();
     let commit_icon = icons::git::commit();
@@ -212,36 +352,260 @@ pub fn status_command(all: bool) -> Result<()> {
         format!("{} BRANCH", branch_icon).bold()
     );
 
-    // Display all repositories
-    for state in all_states {
-        let repo_path = Path::new(&state.name);
+    let groups: HashMap<&str, &str> = config
+        .repositories
+        .iter()
+        .filter_map(|r| r.group.as_deref().map(|g| (r.name.as_str(), g.trim_matches('/'))))
+        .collect();
 
-        for (idx, branch) in state.branches.iter().enumerate() {
-            let repo_name = if idx == 0 {
-                state.name.clone()
-            } else {
-                String::new()
-            };
+    if groups.is_empty() && !tree {
+        // Display all repositories
+        for state in &all_states {
+            print_repo_rows(&config, state, 0, timestamps, ttl);
+        }
+        return Ok(());
+    }
 
-            // Get branch status for coloring
-            let branch_status =
-                get_branch_status(repo_path, &branch.name).unwrap_or(BranchStatus::Synced);
+    // Group repositories, keeping the last-updated order inside each group
+    let mut grouped: BTreeMap<&str, Vec<&RepoState>> = BTreeMap::new();
+    for state in &all_states {
+        let group = groups.get(state.name.as_str()).copied().unwrap_or(UNGROUPED);
+        grouped.entry(group).or_default().push(state);
+    }
+    // Ungrouped repositories come last
+    let mut ordered: Vec<(&str, Vec<&RepoState>)> = grouped.into_iter().collect();
+    ordered.sort_by_key(|(group, _)| *group == UNGROUPED);
+
+    let mut printed: Vec<&str> = Vec::new();
+    for (group, states) in &ordered {
+        println!();
+        let depth = if tree && *group != UNGROUPED {
+            // Print headers for parent groups that haven't been shown yet
+            let segments: Vec<&str> = group.split('/').collect();
+            for depth in 0..segments.len() {
+                let path = &group[..segments[..=depth].join("/").len()];
+                if !printed.contains(&path) {
+                    let members = ordered
+                        .iter()
+                        .filter(|(g, _)| *g == path || g.starts_with(&format!("{}/", path)))
+                        .flat_map(|(_, s)| s.iter().copied());
+                    print_group_header(segments[depth], depth, members, &summaries);
+                    printed.push(path);
+                }
+            }
+            segments.len()
+        } else {
+            print_group_header(group, 0, states.iter().copied(), &summaries);
+            1
+        };
 
-            let branch_display = color_branch(&branch.name, branch_status).to_string();
+        for state in states {
+            print_repo_rows(&config, state, depth, timestamps, ttl);
+        }
+    }
 
-            // Get commit count for the owner
-            let commit_count = branch.get_owner_commit_count();
+    Ok(())
+}
 
+/// Redraw the status every `interval` (e.g., "30s", "5m") until interrupted with Ctrl-C
+/// Errors (e.g., while the config is being edited) are shown in place of the table and retried.
+pub fn status_watch_command(
+    all: bool,
+    tree: bool,
+    timestamps: Option<TimestampFormat>,
+    max_age: Option<&str>,
+    all_repos: bool,
+    interval: &str,
+) -> Result<()> {
+    let period = parse_duration(interval)?.to_std()?.max(std::time::Duration::from_secs(1));
+    loop {
+        // Clear the screen and move the cursor home
+        print!("\x1b[2J\x1b[H");
+        println!(
+            "{} {}\n",
+            icons::status::waiting(),
+            format!("Every {}, last update {} (Ctrl-C to quit)", interval, chrono::Local::now().format("%H:%M:%S"))
+                .bright_black()
+        );
+        if let Err(e) = status_command(all, tree, timestamps, max_age, false, all_repos) {
+            println!("{} {}", icons::status::error(), e);
+        }
+        let _ = io::stdout().flush();
+        thread::sleep(period);
+    }
+}
+
+/// Fetch the CI results of the shown branches (the current one, or all with `all`) from the hosting provider
+/// Results are cached in the state: finished ones until the branch moves, others for `ttl`.
+/// Repositories without a known provider or API token are skipped, and API errors keep the cached result.
+/// Returns whether the cached results changed.
+fn update_ci_status(
+    config: &Config,
+    repo_config: &Repository,
+    repo_path: &Path,
+    state: &mut RepoState,
+    all: bool,
+    ttl: Duration,
+) -> bool {
+    let Some(forge) = ForgeRepo::from_url(&repo_config.url, config) else {
+        return false;
+    };
+    let Some(token) = api_token(repo_config, &forge) else {
+        return false;
+    };
+    let Ok(client) = ForgeClient::new(config, forge, token) else {
+        return false;
+    };
+
+    // Forget the results of deleted branches
+    let known: Vec<String> = state.branches.iter().map(|b| b.name.clone()).collect();
+    let cached = state.ci.len();
+    state.ci.retain(|name, _| known.contains(name));
+    let mut changed = state.ci.len() != cached;
+
+    let branches: Vec<String> = known
+        .into_iter()
+        .filter(|name| all || *name == state.current_branch)
+        .collect();
+    for branch in branches {
+        let Ok(sha) = get_branch_commit_sha(repo_path, &branch) else {
+            continue;
+        };
+        if state.ci.get(&branch).is_some_and(|ci| !ci.is_outdated(&sha, ttl)) {
+            continue;
+        }
+        if let Ok(ci_state) = client.ci_state(&sha) {
+            state.ci.insert(branch, CiStatus { sha, state: ci_state, checked_at: Utc::now() });
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Short colored CI result shown after the branch name
+fn format_ci(state: &RepoState, branch: &str) -> String {
+    match state.ci.get(branch).map(|ci| ci.state) {
+        Some(CiState::Passed) => format!(" {}", "CI passed".green()),
+        Some(CiState::Failed) => format!(" {}", "CI failed".red()),
+        Some(CiState::Running) => format!(" {}", "CI running".yellow()),
+        Some(CiState::None) | None => String::new(),
+    }
+}
+
+/// Print one tab-separated record per shown branch, in configuration order:
+/// repository, branch, current (1/0), dirty (1/0), ahead, behind, owner, owner commits, updated, group, ci
+fn print_porcelain(config: &Config, states: &[RepoState], summaries: &HashMap<String, RepoSummary>) {
+    for repo_config in &config.repositories {
+        let Some(state) = states.iter().find(|s| s.name == repo_config.name) else {
+            continue;
+        };
+        let repo_path = config.resolve_repo_path(&state.name);
+        let dirty = summaries.get(&state.name).is_some_and(|s| s.dirty);
+
+        for branch in &state.branches {
+            let (ahead, behind) = get_branch_sync_status(&repo_path, &branch.name).unwrap_or((0, 0));
             println!(
-                "  {:<28} {:<10} {:<25} {:<20} {}",
-                repo_name,
-                commit_count,
-                branch.owner,
-                format_relative_time(branch.last_updated),
-                branch_display
+                "{}",
+                porcelain_line(&[
+                    state.name.as_str(),
+                    branch.name.as_str(),
+                    porcelain_flag(branch.name == state.current_branch),
+                    porcelain_flag(dirty),
+                    &ahead.to_string(),
+                    &behind.to_string(),
+                    branch.owner.as_str(),
+                    &branch.get_owner_commit_count().to_string(),
+                    &porcelain_time(branch.last_updated),
+                    repo_config.group.as_deref().unwrap_or(""),
+                    state.ci.get(&branch.name).map(|ci| ci.state.label()).unwrap_or(""),
+                ])
             );
         }
     }
+}
 
-    Ok(())
+/// Print a group header with its summary line (repository, dirty and behind counts)
+fn print_group_header<'a>(
+    label: &str,
+    depth: usize,
+    members: impl Iterator<Item = &'a RepoState>,
+    summaries: &HashMap<String, RepoSummary>,
+) {
+    let (mut repos, mut dirty, mut behind) = (0, 0, 0);
+    for state in members {
+        let summary = summaries.get(&state.name).copied().unwrap_or_default();
+        repos += 1;
+        dirty += summary.dirty as usize;
+        behind += summary.behind as usize;
+    }
+
+    let mut details = vec![format!("{} repo{}", repos, if repos == 1 { "" } else { "s" })];
+    if dirty > 0 {
+        details.push(format!("{} dirty", dirty).yellow().to_string());
+    }
+    if behind > 0 {
+        details.push(format!("{} behind", behind).red().to_string());
+    }
+
+    println!(
+        "{}{} {} ({})",
+        "  ".repeat(depth),
+        icons::files::folder(),
+        label.bold(),
+        details.join(", ")
+    );
+}
+
+/// Print the branch rows of a repository, indented for its group depth
+/// The first row is marked when the cached statistics are older than `ttl`, and shows where
+/// the repository lives when that is outside the workspace
+fn print_repo_rows(config: &Config, state: &RepoState, depth: usize, timestamps: TimestampFormat, ttl: Duration) {
+    let repo_path = config.resolve_repo_path(&state.name);
+    let indent = "  ".repeat(depth);
+    let name_width = 28usize.saturating_sub(indent.len());
+
+    let stale_marker = if state.is_stale(ttl) {
+        let refreshed = state
+            .refreshed_at
+            .map(|at| format!("refreshed {}", format_time(at, timestamps)))
+            .unwrap_or_else(|| "never refreshed".to_string());
+        format!(" {}", format!("stale ({})", refreshed).bright_black())
+    } else {
+        String::new()
+    };
+    let location = config
+        .external_repo_path(&state.name)
+        .map(|path| format!(" {}", format!("at {}", path.display()).bright_black()))
+        .unwrap_or_default();
+
+    for (idx, branch) in state.branches.iter().enumerate() {
+        let repo_name = if idx == 0 {
+            state.name.clone()
+        } else {
+            String::new()
+        };
+
+        // Get branch status for coloring
+        let branch_status =
+            get_branch_status(&repo_path, &branch.name).unwrap_or(BranchStatus::Synced);
+
+        let branch_display = color_branch(&branch.name, branch_status).to_string();
+
+        // Get commit count for the owner
+        let commit_count = branch.get_owner_commit_count();
+
+        println!(
+            "  {}{:<name_width$} {:<10} {:<25} {:<20} {}{}{}{}",
+            indent,
+            repo_name,
+            commit_count,
+            branch.owner,
+            format_time(branch.last_updated, timestamps),
+            branch_display,
+            format_ci(state, &branch.name),
+            if idx == 0 { stale_marker.as_str() } else { "" },
+            if idx == 0 { location.as_str() } else { "" }
+        );
+    }
 }
+
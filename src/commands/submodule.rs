@@ -0,0 +1,129 @@
+use anyhow::Result;
+use colored::*;
+use std::time::Instant;
+
+use metagit_core::models::{Config, Repository};
+use crate::utils::{
+    extract_hostname, icons, record_operation, resolve_jobs, run_parallel, start_operation, submodule_status,
+    update_submodules, RepoProgress, SubmoduleState,
+};
+
+/// Initialize and update the submodules of every active repository, recursively
+pub fn submodule_update_command(jobs: Option<usize>, all_repos: bool, debug: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let concurrency = config.concurrency.clone().unwrap_or_default();
+    let jobs = resolve_jobs(jobs, Some(&concurrency), debug);
+    let mut operation = start_operation("submodule update");
+
+    if debug {
+        println!("{}", format!("{}DEBUG MODE ENABLED", icons::symbol("🔍 ", "")).bright_cyan().bold());
+        println!();
+    }
+
+    println!("{} Updating submodules...\n", icons::status::info());
+
+    let mut repos: Vec<(&Repository, _)> = Vec::new();
+    for repo_config in config.active_repositories(all_repos) {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        if !repo_path.exists() {
+            println!("{:<30} {}", repo_config.name.yellow(), "not found".red());
+            operation.add_outcome(&repo_config.name, false, "not found", Default::default());
+            continue;
+        }
+        repos.push((repo_config, repo_path));
+    }
+    let hosts: Vec<String> = repos
+        .iter()
+        .map(|(repo_config, _)| extract_hostname(&repo_config.url).unwrap_or_default())
+        .collect();
+
+    let progress = RepoProgress::new(repos.iter().map(|(repo_config, _)| repo_config.name.as_str()), debug);
+    run_parallel(
+        &repos,
+        &hosts,
+        jobs,
+        &concurrency,
+        |(repo_config, repo_path)| {
+            progress.start(&repo_config.name);
+            let started = Instant::now();
+            (update_submodules(&config, repo_path, repo_config.credential.as_ref()), started.elapsed())
+        },
+        |index, (result, duration)| {
+            let repo_name = &repos[index].0.name;
+            let name = if debug { String::new() } else { format!("{:<30} ", repo_name) };
+            match result {
+                Ok(updated) if updated.is_empty() => {
+                    progress.finish(repo_name, format!("{}{}", name, "Up to date".bright_black()));
+                    operation.add_outcome(repo_name, true, "up to date", duration);
+                }
+                Ok(updated) => {
+                    let message = format!(
+                        "Updated {} submodule{}: {}",
+                        updated.len(),
+                        if updated.len() == 1 { "" } else { "s" },
+                        updated.join(", ")
+                    );
+                    progress.finish(repo_name, format!("{}{}", name, message.green()));
+                    operation.add_outcome(repo_name, true, message, duration);
+                }
+                Err(e) => {
+                    progress.finish(repo_name, format!("{}{}: {}", name, "failed".red(), e));
+                    operation.add_outcome(repo_name, false, e.to_string(), duration);
+                }
+            }
+        },
+    );
+    progress.done();
+
+    record_operation(&config, operation);
+    Ok(())
+}
+
+/// List the submodules that are not initialized or not at the commit their repository records
+pub fn submodule_status_command(all_repos: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    println!("{} Checking submodules...\n", icons::status::info());
+
+    let mut outdated = 0;
+    let mut total = 0;
+    for repo_config in config.active_repositories(all_repos) {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        if !repo_path.exists() {
+            continue;
+        }
+        let statuses = match submodule_status(&repo_path) {
+            Ok(statuses) => statuses,
+            Err(e) => {
+                println!("{:<30} {}: {}", repo_config.name.yellow(), "failed".red(), e);
+                continue;
+            }
+        };
+        total += statuses.len();
+        for status in statuses.iter().filter(|s| s.state != SubmoduleState::UpToDate) {
+            println!(
+                "{:<30} {} {} - {}",
+                repo_config.name.cyan(),
+                icons::status::warning(),
+                status.path,
+                status.state.label().yellow()
+            );
+            outdated += 1;
+        }
+    }
+
+    if total == 0 {
+        println!("{} No submodules in the workspace", icons::status::info());
+    } else if outdated == 0 {
+        println!("{} All {} submodules are up to date", icons::status::success(), total);
+    } else {
+        println!(
+            "\n{} {} of {} submodules need updating. Run '{}'.",
+            icons::status::warning(),
+            outdated,
+            total,
+            "mgit submodule update".cyan()
+        );
+    }
+    Ok(())
+}
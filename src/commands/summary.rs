@@ -0,0 +1,108 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use metagit_core::db::StateDb;
+use metagit_core::models::Config;
+use crate::utils::{format_time, get_head, get_worktree_status, porcelain_line, porcelain_time};
+
+/// Counts of a workspace summary (a repository can be behind, ahead and dirty at once)
+#[derive(Default)]
+struct WorkspaceSummary {
+    repos: usize,
+    synced: usize,
+    behind: usize,
+    ahead: usize,
+    dirty: usize,
+    missing: usize,
+    /// Oldest statistics refresh among the repositories (None if never refreshed)
+    refreshed_at: Option<DateTime<Utc>>,
+}
+
+/// Print a one-line health overview, e.g. "17 repos: 14 synced, 2 behind, 1 dirty; last refresh 2 hours ago"
+/// Uncommitted changes and ahead/behind counts are checked live against the last fetched state of origin
+/// (no network access); the refresh time comes from the state database.
+/// `porcelain` prints the counts as one tab-separated record instead:
+/// repos, synced, behind, ahead, dirty, missing, last refresh
+pub fn summary_command(porcelain: bool, all_repos: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let db_path = config.get_db_path();
+    // Opening would create an empty database
+    let db = if db_path.exists() {
+        Some(StateDb::open(&db_path)?)
+    } else {
+        None
+    };
+
+    let mut summary = WorkspaceSummary::default();
+    let mut never_refreshed = false;
+    for repo in config.active_repositories(all_repos) {
+        summary.repos += 1;
+        let repo_path = config.resolve_repo_path(&repo.name);
+        if !repo_path.exists() {
+            summary.missing += 1;
+            continue;
+        }
+
+        let worktree = match get_head(&repo_path) {
+            Ok((Some(branch), _)) => get_worktree_status(&repo_path, &branch),
+            _ => Default::default(),
+        };
+        summary.dirty += usize::from(worktree.dirty);
+        summary.behind += usize::from(worktree.behind > 0);
+        summary.ahead += usize::from(worktree.ahead > 0);
+        summary.synced += usize::from(!worktree.dirty && worktree.behind == 0 && worktree.ahead == 0);
+
+        let refreshed_at = db
+            .as_ref()
+            .and_then(|db| db.get_repo_state(&repo.name).ok().flatten())
+            .and_then(|state| state.refreshed_at);
+        match refreshed_at {
+            Some(time) => summary.refreshed_at = Some(summary.refreshed_at.map_or(time, |t| t.min(time))),
+            None => never_refreshed = true,
+        }
+    }
+    if never_refreshed {
+        summary.refreshed_at = None;
+    }
+
+    if porcelain {
+        println!(
+            "{}",
+            porcelain_line(&[
+                summary.repos.to_string(),
+                summary.synced.to_string(),
+                summary.behind.to_string(),
+                summary.ahead.to_string(),
+                summary.dirty.to_string(),
+                summary.missing.to_string(),
+                summary.refreshed_at.map(porcelain_time).unwrap_or_default(),
+            ])
+        );
+        return Ok(());
+    }
+
+    let mut parts = vec![format!("{} synced", summary.synced)];
+    for (count, label) in [
+        (summary.behind, "behind"),
+        (summary.ahead, "ahead"),
+        (summary.dirty, "dirty"),
+        (summary.missing, "missing"),
+    ] {
+        if count > 0 {
+            parts.push(format!("{} {}", count, label));
+        }
+    }
+    let refresh = match summary.refreshed_at {
+        Some(time) => format!("last refresh {}", format_time(time, config.timestamps)),
+        None => "never refreshed".to_string(),
+    };
+    println!(
+        "{} repo{}: {}; {}",
+        summary.repos,
+        if summary.repos == 1 { "" } else { "s" },
+        parts.join(", "),
+        refresh
+    );
+
+    Ok(())
+}
@@ -1,44 +1,307 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use colored::*;
 
-use crate::models::Config;
-use crate::utils::{pull_repo, push_repo};
+use metagit_core::models::{ConcurrencyConfig, Config, Repository};
+use crate::utils::{
+    events_enabled, extract_hostname, fetch_remote_branch, get_branch_sync_status, get_head,
+    has_uncommitted_changes, human_println, icons, interrupted, network_timeout, pick_repositories, pull_repo, push_repo,
+    record_operation, report_failures, report_interrupted, resolve_jobs, run_parallel, start_operation, with_timeout,
+    RepoProgress,
+};
+use crate::commands::{pull_commits_limit, pulled_commit_lines, update_worktree_states};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
-pub fn sync_command(debug: bool) -> Result<()> {
-    let config = Config::load_from_project()?;
+/// What `sync` would do to a repository, worked out after fetching it
+enum RepoPlan {
+    UpToDate,
+    Apply { branch: String, pull: usize, push: usize, create: bool },
+    /// The sync would fail or lose work
+    Conflict(String),
+}
+
+impl RepoPlan {
+    fn describe(&self) -> ColoredString {
+        match self {
+            RepoPlan::UpToDate => "up to date".bright_black(),
+            RepoPlan::Apply { branch, pull, push, create } => {
+                let mut actions = Vec::new();
+                if *pull > 0 {
+                    actions.push(format!("will fast-forward {}", commits(*pull)));
+                }
+                if *create {
+                    actions.push(format!("will create {} on origin", branch));
+                } else if *push > 0 {
+                    actions.push(format!("will push {}", commits(*push)));
+                }
+                actions.join(", ").green()
+            }
+            RepoPlan::Conflict(reason) => format!("conflict expected: {}", reason).yellow(),
+        }
+    }
+}
+
+fn commits(count: usize) -> String {
+    format!("{} commit{}", count, if count == 1 { "" } else { "s" })
+}
+
+/// Fetch the current branch and compare it with origin
+fn plan_repo(config: &Arc<Config>, repo_config: &Repository, repo_path: &Path) -> Result<RepoPlan> {
+    let branch = get_head(repo_path)?
+        .0
+        .ok_or_else(|| anyhow!("HEAD is detached, check out a branch first"))?;
+
+    // libgit2 fetches a missing branch without error, the git CLI fails
+    let timeout = network_timeout(config, repo_config)?;
+    let (config, path, fetched, credential) =
+        (Arc::clone(config), repo_path.to_path_buf(), branch.clone(), repo_config.credential.clone());
+    match with_timeout(timeout, move || fetch_remote_branch(&config, &path, &fetched, credential.as_ref())) {
+        Err(e) if !e.to_string().contains("couldn't find remote ref") => return Err(e),
+        _ => {}
+    }
+    let tracking = format!("refs/remotes/origin/{}", branch);
+    if git2::Repository::open(repo_path)?.find_reference(&tracking).is_err() {
+        return Ok(RepoPlan::Apply { branch, pull: 0, push: 0, create: true });
+    }
+
+    let (push, pull) = get_branch_sync_status(repo_path, &branch)?;
+    Ok(if push > 0 && pull > 0 {
+        RepoPlan::Conflict(format!("{} diverged from origin ({} ahead, {} behind)", branch, push, pull))
+    } else if pull > 0 && has_uncommitted_changes(repo_path)? {
+        RepoPlan::Conflict("uncommitted changes would be overwritten by the fast-forward".to_string())
+    } else if push > 0 || pull > 0 {
+        RepoPlan::Apply { branch, pull, push, create: false }
+    } else {
+        RepoPlan::UpToDate
+    })
+}
+
+/// Fetch every repository and print what `sync` would do
+/// Returns the repositories with changes to apply, in configuration order.
+fn plan_sync<'a>(
+    config: &Arc<Config>,
+    repos: Vec<(&'a Repository, PathBuf)>,
+    hosts: &[String],
+    jobs: usize,
+    concurrency: &ConcurrencyConfig,
+) -> Vec<(&'a Repository, PathBuf)> {
+    let mut plans: Vec<Option<Result<RepoPlan>>> = repos.iter().map(|_| None).collect();
+    run_parallel(
+        &repos,
+        hosts,
+        jobs,
+        concurrency,
+        |(repo_config, repo_path)| plan_repo(config, repo_config, repo_path),
+        |index, plan| plans[index] = Some(plan),
+    );
+
+    let (mut pulls, mut pushes, mut conflicts, mut failures, mut unchanged) = (0, 0, 0, 0, 0);
+    let mut apply = Vec::new();
+    for ((repo_config, repo_path), plan) in repos.into_iter().zip(plans) {
+        match plan {
+            // Not started before Ctrl-C
+            None => human_println!("{:<30} {}", repo_config.name, "not planned".bright_black()),
+            Some(Ok(plan)) => {
+                human_println!("{:<30} {}", repo_config.name, plan.describe());
+                match plan {
+                    RepoPlan::UpToDate => unchanged += 1,
+                    RepoPlan::Conflict(_) => conflicts += 1,
+                    RepoPlan::Apply { pull, push, create, .. } => {
+                        pulls += usize::from(pull > 0);
+                        pushes += usize::from(push > 0 || create);
+                        apply.push((repo_config, repo_path));
+                    }
+                }
+            }
+            Some(Err(e)) => {
+                human_println!("{:<30} {}: {}", repo_config.name, "fetch failed".red(), e);
+                failures += 1;
+            }
+        }
+    }
+
+    let mut summary = Vec::new();
+    for (count, label) in [
+        (pulls, "to fast-forward"),
+        (pushes, "to push"),
+        (conflicts, if conflicts == 1 { "conflict" } else { "conflicts" }),
+        (failures, "failed to fetch"),
+        (unchanged, "up to date"),
+    ] {
+        if count > 0 {
+            summary.push(format!("{} {}", count, label));
+        }
+    }
+    human_println!("\nPlan: {}", if summary.is_empty() { "nothing to sync".to_string() } else { summary.join(", ") });
+    if conflicts + failures > 0 && !apply.is_empty() {
+        human_println!("{} Repositories with conflicts or fetch errors are left untouched", icons::status::warning());
+    }
+    apply
+}
+
+/// Ask whether to apply the plan; never asks (and declines) without a terminal or with JSON events
+fn confirm_apply() -> Result<bool> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() || events_enabled() {
+        return Ok(false);
+    }
+    print!("\nApply this plan? [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Pull and push every active repository (archived ones too with `all_repos`)
+/// With `interactive`, the repositories are picked from a fuzzy-searchable list first.
+/// With `plan`, fetch first and show what would happen, then ask before applying it;
+/// `apply` shows the plan and applies it without asking.
+pub fn sync_command(
+    jobs: Option<usize>,
+    all_repos: bool,
+    interactive: bool,
+    plan: bool,
+    apply: bool,
+    debug: bool,
+) -> Result<()> {
+    // Shared with the network operations, which may outlive a timed out repository's turn
+    let config = Arc::new(Config::load_from_project()?);
+    let concurrency = config.concurrency.clone().unwrap_or_default();
+    let jobs = resolve_jobs(jobs, Some(&concurrency), debug);
+    let limit = pull_commits_limit(&config);
+    let prune = config.prune.unwrap_or(false);
+    let mut operation = start_operation("sync");
+
+    let selected: Vec<&Repository> = config.active_repositories(all_repos).collect();
+    let selected = if interactive { pick_repositories(selected)? } else { selected };
+    if interactive && selected.is_empty() {
+        human_println!("{} No repositories selected", icons::status::info());
+        return Ok(());
+    }
 
     if debug {
-        println!("{}", "🔍 DEBUG MODE ENABLED".bright_cyan().bold());
-        println!();
+        human_println!("{}", format!("{}DEBUG MODE ENABLED", icons::symbol("🔍 ", "")).bright_cyan().bold());
+        human_println!();
     }
 
-    println!("Syncing repositories (pull & push)...\n");
+    if plan || apply {
+        human_println!("{} Planning sync (fetching repositories)...\n", icons::status::info());
+    } else {
+        human_println!("Syncing repositories (pull & push)...\n");
+    }
 
-    for repo_config in &config.repositories {
+    let mut repos = Vec::new();
+    for repo_config in selected {
         let repo_path = config.resolve_repo_path(&repo_config.name);
 
         if !repo_path.exists() {
-            println!("{:<30} {}",repo_config.name.yellow(), "not found".red());
+            human_println!("{:<30} {}",repo_config.name.yellow(), "not found".red());
+            operation.add_outcome(&repo_config.name, false, "not found", Default::default());
             continue;
         }
+        repos.push((repo_config, repo_path));
+    }
+    let hosts: Vec<String> = repos
+        .iter()
+        .map(|(repo_config, _)| extract_hostname(&repo_config.url).unwrap_or_default())
+        .collect();
 
-        print!("{:<30} ", repo_config.name);
-
-        // Pull first
-        match pull_repo(&repo_path, debug) {
-            Ok(msg) => print!("pull: {} ", msg.green()),
-            Err(e) => {
-                println!("pull {}: {}", "failed".red(), e);
-                continue; // Skip push if pull failed
-            }
+    let (repos, hosts) = if plan || apply {
+        let repos = plan_sync(&config, repos, &hosts, jobs, &concurrency);
+        if repos.is_empty() {
+            return Ok(());
         }
-
-        // Then push
-        match push_repo(&repo_path, debug) {
-            Ok(msg) => println!("| push: {}", msg.green()),
-            Err(e) => println!("| push {}: {}", "failed".red(), e),
+        if interrupted() {
+            human_println!("\n{} Interrupted while planning, nothing applied", icons::status::warning());
+            return Ok(());
         }
-    }
+        if !apply && !confirm_apply()? {
+            human_println!("\nNothing applied. Run {} to apply the plan.", "mgit sync --apply".cyan());
+            return Ok(());
+        }
+        human_println!("\nSyncing repositories (pull & push)...\n");
+        let hosts = repos
+            .iter()
+            .map(|(repo_config, _)| extract_hostname(&repo_config.url).unwrap_or_default())
+            .collect();
+        (repos, hosts)
+    } else {
+        (repos, hosts)
+    };
+
+    let progress = RepoProgress::new(repos.iter().map(|(repo_config, _)| repo_config.name.as_str()), debug);
+    let not_started = run_parallel(
+        &repos,
+        &hosts,
+        jobs,
+        &concurrency,
+        |(repo_config, repo_path)| {
+            progress.start(&repo_config.name);
+            let started = Instant::now();
+
+            let timeout = match network_timeout(&config, repo_config) {
+                Ok(timeout) => timeout,
+                Err(e) => return (Err((None, e)), started.elapsed()),
+            };
+
+            // Pull first
+            let (shared, path, credential) = (Arc::clone(&config), repo_path.clone(), repo_config.credential.clone());
+            let pulled = match with_timeout(timeout, move || pull_repo(&shared, &path, credential.as_ref(), prune)) {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    // Skip push if pull failed
+                    return (Err((None, e)), started.elapsed());
+                }
+            };
+
+            // Then push, unless the user interrupted in between
+            if interrupted() {
+                return (Err((Some(pulled), anyhow!("Interrupted before the push"))), started.elapsed());
+            }
+            let (shared, path, credential) = (Arc::clone(&config), repo_path.clone(), repo_config.credential.clone());
+            let result = match with_timeout(timeout, move || push_repo(&shared, &path, credential.as_ref())) {
+                Ok(outcome) => Ok((pulled, outcome.summary())),
+                Err(e) => Err((Some(pulled), e)),
+            };
+            (result, started.elapsed())
+        },
+        |index, (result, duration)| {
+            let repo_name = &repos[index].0.name;
+            match result {
+                Ok((pulled, pushed)) => {
+                    let summary = pulled.summary();
+                    let commits = pulled_commit_lines(&pulled, limit);
+                    progress.finish(
+                        repo_name,
+                        format!("{:<30} pull: {} | push: {}{}", repo_name, summary.green(), pushed.green(), commits),
+                    );
+                    operation.add_outcome(repo_name, true, format!("pull: {} | push: {}", summary, pushed), duration);
+                }
+                Err((None, e)) => {
+                    progress.finish(repo_name, format!("{:<30} pull {}: {}", repo_name, "failed".red(), e));
+                    operation.add_outcome(repo_name, false, format!("pull failed: {}", e), duration);
+                }
+                Err((Some(pulled), e)) => {
+                    let summary = pulled.summary();
+                    let commits = pulled_commit_lines(&pulled, limit);
+                    progress.finish(
+                        repo_name,
+                        format!("{:<30} pull: {} | push {}: {}{}", repo_name, summary.green(), "failed".red(), e, commits),
+                    );
+                    operation.add_outcome(repo_name, false, format!("pull: {} | push failed: {}", summary, e), duration);
+                }
+            }
+        },
+    );
+    progress.done();
+    report_interrupted(
+        repos.len() - not_started.len(),
+        not_started.iter().map(|&index| repos[index].0.name.as_str()),
+    );
+    update_worktree_states(&config, repos.iter().map(|(repo_config, _)| repo_config.name.as_str()));
 
+    report_failures(&config, &operation);
+    record_operation(&config, operation);
     Ok(())
 }
@@ -0,0 +1,230 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use git2::Repository;
+use std::collections::HashMap;
+
+use metagit_core::models::Config;
+use crate::utils::{collect_commits, format_time, icons, resolve_tag_commit, tag_branches, CommitEntry};
+
+/// Differences of one repository between two saved tags
+struct RepoDiff {
+    from_branch: String,
+    to_branch: String,
+    /// Commits in `to` that are not in `from`, newest first
+    added: Vec<CommitEntry>,
+    /// Number of commits in `from` that are not in `to`
+    dropped: usize,
+}
+
+/// List the saved tags, newest first, with who saved them, when and why
+/// Tags saved without metadata are listed last, by name
+pub fn tag_list_command() -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    if config.tags.is_empty() {
+        println!(
+            "{} No saved tags. Use 'mgit save <tag>' to create one.",
+            icons::status::info()
+        );
+        return Ok(());
+    }
+
+    println!("{} Saved tags:\n", icons::status::info());
+
+    let mut names: Vec<&String> = config.tags.keys().collect();
+    names.sort_by(|a, b| {
+        let created = |name: &String| config.tag_info.get(name).map(|info| info.created_at);
+        created(b).cmp(&created(a)).then_with(|| a.cmp(b))
+    });
+
+    for name in names {
+        let count = config.tags[name].len();
+        let repositories = format!("{} repositor{}", count, if count == 1 { "y" } else { "ies" });
+        match config.tag_info.get(name) {
+            Some(info) => {
+                let by = info.author.as_deref().map(|a| format!(" by {}", a)).unwrap_or_default();
+                println!(
+                    "  {} {} - {}, saved {}{}",
+                    icons::status::success(),
+                    name.cyan().bold(),
+                    repositories,
+                    format_time(info.created_at, config.timestamps),
+                    by
+                );
+                if let Some(description) = &info.description {
+                    println!("      {}", description.bright_black());
+                }
+            }
+            None => println!(
+                "  {} {} - {}",
+                icons::status::success(),
+                name.cyan().bold(),
+                repositories
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare two saved tags: per repository, the branch change and the commits between them
+/// `limit` caps the number of commit subjects listed per repository
+pub fn tag_diff_command(from: &str, to: &str, limit: usize) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    let from_branches = tag_branches(&config, from)?;
+    let to_branches = tag_branches(&config, to)?;
+
+    println!(
+        "{} Comparing saved tags '{}' {} '{}'...\n",
+        icons::status::info(),
+        from.cyan().bold(),
+        icons::symbol("→", "->"),
+        to.cyan().bold()
+    );
+
+    let mut changed_count = 0;
+    let mut commit_count = 0;
+    let mut error_count = 0;
+
+    for repo_config in &config.repositories {
+        let name = &repo_config.name;
+        let in_from = from_branches.as_ref().is_none_or(|b| b.contains_key(name));
+        let in_to = to_branches.as_ref().is_none_or(|b| b.contains_key(name));
+
+        if !in_from && !in_to {
+            continue;
+        }
+        if !in_from || !in_to {
+            let (tag, branches) = if in_from {
+                (from, &from_branches)
+            } else {
+                (to, &to_branches)
+            };
+            let branch = branches.as_ref().and_then(|b| b.get(name)).map(|b| b.as_str()).unwrap_or("");
+            println!(
+                "  {} {} - only in '{}' ({})",
+                icons::status::warning(),
+                name.yellow(),
+                tag,
+                branch
+            );
+            changed_count += 1;
+            continue;
+        }
+
+        let repo_path = config.resolve_repo_path(name);
+        let result = Repository::open(&repo_path)
+            .map_err(|_| anyhow!("repository not found"))
+            .and_then(|repo| {
+                diff_repo(&repo, name, (from, from_branches.as_ref()), (to, to_branches.as_ref()), &config.users)
+            });
+
+        let diff = match result {
+            Ok(diff) => diff,
+            Err(e) => {
+                println!("  {} {} - {}", icons::status::error(), name.yellow(), e);
+                error_count += 1;
+                continue;
+            }
+        };
+
+        let branch_display = if diff.from_branch == diff.to_branch {
+            diff.from_branch.clone()
+        } else {
+            format!("{} {} {}", diff.from_branch, icons::symbol("→", "->"), diff.to_branch.green())
+        };
+
+        if diff.added.is_empty() && diff.dropped == 0 && diff.from_branch == diff.to_branch {
+            println!(
+                "  {} {} - {}, {}",
+                icons::status::success(),
+                name.cyan(),
+                branch_display,
+                "no changes".bright_black()
+            );
+            continue;
+        }
+
+        let mut counts = vec![format!(
+            "{} new commit{}",
+            diff.added.len(),
+            if diff.added.len() == 1 { "" } else { "s" }
+        )
+        .green()
+        .to_string()];
+        if diff.dropped > 0 {
+            counts.push(format!("{} dropped", diff.dropped).red().to_string());
+        }
+        println!(
+            "  {} {} - {}, {}",
+            icons::status::success(),
+            name.cyan(),
+            branch_display,
+            counts.join(", ")
+        );
+
+        for entry in diff.added.iter().take(limit) {
+            println!(
+                "      {} {} ({}, {})",
+                icons::symbol("•", "-"),
+                entry.summary,
+                entry.short_sha.bright_black(),
+                entry.author
+            );
+        }
+        if diff.added.len() > limit {
+            println!("      {} and {} more", icons::symbol("…", "..."), diff.added.len() - limit);
+        }
+
+        changed_count += 1;
+        commit_count += diff.added.len();
+    }
+
+    println!();
+    println!(
+        "{} {} repositories changed, {} new commits ({} errors)",
+        icons::status::success(),
+        changed_count,
+        commit_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} Some repositories could not be compared. Check the errors above.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Compare the commits two tags point to in one repository
+fn diff_repo(
+    repo: &Repository,
+    name: &str,
+    (from, from_branches): (&str, Option<&HashMap<String, String>>),
+    (to, to_branches): (&str, Option<&HashMap<String, String>>),
+    users: &HashMap<String, Vec<String>>,
+) -> Result<RepoDiff> {
+    let from_oid = resolve_tag_commit(repo, from, from_branches, name)?;
+    let to_oid = resolve_tag_commit(repo, to, to_branches, name)?;
+    let (_, dropped) = repo.graph_ahead_behind(to_oid, from_oid)?;
+
+    Ok(RepoDiff {
+        from_branch: branch_name(repo, from_branches, name),
+        to_branch: branch_name(repo, to_branches, name),
+        added: collect_commits(repo, from_oid, to_oid, users)?,
+        dropped,
+    })
+}
+
+/// Branch a tag records for a repository (main/master for the reserved tags)
+fn branch_name(repo: &Repository, branches: Option<&HashMap<String, String>>, name: &str) -> String {
+    match branches {
+        Some(branches) => branches.get(name).cloned().unwrap_or_default(),
+        None if repo.find_branch("main", git2::BranchType::Local).is_ok() => "main".to_string(),
+        None => "master".to_string(),
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use git2::{Oid, Repository};
+use std::path::Path;
+use std::time::Instant;
+
+use metagit_core::db::StateDb;
+use metagit_core::models::{Config, RepoHead};
+use crate::utils::{format_time, get_head, has_uncommitted_changes, icons, record_operation, start_operation};
+
+/// Put the repositories changed by the last pull/restore back to their previous branch and commit
+/// Repositories with uncommitted changes, or that changed since the operation, are skipped unless `force`
+pub fn undo_command(force: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let db_path = config.get_db_path();
+    let db = StateDb::open(&db_path)?;
+
+    let snapshot = db.get_undo_snapshot()?.ok_or_else(|| {
+        anyhow!("Nothing to undo. 'mgit pull' and 'mgit restore' record the state they change.")
+    })?;
+
+    println!(
+        "{} Undoing '{}' from {}...\n",
+        icons::status::info(),
+        snapshot.command.cyan().bold(),
+        format_time(snapshot.created_at, config.timestamps)
+    );
+
+    let mut success_count = 0;
+    let mut error_count = 0;
+    let mut operation = start_operation(format!("undo {}", snapshot.command));
+
+    for head in &snapshot.repos {
+        let repo_path = config.resolve_repo_path(&head.name);
+        let started = Instant::now();
+
+        match undo_repo(&repo_path, head, force) {
+            Ok(message) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::success(),
+                    head.name.cyan(),
+                    message.green()
+                );
+                operation.add_outcome(&head.name, true, message, started.elapsed());
+                success_count += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} - {}",
+                    icons::status::error(),
+                    head.name.yellow(),
+                    e
+                );
+                operation.add_outcome(&head.name, false, e.to_string(), started.elapsed());
+                error_count += 1;
+            }
+        }
+    }
+
+    // Keep the snapshot when something failed, so the undo can be retried (e.g., with --force)
+    if error_count == 0 {
+        db.clear_undo_snapshot()?;
+    }
+    drop(db);
+    record_operation(&config, operation);
+
+    println!();
+    println!(
+        "{} Undo complete! ({} repositories, {} errors)",
+        icons::status::success(),
+        success_count,
+        error_count
+    );
+
+    if error_count > 0 {
+        println!(
+            "\n{} Some repositories were not reverted. Check the errors above, or use --force.",
+            icons::status::warning()
+        );
+    }
+
+    Ok(())
+}
+
+/// Revert one repository to its recorded branch and commit, returns what was done
+fn undo_repo(repo_path: &Path, head: &RepoHead, force: bool) -> Result<String> {
+    if !repo_path.exists() {
+        return Err(anyhow!("repository not found"));
+    }
+    let (branch, current) = get_head(repo_path)?;
+
+    if branch == head.branch && current == head.before {
+        return Ok("already reverted".to_string());
+    }
+    if !force {
+        if has_uncommitted_changes(repo_path)? {
+            return Err(anyhow!("has uncommitted changes (use --force to discard them)"));
+        }
+        if branch != head.after_branch || head.after.as_deref() != Some(current.as_str()) {
+            return Err(anyhow!("changed since the operation (use --force to undo anyway)"));
+        }
+    }
+
+    let repo = Repository::open(repo_path)?;
+    let before = Oid::from_str(&head.before)?;
+    repo.find_commit(before)
+        .map_err(|_| anyhow!("commit {} no longer exists", short_sha(&head.before)))?;
+
+    let mut changes = Vec::new();
+    match &head.branch {
+        Some(name) => {
+            let refname = format!("refs/heads/{}", name);
+            let mut reference = repo.find_reference(&refname)?;
+            if reference.target() != Some(before) {
+                reference.set_target(before, "mgit undo")?;
+                changes.push(format!("reset {} to {}", name, short_sha(&head.before)));
+            }
+            if branch.as_deref() != Some(name.as_str()) {
+                changes.push(format!("switched back to {}", name));
+            }
+            repo.set_head(&refname)?;
+        }
+        None => {
+            repo.set_head_detached(before)?;
+            changes.push(format!("detached at {}", short_sha(&head.before)));
+        }
+    }
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
+
+    Ok(changes.join(", "))
+}
+
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+
+use metagit_core::models::{Config, Repository};
+use crate::utils::{find_corruption, fsck_repository, icons, repair_repository, CorruptedRef};
+
+/// What happens to a corrupted ref, or happened with `repaired`
+pub fn describe_ref_repair(corrupted: &CorruptedRef, repaired: bool) -> String {
+    match (&corrupted.recovery, repaired) {
+        (Some((source, commit)), true) => {
+            format!("restored corrupted ref {} from {} ({:.7})", corrupted.path, source.label(), commit)
+        }
+        (Some((source, commit)), false) => {
+            format!("found corrupted ref {}, recoverable from {} ({:.7})", corrupted.path, source.label(), commit)
+        }
+        (None, true) => format!("removed corrupted ref {}", corrupted.path),
+        (None, false) => format!("found corrupted ref {}, nothing to recover it from", corrupted.path),
+    }
+}
+
+/// Check the integrity of every repository (or the given ones): corrupted FETCH_HEAD, corrupted
+/// loose refs and whatever `git fsck` finds. With `repair`, FETCH_HEAD is removed and the refs are
+/// recovered from packed-refs or the reflog, or removed when nothing can recover them.
+/// Exits non-zero when a repository needs attention (corruption left in place, fsck findings, errors).
+pub fn verify_command(repos: &[String], repair: bool, all_repos: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+    for name in repos {
+        if !config.repositories.iter().any(|r| &r.name == name) {
+            return Err(anyhow!("Repository '{}' not found in configuration", name));
+        }
+    }
+    let selected: Vec<&Repository> = if repos.is_empty() {
+        config.active_repositories(all_repos).collect()
+    } else {
+        config.repositories.iter().filter(|r| repos.contains(&r.name)).collect()
+    };
+
+    println!(
+        "{} {} repositories...\n",
+        icons::status::info(),
+        if repair { "Verifying and repairing" } else { "Verifying" }
+    );
+
+    let (mut attention, mut repaired, mut repairable) = (0, 0, 0);
+    for repo_config in &selected {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        if !repo_path.exists() {
+            println!("{:<30} {} {}", repo_config.name.yellow(), icons::status::error(), "not found".red());
+            attention += 1;
+            continue;
+        }
+
+        let scanned = if repair { repair_repository(&repo_path) } else { find_corruption(&repo_path) };
+        let corruption = match scanned {
+            Ok(corruption) => corruption,
+            Err(e) => {
+                println!("{:<30} {} {}", repo_config.name.yellow(), icons::status::error(), e.to_string().red());
+                attention += 1;
+                continue;
+            }
+        };
+        let fsck = fsck_repository(&repo_path).unwrap_or_else(|e| vec![e.to_string()]);
+
+        let mut lines = Vec::new();
+        let (icon, action) = if repair {
+            (icons::status::success(), "removed")
+        } else {
+            (icons::status::warning(), "found")
+        };
+        if corruption.corrupted_fetch_head {
+            lines.push(format!("{} {} corrupted FETCH_HEAD", icon, action));
+        }
+        for corrupted in &corruption.corrupted_refs {
+            lines.push(format!("{} {}", icon, describe_ref_repair(corrupted, repair)));
+        }
+        if let Some(backup_dir) = &corruption.backup_dir {
+            let backup_dir = backup_dir.strip_prefix(&repo_path).unwrap_or(backup_dir);
+            lines.push(format!("{} backed up the refs to {}", icons::status::info(), backup_dir.display()));
+        }
+        for finding in &fsck {
+            lines.push(format!("{} fsck: {}", icons::status::error(), finding));
+        }
+
+        if lines.is_empty() {
+            println!("{:<30} {} {}", repo_config.name, icons::status::success(), "OK".green());
+            continue;
+        }
+        if !repair && corruption.has_fixes() {
+            repairable += 1;
+        }
+        if !fsck.is_empty() || (!repair && corruption.has_fixes()) {
+            attention += 1;
+        } else {
+            repaired += 1;
+        }
+        for (index, line) in lines.iter().enumerate() {
+            let name = if index == 0 { repo_config.name.as_str() } else { "" };
+            println!("{:<30} {}", name.yellow(), line);
+        }
+    }
+
+    println!();
+    if repaired > 0 {
+        println!(
+            "{} Repaired {} repositor{}",
+            icons::status::success(),
+            repaired,
+            if repaired == 1 { "y" } else { "ies" }
+        );
+    }
+    if attention == 0 {
+        println!("{} All {} repositories are intact", icons::status::success(), selected.len());
+        return Ok(());
+    }
+
+    println!(
+        "{} {} of {} repositor{} need{} attention",
+        icons::status::error(),
+        attention,
+        selected.len(),
+        if selected.len() == 1 { "y" } else { "ies" },
+        if attention == 1 { "s" } else { "" }
+    );
+    if repairable > 0 {
+        println!("Run '{}' to repair the corrupted FETCH_HEAD files and refs.", "mgit verify --repair".cyan());
+    }
+    std::process::exit(1);
+}
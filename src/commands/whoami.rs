@@ -0,0 +1,154 @@
+use anyhow::Result;
+use colored::*;
+use std::collections::HashMap;
+
+use metagit_core::models::{Config, Identity};
+use crate::commands::format_identity;
+use crate::utils::{commit_identity, icons, url_host_and_path, CommitIdentity};
+
+/// Owner of a repository in its remote URL ("git@github.com:acme/api.git" -> "github.com/acme")
+fn remote_owner(url: &str) -> Option<String> {
+    let (host, path) = url_host_and_path(url)?;
+    let (owner, _) = path.split_once('/')?;
+    Some(format!("{}/{}", host, owner))
+}
+
+/// For each repository (owner, email), the email most repositories of the same owner use
+/// when it differs from its own. Only a strict majority counts, emails compare case-insensitively.
+fn deviating_emails(repos: &[(Option<String>, Option<String>)]) -> Vec<Option<String>> {
+    let mut counts: HashMap<&str, HashMap<String, (usize, &str)>> = HashMap::new();
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for (owner, email) in repos {
+        let (Some(owner), Some(email)) = (owner, email) else {
+            continue;
+        };
+        *totals.entry(owner).or_default() += 1;
+        counts.entry(owner).or_default().entry(email.to_lowercase()).or_insert((0, email)).0 += 1;
+    }
+
+    repos
+        .iter()
+        .map(|(owner, email)| {
+            let (owner, email) = (owner.as_deref()?, email.as_deref()?);
+            let (count, expected) = counts[owner].values().max_by_key(|(count, _)| *count)?;
+            let majority = *count * 2 > totals[owner];
+            (majority && !expected.eq_ignore_ascii_case(email)).then(|| expected.to_string())
+        })
+        .collect()
+}
+
+/// Show the user.name/user.email each repository commits as and where it is configured
+/// Repositories without an identity, or with another one than the configured `identity`, are flagged.
+/// Without a configured identity, so are repositories with another email than most repositories
+/// of the same owner (e.g., a personal address among work repositories).
+pub fn whoami_command(all_repos: bool) -> Result<()> {
+    let config = Config::load_from_project()?;
+
+    println!("{} Checking commit identities...\n", icons::status::info());
+
+    let mut repos: Vec<(&str, CommitIdentity, Option<String>, Option<Identity>)> = Vec::new();
+    for repo_config in config.active_repositories(all_repos) {
+        let repo_path = config.resolve_repo_path(&repo_config.name);
+        if !repo_path.exists() {
+            continue;
+        }
+        let configured = config.expected_identity(repo_config);
+        match commit_identity(&repo_path) {
+            Ok(identity) => repos.push((&repo_config.name, identity, remote_owner(&repo_config.url), configured)),
+            Err(e) => println!("{:<30} {}: {}", repo_config.name.yellow(), "failed".red(), e),
+        }
+    }
+    // Repositories with a configured identity are checked against it instead
+    let owners_and_emails: Vec<_> = repos
+        .iter()
+        .map(|(_, identity, owner, configured)| {
+            (owner.clone().filter(|_| configured.is_none()), identity.email.clone())
+        })
+        .collect();
+    let deviating = deviating_emails(&owners_and_emails);
+
+    let mut flagged = 0;
+    for ((name, identity, owner, configured), expected_email) in repos.iter().zip(deviating) {
+        let (Some(user), Some(email)) = (&identity.name, &identity.email) else {
+            let missing = if identity.email.is_none() { "user.email" } else { "user.name" };
+            println!("{:<30} {} {}", name.cyan(), icons::status::error(), format!("no {} configured", missing).red());
+            flagged += 1;
+            continue;
+        };
+        let source = format!("({})", identity.source.unwrap_or("unknown")).bright_black();
+        if let Some(configured) = configured.as_ref().filter(|configured| !identity.matches(configured)) {
+            let expected = format_identity(configured.name.as_deref(), configured.email.as_deref());
+            println!(
+                "{:<30} {} <{}> {} {} {}",
+                name.cyan(),
+                user.yellow(),
+                email.yellow(),
+                source,
+                icons::status::warning(),
+                format!("configured: {}", expected).yellow()
+            );
+            flagged += 1;
+            continue;
+        }
+        match expected_email {
+            Some(expected) => {
+                println!(
+                    "{:<30} {} <{}> {} {} {}",
+                    name.cyan(),
+                    user,
+                    email.yellow(),
+                    source,
+                    icons::status::warning(),
+                    format!("other {} repositories use {}", owner.as_deref().unwrap_or_default(), expected).yellow()
+                );
+                flagged += 1;
+            }
+            None => println!("{:<30} {} <{}> {}", name.cyan(), user, email, source),
+        }
+    }
+
+    if repos.is_empty() {
+        println!("{} No cloned repositories", icons::status::info());
+    } else if flagged == 0 {
+        println!("\n{} All repositories commit with a consistent identity", icons::status::success());
+    } else {
+        println!(
+            "\n{} {} repositor{} may commit with the wrong identity. Configure `identity` and run '{}'.",
+            icons::status::warning(),
+            flagged,
+            if flagged == 1 { "y" } else { "ies" },
+            "mgit identity apply".cyan()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deviating_emails() {
+        let repo = |owner: &str, email: &str| (Some(owner.to_string()), Some(email.to_string()));
+        let repos = vec![
+            repo("github.com/acme", "jane@acme.com"),
+            repo("github.com/acme", "Jane@Acme.com"),
+            repo("github.com/acme", "jane@home.me"),
+            repo("github.com/jane", "jane@home.me"),
+            repo("gitlab.com/x", "a@x.com"),
+            repo("gitlab.com/x", "b@x.com"),
+            (None, Some("jane@home.me".to_string())),
+        ];
+        assert_eq!(
+            deviating_emails(&repos),
+            vec![None, None, Some("jane@acme.com".to_string()), None, None, None, None]
+        );
+    }
+
+    #[test]
+    fn test_remote_owner() {
+        assert_eq!(remote_owner("git@github.com:acme/api.git").as_deref(), Some("github.com/acme"));
+        assert_eq!(remote_owner("https://gitlab.com/group/sub/api.git").as_deref(), Some("gitlab.com/group"));
+        assert_eq!(remote_owner("/srv/git/api.git"), None);
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use metagit_core::models::Config;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::utils::icons;
+
+/// Editors `mgit workspace` can generate project files for
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Editor {
+    /// VS Code multi-root workspace (`<dir>.code-workspace`)
+    Vscode,
+    /// IntelliJ IDEA project VCS mappings (`.idea/vcs.xml`)
+    Idea,
+}
+
+pub fn workspace_command(editor: Editor) -> Result<()> {
+    let config = Config::load_from_project()?;
+    let path = workspace_file_path(&config, editor);
+
+    write_workspace_file(&config, editor, &path)?;
+
+    println!(
+        "{} Wrote {} ({} repositories)",
+        icons::status::success(),
+        path.display().to_string().cyan(),
+        config.repositories.len()
+    );
+    println!(
+        "\n{} {} keeps it in sync as repositories are added or removed.",
+        icons::status::info(),
+        "mgit refresh".cyan()
+    );
+
+    Ok(())
+}
+
+/// Regenerate the editor workspace files that already exist, returning the ones that changed
+pub fn update_workspace_files(config: &Config) -> Result<Vec<PathBuf>> {
+    let mut updated = Vec::new();
+    for editor in [Editor::Vscode, Editor::Idea] {
+        let path = workspace_file_path(config, editor);
+        if path.exists() && write_workspace_file(config, editor, &path)? {
+            updated.push(path);
+        }
+    }
+    Ok(updated)
+}
+
+fn workspace_file_path(config: &Config, editor: Editor) -> PathBuf {
+    let root = config.config_dir.clone().unwrap_or_default();
+    match editor {
+        Editor::Vscode => {
+            let root_name = fs::canonicalize(&root)
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| "mgit".to_string());
+            root.join(format!("{}.code-workspace", root_name))
+        }
+        Editor::Idea => root.join(".idea").join("vcs.xml"),
+    }
+}
+
+/// Write the workspace file, returning whether its content changed
+fn write_workspace_file(config: &Config, editor: Editor, path: &Path) -> Result<bool> {
+    let existing = fs::read_to_string(path).ok();
+    let content = match editor {
+        Editor::Vscode => vscode_workspace(config, existing.as_deref())?,
+        Editor::Idea => idea_vcs_mappings(config),
+    };
+    if existing.as_deref() == Some(content.as_str()) {
+        return Ok(false);
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+    Ok(true)
+}
+
+/// Build the `.code-workspace` JSON, keeping everything but `folders` from an existing file
+fn vscode_workspace(config: &Config, existing: Option<&str>) -> Result<String> {
+    let mut workspace = match existing {
+        Some(content) => serde_json::from_str::<Value>(content)
+            .ok()
+            .filter(|v| v.is_object())
+            .ok_or_else(|| anyhow!("Existing workspace file is not a JSON object; move it away to regenerate"))?,
+        None => json!({ "settings": {} }),
+    };
+
+    let folders: Vec<Value> = config
+        .repositories
+        .iter()
+        .map(|repo| json!({ "name": repo.name, "path": repo.path.as_deref().unwrap_or(&repo.name) }))
+        .collect();
+    workspace["folders"] = Value::Array(folders);
+
+    Ok(serde_json::to_string_pretty(&workspace)? + "\n")
+}
+
+/// Build `.idea/vcs.xml` mapping every repository as a Git root
+fn idea_vcs_mappings(config: &Config) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<project version=\"4\">\n  <component name=\"VcsDirectoryMappings\">\n",
+    );
+    for repo in &config.repositories {
+        let path = repo.path.as_deref().unwrap_or(&repo.name);
+        let directory = if Path::new(path).is_absolute() {
+            path.to_string()
+        } else {
+            format!("$PROJECT_DIR$/{}", path)
+        };
+        xml.push_str(&format!(
+            "    <mapping directory=\"{}\" vcs=\"Git\" />\n",
+            escape_xml(&directory)
+        ));
+    }
+    xml.push_str("  </component>\n</project>\n");
+    xml
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
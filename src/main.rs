@@ -1,35 +1,194 @@
 mod commands;
-mod db;
-mod models;
 mod utils;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::Path;
 
 use commands::*;
+use metagit_core::models::{Config, ForgeKind, IconConfig, TimestampFormat};
+use utils::{LogLevel, OutputFormat};
 
 #[derive(Parser)]
-#[command(name = "mgit")]
+#[command(name = "mgit", version)]
 #[command(about = "MetaGit - Enhanced git for multiple repositories", long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Number of repositories processed in parallel by pull, push and sync
+    /// (defaults to the `concurrency.jobs` config value, or the number of CPUs)
+    #[arg(short, long, global = true, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Include archived repositories in status, pull, push, sync and refresh
+    #[arg(long, global = true)]
+    all_repos: bool,
+
+    /// Configuration file to use instead of searching for .mgitconfig.yaml (defaults to $MGIT_CONFIG)
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
+    /// State database to use instead of the workspace's (defaults to $MGIT_DB)
+    #[arg(long, global = true, value_name = "PATH")]
+    db: Option<std::path::PathBuf>,
+
+    /// Configuration profile to apply (see `profiles` in .mgitconfig.yaml; defaults to $MGIT_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Log level: error, warn, info, debug or trace (in --log-file: defaults to debug;
+    /// without it, logs are printed to stderr)
+    #[arg(long, global = true, value_enum, value_name = "LEVEL")]
+    log_level: Option<LogLevel>,
+
+    /// Append the log of network operations (authentication, fetch/push details) to this file
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<std::path::PathBuf>,
+
+    /// Print plain ASCII instead of emoji and Unicode symbols (also the `icons.ascii` config value)
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Stream output as it comes: task steps and hooks write to the terminal directly instead of
+    /// line by line with the repository in front, and pull, push and sync don't draw the live display
+    #[arg(long, global = true)]
+    no_buffer: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize .mgitconfig.yaml by scanning current directory
-    Init,
+    Init {
+        /// Import repositories from another tool's configuration (mr, gita, meta, repo, vcs or gitman) instead of scanning
+        #[arg(long, num_args = 2, value_names = ["TOOL", "FILE"])]
+        import: Option<Vec<String>>,
+
+        /// Workspace URL template (e.g., "git@github.com:myorg/{name}.git"); URLs that follow it are left out
+        #[arg(long)]
+        url_template: Option<String>,
+    },
 
     /// Show status of all repositories
     Status {
         /// Show all branches (not just current branch)
         #[arg(short, long)]
         all: bool,
+
+        /// Nest repositories under their (sub)groups
+        #[arg(long)]
+        tree: bool,
+
+        /// Timestamp format (defaults to the `timestamps` config value, or relative)
+        #[arg(long, value_enum)]
+        timestamps: Option<TimestampFormat>,
+
+        /// Refresh repositories whose cached statistics are older than this (e.g., 12h, 3d)
+        #[arg(long, value_name = "AGE")]
+        max_age: Option<String>,
+
+        /// Stable tab-separated output for scripts (see README)
+        #[arg(long, conflicts_with = "tree")]
+        porcelain: bool,
+
+        /// Keep redrawing the status until interrupted
+        #[arg(long, conflicts_with = "porcelain")]
+        watch: bool,
+
+        /// Time between redraws with --watch (e.g., 10s, 5m)
+        #[arg(long, value_name = "DURATION", default_value = "30s", requires = "watch")]
+        interval: String,
+    },
+
+    /// Exit non-zero if any repository is dirty, behind, ahead or on an unexpected branch (for CI)
+    Check {
+        /// Check for uncommitted changes
+        #[arg(long)]
+        dirty: bool,
+
+        /// Check for commits on origin that are not pulled
+        #[arg(long)]
+        behind: bool,
+
+        /// Check for local commits that are not pushed
+        #[arg(long)]
+        ahead: bool,
+
+        /// Check that each repository is on its pinned `revision`
+        #[arg(long)]
+        branch: bool,
+
+        /// Expected branches: a saved tag, or a branch name for every repository (implies --branch)
+        #[arg(long, value_name = "TAG|BRANCH")]
+        expect: Option<String>,
+
+        /// Fetch from origin before comparing
+        #[arg(long)]
+        fetch: bool,
+    },
+
+    /// Check repositories for corruption (FETCH_HEAD, loose refs, git fsck); exits non-zero if any needs attention
+    Verify {
+        /// Remove corrupted FETCH_HEAD files and refs
+        #[arg(long)]
+        repair: bool,
+
+        /// Repositories to verify (default: all)
+        repos: Vec<String>,
+    },
+
+    /// Print a compact dirty/behind/ahead summary for shell prompts (reads only the state database)
+    Prompt,
+
+    /// Print a one-line workspace health overview (synced, behind, ahead, dirty, last refresh)
+    Summary {
+        /// Stable tab-separated output for scripts (see README)
+        #[arg(long)]
+        porcelain: bool,
+    },
+
+    /// List the configured repositories
+    Ls {
+        /// Stable tab-separated output for scripts (see README)
+        #[arg(long)]
+        porcelain: bool,
+    },
+
+    /// Rename a repository: its directory, its config entry and references, and its cached state
+    Mv {
+        /// Current name of the repository
+        old: String,
+        /// New name (and directory, unless the repository sets `path`)
+        new: String,
     },
 
     /// Pull all repositories
     Pull {
+        /// Pick the repositories to pull from a fuzzy-searchable list
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Stash local changes before updating and reapply them afterwards
+        #[arg(long, conflicts_with = "no_autostash")]
+        autostash: bool,
+
+        /// Don't stash local changes, even if `autostash` is configured
+        #[arg(long)]
+        no_autostash: bool,
+
+        /// Remove remote-tracking branches of branches deleted on origin
+        #[arg(long, conflicts_with = "no_prune")]
+        prune: bool,
+
+        /// Don't prune, even if `prune` is configured
+        #[arg(long)]
+        no_prune: bool,
+
+        /// Output format: human-readable text, or one JSON event per line for tools
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
         /// Enable debug output for troubleshooting connection/credential issues
         #[arg(long)]
         debug: bool,
@@ -37,6 +196,22 @@ enum Commands {
 
     /// Push all repositories
     Push {
+        /// Pick the repositories to push from a fuzzy-searchable list
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Push all local tags instead of the current branch
+        #[arg(long, conflicts_with = "with_tags")]
+        tags: bool,
+
+        /// Push all local tags along with the current branch
+        #[arg(long)]
+        with_tags: bool,
+
+        /// Output format: human-readable text, or one JSON event per line for tools
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
         /// Enable debug output for troubleshooting connection/credential issues
         #[arg(long)]
         debug: bool,
@@ -44,31 +219,335 @@ enum Commands {
 
     /// Sync (pull & push) all repositories
     Sync {
+        /// Pick the repositories to sync from a fuzzy-searchable list
+        #[arg(short, long)]
+        interactive: bool,
+
+        /// Fetch and show what sync would do, then ask before applying it
+        #[arg(long, conflicts_with = "apply")]
+        plan: bool,
+
+        /// Fetch, show the plan and apply it without asking
+        #[arg(long)]
+        apply: bool,
+
+        /// Output format: human-readable text, or one JSON event per line for tools
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+
         /// Enable debug output for troubleshooting connection/credential issues
         #[arg(long)]
         debug: bool,
     },
 
     /// Refresh repository states and collect commit statistics
-    Refresh,
+    Refresh {
+        /// Timestamp format (defaults to the `timestamps` config value, or relative)
+        #[arg(long, value_enum)]
+        timestamps: Option<TimestampFormat>,
+
+        /// Also count lines added/removed per author (slower: diffs every commit)
+        #[arg(long)]
+        lines: bool,
+
+        /// Only count commits made since this date, timestamp or age (e.g., 2025-01-01, 90d)
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+
+        /// Only count commits made before this date, timestamp or age
+        #[arg(long, value_name = "TIME")]
+        until: Option<String>,
+
+        /// Remove the cached states of repositories that are no longer in .mgitconfig.yaml
+        #[arg(long)]
+        prune_db: bool,
+
+        /// Output format: human-readable text, or one JSON event per line for tools
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
+
+    /// Browse the log of pull/push/sync/restore/run operations (most recent first)
+    LogOps {
+        /// Maximum number of operations to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+
+        /// Only show operations of this command (e.g., pull, restore, run)
+        #[arg(long)]
+        command: Option<String>,
+
+        /// Only show operations involving this repository
+        #[arg(long)]
+        repo: Option<String>,
+
+        /// Only show operations with errors
+        #[arg(long)]
+        failed: bool,
+
+        /// Timestamp format (defaults to the `timestamps` config value, or relative)
+        #[arg(long, value_enum)]
+        timestamps: Option<TimestampFormat>,
+    },
+
+    /// Export or import the state database (cached states, operation log) as portable JSON
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
 
     /// Save current branches to a tag
     Save {
         /// Name of the tag to save branches to
         tag: String,
+
+        /// Description shown in 'mgit tag list' (e.g., what the tag is for)
+        #[arg(short, long)]
+        description: Option<String>,
     },
 
     /// Restore branches from a saved tag (use 'master' or 'main' to switch to default branch)
     Restore {
         /// Name of the tag to restore branches from
-        tag: String,
+        #[arg(required_unless_present_any = ["pinned", "resume", "interactive"])]
+        tag: Option<String>,
+
+        /// Pick the saved tag from a fuzzy-searchable list
+        #[arg(short, long, conflicts_with_all = ["tag", "pinned", "resume"])]
+        interactive: bool,
+
+        /// Check out each repository's pinned `revision` instead of a saved tag
+        #[arg(long, conflicts_with = "tag")]
+        pinned: bool,
+
+        /// Retry only the repositories the last restore failed on
+        #[arg(long = "continue", conflicts_with_all = ["tag", "pinned"])]
+        resume: bool,
+
+        /// Fetch branches that don't exist locally from origin before switching
+        #[arg(long)]
+        fetch: bool,
+
+        /// Stash uncommitted changes before switching and reapply them afterwards
+        #[arg(long, conflicts_with = "force")]
+        stash: bool,
+
+        /// Switch even if it discards uncommitted changes
+        #[arg(long)]
+        force: bool,
+
+        /// Don't run the repositories' on_checkout hooks after switching
+        #[arg(long)]
+        no_hooks: bool,
+    },
+
+    /// Revert the repositories changed by the last pull or restore to their previous branch and commit
+    Undo {
+        /// Also revert repositories with uncommitted changes (discarding them) or new commits
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Inspect saved tags
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+
+    /// Create an annotated git tag at HEAD in every repository and record it as a saved tag
+    Release {
+        /// Version / tag name (e.g., v1.2.0)
+        version: String,
+
+        /// Tag message (defaults to "Release <version>")
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Push the new tag to origin
+        #[arg(long)]
+        push: bool,
+
+        /// Enable debug output for troubleshooting connection/credential issues
+        #[arg(long)]
+        debug: bool,
+    },
+
+    /// Apply a patch (unified diff) to every repository, optionally committing it
+    Apply {
+        /// Patch file (e.g., created with git diff or git format-patch)
+        patch: String,
+
+        /// Only apply to these repositories (can be repeated)
+        #[arg(short, long = "repo", value_name = "NAME")]
+        repos: Vec<String>,
+
+        /// Only check whether the patch applies, without changing anything
+        #[arg(long)]
+        check: bool,
+
+        /// Fall back to a 3-way merge when the patch does not apply cleanly
+        #[arg(long = "3way")]
+        three_way: bool,
+
+        /// Commit the applied changes with this message
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+
+    /// Bundle a snapshot of all repositories into a single archive (.tar.gz or .tar)
+    Archive {
+        /// Output archive file (e.g., workspace.tar.gz)
+        output: String,
+
+        /// Archive the branches recorded in a saved tag instead of the current branches
+        #[arg(long)]
+        tag: Option<String>,
+    },
+
+    /// Create or apply git bundles for offline transfer between workspaces
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+
+    /// Generate a Markdown changelog of the commits between two saved tags (or a tag and HEAD)
+    Changelog {
+        /// Starting tag (commits already in this tag are excluded)
+        from: String,
+
+        /// Ending tag (defaults to the current HEAD of each repository)
+        to: Option<String>,
+
+        /// Write the changelog to a file instead of printing it
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// List old branches that are merged or have no upstream, with their owners
+    Stale {
+        /// Age of the last commit after which a branch is stale (defaults to `stale_after`, or 90d)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+    },
+
+    /// Delete stale branches, chosen interactively or all at once with --yes
+    Cleanup {
+        /// Age of the last commit after which a branch is stale (defaults to `stale_after`, or 90d)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        /// Delete all stale branches without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Also delete the branches on origin
+        #[arg(long)]
+        remote: bool,
+
+        /// Also delete branches that are not merged into master/main (their commits are lost)
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Report branch and commit ownership per repository and group (from refreshed data)
+    Owners {
+        /// Report format
+        #[arg(long, value_enum, default_value = "md")]
+        format: OwnersFormat,
+
+        /// Write the report to a file instead of printing it
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Find which repositories have a commit and which local/remote branches contain it
+    Contains {
+        /// Commit SHA (full or abbreviated)
+        sha: String,
+    },
+
+    /// Manage SSH passphrases and HTTPS tokens stored in the OS keychain
+    Credential {
+        #[command(subcommand)]
+        action: CredentialAction,
+    },
+
+    /// Sign in to GitHub or GitLab in the browser and store the token in the OS keychain
+    Login {
+        /// Hosting provider
+        provider: ForgeKind,
+
+        /// Host of a self-hosted instance (default: github.com / gitlab.com)
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Client ID of the OAuth app to sign in with (default: oauth_client_ids in the config)
+        #[arg(long)]
+        client_id: Option<String>,
+    },
+
+    /// Show the identity (user.name/user.email) each repository commits as
+    Whoami,
+
+    /// Set the configured commit identity in the repositories
+    Identity {
+        #[command(subcommand)]
+        action: IdentityAction,
+    },
+
+    /// Edit the origin remotes of all repositories
+    Remote {
+        #[command(subcommand)]
+        action: RemoteAction,
+    },
+
+    /// Initialize, update and check the submodules of all repositories
+    Submodule {
+        #[command(subcommand)]
+        action: SubmoduleAction,
+    },
+
+    /// Pull requests (GitLab: merge requests) across repositories
+    Pr {
+        #[command(subcommand)]
+        action: PrAction,
+    },
+
+    /// Encrypt credentials and repository tokens in .mgitconfig.yaml with age
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+
+    /// Distribute the git hooks declared in .mgitconfig.yaml to every repository
+    Hooks {
+        #[command(subcommand)]
+        action: HooksAction,
+    },
+
+    /// Generate an editor workspace listing all configured repositories (kept in sync by refresh)
+    Workspace {
+        /// Editor to generate the workspace for
+        #[arg(value_enum)]
+        editor: Editor,
+    },
+
+    /// Generate man pages (prints mgit(1) to stdout, or writes all pages to a directory)
+    Man {
+        /// Directory to write mgit.1 and one page per subcommand into
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
     /// Run a task defined in .mgitconfig.yaml (run without task name to list available tasks)
     Run {
-        /// Name of the task to run (optional - omit to list all tasks)
+        /// Name of the task to run, an alias, or a namespace like build:* (optional - omit to list all tasks)
         task_name: Option<String>,
 
+        /// Pick the task from a fuzzy-searchable list
+        #[arg(short, long, conflicts_with = "task_name")]
+        interactive: bool,
+
         /// Show detailed task information
         #[arg(short, long)]
         detailed: bool,
@@ -76,23 +555,467 @@ enum Commands {
         /// Define variables for substitution (e.g., -DVAR1=value1 -DVAR2=value2)
         #[arg(short = 'D', value_name = "VAR=VALUE")]
         defines: Vec<String>,
+
+        /// Run the task again whenever files in its repositories change (Ctrl-C to quit)
+        #[arg(short, long)]
+        watch: bool,
+    },
+
+    /// Show the variables a task would see, with their sources and substituted values
+    Env {
+        /// Only show these variables
+        names: Vec<String>,
+
+        /// Define variables as for `mgit run` (e.g., -DVAR1=value1 -DVAR2=value2)
+        #[arg(short = 'D', value_name = "VAR=VALUE")]
+        defines: Vec<String>,
+    },
+
+    /// Any other command runs the `mgit-<command>` executable from PATH
+    #[command(external_subcommand)]
+    External(Vec<OsString>),
+}
+
+#[derive(Subcommand)]
+enum BundleAction {
+    /// Write a bundle with all branches and tags of every repository into a directory
+    Create {
+        /// Directory where <repo>.bundle files are written
+        dir: String,
+    },
+
+    /// Import bundles from a directory and fast-forward the current branches
+    Apply {
+        /// Directory containing <repo>.bundle files
+        dir: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    /// List saved tags with when, by whom and why they were saved
+    List,
+
+    /// Show per repository the branch change and the commits between two saved tags
+    Diff {
+        /// Saved tag to compare from (or 'main'/'master')
+        from: String,
+
+        /// Saved tag to compare to (or 'main'/'master')
+        to: String,
+
+        /// Maximum number of commit subjects listed per repository
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum HooksAction {
+    /// Symlink (or copy) the configured hook scripts into each repository's hooks directory
+    Install {
+        /// Copy the scripts instead of symlinking them (always done on Windows)
+        #[arg(long)]
+        copy: bool,
+
+        /// Replace existing hooks that differ from the configured ones
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CredentialAction {
+    /// Store a secret for a host (prompts for the value)
+    Set {
+        /// Hostname the secret belongs to (e.g., github.com)
+        host: String,
+
+        /// Store an HTTPS access token
+        #[arg(long)]
+        token: bool,
+
+        /// Store the passphrase of the host's SSH key
+        #[arg(long)]
+        passphrase: bool,
+    },
+
+    /// Remove a stored secret for a host
+    Delete {
+        /// Hostname the secret belongs to (e.g., github.com)
+        host: String,
+
+        /// Remove the HTTPS access token
+        #[arg(long)]
+        token: bool,
+
+        /// Remove the SSH key passphrase
+        #[arg(long)]
+        passphrase: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbAction {
+    /// Write the state database to a JSON file (e.g., before moving to another machine)
+    Export {
+        /// File to write
+        file: std::path::PathBuf,
+    },
+
+    /// Merge a file written by `mgit db export` into the state database
+    Import {
+        /// File to read
+        file: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretsAction {
+    /// Move the credentials and repository tokens into `encrypted_secrets`
+    Encrypt {
+        /// Encrypt with a passphrase (prompted) instead of the configured age identity
+        #[arg(long)]
+        passphrase: bool,
+    },
+
+    /// Write the encrypted secrets back to the configuration in plain text
+    Decrypt,
+}
+
+#[derive(Subcommand)]
+enum RemoteAction {
+    /// Rewrite the origin URLs in .mgitconfig.yaml and in the repositories' git remotes
+    SetUrl {
+        /// Text to replace in the URLs (e.g., an old organization name)
+        #[arg(requires = "to")]
+        from: Option<String>,
+
+        /// Replacement text
+        to: Option<String>,
+
+        /// Switch HTTPS URLs to SSH (git@host:path)
+        #[arg(long, conflicts_with = "https")]
+        ssh: bool,
+
+        /// Switch SSH URLs to HTTPS
+        #[arg(long)]
+        https: bool,
+
+        /// Only change these repositories (can be repeated)
+        #[arg(short, long = "repo", value_name = "NAME")]
+        repos: Vec<String>,
+
+        /// Show the new URLs without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum IdentityAction {
+    /// Write the configured `identity` into each repository's local user.name/user.email
+    Apply {
+        /// Only show which repositories would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SubmoduleAction {
+    /// Initialize and update submodules recursively to the commits their repositories record
+    Update {
+        /// Enable debug output for troubleshooting connection/credential issues
+        #[arg(long)]
+        debug: bool,
+    },
+
+    /// List submodules that are not initialized or out of date
+    Status,
+}
+
+#[derive(Subcommand)]
+enum PrAction {
+    /// Open a pull request for every repository whose current branch is ahead of its base branch
+    Create {
+        /// Title template ($(REPO), $(BRANCH) and $(BASE) are set per repository)
+        #[arg(short, long)]
+        title: String,
+
+        /// Description template
+        #[arg(short, long, conflicts_with = "body_file")]
+        body: Option<String>,
+
+        /// Read the description template from a file
+        #[arg(long, value_name = "PATH")]
+        body_file: Option<String>,
+
+        /// Target branch (default: origin's default branch)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Open draft pull requests
+        #[arg(long)]
+        draft: bool,
+    },
+}
+
+/// Whether the command was given `--debug`
+fn debug_requested(command: &Commands) -> bool {
+    match command {
+        Commands::Pull { debug, .. }
+        | Commands::Push { debug, .. }
+        | Commands::Sync { debug, .. }
+        | Commands::Release { debug, .. }
+        | Commands::Submodule { action: SubmoduleAction::Update { debug } } => *debug,
+        _ => false,
+    }
+}
+
+/// Pass `--config`, `--profile` and `--db` on through the environment: every config and state
+/// database load uses the config file, profile and database found there
+fn export_global_options(cli: &Cli) {
+    if let Some(config) = &cli.config {
+        std::env::set_var("MGIT_CONFIG", config);
+    }
+    if let Some(profile) = &cli.profile {
+        std::env::set_var("MGIT_PROFILE", profile);
+    }
+    if let Some(db) = &cli.db {
+        std::env::set_var("MGIT_DB", db);
+    }
+}
+
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // Log messages are grouped under the command, and under the repository inside the core
+    utils::init_logging(cli.log_level, cli.log_file.as_deref(), debug_requested(&cli.command))?;
+    let _command_span = tracing::info_span!("mgit", command = matches.subcommand_name().unwrap_or_default()).entered();
+
+    export_global_options(&cli);
+
+    utils::set_streaming(cli.no_buffer);
+    // Ctrl-C stops the long-running commands gracefully; the others are simply ended
+    if matches!(cli.command, Commands::Pull { .. } | Commands::Push { .. } | Commands::Sync { .. } | Commands::Run { .. }) {
+        utils::install_interrupt_handler()?;
+    }
+
+    // Ask for HTTPS credentials on the terminal when nothing else provides them
+    metagit_core::git::set_credential_prompt(utils::prompt_credentials);
+
+    // The prompt summary must stay fast: no config loading at all
+    if let Commands::Prompt = cli.command {
+        if cli.ascii {
+            utils::icons::configure(IconConfig { ascii: Some(true), ..Default::default() });
+        }
+        return prompt_command();
+    }
+
+    // Move the legacy global config and state database to their XDG locations
+    let workspace_dir = Config::find_project_config().and_then(|path| path.parent().map(Path::to_path_buf));
+    for note in metagit_core::paths::migrate_legacy_files(workspace_dir.as_deref()) {
+        eprintln!("{} {}", utils::icons::status::info(), note);
+    }
+
+    // Apply the icon settings from the workspace (or global) config before any output
+    let mut icon_config = Config::load_icons().unwrap_or_default();
+    if cli.ascii {
+        icon_config.ascii = Some(true);
+    }
+    utils::icons::configure(icon_config);
 
     match cli.command {
-        Commands::Init => init_command()?,
-        Commands::Status { all } => status_command(all)?,
-        Commands::Pull { debug } => pull_command(debug)?,
-        Commands::Push { debug } => push_command(debug)?,
-        Commands::Sync { debug } => sync_command(debug)?,
-        Commands::Refresh => refresh_command()?,
-        Commands::Save { tag } => save_command(&tag)?,
-        Commands::Restore { tag } => restore_command(&tag)?,
-        Commands::Run { task_name, detailed, defines } => run_command(task_name.as_deref(), detailed, defines)?,
+        Commands::Init { import, url_template } => {
+            let import = import.as_ref().map(|args| (args[0].as_str(), Path::new(&args[1])));
+            init_command(import, url_template)?
+        }
+        Commands::Status { all, tree, timestamps, max_age, porcelain, watch, interval } => {
+            if watch {
+                status_watch_command(all, tree, timestamps, max_age.as_deref(), cli.all_repos, &interval)?
+            } else {
+                status_command(all, tree, timestamps, max_age.as_deref(), porcelain, cli.all_repos)?
+            }
+        }
+        Commands::Check { dirty, behind, ahead, branch, expect, fetch } => {
+            check_command(CheckCriteria { dirty, behind, ahead, branch }, expect.as_deref(), fetch, cli.all_repos)?
+        }
+        Commands::Verify { repair, repos } => verify_command(&repos, repair, cli.all_repos)?,
+        Commands::Prompt => prompt_command()?,
+        Commands::Summary { porcelain } => summary_command(porcelain, cli.all_repos)?,
+        Commands::Ls { porcelain } => ls_command(porcelain, cli.all_repos)?,
+        Commands::Mv { old, new } => mv_command(&old, &new)?,
+        Commands::Pull { interactive, autostash, no_autostash, prune, no_prune, format, debug } => {
+            if format == OutputFormat::Jsonl {
+                utils::enable_events("pull");
+            }
+            let autostash = (autostash || no_autostash).then_some(autostash);
+            let prune = (prune || no_prune).then_some(prune);
+            pull_command(cli.jobs, cli.all_repos, interactive, autostash, prune, debug)?
+        }
+        Commands::Push { interactive, tags, with_tags, format, debug } => {
+            if format == OutputFormat::Jsonl {
+                utils::enable_events("push");
+            }
+            let refs = match (tags, with_tags) {
+                (true, _) => PushRefs::Tags,
+                (_, true) => PushRefs::BranchAndTags,
+                _ => PushRefs::Branch,
+            };
+            push_command(cli.jobs, cli.all_repos, interactive, refs, debug)?
+        }
+        Commands::Sync { interactive, plan, apply, format, debug } => {
+            if format == OutputFormat::Jsonl {
+                utils::enable_events("sync");
+            }
+            sync_command(cli.jobs, cli.all_repos, interactive, plan, apply, debug)?
+        }
+        Commands::Refresh { timestamps, lines, since, until, prune_db, format } => {
+            if format == OutputFormat::Jsonl {
+                utils::enable_events("refresh");
+            }
+            refresh_command(timestamps, lines, since.as_deref(), until.as_deref(), cli.all_repos, prune_db)?
+        }
+        Commands::LogOps { limit, command, repo, failed, timestamps } => {
+            log_ops_command(limit, command.as_deref(), repo.as_deref(), failed, timestamps)?
+        }
+        Commands::Db { action } => match action {
+            DbAction::Export { file } => db_export_command(&file)?,
+            DbAction::Import { file } => db_import_command(&file)?,
+        },
+        Commands::Save { tag, description } => save_command(&tag, description.as_deref())?,
+        Commands::Restore { tag, pinned: _, interactive, resume, fetch, stash, force, no_hooks } => {
+            let source = match tag.as_deref() {
+                _ if resume => RestoreSource::Continue,
+                _ if interactive => RestoreSource::Pick,
+                Some(tag) => RestoreSource::Tag(tag),
+                None => RestoreSource::Pinned,
+            };
+            restore_command(source, fetch, stash, force, !no_hooks)?
+        }
+        Commands::Undo { force } => undo_command(force)?,
+        Commands::Tag { action } => match action {
+            TagAction::List => tag_list_command()?,
+            TagAction::Diff { from, to, limit } => tag_diff_command(&from, &to, limit)?,
+        },
+        Commands::Release { version, message, push, debug } => {
+            release_command(&version, message.as_deref(), push, debug)?
+        }
+        Commands::Apply { patch, repos, check, three_way, message } => {
+            apply_command(&patch, &repos, check, three_way, message.as_deref())?
+        }
+        Commands::Archive { output, tag } => archive_command(&output, tag.as_deref())?,
+        Commands::Bundle { action } => match action {
+            BundleAction::Create { dir } => bundle_create_command(&dir)?,
+            BundleAction::Apply { dir } => bundle_apply_command(&dir)?,
+        },
+        Commands::Changelog { from, to, output } => {
+            changelog_command(&from, to.as_deref(), output.as_deref())?
+        }
+        Commands::Stale { older_than } => stale_command(older_than.as_deref())?,
+        Commands::Cleanup { older_than, yes, remote, force } => {
+            cleanup_command(older_than.as_deref(), yes, remote, force)?
+        }
+        Commands::Owners { format, output } => owners_command(format, output.as_deref())?,
+        Commands::Contains { sha } => contains_command(&sha)?,
+        Commands::Whoami => whoami_command(cli.all_repos)?,
+        Commands::Identity { action } => match action {
+            IdentityAction::Apply { dry_run } => identity_apply_command(cli.all_repos, dry_run)?,
+        },
+        Commands::Remote { action } => match action {
+            RemoteAction::SetUrl { from, to, ssh, https, repos, dry_run } => {
+                let replace = from.as_deref().zip(to.as_deref());
+                let scheme = match (ssh, https) {
+                    (true, _) => Some(UrlScheme::Ssh),
+                    (_, true) => Some(UrlScheme::Https),
+                    _ => None,
+                };
+                remote_set_url_command(replace, scheme, &repos, dry_run)?
+            }
+        },
+        Commands::Submodule { action } => match action {
+            SubmoduleAction::Update { debug } => submodule_update_command(cli.jobs, cli.all_repos, debug)?,
+            SubmoduleAction::Status => submodule_status_command(cli.all_repos)?,
+        },
+        Commands::Pr { action } => match action {
+            PrAction::Create { title, body, body_file, base, draft } => {
+                pr_create_command(&title, body, body_file.as_deref(), base.as_deref(), draft, cli.all_repos)?
+            }
+        },
+        Commands::Secrets { action } => match action {
+            SecretsAction::Encrypt { passphrase } => secrets_encrypt_command(passphrase)?,
+            SecretsAction::Decrypt => secrets_decrypt_command()?,
+        },
+        Commands::Credential { action } => match action {
+            CredentialAction::Set { host, token, passphrase } => {
+                credential_set_command(&host, token, passphrase)?
+            }
+            CredentialAction::Delete { host, token, passphrase } => {
+                credential_delete_command(&host, token, passphrase)?
+            }
+        },
+        Commands::Login { provider, host, client_id } => {
+            login_command(provider, host.as_deref(), client_id.as_deref())?
+        }
+        Commands::Hooks { action } => match action {
+            HooksAction::Install { copy, force } => hooks_install_command(copy, force)?,
+        },
+        Commands::Workspace { editor } => workspace_command(editor)?,
+        Commands::Man { output } => man_command(Cli::command(), output.as_deref())?,
+        Commands::External(args) => plugin_command(args)?,
+        Commands::Run { task_name, interactive, detailed, defines, watch } => {
+            run_command(task_name.as_deref(), interactive, detailed, defines, watch)?
+        }
+        Commands::Env { names, defines } => env_command(names, defines)?,
     }
 
+    if utils::interrupted() {
+        std::process::exit(utils::INTERRUPTED_EXIT_CODE);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// The tests below set process-wide environment variables
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_profile_option_takes_precedence_over_environment() {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::set_var("MGIT_PROFILE", "oss");
+
+        export_global_options(&Cli::try_parse_from(["mgit", "status"]).unwrap());
+        assert_eq!(std::env::var("MGIT_PROFILE").unwrap(), "oss");
+        export_global_options(&Cli::try_parse_from(["mgit", "--profile", "work", "status"]).unwrap());
+        assert_eq!(std::env::var("MGIT_PROFILE").unwrap(), "work");
+        std::env::remove_var("MGIT_PROFILE");
+    }
+
+    #[test]
+    fn test_profile_reaches_config_loading() {
+        let _lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let path = std::env::temp_dir().join(format!("mgit-test-profile-{}.yaml", std::process::id()));
+        std::fs::write(
+            &path,
+            "repositories:\n  - { name: api, url: u }\n  - { name: web, url: u }\nprofiles:\n  work:\n    repositories: [api]\n",
+        )
+        .unwrap();
+
+        let cli = Cli::try_parse_from(["mgit", "--config", path.to_str().unwrap(), "status", "--profile", "work"]).unwrap();
+        export_global_options(&cli);
+        let config = Config::load_from_project();
+        std::fs::remove_file(&path).ok();
+        std::env::remove_var("MGIT_CONFIG");
+        std::env::remove_var("MGIT_PROFILE");
+
+        let config = config.unwrap();
+        assert_eq!(config.active_profile.as_ref().map(|(name, _)| name.as_str()), Some("work"));
+        assert_eq!(config.repositories.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["api"]);
+    }
+}
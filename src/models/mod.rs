@@ -1,5 +0,0 @@
-pub mod config;
-pub mod repo_state;
-
-pub use config::*;
-pub use repo_state::*;
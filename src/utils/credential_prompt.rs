@@ -0,0 +1,31 @@
+use colored::*;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use crate::utils::suspend_progress;
+
+/// Ask on the terminal for the user name and password of an HTTPS remote
+/// The user name is skipped when the URL has one; the password is read without echo.
+/// Returns None when not on a terminal or when the user enters nothing.
+pub fn prompt_credentials(url: &str, username: Option<&str>) -> Option<(String, String)> {
+    if !io::stdin().is_terminal() {
+        return None;
+    }
+    suspend_progress(|| {
+        println!("{} {}", "Authentication required for".yellow(), url.cyan());
+        let user = match username {
+            Some(user) => user.to_string(),
+            None => {
+                print!("Username: ");
+                io::stdout().flush().ok()?;
+                let mut line = String::new();
+                io::stdin().lock().read_line(&mut line).ok()?;
+                line.trim().to_string()
+            }
+        };
+        if user.is_empty() {
+            return None;
+        }
+        let password = rpassword::prompt_password(format!("Password for {}: ", user)).ok()?;
+        (!password.is_empty()).then_some((user, password))
+    })
+}
@@ -0,0 +1,140 @@
+//! Structured events of bulk operations for wrappers and editor plugins (`--format jsonl`)
+//! Every event is one JSON object per line on stdout; the human-readable output is left out.
+//! The fields of each event are documented in the README and only ever grow.
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use metagit_core::git::{set_progress_handler, TransferProgress};
+use metagit_core::models::{set_outcome_handler, RepoOutcome};
+use crate::utils::porcelain_time;
+
+/// Output formats of pull/push/sync/refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable lines with live spinners
+    Text,
+    /// One JSON event per line (started, progress, finished, error)
+    Jsonl,
+}
+
+/// One line of the event stream
+#[derive(Serialize)]
+struct Event<'a> {
+    event: &'a str,
+    command: &'a str,
+    repo: &'a str,
+    time: String,
+    #[serde(flatten)]
+    fields: Value,
+}
+
+/// Set once by [`enable_events`]
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Command named in every event
+static COMMAND: OnceLock<&'static str> = OnceLock::new();
+
+thread_local! {
+    /// Repository the worker thread is running, with the last progress percentage reported
+    static CURRENT_REPO: RefCell<Option<(String, Option<usize>)>> = const { RefCell::new(None) };
+}
+
+/// Print `println!` output only when events are not enabled
+macro_rules! human_println {
+    ($($arg:tt)*) => {
+        if !$crate::utils::events_enabled() {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use human_println;
+
+/// Switch to JSON events for `command`: outcomes and transfer progress are reported as they happen
+pub fn enable_events(command: &'static str) {
+    let _ = COMMAND.set(command);
+    ENABLED.store(true, Ordering::Relaxed);
+    set_outcome_handler(emit_outcome);
+    set_progress_handler(emit_progress);
+}
+
+pub fn events_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Write one event line: `event`, `command`, `repo` and `time`, followed by `fields`
+/// Does nothing unless events are enabled.
+pub fn emit_event(event: &str, repo: &str, fields: Value) {
+    if !events_enabled() {
+        return;
+    }
+    let event = Event {
+        event,
+        command: COMMAND.get().copied().unwrap_or_default(),
+        repo,
+        time: porcelain_time(Utc::now()),
+        fields,
+    };
+    // One write per line so events of parallel repositories never interleave
+    let mut out = io::stdout().lock();
+    let _ = writeln!(out, "{}", serde_json::to_string(&event).unwrap_or_default());
+    let _ = out.flush();
+}
+
+/// "started" event; later progress events of this thread belong to `repo`
+pub fn emit_started(repo: &str) {
+    CURRENT_REPO.with(|current| *current.borrow_mut() = Some((repo.to_string(), None)));
+    emit_event("started", repo, json!({}));
+}
+
+/// Repository the progress events of this thread belong to
+pub fn progress_repo() -> Option<String> {
+    CURRENT_REPO.with(|current| current.borrow().as_ref().map(|(repo, _)| repo.clone()))
+}
+
+/// Report the progress events of this thread for `repo` (e.g., a thread running one of its operations)
+pub fn set_progress_repo(repo: Option<String>) {
+    CURRENT_REPO.with(|current| *current.borrow_mut() = repo.map(|repo| (repo, None)));
+}
+
+/// "finished" or "error" event of a recorded outcome
+fn emit_outcome(outcome: &RepoOutcome) {
+    emit_event(
+        if outcome.success { "finished" } else { "error" },
+        &outcome.name,
+        json!({ "message": outcome.message, "duration_ms": outcome.duration_ms }),
+    );
+}
+
+/// "progress" event, at most one per percent of the objects transferred
+fn emit_progress(progress: TransferProgress) {
+    if progress.total_objects == 0 {
+        return;
+    }
+    let percent = progress.objects * 100 / progress.total_objects;
+    let repo = CURRENT_REPO.with(|current| {
+        let mut current = current.borrow_mut();
+        let (repo, last) = current.as_mut()?;
+        (*last != Some(percent)).then(|| {
+            *last = Some(percent);
+            repo.clone()
+        })
+    });
+    if let Some(repo) = repo {
+        emit_event(
+            "progress",
+            &repo,
+            json!({
+                "objects": progress.objects,
+                "total_objects": progress.total_objects,
+                "bytes": progress.bytes,
+                "percent": percent,
+            }),
+        );
+    }
+}
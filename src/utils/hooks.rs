@@ -0,0 +1,38 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+use metagit_core::models::{Config, Repository};
+
+use crate::utils::{execute_script, RepoOutput, ScriptType, VarContext};
+
+/// Run a repository's `on_checkout` hooks in order after its branch was switched to `branch`
+/// Hook output is printed with the repository's name in front; the first failing hook stops the rest.
+pub fn run_checkout_hooks(config: &Config, repo: &Repository, repo_path: &Path, branch: &str) -> Result<()> {
+    if repo.on_checkout.is_empty() {
+        return Ok(());
+    }
+
+    let project_dir = config.config_dir.clone().unwrap_or_else(|| repo_path.to_path_buf());
+    let defines = vec![format!("REPO={}", repo.name), format!("BRANCH={}", branch)];
+    let ctx = VarContext::new(&project_dir, &config.env_file_paths(), &config.variables, defines)?;
+    let shells = config.shells.with_overrides(repo.shells.as_ref());
+    let output = RepoOutput::new(&repo.name);
+
+    for hook in &repo.on_checkout {
+        let command = ctx.substitute(hook)?;
+        let script_type = if cfg!(windows) { ScriptType::Batch } else { ScriptType::Shell };
+        let status = execute_script(script_type, &command, &[], repo_path, &shells, false)
+            .and_then(|child| Ok(output.wait(child)?))
+            .map_err(|e| anyhow!("on_checkout hook '{}' could not run: {}", command, e))?;
+        if !status.success() {
+            return Err(anyhow!(
+                "on_checkout hook '{}' failed (exit code: {})",
+                command,
+                status.code().unwrap_or(-1)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
@@ -1,157 +1,153 @@
+use metagit_core::models::IconConfig;
 use std::env;
+use std::sync::OnceLock;
 
-/// Check if Nerd Fonts should be used based on environment variable
+/// Icon settings from the `icons` config section, set once at startup
+static CONFIG: OnceLock<IconConfig> = OnceLock::new();
+
+/// Apply the `icons` config section (call once, before any output)
+pub fn configure(config: IconConfig) {
+    let _ = CONFIG.set(config);
+}
+
+/// Check if Nerd Fonts should be used
+/// The NERD_FONT/USE_NERD_FONT environment variables take precedence over `icons.nerd_font`
 pub fn use_nerd_fonts() -> bool {
-    env::var("NERD_FONT").unwrap_or_default() == "1"
-        || env::var("USE_NERD_FONT").unwrap_or_default() == "1"
+    let from_env = ["NERD_FONT", "USE_NERD_FONT"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .collect::<Vec<_>>();
+    if !from_env.is_empty() {
+        return from_env.iter().any(|value| value == "1");
+    }
+    CONFIG.get().and_then(|c| c.nerd_font).unwrap_or(false)
+}
+
+/// Check if output should be plain ASCII
+/// `--ascii` or `icons.ascii`; without either, dumb terminals (TERM=dumb) get ASCII
+pub fn use_ascii() -> bool {
+    CONFIG
+        .get()
+        .and_then(|c| c.ascii)
+        .unwrap_or_else(|| env::var("TERM").is_ok_and(|term| term == "dumb"))
+}
+
+/// A symbol used in running text (arrows, bullets, ...), or its replacement in ASCII mode
+pub fn symbol(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if use_ascii() {
+        ascii
+    } else {
+        unicode
+    }
+}
+
+/// Pick an icon: per-icon override from the config, else the ASCII, Nerd Font or Unicode glyph
+/// In ASCII mode, overrides that aren't ASCII are ignored.
+fn icon(name: &str, nerd_font: char, unicode: &str, ascii: &str) -> String {
+    let ascii_only = use_ascii();
+    let overridden = CONFIG.get().and_then(|c| c.overrides.get(name));
+    if let Some(glyph) = overridden.filter(|glyph| !ascii_only || glyph.is_ascii()) {
+        return glyph.clone();
+    }
+    if ascii_only {
+        ascii.to_string()
+    } else if use_nerd_fonts() {
+        nerd_font.to_string()
+    } else {
+        unicode.to_string()
+    }
 }
 
 /// Git-related icons
 pub mod git {
-    use super::use_nerd_fonts;
+    use super::icon;
 
     pub fn branch() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-dev-git_branch (U+E0A0)
-            '\u{e0a0}'.to_string()
-        } else {
-            "⎇".to_string() // Unicode branch symbol
-        }
+        // Nerd Font: nf-dev-git_branch (U+E0A0); fallback Unicode branch symbol
+        icon("branch", '\u{e0a0}', "⎇", "*")
     }
 
     #[allow(dead_code)]
     pub fn commit() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-dev-git_commit (U+E729)
-            '\u{e729}'.to_string()
-        } else {
-            "●".to_string()
-        }
+        // Nerd Font: nf-dev-git_commit (U+E729)
+        icon("commit", '\u{e729}', "●", "o")
     }
 
     #[allow(dead_code)]
     pub fn repo() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-github (U+F09B)
-            '\u{f09b}'.to_string()
-        } else {
-            "⚡".to_string()
-        }
+        // Nerd Font: nf-fa-github (U+F09B)
+        icon("repo", '\u{f09b}', "⚡", "#")
     }
 
     #[allow(dead_code)]
     pub fn modified() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-dev-git_merge (U+E727)
-            '\u{e727}'.to_string()
-        } else {
-            "✎".to_string()
-        }
+        // Nerd Font: nf-dev-git_merge (U+E727)
+        icon("modified", '\u{e727}', "✎", "~")
     }
 
     pub fn owner() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-user (U+F007)
-            '\u{f007}'.to_string()
-        } else {
-            "👤".to_string()
-        }
+        // Nerd Font: nf-fa-user (U+F007)
+        icon("owner", '\u{f007}', "👤", "@")
     }
 }
 
 /// Status icons
 pub mod status {
-    use super::use_nerd_fonts;
+    use super::icon;
 
     pub fn success() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-check_circle (U+F058)
-            '\u{f058}'.to_string()
-        } else {
-            "✓".to_string()
-        }
+        // Nerd Font: nf-fa-check_circle (U+F058)
+        icon("success", '\u{f058}', "✓", "OK")
     }
 
     #[allow(dead_code)]
     pub fn error() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-times_circle (U+F057)
-            '\u{f057}'.to_string()
-        } else {
-            "❌".to_string()
-        }
+        // Nerd Font: nf-fa-times_circle (U+F057)
+        icon("error", '\u{f057}', "❌", "X")
     }
 
     #[allow(dead_code)]
     pub fn warning() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-exclamation_triangle (U+F071)
-            '\u{f071}'.to_string()
-        } else {
-            "⚠".to_string()
-        }
+        // Nerd Font: nf-fa-exclamation_triangle (U+F071)
+        icon("warning", '\u{f071}', "⚠", "!")
     }
 
     #[allow(dead_code)]
     pub fn waiting() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-clock_o (U+F017)
-            '\u{f017}'.to_string()
-        } else {
-            "⏳".to_string()
-        }
+        // Nerd Font: nf-fa-clock_o (U+F017)
+        icon("waiting", '\u{f017}', "⏳", "...")
     }
 
     #[allow(dead_code)]
     pub fn running() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-cog (U+F013)
-            '\u{f013}'.to_string()
-        } else {
-            "⚙".to_string()
-        }
+        // Nerd Font: nf-fa-cog (U+F013)
+        icon("running", '\u{f013}', "⚙", "*")
     }
 
     pub fn info() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-clock_o (U+F017)
-            '\u{f017}'.to_string()
-        } else {
-            "🕒".to_string()
-        }
+        // Nerd Font: nf-fa-clock_o (U+F017)
+        icon("info", '\u{f017}', "🕒", ">")
     }
 }
 
 /// File and folder icons
 pub mod files {
-    use super::use_nerd_fonts;
+    use super::icon;
 
     pub fn folder() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-folder (U+F07B)
-            '\u{f07b}'.to_string()
-        } else {
-            "📁".to_string()
-        }
+        // Nerd Font: nf-fa-folder (U+F07B)
+        icon("folder", '\u{f07b}', "📁", "-")
     }
 
     #[allow(dead_code)]
     pub fn file() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-fa-file (U+F016)
-            '\u{f016}'.to_string()
-        } else {
-            "📄".to_string()
-        }
+        // Nerd Font: nf-fa-file (U+F016)
+        icon("file", '\u{f016}', "📄", "-")
     }
 
     #[allow(dead_code)]
     pub fn script() -> String {
-        if use_nerd_fonts() {
-            // Nerd Font: nf-oct-file_code (U+F010A)
-            '\u{f010a}'.to_string()
-        } else {
-            "📜".to_string()
-        }
+        // Nerd Font: nf-oct-file_code (U+F010A)
+        icon("script", '\u{f010a}', "📜", "$")
     }
 }
@@ -0,0 +1,467 @@
+use anyhow::{anyhow, Result};
+
+/// Multi-repo tools whose configuration `mgit init --import` understands
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportTool {
+    /// myrepos (`.mrconfig`)
+    Mr,
+    /// gita (`repos.csv`)
+    Gita,
+    /// meta (`.meta`)
+    Meta,
+    /// Google repo manifest (`default.xml`)
+    Repo,
+    /// vcstool (`.repos`)
+    Vcs,
+    /// gitman (`gitman.yml`)
+    Gitman,
+}
+
+impl ImportTool {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "mr" | "myrepos" => Ok(ImportTool::Mr),
+            "gita" => Ok(ImportTool::Gita),
+            "meta" => Ok(ImportTool::Meta),
+            "repo" => Ok(ImportTool::Repo),
+            "vcs" | "vcstool" => Ok(ImportTool::Vcs),
+            "gitman" => Ok(ImportTool::Gitman),
+            other => Err(anyhow!(
+                "Unknown import tool '{}' (expected mr, gita, meta, repo, vcs or gitman)",
+                other
+            )),
+        }
+    }
+
+    /// Parse the tool's configuration file content
+    pub fn parse(self, content: &str) -> Result<Vec<ImportedRepo>> {
+        match self {
+            ImportTool::Mr => Ok(parse_mrconfig(content)),
+            ImportTool::Gita => Ok(parse_gita(content)),
+            ImportTool::Meta => parse_meta(content),
+            ImportTool::Repo => parse_repo_manifest(content),
+            ImportTool::Vcs => parse_vcstool(content),
+            ImportTool::Gitman => parse_gitman(content),
+        }
+    }
+}
+
+/// A repository read from another multi-repo tool's configuration
+#[derive(Debug, PartialEq)]
+pub struct ImportedRepo {
+    /// Repository directory as written in the file (relative to the file's directory, or absolute)
+    pub path: String,
+    /// Remote URL, if the file records it (gita doesn't)
+    pub url: Option<String>,
+    /// Pinned revision, if the file records one (repo, vcstool and gitman)
+    pub revision: Option<String>,
+}
+
+/// Parse a myrepos `.mrconfig`: one `[path]` section per repository,
+/// with the URL taken from its `checkout = git clone <url> <dir>` command
+pub fn parse_mrconfig(content: &str) -> Vec<ImportedRepo> {
+    let mut repos: Vec<ImportedRepo> = Vec::new();
+    let mut in_checkout = false;
+    let mut checkout = String::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        // Continuation lines of a multi-line value start with whitespace
+        if in_checkout && line.starts_with(char::is_whitespace) && !trimmed.is_empty() {
+            checkout.push(' ');
+            checkout.push_str(trimmed);
+            continue;
+        }
+        if in_checkout {
+            finish_checkout(&mut repos, &mut checkout);
+            in_checkout = false;
+        }
+
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            if section != "DEFAULT" {
+                repos.push(ImportedRepo {
+                    path: section.trim().to_string(),
+                    url: None,
+                    revision: None,
+                });
+            }
+            continue;
+        }
+        if let Some((key, value)) = trimmed.split_once('=') {
+            if key.trim() == "checkout" {
+                in_checkout = true;
+                checkout = value.trim().to_string();
+            }
+        }
+    }
+    if in_checkout {
+        finish_checkout(&mut repos, &mut checkout);
+    }
+
+    repos
+}
+
+/// Attach the URL of a completed `checkout` value to the section it belongs to
+fn finish_checkout(repos: &mut [ImportedRepo], checkout: &mut String) {
+    if let Some(repo) = repos.last_mut() {
+        if repo.url.is_none() {
+            repo.url = clone_url(checkout);
+        }
+    }
+    checkout.clear();
+}
+
+/// Extract the URL from a `git clone [options] <url> [<dir>]` command line
+fn clone_url(command: &str) -> Option<String> {
+    let words = shell_words(command);
+    let clone = words.iter().position(|w| w == "clone")?;
+
+    let mut args = words[clone + 1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // Options taking a separate value
+            "-b" | "--branch" | "-o" | "--origin" | "--depth" | "-c" | "--config" => {
+                args.next();
+            }
+            arg if arg.starts_with('-') => {}
+            url => return Some(url.to_string()),
+        }
+    }
+    None
+}
+
+/// Split a command line into words, honoring single and double quotes
+fn shell_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() || c == ';' || c == '&' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Parse gita's `repos.csv`: `path,name,...` per line (URLs are read from the repositories)
+pub fn parse_gita(content: &str) -> Vec<ImportedRepo> {
+    content
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(|path| ImportedRepo {
+            path: path.to_string(),
+            url: None,
+            revision: None,
+        })
+        .collect()
+}
+
+/// Parse meta's `.meta` JSON file: `{ "projects": { "<path>": "<url>" } }`
+pub fn parse_meta(content: &str) -> Result<Vec<ImportedRepo>> {
+    let meta: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| anyhow!("Invalid .meta file: {}", e))?;
+    let projects = meta
+        .get("projects")
+        .and_then(|p| p.as_object())
+        .ok_or_else(|| anyhow!("Invalid .meta file: missing \"projects\" object"))?;
+
+    Ok(projects
+        .iter()
+        .map(|(path, url)| ImportedRepo {
+            path: path.clone(),
+            url: url.as_str().map(|u| u.to_string()),
+            revision: None,
+        })
+        .collect())
+}
+
+/// Parse a Google repo manifest (`default.xml`)
+/// Project URLs are `<remote fetch>/<project name>`; relative fetch URLs (`..`) depend on
+/// where the manifest was fetched from, so those projects get their URL from disk instead.
+/// Revisions fall back from the project to its remote and to `<default>`.
+pub fn parse_repo_manifest(content: &str) -> Result<Vec<ImportedRepo>> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| anyhow!("Invalid manifest: {}", e))?;
+    let manifest = doc.root_element();
+    if manifest.tag_name().name() != "manifest" {
+        return Err(anyhow!("Invalid manifest: root element is not <manifest>"));
+    }
+
+    let elements = || manifest.children().filter(|n| n.is_element());
+    let default = elements().find(|n| n.has_tag_name("default"));
+    let default_remote = default.and_then(|d| d.attribute("remote"));
+    let default_revision = default.and_then(|d| d.attribute("revision"));
+    let removed: Vec<&str> = elements()
+        .filter(|n| n.has_tag_name("remove-project"))
+        .filter_map(|n| n.attribute("name"))
+        .collect();
+
+    let mut repos = Vec::new();
+    for project in elements().filter(|n| n.has_tag_name("project")) {
+        let name = project
+            .attribute("name")
+            .ok_or_else(|| anyhow!("Invalid manifest: <project> without a name"))?;
+        if removed.contains(&name) {
+            continue;
+        }
+
+        let remote = project.attribute("remote").or(default_remote).and_then(|remote| {
+            elements().find(|n| n.has_tag_name("remote") && n.attribute("name") == Some(remote))
+        });
+        let url = remote
+            .and_then(|r| r.attribute("fetch"))
+            .filter(|fetch| !fetch.starts_with('.'))
+            .map(|fetch| format!("{}/{}", fetch.trim_end_matches('/'), name));
+        let revision = project
+            .attribute("revision")
+            .or_else(|| remote.and_then(|r| r.attribute("revision")))
+            .or(default_revision)
+            .map(|rev| rev.strip_prefix("refs/heads/").unwrap_or(rev).to_string());
+
+        repos.push(ImportedRepo {
+            path: project.attribute("path").unwrap_or(name).to_string(),
+            url,
+            revision,
+        });
+    }
+
+    Ok(repos)
+}
+
+/// Parse a vcstool `.repos` file: `repositories: { <path>: { type, url, version } }`
+/// Entries of other version control systems (hg, svn, bzr) are ignored.
+pub fn parse_vcstool(content: &str) -> Result<Vec<ImportedRepo>> {
+    let file: serde_yaml::Value =
+        serde_yaml::from_str(content).map_err(|e| anyhow!("Invalid .repos file: {}", e))?;
+    let repositories = file
+        .get("repositories")
+        .and_then(|r| r.as_mapping())
+        .ok_or_else(|| anyhow!("Invalid .repos file: missing \"repositories\" mapping"))?;
+
+    Ok(repositories
+        .iter()
+        .filter(|(_, entry)| entry.get("type").and_then(|t| t.as_str()).unwrap_or("git") == "git")
+        .filter_map(|(path, entry)| {
+            Some(ImportedRepo {
+                path: path.as_str()?.to_string(),
+                url: yaml_string(entry, "url"),
+                revision: yaml_string(entry, "version"),
+            })
+        })
+        .collect())
+}
+
+/// Parse a `gitman.yml`: `sources` (with `repo`, `name`, `rev`) checked out under `location`
+/// Revisions recorded by `gitman lock` in `sources_locked` win over the `sources` ones.
+pub fn parse_gitman(content: &str) -> Result<Vec<ImportedRepo>> {
+    let file: serde_yaml::Value =
+        serde_yaml::from_str(content).map_err(|e| anyhow!("Invalid gitman.yml: {}", e))?;
+    let location = yaml_string(&file, "location").unwrap_or_else(|| "gitman_sources".to_string());
+    let sources = file
+        .get("sources")
+        .and_then(|s| s.as_sequence())
+        .ok_or_else(|| anyhow!("Invalid gitman.yml: missing \"sources\" list"))?;
+    let locked = file.get("sources_locked").and_then(|s| s.as_sequence());
+
+    Ok(sources
+        .iter()
+        .filter(|source| yaml_string(source, "type").unwrap_or_else(|| "git".to_string()) == "git")
+        .filter_map(|source| {
+            let url = yaml_string(source, "repo")?;
+            // gitman names a source after its repository when `name` is omitted
+            let name = yaml_string(source, "name").unwrap_or_else(|| {
+                let last = url.trim_end_matches('/').rsplit(['/', ':']).next().unwrap_or(&url);
+                last.trim_end_matches(".git").to_string()
+            });
+            let locked_rev = locked
+                .into_iter()
+                .flatten()
+                .find(|l| yaml_string(l, "name").as_deref() == Some(name.as_str()))
+                .and_then(|l| yaml_string(l, "rev"));
+
+            Some(ImportedRepo {
+                path: format!("{}/{}", location.trim_end_matches('/'), name),
+                url: Some(url),
+                revision: locked_rev.or_else(|| yaml_string(source, "rev")),
+            })
+        })
+        .collect())
+}
+
+fn yaml_string(value: &serde_yaml::Value, key: &str) -> Option<String> {
+    match value.get(key)? {
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        // Unquoted versions like `1.0` or commit-like numbers parse as numbers
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(path: &str, url: Option<&str>) -> ImportedRepo {
+        ImportedRepo {
+            path: path.to_string(),
+            url: url.map(|u| u.to_string()),
+            revision: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_mrconfig() {
+        let content = "\
+[DEFAULT]
+git_gc = git gc \"$@\"
+
+[src/backend]
+checkout = git clone 'git@github.com:acme/backend.git' 'backend'
+
+# comment
+[src/frontend]
+checkout =
+    git clone --branch develop \"https://github.com/acme/frontend.git\" frontend &&
+    cd frontend
+update = git pull
+
+[notes]
+";
+        assert_eq!(
+            parse_mrconfig(content),
+            vec![
+                repo("src/backend", Some("git@github.com:acme/backend.git")),
+                repo("src/frontend", Some("https://github.com/acme/frontend.git")),
+                repo("notes", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_gita_and_meta() {
+        assert_eq!(
+            parse_gita("/home/me/work/api,api,,\n/home/me/work/web,web,,\n"),
+            vec![repo("/home/me/work/api", None), repo("/home/me/work/web", None)]
+        );
+        let meta = r#"{ "projects": { "libs/core": "git@github.com:acme/core.git" }, "ignore": [] }"#;
+        assert_eq!(
+            parse_meta(meta).unwrap(),
+            vec![repo("libs/core", Some("git@github.com:acme/core.git"))]
+        );
+        assert!(parse_meta("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_repo_manifest() {
+        let manifest = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest>
+  <remote name="origin" fetch="https://github.com/acme/" />
+  <remote name="mirror" fetch=".." revision="stable" />
+  <default remote="origin" revision="refs/heads/main" />
+  <project name="core" path="libs/core" />
+  <project name="tools" revision="4f2a9c1e" />
+  <project name="legacy" remote="mirror" />
+  <project name="dropped" />
+  <remove-project name="dropped" />
+</manifest>"#;
+        let revision = |r: &str| Some(r.to_string());
+        assert_eq!(
+            parse_repo_manifest(manifest).unwrap(),
+            vec![
+                ImportedRepo {
+                    path: "libs/core".to_string(),
+                    url: Some("https://github.com/acme/core".to_string()),
+                    revision: revision("main"),
+                },
+                ImportedRepo {
+                    path: "tools".to_string(),
+                    url: Some("https://github.com/acme/tools".to_string()),
+                    revision: revision("4f2a9c1e"),
+                },
+                ImportedRepo {
+                    path: "legacy".to_string(),
+                    url: None,
+                    revision: revision("stable"),
+                },
+            ]
+        );
+        assert!(parse_repo_manifest("<projects/>").is_err());
+    }
+
+    #[test]
+    fn test_parse_vcstool_and_gitman() {
+        let repos = "\
+repositories:
+  src/ros2/rcl:
+    type: git
+    url: https://github.com/ros2/rcl.git
+    version: rolling
+  src/legacy:
+    type: hg
+    url: https://hg.example.com/legacy
+";
+        let revision = |r: &str| Some(r.to_string());
+        assert_eq!(
+            parse_vcstool(repos).unwrap(),
+            vec![ImportedRepo {
+                path: "src/ros2/rcl".to_string(),
+                url: Some("https://github.com/ros2/rcl.git".to_string()),
+                revision: revision("rolling"),
+            }]
+        );
+
+        let gitman = "\
+location: deps
+sources:
+  - repo: https://github.com/acme/core.git
+    rev: main
+  - repo: git@github.com:acme/tools.git
+    name: acme-tools
+    rev: v1.2
+sources_locked:
+  - repo: https://github.com/acme/core.git
+    name: core
+    rev: 4f2a9c1e
+";
+        assert_eq!(
+            parse_gitman(gitman).unwrap(),
+            vec![
+                ImportedRepo {
+                    path: "deps/core".to_string(),
+                    url: Some("https://github.com/acme/core.git".to_string()),
+                    revision: revision("4f2a9c1e"),
+                },
+                ImportedRepo {
+                    path: "deps/acme-tools".to_string(),
+                    url: Some("git@github.com:acme/tools.git".to_string()),
+                    revision: revision("v1.2"),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use colored::*;
+use metagit_core::git::cancel_operations;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::utils::{human_println, icons};
+
+/// Exit code after an interruption (128 + SIGINT, like a shell)
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Set on the first Ctrl-C
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Process ids of the running task steps and hooks, terminated on Ctrl-C
+static CHILDREN: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// Handle Ctrl-C (and termination requests): stop starting repositories, terminate the running
+/// scripts and cancel the network operations, so the command can finish with a partial summary
+/// A second Ctrl-C exits right away.
+pub fn install_interrupt_handler() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        eprintln!(
+            "\n{} Interrupted, stopping... (press Ctrl-C again to quit right away)",
+            icons::status::warning()
+        );
+        cancel_operations();
+        let children = CHILDREN.lock().unwrap_or_else(|e| e.into_inner());
+        for &pid in children.iter() {
+            terminate(pid);
+        }
+    })?;
+    Ok(())
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Register a running child process, terminated when the user interrupts mgit
+pub fn track_child(pid: u32) {
+    CHILDREN.lock().unwrap_or_else(|e| e.into_inner()).insert(pid);
+    // Started after the handler ran: don't let it run on
+    if interrupted() {
+        terminate(pid);
+    }
+}
+
+pub fn untrack_child(pid: u32) {
+    CHILDREN.lock().unwrap_or_else(|e| e.into_inner()).remove(&pid);
+}
+
+/// Terminate the child's process group (see `execute_script`), so what the script started stops too
+/// Interactive scripts stay in the terminal's group, only the child itself is signalled then.
+#[cfg(unix)]
+fn terminate(pid: u32) {
+    let pid = pid as libc::pid_t;
+    // SAFETY: kill() only sends a signal
+    unsafe {
+        if libc::kill(-pid, libc::SIGTERM) != 0 {
+            libc::kill(pid, libc::SIGTERM);
+        }
+    }
+}
+
+/// Children attached to the same console receive Ctrl-C from Windows themselves
+#[cfg(not(unix))]
+fn terminate(_pid: u32) {}
+
+/// Print what an interrupted parallel command didn't get to, below its results
+pub fn report_interrupted<'a>(done: usize, not_started: impl IntoIterator<Item = &'a str>) {
+    if !interrupted() {
+        return;
+    }
+    let not_started: Vec<&str> = not_started.into_iter().collect();
+    human_println!(
+        "\n{} Interrupted: {} repositor{} finished",
+        icons::status::warning(),
+        done,
+        if done == 1 { "y" } else { "ies" }
+    );
+    if !not_started.is_empty() {
+        human_println!("  {:<30} {}", "not started".yellow(), not_started.join(", "));
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// Levels of `--log-level`, from the fewest messages to the most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Send the log messages of the core (authentication, fetch/push details) to stderr and/or a file
+/// With `file`, messages are appended to it at `level` (default: debug) and stderr only shows them
+/// with `debug` (a command's `--debug`). Without a file, stderr shows them at `level`, or at debug level
+/// with `debug`, and nothing otherwise.
+pub fn init_logging(level: Option<LogLevel>, file: Option<&Path>, debug: bool) -> Result<()> {
+    let terminal_level = match (debug, file, level) {
+        (true, _, _) => LevelFilter::DEBUG,
+        (false, None, Some(level)) => level.into(),
+        _ => LevelFilter::OFF,
+    };
+    let terminal = fmt::layer()
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .without_time()
+        .with_target(false)
+        .with_filter(terminal_level);
+
+    let file = match file {
+        Some(path) => {
+            let log = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Could not open log file {}", path.display()))?;
+            let layer = fmt::layer()
+                .with_writer(Mutex::new(log))
+                .with_ansi(false)
+                .with_thread_ids(true)
+                .with_filter(LevelFilter::from(level.unwrap_or(LogLevel::Debug)));
+            Some(layer)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry().with(terminal).with(file).init();
+    Ok(())
+}
@@ -1,10 +1,40 @@
-pub mod git;
+pub mod credential_prompt;
+pub mod events;
+pub mod hooks;
 pub mod icons;
+pub mod import;
+pub mod interrupt;
+pub mod logging;
+pub mod oplog;
+pub mod output;
+pub mod parallel;
+pub mod porcelain;
+pub mod progress;
 pub mod script;
+pub mod scripting;
+pub mod select;
+pub mod tags;
 pub mod time;
-pub mod vars;
+pub mod timeout;
+pub mod undo;
 
-pub use git::*;
+pub use credential_prompt::*;
+pub use events::*;
+pub use hooks::*;
+pub use import::*;
+pub use interrupt::*;
+pub use logging::*;
+pub use metagit_core::git::*;
+pub use metagit_core::vars::*;
+pub use oplog::*;
+pub use output::*;
+pub use parallel::*;
+pub use porcelain::*;
+pub use progress::*;
 pub use script::*;
+pub use scripting::*;
+pub use select::*;
+pub use tags::*;
 pub use time::*;
-pub use vars::*;
+pub use timeout::*;
+pub use undo::*;
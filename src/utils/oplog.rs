@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use colored::*;
+use metagit_core::db::StateDb;
+use metagit_core::git::get_current_user;
+use metagit_core::models::{Config, Operation};
+use serde::Serialize;
+use std::env;
+use std::fs;
+
+use crate::utils::{human_println, icons};
+
+/// Where the failures of the last pull/push/sync are kept, relative to the workspace
+const LAST_ERRORS_FILE: &str = ".mgit/last-errors.json";
+
+/// Contents of `.mgit/last-errors.json`
+#[derive(Serialize)]
+struct LastErrors<'a> {
+    command: &'a str,
+    started_at: DateTime<Utc>,
+    repositories: usize,
+    failures: Vec<Failure<'a>>,
+}
+
+#[derive(Serialize)]
+struct Failure<'a> {
+    repo: &'a str,
+    message: &'a str,
+}
+
+/// Start recording a command for the operation log
+pub fn start_operation(command: impl Into<String>) -> Operation {
+    let user = get_current_user()
+        .ok()
+        .or_else(|| env::var("USER").or_else(|_| env::var("USERNAME")).ok())
+        .unwrap_or_else(|| "unknown".to_string());
+    Operation::new(command, user)
+}
+
+/// Write a finished operation to the operation log in the state database
+/// The log is best effort: failing to write it only prints a warning
+pub fn record_operation(config: &Config, mut operation: Operation) {
+    operation.finish();
+    let db_path = config.get_db_path();
+    let result = StateDb::open(&db_path)
+        .and_then(|db| db.record_operation(&operation));
+    if let Err(e) = result {
+        eprintln!("{} Could not write the operation log: {}", icons::status::warning(), e);
+    }
+}
+
+/// List the repositories that failed, below the interleaved per-repository output,
+/// and write them to `.mgit/last-errors.json` (removed again after a run without failures)
+pub fn report_failures(config: &Config, operation: &Operation) {
+    let failures: Vec<Failure> = operation
+        .repos
+        .iter()
+        .filter(|outcome| !outcome.success)
+        .map(|outcome| Failure { repo: &outcome.name, message: &outcome.message })
+        .collect();
+
+    let path = config.resolve_path(LAST_ERRORS_FILE);
+    if failures.is_empty() {
+        let _ = fs::remove_file(&path);
+        return;
+    }
+
+    human_println!(
+        "\n{} {} of {} repositor{} failed:",
+        icons::status::error(),
+        failures.len(),
+        operation.repos.len(),
+        if operation.repos.len() == 1 { "y" } else { "ies" }
+    );
+    for failure in &failures {
+        human_println!("  {:<30} {}", failure.repo.yellow(), failure.message.replace('\n', " ").red());
+    }
+
+    let report = LastErrors {
+        command: &operation.command,
+        started_at: operation.started_at,
+        repositories: operation.repos.len(),
+        failures,
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&path, serde_json::to_string_pretty(&report).unwrap_or_default() + "\n"));
+    match written {
+        Ok(()) => human_println!("{}", format!("Saved to {}", LAST_ERRORS_FILE).bright_black()),
+        Err(e) => eprintln!("{} Could not write {}: {}", icons::status::warning(), LAST_ERRORS_FILE, e),
+    }
+}
@@ -0,0 +1,77 @@
+use colored::*;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use crate::utils::{suspend_progress, track_child, untrack_child};
+
+/// Set by `--no-buffer`
+static STREAMING: AtomicBool = AtomicBool::new(false);
+
+/// Stream output as it comes (`--no-buffer`): child processes write to the terminal directly
+/// and parallel commands print their results without the live display
+pub fn set_streaming(streaming: bool) {
+    STREAMING.store(streaming, Ordering::Relaxed);
+}
+
+pub fn streaming() -> bool {
+    STREAMING.load(Ordering::Relaxed)
+}
+
+/// Print `text` (one or more lines) with a single write, above the live display of a parallel
+/// command, so the lines of other repositories can't end up in between
+pub fn print_block(text: &str) {
+    suspend_progress(|| {
+        let mut out = io::stdout().lock();
+        let _ = writeln!(out, "{}", text);
+        let _ = out.flush();
+    });
+}
+
+/// Output of a repository's child process, printed line by line as `<repo> | <line>`
+pub struct RepoOutput {
+    prefix: String,
+}
+
+impl RepoOutput {
+    pub fn new(repo: &str) -> Self {
+        Self { prefix: format!("{} {} ", repo.cyan(), "|".bright_black()) }
+    }
+
+    /// Print one line of the child's output
+    pub fn line(&self, line: &str) {
+        print_block(&format!("{}{}", self.prefix, line));
+    }
+
+    /// Wait for `child`, printing what it writes to its piped stdout and stderr
+    /// A child started without pipes (see `execute_script`) writes to the terminal itself.
+    /// The child is terminated when the user interrupts mgit.
+    pub fn wait(&self, mut child: Child) -> io::Result<ExitStatus> {
+        track_child(child.id());
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        thread::scope(|scope| {
+            if let Some(stderr) = stderr {
+                scope.spawn(|| self.forward(stderr));
+            }
+            if let Some(stdout) = stdout {
+                self.forward(stdout);
+            }
+        });
+        let status = child.wait();
+        untrack_child(child.id());
+        status
+    }
+
+    /// Print every line read from `reader` (invalid UTF-8 is replaced, Windows line endings dropped)
+    fn forward(&self, reader: impl Read) {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut buffer), Ok(n) if n > 0) {
+            let line = String::from_utf8_lossy(&buffer);
+            self.line(line.trim_end_matches(['\n', '\r']));
+            buffer.clear();
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use metagit_core::models::ConcurrencyConfig;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+
+use crate::utils::interrupted;
+
+/// Number of repositories processed at once
+/// `--jobs` wins over the `concurrency.jobs` config value, which defaults to the number of CPUs.
+/// Debug output is only readable sequentially, so `debug` forces a single job.
+pub fn resolve_jobs(jobs: Option<usize>, concurrency: Option<&ConcurrencyConfig>, debug: bool) -> usize {
+    if debug {
+        return 1;
+    }
+    jobs.or_else(|| concurrency.and_then(|c| c.jobs))
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+        .max(1)
+}
+
+/// Items waiting to run and the number of running items per host
+struct Queue {
+    pending: VecDeque<usize>,
+    running: HashMap<String, usize>,
+}
+
+/// Run `task` for every item on up to `jobs` threads
+/// At most `limits.limit_for_host(host)` items of the same host run at once;
+/// items of other hosts may overtake a host that is at its cap.
+/// `on_done` is called on the calling thread, in completion order, with each item's index and result.
+/// After Ctrl-C, no more items are started; the indexes of those are returned.
+pub fn run_parallel<T, R>(
+    items: &[T],
+    hosts: &[String],
+    jobs: usize,
+    limits: &ConcurrencyConfig,
+    task: impl Fn(&T) -> R + Sync,
+    mut on_done: impl FnMut(usize, R),
+) -> Vec<usize>
+where
+    T: Sync,
+    R: Send,
+{
+    let queue = Mutex::new(Queue {
+        pending: (0..items.len()).collect(),
+        running: HashMap::new(),
+    });
+    let slot_freed = Condvar::new();
+    let (sender, receiver) = mpsc::channel();
+    // Log messages of the workers belong to the caller's span (the command)
+    let span = tracing::Span::current();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            let sender = sender.clone();
+            let (queue, slot_freed, task, span) = (&queue, &slot_freed, &task, &span);
+            scope.spawn(move || loop {
+                let _entered = span.enter();
+                // Take the first pending item whose host has a free connection
+                let index = {
+                    let mut state = queue.lock().unwrap();
+                    loop {
+                        if state.pending.is_empty() || interrupted() {
+                            return;
+                        }
+                        let position = state.pending.iter().position(|&i| {
+                            let host = &hosts[i];
+                            state.running.get(host).copied().unwrap_or(0) < limits.limit_for_host(host)
+                        });
+                        match position {
+                            Some(position) => {
+                                let index = state.pending.remove(position).unwrap();
+                                *state.running.entry(hosts[index].clone()).or_insert(0) += 1;
+                                break index;
+                            }
+                            None => state = slot_freed.wait(state).unwrap(),
+                        }
+                    }
+                };
+
+                let result = task(&items[index]);
+
+                if let Some(count) = queue.lock().unwrap().running.get_mut(&hosts[index]) {
+                    *count -= 1;
+                }
+                slot_freed.notify_all();
+                let _ = sender.send((index, result));
+            });
+        }
+        drop(sender);
+
+        for (index, result) in receiver {
+            on_done(index, result);
+        }
+    });
+    queue.into_inner().unwrap().pending.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_run_parallel_respects_host_limit() {
+        let hosts: Vec<String> = (0..12)
+            .map(|i| if i % 3 == 0 { "b.example" } else { "a.example" }.to_string())
+            .collect();
+        let limits = ConcurrencyConfig {
+            per_host: Some(2),
+            hosts: HashMap::from([("b.example".to_string(), 1)]),
+            ..Default::default()
+        };
+        let running_a = AtomicUsize::new(0);
+        let running_b = AtomicUsize::new(0);
+        let peak_a = AtomicUsize::new(0);
+        let peak_b = AtomicUsize::new(0);
+
+        let mut done = Vec::new();
+        run_parallel(
+            &hosts,
+            &hosts,
+            6,
+            &limits,
+            |host| {
+                let (running, peak) = if host == "a.example" {
+                    (&running_a, &peak_a)
+                } else {
+                    (&running_b, &peak_b)
+                };
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(10));
+                running.fetch_sub(1, Ordering::SeqCst);
+            },
+            |index, _| done.push(index),
+        );
+
+        done.sort();
+        assert_eq!(done, (0..12).collect::<Vec<_>>());
+        assert!(peak_a.load(Ordering::SeqCst) <= 2);
+        assert_eq!(peak_b.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,40 @@
+//! Stable, tab-separated output for scripts (`--porcelain`)
+//! The field order of each command is documented in the README and only ever grows at the end.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// Join fields with tabs; tabs and line breaks inside a field become spaces so every record stays one line
+pub fn porcelain_line<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|field| field.as_ref().replace(['\t', '\n', '\r'], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Boolean field: "1" or "0"
+pub fn porcelain_flag(value: bool) -> &'static str {
+    if value {
+        "1"
+    } else {
+        "0"
+    }
+}
+
+/// Timestamp field: RFC 3339 in UTC, whatever the `timestamps` setting
+pub fn porcelain_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_porcelain_line() {
+        assert_eq!(porcelain_line(&["api", "main", "", "1"]), "api\tmain\t\t1");
+        assert_eq!(porcelain_line(&["Jane\tDoe", "two\nlines"]), "Jane Doe\ttwo lines");
+        assert_eq!(porcelain_time(Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap()), "2025-03-01T09:30:00Z");
+    }
+}
@@ -0,0 +1,145 @@
+use colored::*;
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::utils::{emit_started, events_enabled, icons, print_block, streaming};
+
+/// Spinner frames of running repositories
+const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Spinner frames in ASCII mode
+const ASCII_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Redraw interval of the live display
+const TICK: Duration = Duration::from_millis(80);
+
+struct ProgressState {
+    /// Repositories not started yet
+    waiting: HashSet<String>,
+    /// Running repositories, in start order
+    running: Vec<String>,
+    /// Result lines not printed yet
+    finished: Vec<String>,
+    frame: usize,
+}
+
+/// Height of the live area drawn last, cleared before the next redraw
+/// Held while drawing and by [`suspend_progress`], so a prompt isn't drawn over.
+static LIVE_LINES: Mutex<usize> = Mutex::new(0);
+
+/// Live display of a parallel operation: a spinner line per running repository that
+/// turns into the repository's result line when it finishes
+/// Result lines appear in completion order above the live area, so the final output is the same
+/// as without a terminal (or in debug mode), where results are printed as they complete.
+pub struct RepoProgress {
+    state: Arc<Mutex<ProgressState>>,
+    stop: Arc<AtomicBool>,
+    ticker: Option<JoinHandle<()>>,
+}
+
+impl RepoProgress {
+    /// Start the display for the given repositories
+    /// The live area is only drawn when stdout is a terminal, and neither `debug` output nor
+    /// `--no-buffer` is on.
+    /// With JSON events, nothing is drawn or printed; starts are reported as events instead.
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>, debug: bool) -> Self {
+        let state = Arc::new(Mutex::new(ProgressState {
+            waiting: names.into_iter().map(str::to_string).collect(),
+            running: Vec::new(),
+            finished: Vec::new(),
+            frame: 0,
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let ticker = (!debug && !streaming() && !events_enabled() && io::stdout().is_terminal()).then(|| {
+            let (state, stop) = (Arc::clone(&state), Arc::clone(&stop));
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    redraw(&mut state.lock().unwrap(), true);
+                    thread::sleep(TICK);
+                }
+            })
+        });
+
+        Self { state, stop, ticker }
+    }
+
+    /// Mark a repository as running (called from the worker threads)
+    pub fn start(&self, name: &str) {
+        emit_started(name);
+        let mut state = self.state.lock().unwrap();
+        state.waiting.remove(name);
+        state.running.push(name.to_string());
+    }
+
+    /// Replace a repository's spinner with its result line
+    pub fn finish(&self, name: &str, line: String) {
+        let mut state = self.state.lock().unwrap();
+        state.waiting.remove(name);
+        state.running.retain(|r| r != name);
+        if self.ticker.is_some() {
+            state.finished.push(line);
+        } else if !events_enabled() {
+            print_block(&line);
+        }
+    }
+
+    /// Print the remaining result lines and remove the live area
+    pub fn done(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(ticker) = self.ticker.take() {
+            let _ = ticker.join();
+            redraw(&mut self.state.lock().unwrap(), false);
+        }
+    }
+}
+
+/// Remove the live area while `f` runs (e.g., a prompt on the terminal)
+/// The spinners are drawn again below whatever `f` printed.
+pub fn suspend_progress<T>(f: impl FnOnce() -> T) -> T {
+    let mut live_lines = LIVE_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    if *live_lines > 0 {
+        let mut out = io::stdout().lock();
+        let _ = write!(out, "\x1b[{}A\r\x1b[J", *live_lines);
+        let _ = out.flush();
+        *live_lines = 0;
+    }
+    f()
+}
+
+/// Clear the live area, print the new result lines, then draw the running repositories again
+fn redraw(state: &mut ProgressState, live: bool) {
+    let mut live_lines = LIVE_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut out = io::stdout().lock();
+    if *live_lines > 0 {
+        let _ = write!(out, "\x1b[{}A\r\x1b[J", *live_lines);
+    }
+    for line in state.finished.drain(..) {
+        let _ = writeln!(out, "{}", line);
+    }
+
+    *live_lines = 0;
+    if live {
+        let frames: &[&str] = if icons::use_ascii() { &ASCII_FRAMES } else { &FRAMES };
+        let frame = frames[state.frame % frames.len()];
+        state.frame += 1;
+        for name in &state.running {
+            let _ = writeln!(out, "{:<30} {} {}", name, frame.cyan(), "running".bright_black());
+        }
+        *live_lines = state.running.len();
+        if !state.waiting.is_empty() {
+            let _ = writeln!(
+                out,
+                "{} {}",
+                icons::status::waiting(),
+                format!("{} more waiting", state.waiting.len()).bright_black()
+            );
+            *live_lines += 1;
+        }
+    }
+    let _ = out.flush();
+}
@@ -1,14 +1,18 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::path::Path;
 use std::process::{Command, Stdio};
 
-use crate::models::ShellConfig;
+use metagit_core::models::ShellConfig;
+
+use crate::utils::streaming;
 
 pub enum ScriptType {
     Shell,
     Batch,
     PowerShell,
     Executable,
+    /// Embedded rhai script, run in-process by `run_rhai_script`
+    Rhai,
 }
 
 impl ScriptType {
@@ -18,6 +22,7 @@ impl ScriptType {
             "bat" | "cmd" => ScriptType::Batch,
             "ps1" => ScriptType::PowerShell,
             "exe" => ScriptType::Executable,
+            "rhai" => ScriptType::Rhai,
             _ => ScriptType::Shell, // Default to shell
         }
     }
@@ -37,6 +42,7 @@ pub fn execute_script(
     args: &[String],
     working_dir: &Path,
     shell_config: &ShellConfig,
+    interactive: bool,
 ) -> Result<std::process::Child> {
     let mut cmd = match script_type {
         ScriptType::Shell => {
@@ -107,6 +113,9 @@ pub fn execute_script(
             c.args(args);
             c
         }
+        ScriptType::Rhai => {
+            return Err(anyhow!("rhai scripts run inside mgit, use run_rhai_script"));
+        }
     };
 
     // Only add args for shell scripts that are files
@@ -117,9 +126,23 @@ pub fn execute_script(
         }
     }
 
-    cmd.current_dir(working_dir)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit());
+    cmd.current_dir(working_dir);
+    // Output is read line by line by `RepoOutput::wait`, unless it's streamed (`--no-buffer`)
+    // or the script talks to the user
+    if interactive {
+        cmd.stdin(Stdio::inherit()).stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    } else if streaming() {
+        cmd.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    } else {
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    // Its own process group, terminated as a whole on Ctrl-C (see `track_child`); an interactive
+    // script stays in the terminal's foreground group so it can read from it
+    #[cfg(unix)]
+    if !interactive {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
 
     let child = cmd.spawn()?;
     Ok(child)
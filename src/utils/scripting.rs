@@ -0,0 +1,148 @@
+use anyhow::{anyhow, Result};
+use git2::Repository;
+use metagit_core::git::run_git;
+use metagit_core::models::Config;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::utils::VarContext;
+
+/// A repository as seen by rhai scripts
+struct ScriptRepo {
+    name: String,
+    url: String,
+    path: PathBuf,
+    group: Option<String>,
+}
+
+/// Run a rhai task step inside mgit
+/// `script` is a .rhai file (relative to the step's repository) or inline code.
+///
+/// Scripts get these constants:
+/// - `REPO`, `REPO_PATH`: the step's repository name and path
+/// - `ARGS`: the step's arguments (array of strings)
+/// - `VARS`: all substitution variables (map)
+///
+/// and these functions:
+/// - `repos()`: array of maps with `name`, `url`, `path`, `group` and `branch`
+/// - `current_branch(repo)`: current branch of a repository
+/// - `run_git(repo, [args...])`: run git in a repository, returns its output (throws on failure)
+/// - `get_var(name)`: a substitution variable, or `()` if not defined
+pub fn run_rhai_script(
+    script: &str,
+    args: &[String],
+    repo_name: &str,
+    repo_path: &Path,
+    config: &Config,
+    var_context: &VarContext,
+) -> Result<()> {
+    let script_file = repo_path.join(script);
+    let code = if script.ends_with(".rhai") && script_file.is_file() {
+        std::fs::read_to_string(&script_file)?
+    } else {
+        script.to_string()
+    };
+
+    let repos: Rc<Vec<ScriptRepo>> = Rc::new(
+        config
+            .repositories
+            .iter()
+            .map(|repo| ScriptRepo {
+                name: repo.name.clone(),
+                url: repo.url.clone(),
+                path: config.resolve_repo_path(&repo.name),
+                group: repo.group.clone(),
+            })
+            .collect(),
+    );
+
+    let mut engine = Engine::new();
+
+    let all_repos = repos.clone();
+    engine.register_fn("repos", move || -> Array {
+        all_repos
+            .iter()
+            .map(|repo| {
+                let mut map = Map::new();
+                map.insert("name".into(), repo.name.clone().into());
+                map.insert("url".into(), repo.url.clone().into());
+                map.insert("path".into(), repo.path.to_string_lossy().to_string().into());
+                map.insert(
+                    "group".into(),
+                    repo.group.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT),
+                );
+                map.insert(
+                    "branch".into(),
+                    branch_of(&repo.path).unwrap_or_default().into(),
+                );
+                Dynamic::from_map(map)
+            })
+            .collect()
+    });
+
+    let branch_repos = repos.clone();
+    engine.register_fn(
+        "current_branch",
+        move |name: &str| -> Result<String, Box<EvalAltResult>> {
+            let path = find_repo(&branch_repos, name)?;
+            branch_of(&path).ok_or_else(|| format!("'{}' has no current branch", name).into())
+        },
+    );
+
+    let git_repos = repos.clone();
+    engine.register_fn(
+        "run_git",
+        move |name: &str, git_args: Array| -> Result<String, Box<EvalAltResult>> {
+            let path = find_repo(&git_repos, name)?;
+            let git_args: Vec<String> = git_args.into_iter().map(|a| a.to_string()).collect();
+            let git_args: Vec<&str> = git_args.iter().map(|a| a.as_str()).collect();
+            run_git(&path, &git_args)
+                .map(|output| output.trim_end().to_string())
+                .map_err(|e| e.to_string().into())
+        },
+    );
+
+    let vars: Map = var_context
+        .vars()
+        .iter()
+        .map(|(k, v)| (k.as_str().into(), v.clone().into()))
+        .collect();
+    let lookup = vars.clone();
+    engine.register_fn("get_var", move |name: &str| -> Dynamic {
+        lookup.get(name).cloned().unwrap_or(Dynamic::UNIT)
+    });
+
+    let mut scope = Scope::new();
+    scope.push_constant("REPO", repo_name.to_string());
+    scope.push_constant("REPO_PATH", repo_path.to_string_lossy().to_string());
+    scope.push_constant(
+        "ARGS",
+        args.iter().cloned().map(Dynamic::from).collect::<Array>(),
+    );
+    scope.push_constant("VARS", vars);
+
+    engine
+        .run_with_scope(&mut scope, &code)
+        .map_err(|e| anyhow!("rhai script failed: {}", e))
+}
+
+/// Look up a repository path by name
+fn find_repo(repos: &[ScriptRepo], name: &str) -> Result<PathBuf, Box<EvalAltResult>> {
+    repos
+        .iter()
+        .find(|repo| repo.name == name)
+        .map(|repo| repo.path.clone())
+        .ok_or_else(|| format!("Repository '{}' not found", name).into())
+}
+
+/// Current branch of a repository, None if detached or not a repository
+fn branch_of(path: &Path) -> Option<String> {
+    let repo = Repository::open(path).ok()?;
+    let head = repo.head().ok()?;
+    if head.is_branch() {
+        head.shorthand().map(|s| s.to_string())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,103 @@
+use anyhow::{anyhow, Result};
+use inquire::{InquireError, MultiSelect, Select};
+use metagit_core::models::Repository;
+use std::io::{self, BufRead, Write};
+
+/// Turn inquire errors into readable ones; Esc is not an error but an empty choice
+fn picker_result<T>(result: Result<T, InquireError>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(InquireError::OperationCanceled) => Ok(None),
+        Err(InquireError::OperationInterrupted) => Err(anyhow!("Selection interrupted")),
+        Err(InquireError::NotTTY) => Err(anyhow!("Interactive selection needs a terminal")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Fuzzy-pick items: type to filter, space to toggle, enter to confirm
+/// Returns the indices of the chosen labels in ascending order (empty when cancelled)
+pub fn pick_many(prompt: &str, labels: &[String]) -> Result<Vec<usize>> {
+    let picked = picker_result(MultiSelect::new(prompt, labels.to_vec()).with_page_size(15).raw_prompt())?;
+    let mut selected: Vec<usize> = picked.unwrap_or_default().into_iter().map(|option| option.index).collect();
+    selected.sort_unstable();
+    Ok(selected)
+}
+
+/// Fuzzy-pick a single item: type to filter, enter to confirm
+/// Returns None when cancelled
+pub fn pick_one(prompt: &str, labels: &[String]) -> Result<Option<usize>> {
+    let picked = picker_result(Select::new(prompt, labels.to_vec()).with_page_size(15).raw_prompt())?;
+    Ok(picked.map(|option| option.index))
+}
+
+/// Fuzzy-pick repositories by name or group, keeping their configuration order
+pub fn pick_repositories(repos: Vec<&Repository>) -> Result<Vec<&Repository>> {
+    let labels: Vec<String> = repos
+        .iter()
+        .map(|repo| match &repo.group {
+            Some(group) => format!("{} ({})", repo.name, group),
+            None => repo.name.clone(),
+        })
+        .collect();
+    let selected = pick_many("Repositories:", &labels)?;
+    Ok(selected.into_iter().map(|i| repos[i]).collect())
+}
+
+/// Ask the user to pick items from a numbered list (e.g., "1,3-5", "all"; empty picks nothing)
+/// Returns the indices of the chosen labels in ascending order
+pub fn select_items(prompt: &str, labels: &[String]) -> Result<Vec<usize>> {
+    for (i, label) in labels.iter().enumerate() {
+        println!("  {:>3}) {}", i + 1, label);
+    }
+    print!("\n{} [e.g. 1,3-5, all; empty to cancel]: ", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().lock().read_line(&mut input)?;
+    parse_selection(&input, labels.len())
+}
+
+/// Parse a selection of 1-based item numbers and ranges into 0-based indices
+pub fn parse_selection(input: &str, count: usize) -> Result<Vec<usize>> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+
+    let mut selected = Vec::new();
+    for part in input.split([',', ' ']).map(str::trim).filter(|p| !p.is_empty()) {
+        let number = |value: &str| -> Result<usize> {
+            match value.trim().parse::<usize>() {
+                Ok(n) if (1..=count).contains(&n) => Ok(n - 1),
+                _ => Err(anyhow!("Invalid selection '{}' (expected numbers from 1 to {})", value, count)),
+            }
+        };
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (number(start)?, number(end)?);
+                selected.extend(start.min(end)..=start.max(end));
+            }
+            None => selected.push(number(part)?),
+        }
+    }
+
+    selected.sort_unstable();
+    selected.dedup();
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("1,3-5\n", 6).unwrap(), vec![0, 2, 3, 4]);
+        assert_eq!(parse_selection("5-4 2 2", 6).unwrap(), vec![1, 3, 4]);
+        assert_eq!(parse_selection("all", 3).unwrap(), vec![0, 1, 2]);
+        assert!(parse_selection("", 3).unwrap().is_empty());
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("4", 3).is_err());
+        assert!(parse_selection("x", 3).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::{anyhow, Result};
+use git2::{Oid, Repository};
+use chrono::Utc;
+use metagit_core::models::{Config, TagInfo};
+use std::collections::HashMap;
+
+use crate::utils::normalize_signature;
+
+/// A commit as listed in changelogs and tag diffs
+pub struct CommitEntry {
+    pub summary: String,
+    pub short_sha: String,
+    pub author: String,
+}
+
+/// Get the repository branches recorded in a saved tag
+/// Returns None for the reserved tags 'master'/'main' (resolved per repository)
+pub fn tag_branches(config: &Config, tag: &str) -> Result<Option<HashMap<String, String>>> {
+    if tag == "master" || tag == "main" {
+        return Ok(None);
+    }
+    config
+        .tags
+        .get(tag)
+        .cloned()
+        .map(Some)
+        .ok_or_else(|| anyhow!("Tag '{}' not found. Use 'mgit save {}' to create it.", tag, tag))
+}
+
+/// Record a saved tag with its branches, stamped with the current time and git user
+pub fn record_tag(config: &mut Config, tag: &str, branches: HashMap<String, String>, description: Option<&str>) {
+    config.tags.insert(tag.to_string(), branches);
+    config.tag_info.insert(
+        tag.to_string(),
+        TagInfo {
+            created_at: Utc::now(),
+            author: git_user_name(),
+            description: description.map(str::to_string),
+        },
+    );
+}
+
+/// The `user.name` from the git configuration, falling back to the login name
+fn git_user_name() -> Option<String> {
+    git2::Config::open_default()
+        .and_then(|config| config.get_string("user.name"))
+        .ok()
+        .or_else(|| std::env::var("USER").or_else(|_| std::env::var("USERNAME")).ok())
+}
+
+/// Resolve the commit a tag points to in one repository
+/// Local branches are preferred, falling back to origin/<branch>
+pub fn resolve_tag_commit(
+    repo: &Repository,
+    tag: &str,
+    branches: Option<&HashMap<String, String>>,
+    repo_name: &str,
+) -> Result<Oid> {
+    let candidates: Vec<&str> = match branches {
+        Some(branches) => vec![branches
+            .get(repo_name)
+            .ok_or_else(|| anyhow!("not recorded in tag '{}'", tag))?],
+        None => vec!["main", "master"],
+    };
+
+    for branch in &candidates {
+        for refname in [
+            format!("refs/heads/{}", branch),
+            format!("refs/remotes/origin/{}", branch),
+        ] {
+            if let Ok(oid) = repo.refname_to_id(&refname) {
+                return Ok(oid);
+            }
+        }
+    }
+
+    Err(anyhow!("branch '{}' not found", candidates.join("' or '")))
+}
+
+/// Collect the non-merge commits reachable from `to` but not from `from`, newest first
+pub fn collect_commits(
+    repo: &Repository,
+    from: Oid,
+    to: Oid,
+    users: &HashMap<String, Vec<String>>,
+) -> Result<Vec<CommitEntry>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+    revwalk.push(to)?;
+    revwalk.hide(from)?;
+
+    let mut entries = Vec::new();
+    for oid in revwalk.flatten() {
+        let commit = repo.find_commit(oid)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        entries.push(CommitEntry {
+            summary: commit.summary().unwrap_or("").to_string(),
+            short_sha: oid.to_string()[..7].to_string(),
+            author: normalize_signature(&commit.author(), users),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// A repository whose current branch differs from the one saved in a tag
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TagDeviation {
+    pub repo: String,
+    pub current: String,
+    pub saved: String,
+}
+
+/// How the current branches compare to the saved tags
+#[derive(Debug, PartialEq)]
+pub enum TagMatch {
+    /// Saved tags whose every recorded branch is checked out, sorted by name
+    Exact(Vec<String>),
+    /// The closest saved tag and its deviating repositories
+    Partial(String, Vec<TagDeviation>),
+    None,
+}
+
+/// Compare the current branch of each repository (`current`) with the saved tags
+/// Repositories not in `current` (e.g., archived or missing) are ignored.
+/// A tag matches partially when at least half of its repositories are on the saved branch;
+/// the tag with the most matching repositories is reported (ties by name).
+pub fn match_saved_tags(tags: &HashMap<String, HashMap<String, String>>, current: &HashMap<String, String>) -> TagMatch {
+    let mut exact = Vec::new();
+    let mut closest: Option<(usize, &String, Vec<TagDeviation>)> = None;
+
+    for (tag, branches) in tags {
+        let compared: Vec<(&String, &String, &String)> = branches
+            .iter()
+            .filter_map(|(repo, branch)| current.get(repo).map(|now| (repo, now, branch)))
+            .collect();
+        if compared.is_empty() {
+            continue;
+        }
+        let mut deviations: Vec<TagDeviation> = compared
+            .iter()
+            .filter(|(_, now, branch)| now != branch)
+            .map(|(repo, now, branch)| TagDeviation {
+                repo: repo.to_string(),
+                current: now.to_string(),
+                saved: branch.to_string(),
+            })
+            .collect();
+        if deviations.is_empty() {
+            exact.push(tag.clone());
+            continue;
+        }
+
+        let matching = compared.len() - deviations.len();
+        if matching == 0 || matching * 2 < compared.len() {
+            continue;
+        }
+        let better = match &closest {
+            Some((best, best_tag, _)) => matching > *best || (matching == *best && tag < *best_tag),
+            None => true,
+        };
+        if better {
+            deviations.sort();
+            closest = Some((matching, tag, deviations));
+        }
+    }
+
+    if !exact.is_empty() {
+        exact.sort();
+        return TagMatch::Exact(exact);
+    }
+    match closest {
+        Some((_, tag, deviations)) => TagMatch::Partial(tag.clone(), deviations),
+        None => TagMatch::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(r, b)| (r.to_string(), b.to_string())).collect()
+    }
+
+    #[test]
+    fn test_match_saved_tags() {
+        let tags = HashMap::from([
+            ("sprint-41".to_string(), layout(&[("api", "main"), ("web", "main"), ("lib", "main")])),
+            ("sprint-42".to_string(), layout(&[("api", "feat"), ("web", "feat"), ("lib", "main")])),
+        ]);
+
+        let current = layout(&[("api", "feat"), ("web", "feat"), ("lib", "main"), ("new", "main")]);
+        assert_eq!(match_saved_tags(&tags, &current), TagMatch::Exact(vec!["sprint-42".to_string()]));
+
+        let current = layout(&[("api", "feat"), ("web", "fix"), ("lib", "main")]);
+        assert_eq!(
+            match_saved_tags(&tags, &current),
+            TagMatch::Partial(
+                "sprint-42".to_string(),
+                vec![TagDeviation {
+                    repo: "web".to_string(),
+                    current: "fix".to_string(),
+                    saved: "feat".to_string(),
+                }]
+            )
+        );
+
+        let current = layout(&[("api", "x"), ("web", "y"), ("lib", "z")]);
+        assert_eq!(match_saved_tags(&tags, &current), TagMatch::None);
+    }
+}
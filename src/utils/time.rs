@@ -1,34 +1,139 @@
-use chrono::{DateTime, Utc};
+use metagit_core::models::TimestampFormat;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, SecondsFormat, TimeZone, Utc};
+
+/// Format a timestamp in the requested display format
+pub fn format_time(dt: DateTime<Utc>, format: TimestampFormat) -> String {
+    match format {
+        TimestampFormat::Relative => format_relative_time(dt),
+        TimestampFormat::Absolute => dt.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+        TimestampFormat::Iso => dt.to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+/// Format a duration in milliseconds (e.g., "850ms", "2.3s", "4m 12s")
+pub fn format_duration(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{}ms", ms)
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
+
+/// Parse a duration like "30m", "12h", "3d" or "2w" (a bare number is in seconds)
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: i64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid duration '{}'. Expected e.g. 30m, 12h, 3d or 2w", value))?;
+
+    match unit {
+        "" | "s" => Ok(Duration::seconds(number)),
+        "m" => Ok(Duration::minutes(number)),
+        "h" => Ok(Duration::hours(number)),
+        "d" => Ok(Duration::days(number)),
+        "w" => Ok(Duration::weeks(number)),
+        _ => Err(anyhow!(
+            "Invalid duration unit '{}' in '{}'. Use s, m, h, d or w",
+            unit,
+            value
+        )),
+    }
+}
+
+/// Parse a point in time: a date ("2025-01-01", local midnight), an RFC 3339 timestamp,
+/// or a duration before `now` ("90d" = 90 days ago)
+pub fn parse_time_bound(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("Invalid date '{}'", value));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    parse_duration(value).map(|ago| now - ago).map_err(|_| {
+        anyhow!(
+            "Invalid time '{}'. Expected a date (2025-01-01), a timestamp (2025-01-01T09:00:00Z) or an age (90d)",
+            value
+        )
+    })
+}
 
 pub fn format_relative_time(dt: DateTime<Utc>) -> String {
     let now = Utc::now();
     let duration = now.signed_duration_since(dt);
 
     if duration.num_seconds() < 60 {
-        return "just now".to_string();
+        "just now".to_string()
     } else if duration.num_minutes() < 60 {
         let mins = duration.num_minutes();
-        return format!("{} minute{} ago", mins, if mins == 1 { "" } else { "s" });
+        format!("{} minute{} ago", mins, if mins == 1 { "" } else { "s" })
     } else if duration.num_hours() < 24 {
         let hours = duration.num_hours();
-        return format!("{} hour{} ago", hours, if hours == 1 { "" } else { "s" });
+        format!("{} hour{} ago", hours, if hours == 1 { "" } else { "s" })
     } else if duration.num_days() < 7 {
         let days = duration.num_days();
         if days == 0 {
             // Less than 24 hours but in the same day
             return dt.format("%A %I%p").to_string().to_lowercase();
         }
-        return format!("{} day{} ago", days, if days == 1 { "" } else { "s" });
+        format!("{} day{} ago", days, if days == 1 { "" } else { "s" })
     } else if duration.num_days() < 60 {
         // Show weeks for anything less than 2 months
         let weeks = duration.num_weeks();
-        return format!("{} week{} ago", weeks, if weeks == 1 { "" } else { "s" });
+        format!("{} week{} ago", weeks, if weeks == 1 { "" } else { "s" })
     } else if duration.num_days() < 365 {
         // Show months only when >= 2 months
         let months = duration.num_days() / 30;
-        return format!("{} month{} ago", months, if months == 1 { "" } else { "s" });
+        format!("{} month{} ago", months, if months == 1 { "" } else { "s" })
     } else {
         let years = duration.num_days() / 365;
-        return format!("{} year{} ago", years, if years == 1 { "" } else { "s" });
+        format!("{} year{} ago", years, if years == 1 { "" } else { "s" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_time() {
+        let dt = Utc.with_ymd_and_hms(2025, 3, 14, 8, 26, 53).unwrap();
+        assert_eq!(format_time(dt, TimestampFormat::Iso), "2025-03-14T08:26:53Z");
+        assert_eq!(format_time(dt, TimestampFormat::Absolute).len(), "2025-03-14 09:26".len());
+        assert_eq!(format_time(Utc::now(), TimestampFormat::Relative), "just now");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_duration("3d").unwrap(), Duration::days(3));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_duration("3y").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_parse_time_bound() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 14, 8, 0, 0).unwrap();
+        assert_eq!(parse_time_bound("90d", now).unwrap(), now - Duration::days(90));
+        assert_eq!(
+            parse_time_bound("2025-01-01T09:00:00Z", now).unwrap(),
+            Utc.with_ymd_and_hms(2025, 1, 1, 9, 0, 0).unwrap()
+        );
+        let date = parse_time_bound("2025-01-01", now).unwrap();
+        assert_eq!(date.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(), "2025-01-01 00:00");
+        assert!(parse_time_bound("last quarter", now).is_err());
     }
 }
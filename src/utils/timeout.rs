@@ -0,0 +1,162 @@
+use anyhow::{anyhow, Result};
+use metagit_core::git::set_thread_cancel;
+use metagit_core::models::{Config, Repository};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use crate::utils::{parse_duration, progress_repo, set_progress_repo};
+
+/// Time after which a repository's fetch or push is given up: its own `network_timeout`,
+/// else the workspace's; none (or "0") means no limit
+pub fn network_timeout(config: &Config, repo: &Repository) -> Result<Option<Duration>> {
+    let Some(value) = repo.network_timeout.as_deref().or(config.network_timeout.as_deref()) else {
+        return Ok(None);
+    };
+    let timeout = parse_duration(value)
+        .map_err(|e| anyhow!("network_timeout of {}: {}", repo.name, e))?
+        .to_std()
+        .unwrap_or_default();
+    Ok((!timeout.is_zero()).then_some(timeout))
+}
+
+/// Time a timed out operation gets to notice its cancellation and return
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// A network operation that ran out of time (see [`with_timeout`])
+#[derive(Debug)]
+pub struct TimedOut {
+    timeout: Duration,
+    /// The cancelled operation returned within the grace period: the repository is no longer
+    /// being written to. Otherwise it is still running on a thread of its own.
+    pub stopped: bool,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timed out after {}", format_timeout(self.timeout))?;
+        if !self.stopped {
+            write!(f, " (the operation is still running, the repository was left alone)")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Whether `error` is a timeout whose operation may still be writing to the repository
+pub fn still_running(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<TimedOut>().is_some_and(|timed_out| !timed_out.stopped)
+}
+
+/// Run a network operation, giving up on it after `timeout`
+/// The operation runs on a thread of its own. When the time is up, it is cancelled and given
+/// [`CANCEL_GRACE`] to return, so the caller doesn't touch the repository while the operation
+/// still does; a hung connection may not notice the cancellation, and the thread is then left
+/// behind (see [`still_running`]). Without a timeout, it runs on the calling thread.
+pub fn with_timeout<T: Send + 'static>(
+    timeout: Option<Duration>,
+    operation: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    run_with_timeout(timeout, CANCEL_GRACE, operation)
+}
+
+fn run_with_timeout<T: Send + 'static>(
+    timeout: Option<Duration>,
+    grace: Duration,
+    operation: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let Some(timeout) = timeout else {
+        return operation();
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+    let (flag, repo, span) = (Arc::clone(&cancel), progress_repo(), tracing::Span::current());
+    thread::spawn(move || {
+        let _entered = span.enter();
+        set_thread_cancel(flag);
+        set_progress_repo(repo);
+        let _ = sender.send(operation());
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            cancel.store(true, Ordering::Relaxed);
+            // Whatever the operation returns now, it ran out of time
+            let stopped = !matches!(receiver.recv_timeout(grace), Err(mpsc::RecvTimeoutError::Timeout));
+            Err(TimedOut { timeout, stopped }.into())
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(anyhow!("The network operation stopped unexpectedly")),
+    }
+}
+
+/// "500ms" / "90s" / "2m" / "1m 30s"
+fn format_timeout(timeout: Duration) -> String {
+    let secs = timeout.as_secs();
+    if secs == 0 {
+        return format!("{}ms", timeout.as_millis());
+    }
+    match (secs / 60, secs % 60) {
+        (0, s) => format!("{}s", s),
+        (m, 0) => format!("{}m", m),
+        (m, s) => format!("{}m {}s", m, s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metagit_core::git::operations_cancelled;
+
+    #[test]
+    fn test_network_timeout() {
+        let config: Config = serde_yaml::from_str(
+            "network_timeout: 2m\nrepositories:\n  - { name: api, url: u }\n  - { name: web, url: u, network_timeout: \"0\" }\n  - { name: bad, url: u, network_timeout: soon }",
+        )
+        .unwrap();
+        let timeout = |index: usize| network_timeout(&config, &config.repositories[index]);
+        assert_eq!(timeout(0).unwrap(), Some(Duration::from_secs(120)));
+        assert_eq!(timeout(1).unwrap(), None);
+        assert!(timeout(2).unwrap_err().to_string().contains("network_timeout of bad"));
+    }
+
+    #[test]
+    fn test_format_timeout() {
+        assert_eq!(format_timeout(Duration::from_millis(250)), "250ms");
+        assert_eq!(format_timeout(Duration::from_secs(45)), "45s");
+        assert_eq!(format_timeout(Duration::from_secs(120)), "2m");
+        assert_eq!(format_timeout(Duration::from_secs(90)), "1m 30s");
+    }
+
+    #[test]
+    fn test_with_timeout_waits_for_the_cancelled_operation() {
+        let short = Some(Duration::from_millis(50));
+        assert_eq!(run_with_timeout(short, Duration::ZERO, || Ok(1)).unwrap(), 1);
+
+        // Notices the cancellation: returns before the caller goes on
+        let returned = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&returned);
+        let error = run_with_timeout(short, Duration::from_secs(5), move || {
+            while !operations_cancelled() {
+                thread::sleep(Duration::from_millis(5));
+            }
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(returned.load(Ordering::SeqCst));
+        assert!(!still_running(&error));
+        assert_eq!(error.to_string(), "Timed out after 50ms");
+
+        // Hung: reported as still running once the grace period is over
+        let error = run_with_timeout(short, Duration::from_millis(50), || {
+            thread::sleep(Duration::from_secs(1));
+            Ok(())
+        })
+        .unwrap_err();
+        assert!(still_running(&error));
+    }
+}
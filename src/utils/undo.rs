@@ -0,0 +1,58 @@
+use chrono::Utc;
+use metagit_core::db::StateDb;
+use metagit_core::git::get_head;
+use metagit_core::models::{Config, RepoHead, UndoSnapshot};
+
+use crate::utils::icons;
+
+/// Record the branch and HEAD of repositories before an operation that moves them
+/// Repositories whose HEAD can't be read (missing, empty) are left out
+pub fn capture_heads<'a>(
+    command: impl Into<String>,
+    config: &Config,
+    names: impl IntoIterator<Item = &'a str>,
+) -> UndoSnapshot {
+    let repos = names
+        .into_iter()
+        .filter_map(|name| {
+            let (branch, before) = get_head(&config.resolve_repo_path(name)).ok()?;
+            Some(RepoHead {
+                name: name.to_string(),
+                branch,
+                before,
+                after_branch: None,
+                after: None,
+            })
+        })
+        .collect();
+
+    UndoSnapshot {
+        command: command.into(),
+        created_at: Utc::now(),
+        repos,
+    }
+}
+
+/// Complete the snapshot with the HEADs after the operation and keep it for `mgit undo`
+/// Repositories the operation didn't change are dropped; nothing is stored if none changed
+pub fn save_undo(config: &Config, mut snapshot: UndoSnapshot) {
+    for repo in &mut snapshot.repos {
+        if let Ok((branch, after)) = get_head(&config.resolve_repo_path(&repo.name)) {
+            repo.after_branch = branch;
+            repo.after = Some(after);
+        }
+    }
+    snapshot
+        .repos
+        .retain(|r| r.after.as_deref() != Some(r.before.as_str()) || r.after_branch != r.branch);
+    if snapshot.repos.is_empty() {
+        return;
+    }
+
+    let db_path = config.get_db_path();
+    let result = StateDb::open(&db_path)
+        .and_then(|db| db.save_undo_snapshot(&snapshot));
+    if let Err(e) = result {
+        eprintln!("{} Could not record the undo snapshot: {}", icons::status::warning(), e);
+    }
+}